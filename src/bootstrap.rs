@@ -1,54 +1,413 @@
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use std::str::FromStr;
 use ethers::prelude::*;
-use crate::config::Config;
-use crate::chain::{providers, cex_client::CexClient};
+use crate::config::{Config, PollIntervals};
+use crate::chain::cex_client::CexClient;
+use crate::chain::registry::{ChainRegistry, CHAIN_ID_BASE, CHAIN_ID_ETHEREUM};
+use crate::chain::tokens::{load_token_pair, TokenPair};
+use crate::chain::aerodrome_client::resolve_pool;
+use crate::chain::aerodrome_slipstream_client::resolve_slipstream_pool;
+use crate::chain::aerodrome_tracker::AerodromePairTracker;
+use crate::chain::uniswap_v3_client::resolve_v3_pool;
+use crate::chain::uniswap_v4_client::V4PoolParams;
+use crate::chain::uniswap_v4_tracker::V4TrackerSlot;
+use crate::engine::poller::MarketCache;
+use crate::math::uniswap_v4::DYNAMIC_FEE_FLAG;
+use crate::watchlist::{CexPricing, Chain, PairConfig, PairToken, TradeSizeBounds, Watchlist};
+
+/// A Uniswap V3 pool priced in place of its chain's default venue.
+#[derive(Clone, Debug)]
+pub struct V3PoolEntry {
+    pub tokens: TokenPair, // chain_id says which leg it replaces
+    pub fee_ppm: u32,
+    pub pool: Address,     // configured, or discovered via the factory at startup
+}
+
+/// Extra pools a pair's legs may route through, resolved at startup.
+#[derive(Clone, Debug, Default)]
+pub struct RoutingEntry {
+    pub max_hops: usize,
+    pub uniswap_v4: Vec<(TokenPair, V4PoolParams, Option<u32>)>, // Ethereum, with any beforeSwap fee
+    pub aerodrome: Vec<(TokenPair, Address, bool)>, // Base: tokens, pool, stable
+}
+
+/// A pool of the pair itself that split mode may spread a leg over, resolved at startup.
+#[derive(Clone, Debug)]
+pub enum SplitPool {
+    UniswapV4 { tokens: TokenPair, params: V4PoolParams, before_swap_fee: Option<u32> },
+    UniswapV3(V3PoolEntry),
+    Aerodrome { tokens: TokenPair, pool: Address, stable: bool, tick_spacing: Option<i32> },
+}
+
+/// A split pool plus the venue id fills report it under, e.g. "uniswap_v3_500".
+#[derive(Clone, Debug)]
+pub struct SplitVenueEntry {
+    pub id: String,
+    pub pool: SplitPool,
+}
+
+impl SplitPool {
+    fn venue_id(&self) -> String {
+        match self {
+            SplitPool::UniswapV4 { params, .. } if params.fee_ppm == DYNAMIC_FEE_FLAG => "uniswap_dynamic".to_string(),
+            SplitPool::UniswapV4 { params, .. } => format!("uniswap_{}", params.fee_ppm),
+            SplitPool::UniswapV3(v3) if v3.tokens.chain_id == CHAIN_ID_BASE => format!("uniswap_v3_base_{}", v3.fee_ppm),
+            SplitPool::UniswapV3(v3) => format!("uniswap_v3_{}", v3.fee_ppm),
+            SplitPool::Aerodrome { tick_spacing: Some(ts), .. } => format!("slipstream_{}", ts),
+            SplitPool::Aerodrome { stable: true, .. } => "aerodrome_stable".to_string(),
+            SplitPool::Aerodrome { .. } => "aerodrome_volatile".to_string(),
+        }
+    }
+}
+
+/// One watched pair, resolved to addresses and on-chain decimals.
+#[derive(Clone, Debug)]
+pub struct PairEntry {
+    pub name: String,
+    pub eth_pair: TokenPair,  // Uniswap V4 currencies (base may be native ETH = address(0)); Ethereum unless configured elsewhere
+    pub base_pair: TokenPair, // Aerodrome tokens on Base
+    pub v4_pool: V4PoolParams,
+    pub v4_before_swap_fee_ppm: Option<u32>, // fixed beforeSwap LP fee of a dynamic-fee hook
+    pub aerodrome_pool: Address, // configured, or discovered via the factory at startup
+    pub aerodrome_stable: bool,  // sAMM pool rather than volatile
+    pub aerodrome_tick_spacing: Option<i32>, // Slipstream CL pool rather than either
+    pub uniswap_v3: Vec<V3PoolEntry>,        // at most one per chain
+    pub routing: Option<RoutingEntry>,
+    pub split: Vec<SplitVenueEntry>, // split mode's extra venues, with ids distinct from the legs'
+    pub cex: CexPricing, // Coinbase currencies valuing base and quote in USD
+    pub bridge: Vec<PairToken>, // tokens Across rebalances a cross-chain pair with; empty on one chain
+    pub trade_size: TradeSizeBounds,
+}
+
+impl PairEntry {
+    /// Slipstream pools are loaded in full each poll; volatile/stable pairs are tracked.
+    pub fn aerodrome_is_slipstream(&self) -> bool {
+        self.aerodrome_tick_spacing.is_some()
+    }
+
+    /// The V3 pool replacing the default venue on `chain_id`, if one is configured.
+    pub fn uniswap_v3_on(&self, chain_id: u64) -> Option<&V3PoolEntry> {
+        self.uniswap_v3.iter().find(|v3| v3.tokens.chain_id == chain_id)
+    }
+
+    /// The Uniswap leg is the V4 pool, on whichever chain it is configured.
+    pub fn v4_priced(&self) -> bool {
+        self.uniswap_v3_on(CHAIN_ID_ETHEREUM).is_none()
+    }
+
+    /// The Uniswap leg is a V4 pool on Ethereum, tracked from PoolManager events when a WS is set.
+    /// V4 pools on other chains are polled.
+    pub fn v4_tracked(&self) -> bool {
+        self.v4_priced() && self.eth_pair.chain_id == CHAIN_ID_ETHEREUM
+    }
+
+    /// Both legs trade on one chain (a Base V4 pool against Aerodrome): no bridge, one transaction.
+    pub fn same_chain(&self) -> bool {
+        self.ethereum_leg_tokens().chain_id == self.base_leg_tokens().chain_id
+    }
+
+    /// The Base leg is a volatile/stable Aerodrome pair, tracked from Sync events.
+    pub fn aerodrome_tracked(&self) -> bool {
+        !self.aerodrome_is_slipstream() && self.uniswap_v3_on(CHAIN_ID_BASE).is_none()
+    }
+
+    /// Tokens of the pool pricing the Ethereum leg (the Uniswap leg; on the V4 pool's chain when
+    /// that is not Ethereum).
+    pub fn ethereum_leg_tokens(&self) -> &TokenPair {
+        self.uniswap_v3_on(CHAIN_ID_ETHEREUM).map_or(&self.eth_pair, |v3| &v3.tokens)
+    }
+
+    /// Tokens of the pool pricing the Base leg (also the ones bridged back).
+    pub fn base_leg_tokens(&self) -> &TokenPair {
+        self.uniswap_v3_on(CHAIN_ID_BASE).map_or(&self.base_pair, |v3| &v3.tokens)
+    }
+
+    /// Venue id of the Ethereum leg, as it appears in arbitrage directions.
+    pub fn ethereum_leg_id(&self) -> &'static str {
+        if self.v4_priced() { "uniswap" } else { "uniswap_v3" }
+    }
+
+    /// Venue id of the Base leg, as it appears in arbitrage directions.
+    pub fn base_leg_id(&self) -> &'static str {
+        if self.uniswap_v3_on(CHAIN_ID_BASE).is_some() { "uniswap_v3_base" } else { "aerodrome" }
+    }
+
+    async fn resolve(
+        cfg: &PairConfig,
+        chains: &ChainRegistry,
+        aerodrome_factory: Address,
+        aerodrome_cl_factory: Address,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let parse = |field: &str, value: &str| {
+            Address::from_str(value)
+                .map_err(|e| format!("pair '{}': bad {} address '{}': {}", cfg.name, field, value, e))
+        };
+        let v4 = &cfg.uniswap_v4;
+        let aero = &cfg.aerodrome;
+
+        let v4_base = parse("uniswap_v4.base_token", &v4.base_token)?;
+        let v4_quote = parse("uniswap_v4.quote_token", &v4.quote_token)?;
+        let aero_base = parse("aerodrome.base_token", &aero.base_token)?;
+        let aero_quote = parse("aerodrome.quote_token", &aero.quote_token)?;
+
+        let provider = |chain: Chain| chains.provider(chain.chain_id()).map_err(|e| format!("pair '{}': {}", cfg.name, e));
+        let eth_provider = provider(Chain::Ethereum)?;
+        let base_provider = provider(Chain::Base)?;
+
+        // Read decimals once at startup
+        let v4_provider = provider(v4.chain)?;
+        let (eth_pair, base_pair) = tokio::try_join!(
+            load_token_pair(v4_provider, v4.chain.chain_id(), v4_base, v4_quote),
+            load_token_pair(base_provider.clone(), CHAIN_ID_BASE, aero_base, aero_quote),
+        )
+        .map_err(|e| format!("pair '{}': failed to load token metadata: {}", cfg.name, e))?;
+
+        // Fix the pool address once so refreshes and Sync subscriptions agree on it
+        let configured_pool = aero.pool.as_deref().map(|p| parse("aerodrome.pool", p)).transpose()?;
+        let aerodrome_pool = match aero.tick_spacing {
+            Some(ts) => resolve_slipstream_pool(base_provider.clone(), &base_pair, aerodrome_cl_factory, ts, configured_pool).await,
+            None => resolve_pool(base_provider.clone(), &base_pair, aerodrome_factory, aero.stable, configured_pool).await,
+        }
+        .map_err(|e| format!("pair '{}': {}", cfg.name, e))?;
+
+        let mut uniswap_v3 = Vec::new();
+        for v3 in &cfg.uniswap_v3 {
+            let provider = provider(v3.chain)?;
+            let tokens = load_token_pair(
+                provider.clone(),
+                v3.chain.chain_id(),
+                parse("uniswap_v3.base_token", &v3.base_token)?,
+                parse("uniswap_v3.quote_token", &v3.quote_token)?,
+            )
+            .await
+            .map_err(|e| format!("pair '{}': failed to load V3 token metadata: {}", cfg.name, e))?;
+            let configured = v3.pool.as_deref().map(|p| parse("uniswap_v3.pool", p)).transpose()?;
+            let pool = resolve_v3_pool(provider, &tokens, v3.fee, configured)
+                .await
+                .map_err(|e| format!("pair '{}': {}", cfg.name, e))?;
+            uniswap_v3.push(V3PoolEntry { tokens, fee_ppm: v3.fee, pool });
+        }
+
+        let mut routing = None;
+        if let Some(rc) = &cfg.routing {
+            let mut entry = RoutingEntry { max_hops: rc.max_hops, ..Default::default() };
+            for v4 in &rc.uniswap_v4 {
+                let tokens = load_token_pair(
+                    eth_provider.clone(),
+                    CHAIN_ID_ETHEREUM,
+                    parse("routing.uniswap_v4.base_token", &v4.base_token)?,
+                    parse("routing.uniswap_v4.quote_token", &v4.quote_token)?,
+                )
+                .await
+                .map_err(|e| format!("pair '{}': failed to load routing token metadata: {}", cfg.name, e))?;
+                let params = V4PoolParams {
+                    fee_ppm: v4.fee,
+                    tick_spacing: v4.tick_spacing,
+                    hooks: parse("routing.uniswap_v4.hooks", &v4.hooks)?,
+                };
+                entry.uniswap_v4.push((tokens, params, v4.before_swap_fee));
+            }
+            for aero in &rc.aerodrome {
+                let tokens = load_token_pair(
+                    base_provider.clone(),
+                    CHAIN_ID_BASE,
+                    parse("routing.aerodrome.base_token", &aero.base_token)?,
+                    parse("routing.aerodrome.quote_token", &aero.quote_token)?,
+                )
+                .await
+                .map_err(|e| format!("pair '{}': failed to load routing token metadata: {}", cfg.name, e))?;
+                let configured = aero.pool.as_deref().map(|p| parse("routing.aerodrome.pool", p)).transpose()?;
+                let pool = resolve_pool(base_provider.clone(), &tokens, aerodrome_factory, aero.stable, configured)
+                    .await
+                    .map_err(|e| format!("pair '{}': {}", cfg.name, e))?;
+                entry.aerodrome.push((tokens, pool, aero.stable));
+            }
+            routing = Some(entry);
+        }
+
+        let mut split = Vec::new();
+        if let Some(sc) = &cfg.split {
+            for v4 in &sc.uniswap_v4 {
+                let tokens = load_token_pair(
+                    provider(v4.chain)?,
+                    v4.chain.chain_id(),
+                    parse("split.uniswap_v4.base_token", &v4.base_token)?,
+                    parse("split.uniswap_v4.quote_token", &v4.quote_token)?,
+                )
+                .await
+                .map_err(|e| format!("pair '{}': failed to load split token metadata: {}", cfg.name, e))?;
+                let params = V4PoolParams {
+                    fee_ppm: v4.fee,
+                    tick_spacing: v4.tick_spacing,
+                    hooks: parse("split.uniswap_v4.hooks", &v4.hooks)?,
+                };
+                split.push(SplitPool::UniswapV4 { tokens, params, before_swap_fee: v4.before_swap_fee });
+            }
+            for v3 in &sc.uniswap_v3 {
+                let provider = provider(v3.chain)?;
+                let tokens = load_token_pair(
+                    provider.clone(),
+                    v3.chain.chain_id(),
+                    parse("split.uniswap_v3.base_token", &v3.base_token)?,
+                    parse("split.uniswap_v3.quote_token", &v3.quote_token)?,
+                )
+                .await
+                .map_err(|e| format!("pair '{}': failed to load split token metadata: {}", cfg.name, e))?;
+                let configured = v3.pool.as_deref().map(|p| parse("split.uniswap_v3.pool", p)).transpose()?;
+                let pool = resolve_v3_pool(provider, &tokens, v3.fee, configured)
+                    .await
+                    .map_err(|e| format!("pair '{}': {}", cfg.name, e))?;
+                split.push(SplitPool::UniswapV3(V3PoolEntry { tokens, fee_ppm: v3.fee, pool }));
+            }
+            for aero in &sc.aerodrome {
+                let tokens = load_token_pair(
+                    base_provider.clone(),
+                    CHAIN_ID_BASE,
+                    parse("split.aerodrome.base_token", &aero.base_token)?,
+                    parse("split.aerodrome.quote_token", &aero.quote_token)?,
+                )
+                .await
+                .map_err(|e| format!("pair '{}': failed to load split token metadata: {}", cfg.name, e))?;
+                let configured = aero.pool.as_deref().map(|p| parse("split.aerodrome.pool", p)).transpose()?;
+                let pool = match aero.tick_spacing {
+                    Some(ts) => resolve_slipstream_pool(base_provider.clone(), &tokens, aerodrome_cl_factory, ts, configured).await,
+                    None => resolve_pool(base_provider.clone(), &tokens, aerodrome_factory, aero.stable, configured).await,
+                }
+                .map_err(|e| format!("pair '{}': {}", cfg.name, e))?;
+                split.push(SplitPool::Aerodrome { tokens, pool, stable: aero.stable, tick_spacing: aero.tick_spacing });
+            }
+        }
+        let split: Vec<SplitVenueEntry> = split.into_iter().map(|pool| SplitVenueEntry { id: pool.venue_id(), pool }).collect();
+        if let Some(dup) = split.iter().enumerate().find(|(i, v)| split[..*i].iter().any(|w| w.id == v.id)) {
+            return Err(format!("pair '{}': two split pools would both be reported as '{}'", cfg.name, dup.1.id).into());
+        }
+
+        let mut entry = PairEntry {
+            name: cfg.name.clone(),
+            eth_pair,
+            base_pair,
+            v4_pool: V4PoolParams {
+                fee_ppm: v4.fee,
+                tick_spacing: v4.tick_spacing,
+                hooks: parse("uniswap_v4.hooks", &v4.hooks)?,
+            },
+            v4_before_swap_fee_ppm: v4.before_swap_fee,
+            aerodrome_pool,
+            aerodrome_stable: aero.stable,
+            aerodrome_tick_spacing: aero.tick_spacing,
+            uniswap_v3,
+            routing,
+            split,
+            cex: cfg.cex.clone(),
+            bridge: Vec::new(),
+            trade_size: cfg.trade_size,
+        };
+        if !entry.same_chain() {
+            let (uni, aero) = (*entry.ethereum_leg_tokens(), *entry.base_leg_tokens());
+            entry.bridge = match &cfg.bridge {
+                Some(listed) => listed.clone(),
+                None => bridgeable_tokens(chains, &uni, &aero).map_err(|e| format!("pair '{}': {}", cfg.name, e))?,
+            };
+            if entry.bridge.is_empty() {
+                return Err(format!(
+                    "pair '{}': neither token is WETH or USDC on both chain {} and chain {}; list the ones Across bridges under `bridge`",
+                    cfg.name, uni.chain_id, aero.chain_id
+                )
+                .into());
+            }
+        }
+        Ok(entry)
+    }
+}
+
+/// The pair's tokens Across can move between the legs' chains: those that are the same
+/// registry asset (WETH, native ETH included, or USDC) on both.
+fn bridgeable_tokens(chains: &ChainRegistry, a: &TokenPair, b: &TokenPair) -> Result<Vec<PairToken>, String> {
+    let (chain_a, chain_b) = (chains.config(a.chain_id)?, chains.config(b.chain_id)?);
+    let same_asset = |x: Address, y: Address| chain_a.bridge_asset(x).is_some_and(|asset| chain_b.bridge_asset(y) == Some(asset));
+    Ok([
+        (PairToken::Base, same_asset(a.base.address, b.base.address)),
+        (PairToken::Quote, same_asset(a.quote.address, b.quote.address)),
+    ]
+    .into_iter()
+    .filter_map(|(token, bridgeable)| bridgeable.then_some(token))
+    .collect())
+}
 
 #[allow(dead_code)]
 pub struct AppState {
-    pub eth_provider: Arc<Provider<Http>>,
-    pub base_provider: Arc<Provider<Http>>,
+    // Configured chains by id: provider, gas model, bridged token and V4 StateView addresses
+    pub chains: ChainRegistry,
     pub cex_client: CexClient,
-    pub uniswap_state_view: Address,
-    
-    // Token addresses
-    pub eth_weth_address: Address,
-    pub eth_usdc_address: Address,
-    pub base_weth_address: Address,
-    pub base_usdc_address: Address,
+
+    // Watched pairs, in watchlist order (the first one is the API default)
+    pub pairs: Vec<PairEntry>,
+
+    // Latest market state, refreshed in the background by engine::poller
+    pub market: MarketCache,
+    pub poll_intervals: PollIntervals,
+
+    // Optional WS endpoints; with Ethereum WS, V4 state is tracked from PoolManager events
+    pub ethereum_ws_url: Option<String>,
+    pub base_ws_url: Option<String>,
+    pub v4_trackers: HashMap<String, Mutex<V4TrackerSlot>>, // by pair name; no tracker until first load
+    pub aerodrome_trackers: HashMap<String, Mutex<Option<AerodromePairTracker>>>, // likewise
     
     // Protocol addresses
     pub uniswap_universal_router: Address,
+    pub uniswap_pool_manager: Address,
     pub aerodrome_factory_address: Address,
+    pub aerodrome_cl_factory_address: Address,
     pub aerodrome_weth_usdc_volatile_pool: Option<Address>,
     
     // Gas constants
     pub gas_uniswap_v4_total: u64,
     pub gas_aerodrome_swap: u64,
+    pub gas_uniswap_v3_swap: u64,
 }
 
 impl AppState {
-    pub fn new(config: &Config) -> Result<Self, Box<dyn std::error::Error>> {
-        let eth_provider = providers::create_ethereum_provider(&config.ethereum_rpc_url)?;
-        let base_provider = providers::create_base_provider(&config.base_rpc_url)?;
+    pub async fn new(config: &Config) -> Result<Self, Box<dyn std::error::Error>> {
+        let chains = ChainRegistry::connect(&config.chains, config.gas_pricing)?;
+        log::info!("Chains: {}", chains.configs().iter().map(|c| c.name).collect::<Vec<_>>().join(", "));
         let cex_client = CexClient::new(config.cex_api_url.clone());
-        let uniswap_state_view = Address::from_str(&config.uniswap_state_view)?;
+        let aerodrome_factory_address = Address::from_str(&config.aerodrome_factory_address)?;
+        let aerodrome_cl_factory_address = Address::from_str(&config.aerodrome_cl_factory_address)?;
+
+        let watchlist = Watchlist::load_or_default(config)?;
+        let pairs = futures::future::try_join_all(
+            watchlist.pairs.iter()
+                .map(|cfg| PairEntry::resolve(
+                    cfg, &chains, aerodrome_factory_address, aerodrome_cl_factory_address,
+                )),
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+        log::info!("Watching {} pair(s): {}", pairs.len(),
+            pairs.iter().map(|p| p.name.as_str()).collect::<Vec<_>>().join(", "));
+
+        let v4_trackers = pairs.iter().map(|p| (p.name.clone(), Mutex::default())).collect();
+        let aerodrome_trackers = pairs.iter().map(|p| (p.name.clone(), Mutex::new(None))).collect();
 
         Ok(AppState {
-            eth_provider,
-            base_provider,
+            chains,
             cex_client,
-            uniswap_state_view,
-            
-            // Parse token addresses from config
-            eth_weth_address: Address::from_str(&config.eth_weth_address)?,
-            eth_usdc_address: Address::from_str(&config.eth_usdc_address)?,
-            base_weth_address: Address::from_str(&config.base_weth_address)?,
-            base_usdc_address: Address::from_str(&config.base_usdc_address)?,
+
+            pairs,
+
+            market: MarketCache::new(),
+            poll_intervals: config.poll_intervals,
+            ethereum_ws_url: config.ethereum_ws_url.clone(),
+            base_ws_url: config.base_ws_url.clone(),
+            v4_trackers,
+            aerodrome_trackers,
             
             // Protocol addresses
             uniswap_universal_router: Address::from_str(&config.uniswap_universal_router)?,
-            aerodrome_factory_address: Address::from_str(&config.aerodrome_factory_address)?,
+            uniswap_pool_manager: Address::from_str(&config.uniswap_pool_manager)?,
+            aerodrome_factory_address,
+            aerodrome_cl_factory_address,
             aerodrome_weth_usdc_volatile_pool: config.aerodrome_weth_usdc_volatile_pool
                 .as_ref()
                 .map(|addr| Address::from_str(addr))
@@ -59,6 +418,28 @@ impl AppState {
                                   config.gas_uniswap_v4_settle_take_overhead + 
                                   config.gas_uniswap_v4_hook_overhead,
             gas_aerodrome_swap: config.gas_aerodrome_swap,
+            gas_uniswap_v3_swap: config.gas_uniswap_v3_swap,
         })
     }
+
+    /// RPC provider of `chain_id`; errors when that chain has no RPC URL configured.
+    pub fn provider(&self, chain_id: u64) -> Result<Arc<Provider<Http>>, String> {
+        self.chains.provider(chain_id)
+    }
+
+    /// StateView of the V4 deployment on `chain_id`.
+    pub fn uniswap_state_view(&self, chain_id: u64) -> Result<Address, String> {
+        Ok(self.chains.config(chain_id)?.uniswap_v4_state_view)
+    }
+
+    /// Look up a watched pair by name (case-insensitive); `None` selects the first one.
+    pub fn pair(&self, name: Option<&str>) -> Result<&PairEntry, String> {
+        match name {
+            None => self.pairs.first().ok_or_else(|| "no pairs configured".to_string()),
+            Some(n) => self.pairs.iter()
+                .find(|p| p.name.eq_ignore_ascii_case(n))
+                .ok_or_else(|| format!("unknown pair '{}' (watching: {})", n,
+                    self.pairs.iter().map(|p| p.name.as_str()).collect::<Vec<_>>().join(", "))),
+        }
+    }
 }
\ No newline at end of file
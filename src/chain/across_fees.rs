@@ -1,11 +1,13 @@
-use ethers::types::U256;
+use ethers::types::{Address, U256};
 use reqwest::Client;
 use serde::Deserialize;
 use std::{env, time::Duration};
 
-/// Ethereum & Base chain IDs
-pub const CHAIN_ID_ETHEREUM: u64 = 1;
-pub const CHAIN_ID_BASE: u64 = 8453;
+use crate::chain::registry::ChainConfig;
+use crate::math::amount::TokenAmount;
+
+/// Ethereum & Base chain IDs (the registry has the rest)
+pub use crate::chain::registry::{CHAIN_ID_BASE, CHAIN_ID_ETHEREUM};
 
 #[derive(Debug)]
 pub struct TokenAddresses {
@@ -29,16 +31,18 @@ impl FeeDetail {
         Ok(U256::from_dec_str(&self.total)?)
     }
 
+    /// The fee as an amount of the bridged token
+    pub fn total_amount(&self, token_decimals: u8) -> Result<TokenAmount, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(TokenAmount::new(self.total_as_u256()?, token_decimals))
+    }
+
     /// Convert to USD given token decimals & live token USD price
     pub fn total_in_usd(
         &self,
-        token_decimals: u32,
+        token_decimals: u8,
         token_price_usd: f64,
     ) -> Result<f64, Box<dyn std::error::Error + Send + Sync>> {
-        let raw_amount = self.total_as_u256()?;
-        let divisor = 10u128.pow(token_decimals);
-        let amount_in_token = raw_amount.as_u128() as f64 / divisor as f64;
-        Ok(amount_in_token * token_price_usd)
+        Ok(self.total_amount(token_decimals)?.to_f64() * token_price_usd)
     }
 }
 
@@ -103,6 +107,20 @@ pub async fn get_across_relay_fee(
     Ok(resp)
 }
 
+/// Across fee for bridging a token from `origin` to `dest`, any two configured chains, given
+/// its address on each (native ETH is bridged as the chain's WETH).
+pub async fn get_bridge_fee(
+    origin: &ChainConfig,
+    origin_token: Address,
+    dest: &ChainConfig,
+    dest_token: Address,
+    amount_smallest_unit: &str,
+) -> Result<SuggestedFees, Box<dyn std::error::Error + Send + Sync>> {
+    let token_origin = format!("{:?}", origin.bridge_address(origin_token));
+    let token_dest = format!("{:?}", dest.bridge_address(dest_token));
+    get_across_relay_fee(origin.chain_id, dest.chain_id, &token_origin, &token_dest, amount_smallest_unit).await
+}
+
 /// WETH Ethereum → Base
 pub async fn get_weth_fee_eth_to_base(amount_wei: &str) -> Result<SuggestedFees, Box<dyn std::error::Error + Send + Sync>> {
     let a = get_token_addresses()?;
@@ -165,6 +183,14 @@ mod tests {
         let usdc_usd = usdc_fee.total_in_usd(6, 1.0)
             .expect("Failed to convert USDC fee to USD"); // $1/USDC
         assert!((usdc_usd - 5.0).abs() < 0.01); // 5 USDC * $1 = $5
+
+        // Past u128 the old as_u128() cast panicked; 2^130 wei is ~1.36e21 ETH
+        let huge_fee = FeeDetail {
+            total: (U256::one() << 130).to_string(),
+            pct: None,
+        };
+        let huge_usd = huge_fee.total_in_usd(18, 1.0).expect("Failed to convert huge fee");
+        assert!((huge_usd / (2f64.powi(130) / 1e18) - 1.0).abs() < 1e-12);
     }
 
     #[test]
@@ -2,6 +2,8 @@ use ethers::prelude::*;
 use ethers::contract::Multicall;
 use std::sync::Arc;
 
+use crate::chain::providers::{latest_block_ref, BlockRef};
+use crate::chain::tokens::TokenPair;
 use crate::math::aerodrome_volatile::VolatilePairState;
 
 ethers::contract::abigen!(
@@ -19,62 +21,156 @@ ethers::contract::abigen!(
     "./abis/AerodromePairFees.json",
 );
 
-pub async fn load_volatile_pair_snapshot(
+/// Configured pool address, or the factory's stable/volatile pool for `pair`.
+pub async fn resolve_pool(
     provider: Arc<Provider<Http>>,
-    weth: Address,
-    usdc: Address,
+    pair: &TokenPair,
     factory_address: Address,
+    stable: bool,
     pool_address: Option<Address>,
-) -> Result<(VolatilePairState, bool), Box<dyn std::error::Error + Send + Sync>> {
-    // 1) Use provided pool address or discover via factory
-    let pool_addr = match pool_address {
+) -> Result<Address, Box<dyn std::error::Error + Send + Sync>> {
+    match pool_address {
         Some(addr) => {
             log::debug!("Using provided Aerodrome pool address: {}", addr);
-            addr
+            Ok(addr)
         }
         None => {
             log::debug!("Discovering Aerodrome pool via factory");
-            let factory = AerodromeFactory::new(factory_address, provider.clone());
-            let discovered_addr = factory.get_pool(weth, usdc, false).call().await?;
+            let factory = AerodromeFactory::new(factory_address, provider);
+            let discovered_addr = factory
+                .get_pool(pair.base.address, pair.quote.address, stable)
+                .call()
+                .await?;
             if discovered_addr == Address::zero() {
-                return Err("Aerodrome volatile pool not found".into());
+                return Err(format!("Aerodrome {} pool not found", pool_kind(stable)).into());
             }
-            discovered_addr
+            Ok(discovered_addr)
         }
-    };
+    }
+}
+
+fn pool_kind(stable: bool) -> &'static str {
+    if stable { "stable" } else { "volatile" }
+}
+
+/// Load the volatile pool for `pair` (given or discovered via the factory).
+/// Returns the snapshot and whether `pair.base` is token0.
+pub async fn load_volatile_pair_snapshot(
+    provider: Arc<Provider<Http>>,
+    pair: &TokenPair,
+    factory_address: Address,
+    pool_address: Option<Address>,
+) -> Result<(VolatilePairState, bool), Box<dyn std::error::Error + Send + Sync>> {
+    load_pair_snapshot(provider, pair, factory_address, false, pool_address).await
+}
+
+/// Load the stable or volatile pool for `pair` (given or discovered via the factory).
+/// Every read is pinned to the latest block, which is recorded on the state.
+/// Returns the snapshot and whether `pair.base` is token0.
+pub async fn load_pair_snapshot(
+    provider: Arc<Provider<Http>>,
+    pair: &TokenPair,
+    factory_address: Address,
+    stable: bool,
+    pool_address: Option<Address>,
+) -> Result<(VolatilePairState, bool), Box<dyn std::error::Error + Send + Sync>> {
+    // 1) Use provided pool address or discover via factory
+    let pool_addr = resolve_pool(provider.clone(), pair, factory_address, stable, pool_address).await?;
 
     // Prepare contract handles bound to the discovered pool
     let pool = AerodromePool::new(pool_addr, provider.clone());
     let factory = AerodromeFactory::new(factory_address, provider.clone());
 
-    // 2) One MULTICALL for token0, token1, reserves, fee
+    // 2) One MULTICALL for token0, token1, curve, reserves, fee, all at the same block
     //    Multicall::new(provider, None) auto-detects the chain's Multicall address.
-    let (token0, token1, (r0, r1, _ts), fee_raw): (Address, Address, (U256, U256, U256), U256) = {
-        let mut mc = Multicall::new(provider.clone(), None).await?;
+    let block = latest_block_ref(&provider).await?;
+    type Reads = (Address, Address, bool, (U256, U256, U256), U256);
+    let (token0, token1, pool_stable, (r0, r1, _ts), fee_raw): Reads = {
+        let mut mc = Multicall::new(provider.clone(), None).await?.block(block.number);
         mc.add_call(pool.token_0(), false);
         mc.add_call(pool.token_1(), false);
+        mc.add_call(pool.stable(), false);
         mc.add_call(pool.get_reserves(), false);
-        mc.add_call(factory.get_fee(pool_addr, false), false); // getFee(pool, volatile=false)
+        mc.add_call(factory.get_fee(pool_addr, stable), false); // getFee(pool, stable)
         mc.call().await?
     };
+    if pool_stable != stable {
+        return Err(format!(
+            "Aerodrome pool {:?} is a {} pool, but a {} pool was configured",
+            pool_addr, pool_kind(pool_stable), pool_kind(stable)
+        )
+        .into());
+    }
+
+    let token0_is_base = token0 == pair.base.address;
+    let (expected0, expected1) = pair.sorted_addresses();
+    if (token0, token1) != (expected0, expected1) {
+        return Err(format!(
+            "Aerodrome pool {:?} holds {:?}/{:?}, not the configured pair",
+            pool_addr, token0, token1
+        )
+        .into());
+    }
 
     let fee_bps = fee_raw.as_u32();
-    let token0_is_weth = token0 == weth;
+    let (decimals0, decimals1) = pair.decimals_for(token0_is_base);
 
-    log::debug!("Fetched Aerodrome state — reserves: {} / {}, fee_bps: {}", r0, r1, fee_bps);
+    log::debug!(
+        "Fetched Aerodrome {} pool at block {} — reserves: {} / {}, fee_bps: {}",
+        pool_kind(stable), block.number, r0, r1, fee_bps
+    );
 
-    let pair = VolatilePairState {
+    let state = VolatilePairState {
         token0,
         token1,
         reserve0: r0,
         reserve1: r1,
-        // If you actually need to read decimals on-chain, swap these heuristics for ERC20 calls.
-        decimals0: if token0_is_weth { 18 } else { 6 },
-        decimals1: if token0_is_weth { 6 } else { 18 },
+        decimals0,
+        decimals1,
         fee_bps,
+        block_number: block.number,
+        block_timestamp: block.timestamp,
+        stable,
+        chain_id: pair.chain_id,
+        gas_units: 0, // priced by the caller
     };
 
-    Ok((pair, token0_is_weth))
+    Ok((state, token0_is_base))
+}
+
+/// The parts of a known pool that change after startup.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PairReserves {
+    pub reserve0: U256,
+    pub reserve1: U256,
+    pub fee_bps: Option<u32>, // only when requested
+    pub block: BlockRef,      // block every value was read at
+}
+
+/// Reserves of a known pool (token ordering and curve are immutable), plus the fee when asked for.
+/// One getReserves call, or one multicall when the fee is included, pinned to the latest block.
+pub async fn load_pair_reserves(
+    provider: Arc<Provider<Http>>,
+    factory_address: Address,
+    pool_addr: Address,
+    stable: bool,
+    with_fee: bool,
+) -> Result<PairReserves, Box<dyn std::error::Error + Send + Sync>> {
+    let block = latest_block_ref(&provider).await?;
+    let pool = AerodromePool::new(pool_addr, provider.clone());
+    if !with_fee {
+        let (reserve0, reserve1, _ts) = pool.get_reserves().block(block.number).call().await?;
+        return Ok(PairReserves { reserve0, reserve1, fee_bps: None, block });
+    }
+
+    let factory = AerodromeFactory::new(factory_address, provider.clone());
+    let ((reserve0, reserve1, _ts), fee_raw): ((U256, U256, U256), U256) = {
+        let mut mc = Multicall::new(provider.clone(), None).await?.block(block.number);
+        mc.add_call(pool.get_reserves(), false);
+        mc.add_call(factory.get_fee(pool_addr, stable), false);
+        mc.call().await?
+    };
+    Ok(PairReserves { reserve0, reserve1, fee_bps: Some(fee_raw.as_u32()), block })
 }
 
 #[cfg(test)]
@@ -152,6 +248,11 @@ mod tests {
             decimals0: if token0_is_weth { 18 } else { 6 },
             decimals1: if token0_is_weth { 6 } else { 18 },
             fee_bps,
+            block_number: 0,
+            block_timestamp: 0,
+            stable: false,
+            chain_id: 0,
+            gas_units: 0,
         };
 
         // Verify all fields are set correctly
@@ -186,6 +287,11 @@ mod tests {
             decimals0: if token0_is_weth { 18 } else { 6 },
             decimals1: if token0_is_weth { 6 } else { 18 },
             fee_bps,
+            block_number: 0,
+            block_timestamp: 0,
+            stable: false,
+            chain_id: 0,
+            gas_units: 0,
         };
 
         // Verify decimals are swapped correctly
@@ -253,6 +359,11 @@ mod tests {
                 decimals0: 18,
                 decimals1: 6,
                 fee_bps: 30,
+                block_number: 0,
+                block_timestamp: 0,
+                stable: false,
+                chain_id: 0,
+                gas_units: 0,
             };
 
             // Verify reserves are stored correctly
@@ -353,6 +464,11 @@ mod tests {
                 decimals0: if token0_is_weth { 18 } else { 6 },
                 decimals1: if token0_is_weth { 6 } else { 18 },
                 fee_bps: 30,
+                block_number: 0,
+                block_timestamp: 0,
+                stable: false,
+                chain_id: 0,
+                gas_units: 0,
             };
 
             // Verify consistency
@@ -385,6 +501,11 @@ mod tests {
                 decimals0: 18,
                 decimals1: 6,
                 fee_bps: 30,
+                block_number: 0,
+                block_timestamp: 0,
+                stable: false,
+                chain_id: 0,
+                gas_units: 0,
             };
 
             // Should handle extreme values without panicking
@@ -416,6 +537,11 @@ mod tests {
                 decimals0: 18,
                 decimals1: 6,
                 fee_bps,
+                block_number: 0,
+                block_timestamp: 0,
+                stable: false,
+                chain_id: 0,
+                gas_units: 0,
             };
 
             // Verify fee is stored correctly
@@ -441,6 +567,11 @@ mod tests {
             decimals0: 18,
             decimals1: 6,
             fee_bps: 30,
+            block_number: 0,
+            block_timestamp: 0,
+            stable: false,
+            chain_id: 0,
+            gas_units: 0,
         };
 
         // The struct allows this, but in practice this would be invalid
@@ -455,6 +586,11 @@ mod tests {
             decimals0: 18,
             decimals1: 6,
             fee_bps: 30,
+            block_number: 0,
+            block_timestamp: 0,
+            stable: false,
+            chain_id: 0,
+            gas_units: 0,
         };
 
         assert_ne!(pair_different_tokens.token0, pair_different_tokens.token1);
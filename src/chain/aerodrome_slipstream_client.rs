@@ -0,0 +1,189 @@
+// src/chain/aerodrome_slipstream_client.rs
+//
+// Aerodrome Slipstream (concentrated liquidity) pools on Base, loaded into the same
+// `PoolState` the V4 simulator runs on. Slipstream is a Uniswap V3 fork: slot0, liquidity,
+// tickBitmap and ticks live on each pool contract, and the tick math is unchanged.
+// - Pools are keyed by (token0, token1, tickSpacing) in the CL factory, not by fee
+// - fee() is the pool's effective swap fee in pips (the factory's fee module resolves it)
+// - Reads are pinned to one block, recorded on the state
+
+use ethers::contract::{abigen, Multicall};
+use ethers::prelude::*;
+use ethers::providers::{Http, Provider};
+use std::sync::Arc;
+
+use crate::chain::providers::latest_block_ref;
+use crate::chain::tick_scan::{
+    fetch_initialized_ticks, fetch_tick_liquidity, scanned_tick_range, synthetic_tick_data, TickScanConfig,
+};
+use crate::chain::tokens::TokenPair;
+use crate::chain::uniswap_v4_client::u256_to_bigint;
+use crate::math::uniswap_v4::{create_pool_with_real_data, PoolState};
+
+/// Slipstream CLFactory on Base.
+pub const AERODROME_CL_FACTORY: &str = "0x5e7BB104d84c7CB9B682AaC2F3d509f5F406809A";
+
+abigen!(
+    SlipstreamPool,
+    r#"[
+        function token0() view returns (address)
+        function token1() view returns (address)
+        function fee() view returns (uint24)
+        function tickSpacing() view returns (int24)
+        function liquidity() view returns (uint128)
+        function slot0() view returns (uint160 sqrtPriceX96, int24 tick, uint16 observationIndex, uint16 observationCardinality, uint16 observationCardinalityNext, bool unlocked)
+        function tickBitmap(int16 wordPosition) view returns (uint256)
+        function ticks(int24 tick) view returns (uint128 liquidityGross, int128 liquidityNet, int128 stakedLiquidityNet, uint256 feeGrowthOutside0X128, uint256 feeGrowthOutside1X128, uint256 rewardGrowthOutsideX128, int56 tickCumulativeOutside, uint160 secondsPerLiquidityOutsideX128, uint32 secondsOutside, bool initialized)
+    ]"#
+);
+
+abigen!(
+    SlipstreamFactory,
+    r#"[
+        function getPool(address tokenA, address tokenB, int24 tickSpacing) view returns (address)
+    ]"#
+);
+
+/// Raw slot0 row: (sqrtPriceX96, tick, observationIndex, observationCardinality, observationCardinalityNext, unlocked)
+type Slot0Row = (U256, i32, u16, u16, u16, bool);
+
+/// Raw ticks() row; only liquidityGross and liquidityNet are used.
+type TickRow = (u128, i128, i128, U256, U256, U256, i64, U256, u32, bool);
+
+/// Configured pool address, or the CL factory's pool for `pair` at `tick_spacing`.
+pub async fn resolve_slipstream_pool(
+    provider: Arc<Provider<Http>>,
+    pair: &TokenPair,
+    factory_address: Address,
+    tick_spacing: i32,
+    pool_address: Option<Address>,
+) -> Result<Address, Box<dyn std::error::Error + Send + Sync>> {
+    if let Some(addr) = pool_address {
+        log::debug!("Using provided Slipstream pool address: {}", addr);
+        return Ok(addr);
+    }
+    log::debug!("Discovering Slipstream pool via the CL factory");
+    let factory = SlipstreamFactory::new(factory_address, provider);
+    let addr = factory.get_pool(pair.base.address, pair.quote.address, tick_spacing).call().await?;
+    if addr == Address::zero() {
+        return Err(format!("Aerodrome Slipstream pool with tick spacing {} not found", tick_spacing).into());
+    }
+    Ok(addr)
+}
+
+/// Load a Slipstream pool for `pair`. Every read is pinned to the latest block.
+/// Returns the pool and whether `pair.base` is token0.
+pub async fn load_slipstream_pool(
+    provider: Arc<Provider<Http>>,
+    pair: &TokenPair,
+    pool_address: Address,
+    multicall_addr: Option<Address>,
+) -> Result<(PoolState, bool), Box<dyn std::error::Error + Send + Sync>> {
+    let pool = SlipstreamPool::new(pool_address, provider.clone());
+    let block = latest_block_ref(&provider).await?;
+
+    // 1) Tokens, fee, spacing, slot0 and liquidity in ONE multicall
+    let mut mc = Multicall::new(provider.clone(), multicall_addr).await?.block(block.number);
+    mc.add_call(pool.token_0(), false)
+        .add_call(pool.token_1(), false)
+        .add_call(pool.fee(), false)
+        .add_call(pool.tick_spacing(), false)
+        .add_call(pool.slot_0(), false)
+        .add_call(pool.liquidity(), false);
+    let (token0, token1, fee_pips, tick_spacing, slot0, liquidity): (Address, Address, u32, i32, Slot0Row, u128) =
+        mc.call().await?;
+    let (sqrt_price_x96, current_tick, ..) = slot0;
+
+    if (token0, token1) != pair.sorted_addresses() {
+        return Err(format!(
+            "Slipstream pool {:?} holds {:?}/{:?}, not the configured pair",
+            pool_address, token0, token1
+        )
+        .into());
+    }
+    log::debug!("Slipstream state at block {} — tick: {}, liquidity: {}, fee: {} pips",
+        block.number, current_tick, liquidity, fee_pips);
+
+    // 2) Tick data via bitmaps + ticks()
+    let scan = TickScanConfig::default();
+    let candidate_ticks = fetch_initialized_ticks(
+        provider.clone(), multicall_addr, block.number, current_tick, tick_spacing, scan.word_range,
+        |wp| pool.tick_bitmap(wp),
+    )
+    .await?;
+    let synthetic = candidate_ticks.is_empty();
+    let tick_data = if synthetic {
+        log::warn!("Slipstream pool {:?}: no initialized ticks within ±{} words; pricing on synthetic liquidity",
+            pool_address, scan.word_range);
+        synthetic_tick_data(current_tick)
+    } else {
+        fetch_tick_liquidity(
+            provider.clone(), multicall_addr, block.number, &candidate_ticks, &scan,
+            |t| pool.ticks(t),
+            |(liquidity_gross, liquidity_net, ..): TickRow| (liquidity_gross, liquidity_net),
+        )
+        .await?
+    };
+
+    // 3) Build pool (no hooks on Slipstream)
+    let mut state = create_pool_with_real_data(
+        token0,
+        token1,
+        fee_pips,
+        tick_spacing,
+        Address::zero(),
+        u256_to_bigint(sqrt_price_x96),
+        current_tick,
+        liquidity.into(),
+        tick_data,
+    );
+    state.block_number = block.number;
+    state.block_timestamp = block.timestamp;
+    state.chain_id = pair.chain_id;
+    state.synthetic_ticks = synthetic;
+    state.loaded_ticks = (!synthetic).then(|| scanned_tick_range(current_tick, tick_spacing, scan.word_range));
+
+    Ok((state, pair.base_is_token0()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::abi::{encode, AbiDecode, Token};
+    use ethers::utils::keccak256;
+
+    fn selector(signature: &str) -> [u8; 4] {
+        keccak256(signature)[..4].try_into().unwrap()
+    }
+
+    #[test]
+    fn test_abi_matches_cl_pool() {
+        assert_eq!(Slot0Call::selector(), selector("slot0()"));
+        assert_eq!(TicksCall::selector(), selector("ticks(int24)"));
+        assert_eq!(TickBitmapCall::selector(), selector("tickBitmap(int16)"));
+        assert_eq!(LiquidityCall::selector(), selector("liquidity()"));
+        // Keyed by tick spacing where Uniswap V3's getPool takes a fee
+        assert_eq!(GetPoolCall::selector(), selector("getPool(address,address,int24)"));
+    }
+
+    #[test]
+    fn test_ticks_row_decodes_gross_and_net() {
+        // ticks() returns ten words; liquidityNet is signed
+        let raw = encode(&[
+            Token::Uint(5_000u64.into()),
+            Token::Int(I256::from(-3_000).into_raw()),
+            Token::Int(I256::from(-1_000).into_raw()),
+            Token::Uint(U256::zero()),
+            Token::Uint(U256::zero()),
+            Token::Uint(U256::zero()),
+            Token::Int(U256::zero()),
+            Token::Uint(U256::zero()),
+            Token::Uint(U256::zero()),
+            Token::Bool(true),
+        ]);
+        let row = TicksReturn::decode(raw).expect("ticks() row");
+        assert_eq!(row.liquidity_gross, 5_000);
+        assert_eq!(row.liquidity_net, -3_000);
+        assert!(row.initialized);
+    }
+}
@@ -0,0 +1,197 @@
+// src/chain/aerodrome_tracker.rs
+//
+// Keeps an Aerodrome VolatilePairState current without re-reading what doesn't change.
+// - token0/token1 and decimals come from the startup load and never change
+// - Sync events carry the new reserves outright
+// - SetCustomFee for our pool asks for a full reload (the factory resolves the effective fee)
+
+use ethers::contract::parse_log;
+use ethers::prelude::*;
+
+use crate::chain::aerodrome_client::PairReserves;
+use crate::chain::events::{SetCustomFeeFilter, SyncFilter, TrackerUpdate};
+use crate::chain::providers::BlockRef;
+use crate::math::aerodrome_volatile::VolatilePairState;
+
+pub struct AerodromePairTracker {
+    pool: Address,
+    state: VolatilePairState,
+    token0_is_base: bool,
+    read_block: u64,                   // block of the last full load or polled read
+    last_applied: Option<(u64, U256)>, // (block, log index) of the last Sync applied
+}
+
+impl AerodromePairTracker {
+    /// Start from a full load; Sync logs up to and including its block are skipped.
+    pub fn new(pool: Address, state: VolatilePairState, token0_is_base: bool) -> Self {
+        Self { pool, read_block: state.block_number, state, token0_is_base, last_applied: None }
+    }
+
+    pub fn state(&self) -> &VolatilePairState {
+        &self.state
+    }
+
+    pub fn token0_is_base(&self) -> bool {
+        self.token0_is_base
+    }
+
+    /// Latest block reflected in `state()`.
+    pub fn block(&self) -> BlockRef {
+        BlockRef { number: self.state.block_number, timestamp: self.state.block_timestamp }
+    }
+
+    /// Apply a polled read. Older than what events already gave us => ignored.
+    pub fn apply_reserves(&mut self, reading: PairReserves) -> TrackerUpdate {
+        if reading.block.number < self.state.block_number {
+            return TrackerUpdate::Ignored;
+        }
+        self.state.reserve0 = reading.reserve0;
+        self.state.reserve1 = reading.reserve1;
+        if let Some(fee_bps) = reading.fee_bps {
+            self.state.fee_bps = fee_bps;
+        }
+        self.state.block_number = reading.block.number;
+        self.state.block_timestamp = reading.block.timestamp;
+        self.read_block = reading.block.number;
+        TrackerUpdate::Applied
+    }
+
+    /// `block_timestamp` is that of the log's block (logs don't carry one).
+    pub fn apply_log(&mut self, log: &Log, block_timestamp: u64) -> TrackerUpdate {
+        let topic0 = log.topics.first().copied();
+
+        // Factory log: only a custom fee for our pool matters
+        if topic0 == Some(SetCustomFeeFilter::signature()) {
+            let ours = log.topics.get(1).is_some_and(|t| Address::from(*t) == self.pool);
+            return if ours {
+                TrackerUpdate::NeedsResync("custom fee changed".into())
+            } else {
+                TrackerUpdate::Ignored
+            };
+        }
+
+        if log.address != self.pool || topic0 != Some(SyncFilter::signature()) {
+            return TrackerUpdate::Ignored;
+        }
+        if log.removed == Some(true) {
+            return TrackerUpdate::NeedsResync("log removed by a reorg".into());
+        }
+        let (Some(block), Some(index)) = (log.block_number, log.log_index) else {
+            return TrackerUpdate::Ignored; // pending logs carry no position
+        };
+        let position = (block.as_u64(), index);
+        // Already covered by a read, or a duplicate after a reconnect
+        if position.0 <= self.read_block || self.last_applied.is_some_and(|last| position <= last) {
+            return TrackerUpdate::Ignored;
+        }
+
+        match parse_log::<SyncFilter>(log.clone()) {
+            Ok(sync) => {
+                self.state.reserve0 = sync.reserve_0;
+                self.state.reserve1 = sync.reserve_1;
+                self.state.block_number = position.0;
+                self.state.block_timestamp = block_timestamp;
+                self.last_applied = Some(position);
+                TrackerUpdate::Applied
+            }
+            Err(e) => TrackerUpdate::NeedsResync(format!("undecodable Sync log: {}", e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::abi::{encode, Token};
+
+    const POOL: Address = H160([0xAE; 20]);
+    const TS: u64 = 1_700_000_002;
+
+    fn tracker() -> AerodromePairTracker {
+        let state = VolatilePairState {
+            token0: Address::from([0x01; 20]),
+            token1: Address::from([0x02; 20]),
+            reserve0: U256::from(1_000u64),
+            reserve1: U256::from(4_000_000u64),
+            decimals0: 18,
+            decimals1: 6,
+            fee_bps: 30,
+            block_number: 100,
+            block_timestamp: 1_700_000_000,
+            stable: false,
+            chain_id: 0,
+            gas_units: 0,
+        };
+        AerodromePairTracker::new(POOL, state, true)
+    }
+
+    fn sync_log(block: u64, index: u64, r0: u64, r1: u64) -> Log {
+        Log {
+            address: POOL,
+            topics: vec![SyncFilter::signature()],
+            data: encode(&[Token::Uint(r0.into()), Token::Uint(r1.into())]).into(),
+            block_number: Some(block.into()),
+            log_index: Some(index.into()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_sync_sets_reserves() {
+        let mut t = tracker();
+        assert_eq!(t.apply_log(&sync_log(101, 4, 1_100, 3_700_000), TS), TrackerUpdate::Applied);
+        assert_eq!(t.state().reserve0, U256::from(1_100u64));
+        assert_eq!(t.state().reserve1, U256::from(3_700_000u64));
+        assert_eq!(t.state().fee_bps, 30); // untouched
+        assert_eq!(t.block(), BlockRef { number: 101, timestamp: TS });
+    }
+
+    #[test]
+    fn test_sync_ordering() {
+        let mut t = tracker();
+        assert_eq!(t.apply_log(&sync_log(100, 0, 1, 1), TS), TrackerUpdate::Ignored); // in the startup load
+
+        assert_eq!(t.apply_log(&sync_log(101, 1, 5, 5), TS), TrackerUpdate::Applied);
+        assert_eq!(t.apply_log(&sync_log(101, 2, 6, 6), TS), TrackerUpdate::Applied); // same block, later log
+        assert_eq!(t.apply_log(&sync_log(101, 2, 6, 6), TS), TrackerUpdate::Ignored); // replay
+        assert_eq!(t.state().reserve0, U256::from(6u64));
+
+        let mut other = sync_log(102, 0, 9, 9);
+        other.address = Address::from([0x03; 20]);
+        assert_eq!(t.apply_log(&other, TS), TrackerUpdate::Ignored);
+    }
+
+    #[test]
+    fn test_polled_reserves_never_roll_back_events() {
+        let mut t = tracker();
+        t.apply_log(&sync_log(105, 0, 7, 7), TS);
+        let reading = |number| PairReserves {
+            reserve0: U256::from(1u64),
+            reserve1: U256::from(1u64),
+            fee_bps: Some(5),
+            block: BlockRef { number, timestamp: 1_700_000_000 + 2 * (number - 100) },
+        };
+        assert_eq!(t.apply_reserves(reading(104)), TrackerUpdate::Ignored);
+        assert_eq!(t.state().reserve0, U256::from(7u64));
+
+        assert_eq!(t.apply_reserves(reading(106)), TrackerUpdate::Applied);
+        assert_eq!(t.state().reserve0, U256::from(1u64));
+        assert_eq!(t.state().fee_bps, 5);
+        assert_eq!(t.block(), BlockRef { number: 106, timestamp: 1_700_000_012 });
+    }
+
+    #[test]
+    fn test_custom_fee_for_our_pool_requests_resync() {
+        let mut t = tracker();
+        let fee_log = |pool: Address| Log {
+            address: Address::from([0xFA; 20]),
+            topics: vec![SetCustomFeeFilter::signature(), H256::from(pool)],
+            data: encode(&[Token::Uint(5u64.into())]).into(),
+            block_number: Some(101u64.into()),
+            log_index: Some(0u64.into()),
+            ..Default::default()
+        };
+        assert!(matches!(t.apply_log(&fee_log(POOL), TS), TrackerUpdate::NeedsResync(_)));
+        assert_eq!(t.apply_log(&fee_log(Address::from([0x09; 20])), TS), TrackerUpdate::Ignored);
+    }
+}
@@ -28,8 +28,18 @@ impl CexClient {
 
 
     pub async fn get_coinbase_price(&self) -> Result<f64> {
+        self.usd_rate(&self.api_url).await
+    }
+
+    /// USD price of another Coinbase currency (e.g. "BTC"): the same endpoint, with its
+    /// `currency` query parameter swapped for `currency`.
+    pub async fn get_usd_price(&self, currency: &str) -> Result<f64> {
+        self.usd_rate(&currency_url(&self.api_url, currency)?).await
+    }
+
+    async fn usd_rate(&self, url: &str) -> Result<f64> {
         let response: CoinbaseResponse = self.client
-            .get(&self.api_url)
+            .get(url)
             .send()
             .await
             .context("Failed to fetch from Coinbase API")?
@@ -47,6 +57,16 @@ impl CexClient {
     }
 }
 
+fn currency_url(api_url: &str, currency: &str) -> Result<String> {
+    let mut url = url::Url::parse(api_url).context("Invalid CEX API URL")?;
+    let others: Vec<(String, String)> = url.query_pairs()
+        .filter(|(k, _)| k != "currency")
+        .map(|(k, v)| (k.into_owned(), v.into_owned()))
+        .collect();
+    url.query_pairs_mut().clear().extend_pairs(others).append_pair("currency", currency);
+    Ok(url.into())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -82,6 +102,16 @@ mod tests {
     }
 
     #[test]
+    fn test_currency_url() {
+        let url = currency_url("https://api.coinbase.com/v2/exchange-rates?currency=ETH", "BTC").unwrap();
+        assert_eq!(url, "https://api.coinbase.com/v2/exchange-rates?currency=BTC");
+        let url = currency_url("http://localhost:9000/rates", "CBETH").unwrap();
+        assert_eq!(url, "http://localhost:9000/rates?currency=CBETH");
+        assert!(currency_url("not a url", "BTC").is_err());
+    }
+
+    #[test]
+    #[allow(clippy::expect_fun_call)]
     fn test_price_parsing_logic() {
         // Test the price parsing logic used in get_coinbase_price
         let test_cases = vec![
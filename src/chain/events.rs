@@ -0,0 +1,124 @@
+// src/chain/events.rs
+//
+// On-chain events that change the state we price from.
+// - Uniswap V4: Swap / ModifyLiquidity on the singleton PoolManager, keyed by poolId (topic1)
+// - Aerodrome: Sync on each pair contract (emitted after every swap, mint and burn),
+//   SetCustomFee on the factory
+// Used with WS subscriptions to refresh snapshots when the state actually moves.
+
+use ethers::prelude::*;
+
+/// Uniswap V4 PoolManager on Ethereum mainnet.
+pub const UNISWAP_V4_POOL_MANAGER: &str = "0x000000000004444c5dc75cB358380D2e3dE08A90";
+
+abigen!(
+    PoolManagerEvents,
+    r#"[
+        event Swap(bytes32 indexed id, address indexed sender, int128 amount0, int128 amount1, uint160 sqrtPriceX96, uint128 liquidity, int24 tick, uint24 fee)
+        event ModifyLiquidity(bytes32 indexed id, address indexed sender, int24 tickLower, int24 tickUpper, int256 liquidityDelta, bytes32 salt)
+    ]"#
+);
+
+abigen!(
+    AerodromePairEvents,
+    r#"[
+        event Sync(uint256 reserve0, uint256 reserve1)
+    ]"#
+);
+
+abigen!(
+    AerodromeFactoryEvents,
+    r#"[
+        event SetCustomFee(address indexed pool, uint256 fee)
+    ]"#
+);
+
+/// Outcome of feeding one log to a state tracker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TrackerUpdate {
+    Applied,
+    /// Other pool, already reflected in the snapshot, or a duplicate.
+    Ignored,
+    /// The tracked state can no longer be trusted; reload it in full.
+    NeedsResync(String),
+}
+
+/// Swap and ModifyLiquidity logs for the given pools.
+pub fn v4_pool_events_filter(pool_manager: Address, pool_ids: &[H256]) -> Filter {
+    Filter::new()
+        .address(pool_manager)
+        .topic0(vec![SwapFilter::signature(), ModifyLiquidityFilter::signature()])
+        .topic1(pool_ids.to_vec())
+}
+
+/// Sync logs for the given Aerodrome pairs, and custom-fee changes on their factory.
+pub fn aerodrome_events_filter(pairs: &[Address], factory: Address) -> Filter {
+    let mut addresses = pairs.to_vec();
+    addresses.push(factory);
+    Filter::new()
+        .address(addresses)
+        .topic0(vec![SyncFilter::signature(), SetCustomFeeFilter::signature()])
+}
+
+/// poolId of a PoolManager log (None for anything without an indexed id).
+pub fn v4_log_pool_id(log: &Log) -> Option<H256> {
+    log.topics.get(1).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::utils::keccak256;
+
+    #[test]
+    fn test_event_signatures() {
+        assert_eq!(
+            SwapFilter::signature(),
+            H256::from(keccak256("Swap(bytes32,address,int128,int128,uint160,uint128,int24,uint24)"))
+        );
+        assert_eq!(
+            ModifyLiquidityFilter::signature(),
+            H256::from(keccak256("ModifyLiquidity(bytes32,address,int24,int24,int256,bytes32)"))
+        );
+        // Aerodrome reserves are uint256, unlike the uint112 of Uniswap V2's Sync
+        assert_eq!(SyncFilter::signature(), H256::from(keccak256("Sync(uint256,uint256)")));
+        assert_eq!(SetCustomFeeFilter::signature(), H256::from(keccak256("SetCustomFee(address,uint256)")));
+    }
+
+    #[test]
+    fn test_aerodrome_filter_includes_factory() {
+        let pair = Address::from([0x01; 20]);
+        let factory = Address::from([0x02; 20]);
+        let filter = aerodrome_events_filter(&[pair], factory);
+        assert_eq!(filter.address, Some(ValueOrArray::Array(vec![pair, factory])));
+        let topic0 = filter.topics[0].clone().expect("topic0 set");
+        assert_eq!(
+            topic0,
+            ValueOrArray::Array(vec![Some(SyncFilter::signature()), Some(SetCustomFeeFilter::signature())])
+        );
+    }
+
+    #[test]
+    fn test_v4_filter_topics() {
+        let manager: Address = UNISWAP_V4_POOL_MANAGER.parse().unwrap();
+        let ids = [H256::repeat_byte(0x01), H256::repeat_byte(0x02)];
+        let filter = v4_pool_events_filter(manager, &ids);
+
+        assert_eq!(filter.address, Some(ValueOrArray::Value(manager)));
+        let topic0 = filter.topics[0].clone().expect("topic0 set");
+        assert_eq!(
+            topic0,
+            ValueOrArray::Array(vec![Some(SwapFilter::signature()), Some(ModifyLiquidityFilter::signature())])
+        );
+        let topic1 = filter.topics[1].clone().expect("topic1 set");
+        assert_eq!(topic1, ValueOrArray::Array(ids.iter().map(|id| Some(*id)).collect()));
+    }
+
+    #[test]
+    fn test_v4_log_pool_id() {
+        let id = H256::repeat_byte(0xAB);
+        let log = Log { topics: vec![SwapFilter::signature(), id], ..Default::default() };
+        assert_eq!(v4_log_pool_id(&log), Some(id));
+        assert_eq!(v4_log_pool_id(&Log::default()), None);
+    }
+}
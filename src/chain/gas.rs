@@ -1,16 +1,33 @@
 // src/chain/gas.rs
 //
-// Fast gas estimation utilities for L1 Ethereum and Base (OP Stack).
+// Fast gas estimation utilities for L1 Ethereum and the L2s, behind a pluggable `GasModel`
+// the registry picks per chain:
+// - L1: EIP-1559 pricing from eth_feeHistory: the pending block's base fee, projected N blocks
+//   ahead, plus a percentile of recent priority fees, with low/median/high scenarios
+// - OP Stack (Base, Optimism, Unichain): L2 execution plus the GasPriceOracle L1 data fee
+// - Arbitrum: NodeInterface prices the L1 calldata as extra L2 gas units at the L2 base fee
 // - Minimal RPCs
 // - Parallel reads where possible
 // - No heavy conversions
 
 use ethers::prelude::*;
+use futures::future::BoxFuture;
+use std::fmt::Debug;
 use std::sync::Arc;
 
+use crate::chain::registry::GasModelKind;
+use crate::chain::tokens::NATIVE_DECIMALS;
+use crate::math::amount::TokenAmount;
+
 /// OP Stack / Base GasPriceOracle predeploy (constant across OP chains)
 pub const GAS_PRICE_ORACLE: &str = "0x420000000000000000000000000000000000000F";
 
+/// Arbitrum NodeInterface: a virtual contract that only answers eth_call
+pub const ARB_NODE_INTERFACE: &str = "0x00000000000000000000000000000000000000C8";
+
+/// Intrinsic gas of a transaction, paid once however many swaps it carries.
+pub const TX_INTRINSIC_GAS: u64 = 21_000;
+
 abigen!(
     GasPriceOracle,
     r#"[
@@ -18,48 +35,259 @@ abigen!(
     ]"#
 );
 
+abigen!(
+    NodeInterface,
+    r#"[
+        function gasEstimateL1Component(address to, bool contractCreation, bytes data) payable returns (uint64 gasEstimateForL1, uint256 baseFee, uint256 l1BaseFeeEstimate)
+    ]"#
+);
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Priority-fee percentiles of the low and high L1 scenarios.
+pub const LOW_PRIORITY_FEE_PERCENTILE: f64 = 10.0;
+pub const HIGH_PRIORITY_FEE_PERCENTILE: f64 = 90.0;
+
+/// How L1 gas is priced from eth_feeHistory.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Eip1559Pricing {
+    pub priority_fee_percentile: f64, // tip the median scenario (and the estimate) pays, 0-100
+    pub base_fee_blocks_ahead: u64,   // base fee projected this many blocks past the pending one
+    pub fee_history_blocks: u64,      // recent blocks the tips and gas usage are sampled from
+}
+
+impl Default for Eip1559Pricing {
+    fn default() -> Self {
+        Self { priority_fee_percentile: 50.0, base_fee_blocks_ahead: 1, fee_history_blocks: 20 }
+    }
+}
+
+/// One EIP-1559 pricing of an estimate's gas.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GasScenario {
+    pub base_fee: U256,
+    pub priority_fee: U256,
+    pub total_usd: f64,
+}
+
+impl GasScenario {
+    pub fn gas_price(&self) -> U256 {
+        self.base_fee.saturating_add(self.priority_fee)
+    }
+}
+
+/// Low: blocks empty until inclusion, low tip. Median: recent gas usage persists, the configured
+/// tip. High: blocks full, high tip.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GasScenarios {
+    pub low: GasScenario,
+    pub median: GasScenario,
+    pub high: GasScenario,
+}
+
+impl GasScenarios {
+    fn map(&self, f: impl Fn(&GasScenario) -> GasScenario) -> Self {
+        Self { low: f(&self.low), median: f(&self.median), high: f(&self.high) }
+    }
+}
+
 /// Result DTO for a single-chain gas estimate
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
 pub struct GasEstimate {
-    pub gas_limit: U256,
+    pub gas_limit: U256,    // execution gas, plus l1_gas_units on Arbitrum
     pub gas_price: U256,
-    pub l1_data_fee: U256,
+    pub l1_data_fee: U256,  // OP Stack: L1 data fee in wei, paid on top of gas
+    pub l1_gas_units: U256, // Arbitrum: L1 calldata cost as L2 gas, part of gas_limit
+    pub scenarios: Option<GasScenarios>, // L1 only; the estimate itself is the median
     pub total_wei: U256,
     pub total_eth: f64,
     pub total_usd: f64,
 }
 
+impl GasEstimate {
+    /// USD cost of `gas_units` at this estimate's gas price and ETH/USD rate, plus its L1 data fee
+    /// (or its L1 gas units).
+    pub fn cost_usd_for(&self, gas_units: u64) -> f64 {
+        if self.total_eth <= 0.0 {
+            return 0.0;
+        }
+        wei_to_eth_f64_fast(self.wei_for(gas_units)) * (self.total_usd / self.total_eth)
+    }
+
+    /// USD of one more unit of gas at this estimate's gas price; the L1 data fee doesn't grow with it.
+    pub fn usd_per_gas_unit(&self) -> f64 {
+        if self.total_eth <= 0.0 {
+            return 0.0;
+        }
+        wei_to_eth_f64_fast(self.gas_price) * (self.total_usd / self.total_eth)
+    }
+
+    fn wei_for(&self, gas_units: u64) -> U256 {
+        self.wei_at(self.gas_price, gas_units)
+    }
+
+    fn wei_at(&self, gas_price: U256, gas_units: u64) -> U256 {
+        let units = U256::from(gas_units).saturating_add(self.l1_gas_units);
+        gas_price.saturating_mul(units).saturating_add(self.l1_data_fee)
+    }
+
+    /// Gas the transaction executes, without Arbitrum's L1 component.
+    pub fn execution_gas(&self) -> U256 {
+        self.gas_limit.saturating_sub(self.l1_gas_units)
+    }
+
+    /// The same chain's estimate for one transaction of `gas_units`; 0 (a pool with no gas
+    /// units set) keeps the estimate as it is.
+    pub fn repriced_for(&self, gas_units: u64) -> GasEstimate {
+        if gas_units == 0 {
+            return self.clone();
+        }
+        let total_wei = self.wei_for(gas_units);
+        let total_eth = wei_to_eth_f64_fast(total_wei);
+        GasEstimate {
+            gas_limit: U256::from(gas_units).saturating_add(self.l1_gas_units),
+            gas_price: self.gas_price,
+            l1_data_fee: self.l1_data_fee,
+            l1_gas_units: self.l1_gas_units,
+            scenarios: self.scenarios.map(|s| s.map(|scenario| GasScenario {
+                total_usd: if self.total_eth > 0.0 {
+                    wei_to_eth_f64_fast(self.wei_at(scenario.gas_price(), gas_units)) * (self.total_usd / self.total_eth)
+                } else {
+                    0.0
+                },
+                ..*scenario
+            })),
+            total_wei,
+            total_eth,
+            total_usd: self.cost_usd_for(gas_units),
+        }
+    }
+
+    /// This swap and `other` (same chain) sent as one transaction: their gas adds up, less
+    /// the intrinsic gas counted in both, and the L1 data fee (or L1 gas) is paid once.
+    pub fn bundled_with(&self, other: &GasEstimate) -> GasEstimate {
+        let (mine, theirs) = (self.execution_gas(), other.execution_gas());
+        let units = mine.saturating_add(theirs);
+        let units = if mine >= U256::from(TX_INTRINSIC_GAS) && theirs >= U256::from(TX_INTRINSIC_GAS) {
+            units - U256::from(TX_INTRINSIC_GAS)
+        } else {
+            units
+        };
+        self.repriced_for(units.min(U256::from(u64::MAX)).as_u64())
+    }
+}
+
+/// USD gas of both legs of an arbitrage, each leg priced on its own chain's estimate.
+/// Legs on one chain go out as a single transaction; legs on two chains pay one each.
+pub fn legs_cost_usd(sell: &GasEstimate, buy: &GasEstimate, same_chain: bool) -> f64 {
+    if same_chain {
+        sell.bundled_with(buy).total_usd
+    } else {
+        sell.total_usd + buy.total_usd
+    }
+}
+
 /// Fast convert U256 wei -> f64 ETH (lossy, for reporting)
 #[inline]
 fn wei_to_eth_f64_fast(v: U256) -> f64 {
-    // Gas fees are well within u128 range.
-    (v.as_u128() as f64) / 1e18
+    TokenAmount::new(v, NATIVE_DECIMALS).to_f64()
 }
 
-/// Estimate gas cost on **Ethereum L1** using predefined gas limit.
-pub async fn estimate_eth_cost_usd(
-    provider: Arc<Provider<Http>>,
-    gas_units: u64,
-    eth_price_usd: f64,
-) -> Result<GasEstimate, Box<dyn std::error::Error + Send + Sync>> {
-    let gas_limit = U256::from(gas_units);
+/// Base fee of the block after one with `base_fee` and `gas_used_ratio` (EIP-1559: moves by up
+/// to 1/8 toward keeping blocks half full).
+pub fn next_base_fee(base_fee: U256, gas_used_ratio: f64) -> U256 {
+    const TARGET_PPM: u64 = 500_000;
+    let used_ppm = (gas_used_ratio.clamp(0.0, 1.0) * 1e6).round() as u64;
+    if used_ppm > TARGET_PPM {
+        let delta = base_fee.saturating_mul(U256::from(used_ppm - TARGET_PPM)) / TARGET_PPM / 8;
+        base_fee.saturating_add(delta.max(U256::one()))
+    } else {
+        base_fee - base_fee.saturating_mul(U256::from(TARGET_PPM - used_ppm)) / TARGET_PPM / 8
+    }
+}
 
-    // Single lightweight RPC
-    let gas_price = provider.get_gas_price().await?;
+/// `base_fee` after `blocks` blocks that each use `gas_used_ratio` of their gas limit.
+pub fn project_base_fee(base_fee: U256, gas_used_ratio: f64, blocks: u64) -> U256 {
+    (0..blocks).fold(base_fee, |fee, _| next_base_fee(fee, gas_used_ratio))
+}
+
+/// Reward percentiles to ask eth_feeHistory for: ascending and distinct, as nodes require.
+fn reward_percentiles(pricing: &Eip1559Pricing) -> Vec<f64> {
+    let mut percentiles = vec![
+        LOW_PRIORITY_FEE_PERCENTILE,
+        pricing.priority_fee_percentile.clamp(0.0, 100.0),
+        HIGH_PRIORITY_FEE_PERCENTILE,
+    ];
+    percentiles.sort_by(f64::total_cmp);
+    percentiles.dedup();
+    percentiles
+}
 
-    let total_wei = gas_price.checked_mul(gas_limit).unwrap_or_default();
+/// Low/median/high base fee and tip from a fee history queried at `percentiles`. Each tip is the
+/// median over the sampled blocks of that percentile's reward; USD totals are left at zero.
+fn eip1559_scenarios(history: &FeeHistory, pricing: &Eip1559Pricing, percentiles: &[f64]) -> Result<GasScenarios, String> {
+    // The entry past the newest block is the pending block's base fee
+    let pending = *history.base_fee_per_gas.last().ok_or("eth_feeHistory returned no base fees")?;
+    let ratios = &history.gas_used_ratio;
+    let recent_usage = if ratios.is_empty() { 0.5 } else { ratios.iter().sum::<f64>() / ratios.len() as f64 };
+
+    let tip = |percentile: f64| {
+        let Some(col) = percentiles.iter().position(|p| *p == percentile) else { return U256::zero() };
+        let mut tips: Vec<U256> = history.reward.iter().filter_map(|block| block.get(col).copied()).collect();
+        tips.sort();
+        tips.get(tips.len() / 2).copied().unwrap_or_default()
+    };
+    let scenario = |usage: f64, percentile: f64| GasScenario {
+        base_fee: project_base_fee(pending, usage, pricing.base_fee_blocks_ahead),
+        priority_fee: tip(percentile),
+        total_usd: 0.0,
+    };
+    Ok(GasScenarios {
+        low: scenario(0.0, LOW_PRIORITY_FEE_PERCENTILE),
+        median: scenario(recent_usage, pricing.priority_fee_percentile.clamp(0.0, 100.0)),
+        high: scenario(1.0, HIGH_PRIORITY_FEE_PERCENTILE),
+    })
+}
+
+/// An L1 estimate priced at the median scenario, every scenario's USD total filled in.
+fn l1_estimate(gas_units: u64, scenarios: GasScenarios, eth_price_usd: f64) -> GasEstimate {
+    let gas_limit = U256::from(gas_units);
+    let usd = |gas_price: U256| wei_to_eth_f64_fast(gas_price.saturating_mul(gas_limit)) * eth_price_usd;
+    let scenarios = scenarios.map(|s| GasScenario { total_usd: usd(s.gas_price()), ..*s });
+
+    let gas_price = scenarios.median.gas_price();
+    let total_wei = gas_price.saturating_mul(gas_limit);
     let total_eth = wei_to_eth_f64_fast(total_wei);
-    let total_usd = total_eth * eth_price_usd;
 
-    Ok(GasEstimate {
+    GasEstimate {
         gas_limit,
         gas_price,
         l1_data_fee: U256::zero(),
+        l1_gas_units: U256::zero(),
+        scenarios: Some(scenarios),
         total_wei,
         total_eth,
-        total_usd,
-    })
+        total_usd: total_eth * eth_price_usd,
+    }
+}
+
+/// Estimate gas cost on **Ethereum L1** using predefined gas limit: the pending block's base fee
+/// projected `pricing.base_fee_blocks_ahead` blocks out, plus a percentile of recent tips.
+pub async fn estimate_eth_cost_usd(
+    provider: Arc<Provider<Http>>,
+    gas_units: u64,
+    pricing: Eip1559Pricing,
+    eth_price_usd: f64,
+) -> Result<GasEstimate, Box<dyn std::error::Error + Send + Sync>> {
+    // Single RPC: base fees (pending one included), gas usage and tips of recent blocks
+    let percentiles = reward_percentiles(&pricing);
+    let history = provider
+        .fee_history(pricing.fee_history_blocks.max(1), BlockNumber::Latest, &percentiles)
+        .await?;
+    let scenarios = eip1559_scenarios(&history, &pricing, &percentiles)?;
+
+    Ok(l1_estimate(gas_units, scenarios, eth_price_usd))
 }
 
 /// Estimate gas cost on **Base (OP Stack L2)** using predefined gas limit.
@@ -101,12 +329,108 @@ pub async fn estimate_base_cost_usd(
         gas_limit,
         gas_price,
         l1_data_fee,
+        l1_gas_units: U256::zero(),
+        scenarios: None,
         total_wei,
         total_eth,
         total_usd,
     })
 }
 
+/// Estimate gas cost on **Arbitrum** using predefined gas limit. NodeInterface prices the
+/// calldata's L1 cost as extra L2 gas, which is charged at the L2 base fee with the rest.
+pub async fn estimate_arbitrum_cost_usd(
+    provider: Arc<Provider<Http>>,
+    gas_units: u64,
+    sample_calldata: &[u8],
+    eth_price_usd: f64,
+) -> Result<GasEstimate, Box<dyn std::error::Error + Send + Sync>> {
+    let node_addr: Address = ARB_NODE_INTERFACE.parse()
+        .map_err(|e| format!("Failed to parse NodeInterface address: {}", e))?;
+    let node = NodeInterface::new(node_addr, provider);
+
+    // One call returns both the L1 component and the L2 base fee it is priced at
+    let (l1_gas_units, base_fee, _l1_base_fee) = node
+        .gas_estimate_l1_component(Address::zero(), false, ethers::types::Bytes::from(sample_calldata.to_vec()))
+        .call()
+        .await?;
+
+    Ok(arbitrum_estimate(gas_units, l1_gas_units, base_fee, eth_price_usd))
+}
+
+fn arbitrum_estimate(gas_units: u64, l1_gas_units: u64, base_fee: U256, eth_price_usd: f64) -> GasEstimate {
+    let l1_gas_units = U256::from(l1_gas_units);
+    let gas_limit = U256::from(gas_units).saturating_add(l1_gas_units);
+    let total_wei = base_fee.saturating_mul(gas_limit);
+    let total_eth = wei_to_eth_f64_fast(total_wei);
+
+    GasEstimate {
+        gas_limit,
+        gas_price: base_fee,
+        l1_data_fee: U256::zero(),
+        l1_gas_units,
+        scenarios: None,
+        total_wei,
+        total_eth,
+        total_usd: total_eth * eth_price_usd,
+    }
+}
+
+/// Typical swap calldata, for the L1 cost of OP Stack and Arbitrum transactions.
+const SAMPLE_SWAP_CALLDATA: &[u8] = b"0x1234567890abcdef1234567890abcdef1234567890abcdef1234567890abcdef";
+
+/// How a chain charges for a transaction. The registry holds one per configured chain.
+pub trait GasModel: Debug + Send + Sync {
+    /// Cost of one transaction of `gas_units` execution gas, with ETH at `eth_price_usd`.
+    fn estimate(
+        &self,
+        provider: Arc<Provider<Http>>,
+        gas_units: u64,
+        eth_price_usd: f64,
+    ) -> BoxFuture<'_, Result<GasEstimate, BoxError>>;
+}
+
+/// Ethereum: EIP-1559 base fee plus priority fee, times gas.
+#[derive(Debug)]
+pub struct L1Gas {
+    pub pricing: Eip1559Pricing,
+}
+
+impl GasModel for L1Gas {
+    fn estimate(&self, provider: Arc<Provider<Http>>, gas_units: u64, eth_price_usd: f64) -> BoxFuture<'_, Result<GasEstimate, BoxError>> {
+        Box::pin(estimate_eth_cost_usd(provider, gas_units, self.pricing, eth_price_usd))
+    }
+}
+
+/// Base, Optimism, Unichain: L2 execution plus the GasPriceOracle L1 data fee.
+#[derive(Debug)]
+pub struct OpStackGas;
+
+impl GasModel for OpStackGas {
+    fn estimate(&self, provider: Arc<Provider<Http>>, gas_units: u64, eth_price_usd: f64) -> BoxFuture<'_, Result<GasEstimate, BoxError>> {
+        Box::pin(estimate_base_cost_usd(provider, gas_units, SAMPLE_SWAP_CALLDATA, eth_price_usd))
+    }
+}
+
+/// Arbitrum Nitro: the L1 calldata cost folded into gas units (NodeInterface).
+#[derive(Debug)]
+pub struct ArbitrumGas;
+
+impl GasModel for ArbitrumGas {
+    fn estimate(&self, provider: Arc<Provider<Http>>, gas_units: u64, eth_price_usd: f64) -> BoxFuture<'_, Result<GasEstimate, BoxError>> {
+        Box::pin(estimate_arbitrum_cost_usd(provider, gas_units, SAMPLE_SWAP_CALLDATA, eth_price_usd))
+    }
+}
+
+/// The gas model a chain's config names; L1 chains are priced with `pricing`.
+pub fn gas_model(kind: GasModelKind, pricing: Eip1559Pricing) -> Arc<dyn GasModel> {
+    match kind {
+        GasModelKind::L1 => Arc::new(L1Gas { pricing }),
+        GasModelKind::OpStack => Arc::new(OpStackGas),
+        GasModelKind::Arbitrum => Arc::new(ArbitrumGas),
+    }
+}
+
 /// Simplified gas estimation that returns both ETH and Base estimates
 pub async fn estimate_simple_gas_costs(
     eth_provider: Arc<Provider<Http>>,
@@ -115,12 +439,9 @@ pub async fn estimate_simple_gas_costs(
     gas_uniswap_units: u64,
     gas_aerodrome_units: u64,
 ) -> Result<(GasEstimate, GasEstimate), Box<dyn std::error::Error + Send + Sync>> {
-    // Sample calldata for L1 fee estimation (typical swap calldata size)
-    let sample_calldata = b"0x1234567890abcdef1234567890abcdef1234567890abcdef1234567890abcdef";
-
     let (eth_estimate, base_estimate) = tokio::try_join!(
-        estimate_eth_cost_usd(eth_provider, gas_uniswap_units, eth_price_usd),
-        estimate_base_cost_usd(base_provider, gas_aerodrome_units, sample_calldata, eth_price_usd),
+        estimate_eth_cost_usd(eth_provider, gas_uniswap_units, Eip1559Pricing::default(), eth_price_usd),
+        estimate_base_cost_usd(base_provider, gas_aerodrome_units, SAMPLE_SWAP_CALLDATA, eth_price_usd),
     )?;
 
     Ok((eth_estimate, base_estimate))
@@ -140,6 +461,8 @@ pub fn create_test_gas_estimate(gas_price_gwei: u64, gas_limit: u64, eth_price_u
         gas_limit: gas_limit_u256,
         gas_price,
         l1_data_fee: U256::zero(),
+        l1_gas_units: U256::zero(),
+        scenarios: None,
         total_wei,
         total_eth,
         total_usd,
@@ -203,6 +526,52 @@ mod tests {
     }
 
     #[test]
+    fn test_cost_usd_for_other_gas_units() {
+        // 200k gas at 25 gwei, ETH at $3500 -> $17.50; half the gas costs half
+        let mut estimate = create_test_gas_estimate(25, 200_000, 3500.0);
+        assert!((estimate.cost_usd_for(200_000) - estimate.total_usd).abs() < 1e-9);
+        assert!((estimate.cost_usd_for(100_000) - 8.75).abs() < 1e-9);
+
+        // The L1 data fee is paid once, whatever the execution gas
+        estimate.l1_data_fee = U256::from(1_000_000_000_000u64); // 1e-6 ETH
+        assert!((estimate.cost_usd_for(0) - 0.0035).abs() < 1e-9);
+
+        estimate.total_eth = 0.0;
+        assert_eq!(estimate.cost_usd_for(100_000), 0.0);
+    }
+
+    #[test]
+    fn test_usd_per_gas_unit() {
+        // 25 gwei at $3500: 100k more gas costs $8.75, with or without an L1 data fee
+        let mut estimate = create_test_gas_estimate(25, 200_000, 3500.0);
+        assert!((estimate.usd_per_gas_unit() * 100_000.0 - 8.75).abs() < 1e-9);
+        estimate.l1_data_fee = U256::from(1_000_000_000_000u64);
+        assert!((estimate.usd_per_gas_unit() * 100_000.0 - 8.75).abs() < 1e-9);
+        estimate.total_eth = 0.0;
+        assert_eq!(estimate.usd_per_gas_unit(), 0.0);
+    }
+
+    #[test]
+    fn test_same_chain_legs_share_one_transaction() {
+        // Two 150k swaps at 1 gwei, ETH at $4000: $0.60 each as separate transactions
+        let mut swap = create_test_gas_estimate(1, 150_000, 4000.0);
+        swap.l1_data_fee = U256::from(50_000_000_000_000u64); // 5e-5 ETH = $0.20
+        let swap = swap.repriced_for(150_000);
+        assert!((swap.total_usd - 0.8).abs() < 1e-9);
+        assert_eq!(swap.repriced_for(0).total_wei, swap.total_wei);
+
+        // One transaction: 279k gas and a single L1 data fee
+        let bundle = swap.bundled_with(&swap);
+        assert_eq!(bundle.gas_limit, U256::from(279_000u64));
+        assert_eq!(bundle.l1_data_fee, swap.l1_data_fee);
+        assert!((bundle.total_usd - (1.116 + 0.2)).abs() < 1e-9);
+
+        assert!((legs_cost_usd(&swap, &swap, true) - bundle.total_usd).abs() < 1e-12);
+        assert!((legs_cost_usd(&swap, &swap, false) - 1.6).abs() < 1e-9);
+    }
+
+    #[test]
+    #[allow(clippy::len_zero)]
     fn test_gas_estimate_debug_clone() {
         let estimate = create_test_gas_estimate(20, 150_000, 3000.0);
 
@@ -278,6 +647,8 @@ mod tests {
             gas_price: U256::from(1_000_000_000u64), // 1 gwei
             l1_data_fee: U256::from_dec_str("50000000000000")
                 .expect("Failed to parse L1 data fee"),
+            l1_gas_units: U256::zero(),
+            scenarios: None,
             total_wei: U256::from_dec_str("200000000000000")
                 .expect("Failed to parse total wei"),
             total_eth: 0.0002,
@@ -288,6 +659,98 @@ mod tests {
         assert!(base_estimate.total_wei > base_estimate.gas_price * base_estimate.gas_limit);
     }
 
+    #[test]
+    fn test_arbitrum_folds_l1_gas_into_units() {
+        // 150k execution + 50k L1 gas at a 0.01 gwei base fee, ETH at $4000: 2e-6 ETH = $0.008
+        let base_fee = U256::from(10_000_000u64);
+        let estimate = arbitrum_estimate(150_000, 50_000, base_fee, 4000.0);
+        assert_eq!(estimate.gas_limit, U256::from(200_000u64));
+        assert_eq!(estimate.execution_gas(), U256::from(150_000u64));
+        assert_eq!(estimate.l1_data_fee, U256::zero());
+        assert!((estimate.total_usd - 0.008).abs() < 1e-12);
+
+        // Repricing keeps the L1 component; bundling pays it once
+        let swap = estimate.repriced_for(100_000);
+        assert_eq!(swap.gas_limit, U256::from(150_000u64));
+        assert!((swap.total_usd - 0.006).abs() < 1e-12);
+        assert!((estimate.cost_usd_for(100_000) - 0.006).abs() < 1e-12);
+        let bundle = estimate.bundled_with(&estimate);
+        assert_eq!(bundle.gas_limit, U256::from(279_000u64 + 50_000));
+    }
+
+    const GWEI: u64 = 1_000_000_000;
+
+    #[test]
+    fn test_next_base_fee() {
+        let base = U256::from(10 * GWEI);
+        assert_eq!(next_base_fee(base, 0.5), base); // at target
+        assert_eq!(next_base_fee(base, 1.0), U256::from(11_250_000_000u64)); // +12.5%
+        assert_eq!(next_base_fee(base, 0.0), U256::from(8_750_000_000u64)); // -12.5%
+        assert_eq!(next_base_fee(base, 0.75), U256::from(10_625_000_000u64));
+        assert_eq!(next_base_fee(U256::from(7u64), 0.51), U256::from(8u64)); // rises by at least 1 wei
+        assert_eq!(project_base_fee(base, 1.0, 2), U256::from(12_656_250_000u64));
+        assert_eq!(project_base_fee(base, 0.0, 0), base);
+    }
+
+    fn fee_history(base_fees: &[u64], ratios: &[f64], rewards: &[[u64; 3]]) -> FeeHistory {
+        FeeHistory {
+            base_fee_per_gas: base_fees.iter().map(|f| U256::from(*f)).collect(),
+            gas_used_ratio: ratios.to_vec(),
+            oldest_block: U256::from(21_000_000u64),
+            reward: rewards.iter().map(|r| r.iter().map(|t| U256::from(*t)).collect()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_eip1559_scenarios() {
+        // Three blocks at 75% usage; the pending block's base fee is 10 gwei
+        let history = fee_history(
+            &[9 * GWEI, 9 * GWEI, 9 * GWEI, 10 * GWEI],
+            &[0.75, 0.75, 0.75],
+            &[[GWEI / 10, GWEI, 5 * GWEI], [GWEI / 5, 2 * GWEI, 4 * GWEI], [0, 3 * GWEI, 3 * GWEI]],
+        );
+        let pricing = Eip1559Pricing { priority_fee_percentile: 50.0, base_fee_blocks_ahead: 1, fee_history_blocks: 3 };
+        let percentiles = reward_percentiles(&pricing);
+        assert_eq!(percentiles, vec![10.0, 50.0, 90.0]);
+        let s = eip1559_scenarios(&history, &pricing, &percentiles).unwrap();
+
+        assert_eq!(s.low.base_fee, U256::from(8_750_000_000u64));
+        assert_eq!(s.median.base_fee, U256::from(10_625_000_000u64));
+        assert_eq!(s.high.base_fee, U256::from(11_250_000_000u64));
+        assert_eq!(s.low.priority_fee, U256::from(GWEI / 10)); // median across blocks
+        assert_eq!(s.median.priority_fee, U256::from(2 * GWEI));
+        assert_eq!(s.high.priority_fee, U256::from(4 * GWEI));
+
+        // The estimate is the median scenario; 200k gas at 12.625 gwei, ETH at $4000 = $10.10
+        let estimate = l1_estimate(200_000, s, 4000.0);
+        assert_eq!(estimate.gas_price, U256::from(12_625_000_000u64));
+        assert!((estimate.total_usd - 10.1).abs() < 1e-9);
+        let scenarios = estimate.scenarios.unwrap();
+        assert_eq!(scenarios.median.total_usd, estimate.total_usd);
+        assert!(scenarios.low.total_usd < estimate.total_usd && estimate.total_usd < scenarios.high.total_usd);
+
+        // Repricing for a leg's gas carries the scenarios along
+        let half = estimate.repriced_for(100_000).scenarios.unwrap();
+        assert!((half.high.total_usd - scenarios.high.total_usd / 2.0).abs() < 1e-9);
+
+        // A configured percentile outside the fixed pair gets its own column
+        let p90 = Eip1559Pricing { priority_fee_percentile: 90.0, ..pricing };
+        assert_eq!(reward_percentiles(&p90), vec![10.0, 90.0]);
+        let s = eip1559_scenarios(&history, &p90, &reward_percentiles(&p90)).unwrap();
+        assert_eq!(s.median.priority_fee, s.high.priority_fee);
+
+        assert!(eip1559_scenarios(&fee_history(&[], &[], &[]), &pricing, &percentiles).is_err());
+    }
+
+    #[test]
+    fn test_gas_model_per_kind() {
+        let pricing = Eip1559Pricing::default();
+        assert!(format!("{:?}", gas_model(GasModelKind::L1, pricing)).starts_with("L1Gas"));
+        assert_eq!(format!("{:?}", gas_model(GasModelKind::OpStack, pricing)), "OpStackGas");
+        assert_eq!(format!("{:?}", gas_model(GasModelKind::Arbitrum, pricing)), "ArbitrumGas");
+        assert!(ARB_NODE_INTERFACE.parse::<Address>().is_ok());
+    }
+
     #[test]
     fn test_gas_price_oracle_constant() {
         // Verify the constant is correctly formatted
@@ -344,6 +807,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::legacy_numeric_constants)]
     fn test_overflow_protection() {
         // Test that our functions handle potential overflows gracefully
         use std::u64::MAX;
@@ -1,6 +1,14 @@
 pub mod providers;
+pub mod registry;
+pub mod tokens;
+pub mod events;
+pub mod tick_scan;
 pub mod uniswap_v4_client;
+pub mod uniswap_v4_tracker;
+pub mod uniswap_v3_client;
 pub mod aerodrome_client;
+pub mod aerodrome_tracker;
+pub mod aerodrome_slipstream_client;
 pub mod gas;
 pub mod cex_client;
 pub mod across_fees;
\ No newline at end of file
@@ -1,13 +1,28 @@
 use ethers::prelude::*;
 use std::sync::Arc;
 
-pub fn create_ethereum_provider(rpc_url: &str) -> Result<Arc<Provider<Http>>, Box<dyn std::error::Error>> {
+/// HTTP provider of one chain; the registry holds one per configured chain.
+pub fn create_http_provider(rpc_url: &str) -> Result<Arc<Provider<Http>>, Box<dyn std::error::Error>> {
     let provider = Provider::<Http>::try_from(rpc_url)?;
     // Could add middleware for retries, timeouts, etc.
     Ok(Arc::new(provider))
 }
+/// Optional WebSocket provider, used only for subscriptions (newHeads, logs).
+pub async fn create_ws_provider(ws_url: &str) -> Result<Provider<Ws>, Box<dyn std::error::Error + Send + Sync>> {
+    let provider = Provider::<Ws>::connect(ws_url).await?;
+    Ok(provider)
+}
 
-pub fn create_base_provider(rpc_url: &str) -> Result<Arc<Provider<Http>>, Box<dyn std::error::Error>> {
-    let provider = Provider::<Http>::try_from(rpc_url)?;
-    Ok(Arc::new(provider))
-}
\ No newline at end of file
+/// The block a snapshot was read at.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BlockRef {
+    pub number: u64,
+    pub timestamp: u64, // unix seconds
+}
+
+/// Latest block; calls pinned to its number see one consistent chain state.
+pub async fn latest_block_ref(provider: &Provider<Http>) -> Result<BlockRef, Box<dyn std::error::Error + Send + Sync>> {
+    let block = provider.get_block(BlockNumber::Latest).await?.ok_or("latest block not available")?;
+    let number = block.number.ok_or("latest block has no number")?.as_u64();
+    Ok(BlockRef { number, timestamp: block.timestamp.as_u64() })
+}
@@ -0,0 +1,325 @@
+// src/chain/registry.rs
+//
+// Chains the service can price on, keyed by chain id. Each has built-in defaults (gas model,
+// WETH/USDC addresses Across bridges, Uniswap V4 StateView) that the environment can override,
+// and is connected when its RPC URL is set.
+// - Ethereum and Base are required; Arbitrum, Optimism and Unichain are opt-in
+// - Gas: each chain is estimated by the `GasModel` its kind names (chain::gas): L1 pricing on
+//   Ethereum, the GasPriceOracle L1 data fee on OP Stack chains, NodeInterface L1 gas on Arbitrum
+// - Aerodrome only exists on Base; the other chains host the Uniswap leg
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use ethers::providers::{Http, Provider};
+use ethers::types::Address;
+
+use crate::chain::gas::{gas_model, Eip1559Pricing, GasModel};
+use crate::chain::providers::create_http_provider;
+
+pub const CHAIN_ID_ETHEREUM: u64 = 1;
+pub const CHAIN_ID_OPTIMISM: u64 = 10;
+pub const CHAIN_ID_UNICHAIN: u64 = 130;
+pub const CHAIN_ID_BASE: u64 = 8453;
+pub const CHAIN_ID_ARBITRUM: u64 = 42161;
+
+/// How a chain charges for a transaction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GasModelKind {
+    /// Gas price times gas.
+    L1,
+    /// L2 execution plus the L1 data fee from the GasPriceOracle predeploy.
+    OpStack,
+    /// Arbitrum Nitro: L1 calldata cost charged as extra L2 gas (NodeInterface).
+    Arbitrum,
+}
+
+/// Built-in facts about a chain, and the env vars that override them.
+#[derive(Clone, Copy, Debug)]
+pub struct ChainSpec {
+    pub chain_id: u64,
+    pub name: &'static str, // as written in the watchlist
+    pub gas_model: GasModelKind,
+    pub weth: &'static str,
+    pub usdc: &'static str,
+    pub uniswap_v4_state_view: &'static str,
+    pub rpc_url_env: &'static str,
+    pub token_env_prefix: &'static str, // <PREFIX>_WETH_ADDRESS, <PREFIX>_USDC_ADDRESS
+    pub state_view_env: &'static str,
+    pub required: bool,
+}
+
+pub const KNOWN_CHAINS: [ChainSpec; 5] = [
+    ChainSpec {
+        chain_id: CHAIN_ID_ETHEREUM,
+        name: "ethereum",
+        gas_model: GasModelKind::L1,
+        weth: "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2",
+        usdc: "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48",
+        uniswap_v4_state_view: "0x7fFE42C4a5DEeA5b0feC41C94C136Cf115597227",
+        rpc_url_env: "ETHEREUM_RPC_URL",
+        token_env_prefix: "ETH",
+        state_view_env: "UNISWAP_V4_STATE_VIEW",
+        required: true,
+    },
+    ChainSpec {
+        chain_id: CHAIN_ID_BASE,
+        name: "base",
+        gas_model: GasModelKind::OpStack,
+        weth: "0x4200000000000000000000000000000000000006",
+        usdc: "0x833589fCD6eDb6E08f4c7C32D4f71b54bDA02913",
+        uniswap_v4_state_view: "0xA3c0c9b65baD0b08107Aa264b0F3dB444b867A71",
+        rpc_url_env: "BASE_RPC_URL",
+        token_env_prefix: "BASE",
+        state_view_env: "UNISWAP_V4_STATE_VIEW_BASE",
+        required: true,
+    },
+    ChainSpec {
+        chain_id: CHAIN_ID_ARBITRUM,
+        name: "arbitrum",
+        gas_model: GasModelKind::Arbitrum,
+        weth: "0x82aF49447D8a07e3bd95BD0d56f35241523fBab1",
+        usdc: "0xaf88d065e77c8cC2239327C5EDb3A432268e5831",
+        uniswap_v4_state_view: "0x76Fd297e2D437cd7f76d50F01AfE6160f86e9990",
+        rpc_url_env: "ARBITRUM_RPC_URL",
+        token_env_prefix: "ARBITRUM",
+        state_view_env: "UNISWAP_V4_STATE_VIEW_ARBITRUM",
+        required: false,
+    },
+    ChainSpec {
+        chain_id: CHAIN_ID_OPTIMISM,
+        name: "optimism",
+        gas_model: GasModelKind::OpStack,
+        weth: "0x4200000000000000000000000000000000000006",
+        usdc: "0x0b2C639c533813f4Aa9D7837CAf62653d097Ff85",
+        uniswap_v4_state_view: "0xc18a3169788F4F75A170290584ECA6395C75Ecdb",
+        rpc_url_env: "OPTIMISM_RPC_URL",
+        token_env_prefix: "OPTIMISM",
+        state_view_env: "UNISWAP_V4_STATE_VIEW_OPTIMISM",
+        required: false,
+    },
+    ChainSpec {
+        chain_id: CHAIN_ID_UNICHAIN,
+        name: "unichain",
+        gas_model: GasModelKind::OpStack,
+        weth: "0x4200000000000000000000000000000000000006",
+        usdc: "0x078D782b760474a361dDA0AF3839290b0EF57AD6",
+        uniswap_v4_state_view: "0x86e8631A016F9068C3f085fAF484Ee3F5fDee8f2",
+        rpc_url_env: "UNICHAIN_RPC_URL",
+        token_env_prefix: "UNICHAIN",
+        state_view_env: "UNISWAP_V4_STATE_VIEW_UNICHAIN",
+        required: false,
+    },
+];
+
+/// The built-in spec of `chain_id`, if it is one we know.
+pub fn chain_spec(chain_id: u64) -> Option<&'static ChainSpec> {
+    KNOWN_CHAINS.iter().find(|c| c.chain_id == chain_id)
+}
+
+/// Asset Across moves when a cross-chain arbitrage is rebalanced.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BridgeAsset {
+    Weth,
+    Usdc,
+}
+
+/// A chain as configured: its spec with env overrides applied.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChainConfig {
+    pub chain_id: u64,
+    pub name: &'static str,
+    pub rpc_url: String,
+    pub gas_model: GasModelKind,
+    pub weth: Address,
+    pub usdc: Address,
+    pub uniswap_v4_state_view: Address,
+}
+
+impl ChainConfig {
+    /// `spec` with overrides from `var` (an env lookup). None when the chain's RPC URL is unset,
+    /// which is an error for a required chain.
+    pub fn from_env(spec: &ChainSpec, var: impl Fn(&str) -> Option<String>) -> Result<Option<Self>, String> {
+        let Some(rpc_url) = var(spec.rpc_url_env).filter(|u| !u.is_empty()) else {
+            return if spec.required { Err(format!("{} must be set", spec.rpc_url_env)) } else { Ok(None) };
+        };
+        let address = |key: String, default: &str| {
+            let value = var(&key).unwrap_or_else(|| default.to_string());
+            Address::from_str(&value).map_err(|e| format!("bad {} '{}': {}", key, value, e))
+        };
+        Ok(Some(ChainConfig {
+            chain_id: spec.chain_id,
+            name: spec.name,
+            rpc_url,
+            gas_model: spec.gas_model,
+            weth: address(format!("{}_WETH_ADDRESS", spec.token_env_prefix), spec.weth)?,
+            usdc: address(format!("{}_USDC_ADDRESS", spec.token_env_prefix), spec.usdc)?,
+            uniswap_v4_state_view: address(spec.state_view_env.to_string(), spec.uniswap_v4_state_view)?,
+        }))
+    }
+
+    /// Every known chain with an RPC URL in the environment.
+    pub fn all_from_env() -> Result<Vec<Self>, String> {
+        let mut chains = Vec::new();
+        for spec in &KNOWN_CHAINS {
+            if let Some(chain) = Self::from_env(spec, |key| std::env::var(key).ok())? {
+                chains.push(chain);
+            }
+        }
+        Ok(chains)
+    }
+
+    /// This chain's address of `asset`.
+    pub fn bridge_token(&self, asset: BridgeAsset) -> Address {
+        match asset {
+            BridgeAsset::Weth => self.weth,
+            BridgeAsset::Usdc => self.usdc,
+        }
+    }
+
+    /// The asset `token` is on this chain; native ETH (address(0)) counts as WETH.
+    pub fn bridge_asset(&self, token: Address) -> Option<BridgeAsset> {
+        if token.is_zero() || token == self.weth {
+            Some(BridgeAsset::Weth)
+        } else if token == self.usdc {
+            Some(BridgeAsset::Usdc)
+        } else {
+            None
+        }
+    }
+
+    /// The address Across is asked to bridge for `token`: native ETH goes as the chain's WETH.
+    pub fn bridge_address(&self, token: Address) -> Address {
+        if token.is_zero() { self.weth } else { token }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct ChainEntry {
+    config: ChainConfig,
+    provider: Arc<Provider<Http>>,
+    gas: Arc<dyn GasModel>,
+}
+
+/// Configured chains, each with its HTTP provider and gas model.
+#[derive(Clone, Debug, Default)]
+pub struct ChainRegistry {
+    chains: HashMap<u64, ChainEntry>,
+}
+
+impl ChainRegistry {
+    pub fn connect(configs: &[ChainConfig], pricing: Eip1559Pricing) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut chains = HashMap::new();
+        for config in configs {
+            let provider = create_http_provider(&config.rpc_url)?;
+            let gas = gas_model(config.gas_model, pricing);
+            chains.insert(config.chain_id, ChainEntry { config: config.clone(), provider, gas });
+        }
+        Ok(ChainRegistry { chains })
+    }
+
+    fn entry(&self, chain_id: u64) -> Result<&ChainEntry, String> {
+        self.chains.get(&chain_id).ok_or_else(|| not_configured(chain_id))
+    }
+
+    pub fn config(&self, chain_id: u64) -> Result<&ChainConfig, String> {
+        Ok(&self.entry(chain_id)?.config)
+    }
+
+    pub fn provider(&self, chain_id: u64) -> Result<Arc<Provider<Http>>, String> {
+        Ok(self.entry(chain_id)?.provider.clone())
+    }
+
+    /// How `chain_id` prices gas.
+    pub fn gas_model(&self, chain_id: u64) -> Result<Arc<dyn GasModel>, String> {
+        Ok(self.entry(chain_id)?.gas.clone())
+    }
+
+    /// Configured chains, by chain id.
+    pub fn configs(&self) -> Vec<&ChainConfig> {
+        let mut configs: Vec<_> = self.chains.values().map(|entry| &entry.config).collect();
+        configs.sort_by_key(|c| c.chain_id);
+        configs
+    }
+}
+
+fn not_configured(chain_id: u64) -> String {
+    match chain_spec(chain_id) {
+        Some(spec) => format!("chain {} is not configured; set {}", spec.name, spec.rpc_url_env),
+        None => format!("chain {} is not supported", chain_id),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(pairs: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let map: HashMap<String, String> = pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        move |key| map.get(key).cloned()
+    }
+
+    #[test]
+    fn test_known_chains() {
+        let ids: Vec<u64> = KNOWN_CHAINS.iter().map(|c| c.chain_id).collect();
+        assert_eq!(ids, vec![1, 8453, 42161, 10, 130]);
+        assert_eq!(chain_spec(CHAIN_ID_ARBITRUM).map(|c| c.gas_model), Some(GasModelKind::Arbitrum));
+        assert_eq!(chain_spec(CHAIN_ID_UNICHAIN).map(|c| c.gas_model), Some(GasModelKind::OpStack));
+        assert!(chain_spec(137).is_none());
+        for spec in &KNOWN_CHAINS {
+            let chain = ChainConfig::from_env(spec, env(&[(spec.rpc_url_env, "http://localhost:8545")]))
+                .expect("built-in addresses parse")
+                .expect("RPC URL set");
+            assert!(!chain.weth.is_zero() && !chain.usdc.is_zero() && !chain.uniswap_v4_state_view.is_zero());
+        }
+    }
+
+    #[test]
+    fn test_chain_config_from_env() {
+        let eth = chain_spec(CHAIN_ID_ETHEREUM).unwrap();
+        assert!(ChainConfig::from_env(eth, env(&[])).is_err()); // required
+
+        let arb = chain_spec(CHAIN_ID_ARBITRUM).unwrap();
+        assert_eq!(ChainConfig::from_env(arb, env(&[])), Ok(None)); // opt-in
+        assert_eq!(ChainConfig::from_env(arb, env(&[("ARBITRUM_RPC_URL", "")])), Ok(None));
+
+        let usdc = "0x00000000000000000000000000000000000000aa";
+        let chain = ChainConfig::from_env(arb, env(&[("ARBITRUM_RPC_URL", "http://arb"), ("ARBITRUM_USDC_ADDRESS", usdc)]))
+            .unwrap()
+            .unwrap();
+        assert_eq!(chain.usdc, Address::from_str(usdc).unwrap());
+        assert_eq!(chain.bridge_token(BridgeAsset::Usdc), chain.usdc);
+        assert_eq!(chain.bridge_token(BridgeAsset::Weth), Address::from_str(arb.weth).unwrap());
+        assert_eq!(chain.bridge_asset(Address::zero()), Some(BridgeAsset::Weth));
+        assert_eq!(chain.bridge_asset(chain.weth), Some(BridgeAsset::Weth));
+        assert_eq!(chain.bridge_asset(chain.usdc), Some(BridgeAsset::Usdc));
+        assert_eq!(chain.bridge_asset(Address::from([0x11; 20])), None);
+        assert_eq!(chain.bridge_address(Address::zero()), chain.weth);
+        assert_eq!(chain.bridge_address(chain.usdc), chain.usdc);
+
+        // Ethereum keeps its historical env names
+        let bad = env(&[("ETHEREUM_RPC_URL", "http://eth"), ("ETH_WETH_ADDRESS", "not-an-address")]);
+        assert!(ChainConfig::from_env(eth, bad).unwrap_err().contains("ETH_WETH_ADDRESS"));
+    }
+
+    #[test]
+    fn test_registry_lookups() {
+        let chains: Vec<ChainConfig> = [CHAIN_ID_ETHEREUM, CHAIN_ID_BASE, CHAIN_ID_OPTIMISM]
+            .iter()
+            .map(|id| {
+                let spec = chain_spec(*id).unwrap();
+                ChainConfig::from_env(spec, env(&[(spec.rpc_url_env, "http://localhost:8545")])).unwrap().unwrap()
+            })
+            .collect();
+        let registry = ChainRegistry::connect(&chains, Eip1559Pricing::default()).unwrap();
+        let ids: Vec<u64> = registry.configs().iter().map(|c| c.chain_id).collect();
+        assert_eq!(ids, vec![1, 10, 8453]);
+        assert_eq!(registry.config(CHAIN_ID_OPTIMISM).unwrap().name, "optimism");
+        assert!(registry.provider(CHAIN_ID_BASE).is_ok());
+        assert_eq!(format!("{:?}", registry.gas_model(CHAIN_ID_OPTIMISM).unwrap()), "OpStackGas");
+        assert!(format!("{:?}", registry.gas_model(CHAIN_ID_ETHEREUM).unwrap()).starts_with("L1Gas"));
+        assert!(registry.provider(CHAIN_ID_ARBITRUM).unwrap_err().contains("ARBITRUM_RPC_URL"));
+        assert!(registry.config(137).unwrap_err().contains("not supported"));
+    }
+}
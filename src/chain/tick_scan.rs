@@ -0,0 +1,224 @@
+// src/chain/tick_scan.rs
+//
+// Tick-bitmap scanning shared by the concentrated-liquidity clients (Uniswap V4 StateView,
+// Aerodrome Slipstream). Both lay out initialized ticks the V3 way: one 256-bit word per
+// 256 tick spacings, so only the call that reads a word or a tick differs per contract.
+// - All bitmap words around the current tick in ONE multicall
+// - Tick infos in chunked multicalls, a bounded number in flight
+// - Every read pinned to the caller's block
+// - No initialized ticks in range falls back to synthetic liquidity, which the pool is flagged
+//   with; loaders that know their largest trade widen the scan first (see TickScanConfig::widen)
+
+use ethers::abi::Tokenizable;
+use ethers::contract::{ContractCall, Multicall};
+use ethers::prelude::*;
+use futures::{stream, StreamExt, TryStreamExt};
+use num_bigint::BigInt;
+use std::sync::Arc;
+
+/// How wide and how hard to scan the tick bitmap around the current tick.
+#[derive(Clone, Copy, Debug)]
+pub struct TickScanConfig {
+    pub word_range: i16,             // ±N bitmap words around current
+    pub max_word_range: i16,         // widest adaptive scan (word_range doubles up to this)
+    pub tickinfo_chunk_size: usize,  // ticks per tick-info multicall (try 8192 if your RPC allows)
+    pub parallel_chunks: usize,      // multicalls in flight at once
+}
+
+impl Default for TickScanConfig {
+    fn default() -> Self {
+        Self { word_range: 24, max_word_range: 192, tickinfo_chunk_size: 4096, parallel_chunks: 6 }
+    }
+}
+
+impl TickScanConfig {
+    /// The next, doubled, word range after `word_range`; None once `max_word_range` is reached.
+    pub fn widen(&self, word_range: i16) -> Option<i16> {
+        (word_range < self.max_word_range).then(|| word_range.saturating_mul(2).min(self.max_word_range))
+    }
+}
+
+/// Bitmap word the scan is centred on (truncating division, as the scan has always used).
+pub(crate) fn scan_center_word(current_tick: i32, tick_spacing: i32) -> i32 {
+    current_tick / tick_spacing / 256
+}
+
+/// Word positions scanned: ±`word_range` around the centre word.
+fn scan_word_positions(current_tick: i32, tick_spacing: i32, word_range: i16) -> Vec<i16> {
+    let current_word = scan_center_word(current_tick, tick_spacing);
+    (-word_range..=word_range)
+        .map(|off| (current_word as i16).saturating_add(off))
+        .collect()
+}
+
+/// Inclusive tick range covered by scanning ±`word_range` bitmap words around `current_tick`.
+pub fn scanned_tick_range(current_tick: i32, tick_spacing: i32, word_range: i16) -> (i32, i32) {
+    let center = scan_center_word(current_tick, tick_spacing);
+    let lo_word = (center as i16).saturating_sub(word_range) as i32;
+    let hi_word = (center as i16).saturating_add(word_range) as i32;
+    (lo_word * 256 * tick_spacing, (hi_word * 256 + 255) * tick_spacing)
+}
+
+/// Set bits of each bitmap word -> sorted, de-duplicated ticks.
+fn initialized_ticks(word_positions: &[i16], bitmaps: &[U256], tick_spacing: i32) -> Vec<i32> {
+    let mut ticks = Vec::new();
+    for (wp, word) in word_positions.iter().zip(bitmaps) {
+        if word.is_zero() {
+            continue;
+        }
+        let word_pos = *wp as i32;
+        for bit in 0..256usize {
+            if word.bit(bit) {
+                ticks.push(word_pos * 256 * tick_spacing + (bit as i32) * tick_spacing);
+            }
+        }
+    }
+    ticks.sort_unstable();
+    ticks.dedup();
+    ticks
+}
+
+/// Fallback when no initialized ticks were found: synthetic wide ranges to keep downstream pricing working.
+pub(crate) fn synthetic_tick_data(current_tick: i32) -> Vec<(i32, BigInt)> {
+    let mut tick_data = Vec::new();
+    let wide_range = 12_000;
+    let synthetic_liq = BigInt::from(1_000_000_000_000_000_000_000_000u128);
+    for i in 0..10 {
+        let lower = current_tick - wide_range + (i * wide_range / 5);
+        let upper = current_tick + wide_range - (i * wide_range / 5);
+        let per = &synthetic_liq / BigInt::from(10u8);
+        tick_data.push((lower, per.clone()));
+        tick_data.push((upper, -per));
+    }
+    tick_data
+}
+
+/// Initialized ticks in the scanned words, all bitmaps in ONE multicall (homogeneous => call_array()).
+/// `bitmap(word_pos)` builds the contract's bitmap read.
+pub(crate) async fn fetch_initialized_ticks<M, F>(
+    client: Arc<M>,
+    multicall_addr: Option<Address>,
+    block: u64,
+    current_tick: i32,
+    tick_spacing: i32,
+    word_range: i16,
+    bitmap: F,
+) -> Result<Vec<i32>, Box<dyn std::error::Error + Send + Sync>>
+where
+    M: Middleware + 'static,
+    F: Fn(i16) -> ContractCall<M, U256>,
+{
+    let word_positions = scan_word_positions(current_tick, tick_spacing, word_range);
+    let mut mc = Multicall::new(client, multicall_addr).await?.block(block);
+    for wp in &word_positions {
+        mc.add_call(bitmap(*wp), false);
+    }
+    let bitmaps: Vec<U256> = mc.call_array().await?;
+    Ok(initialized_ticks(&word_positions, &bitmaps, tick_spacing))
+}
+
+/// Net liquidity of `ticks` via Multicall, **chunked + bounded parallel**; ticks with zero
+/// gross liquidity are dropped. `tick_info(tick)` builds the read, `gross_net` picks
+/// (liquidityGross, liquidityNet) out of its row.
+pub(crate) async fn fetch_tick_liquidity<M, D, F>(
+    client: Arc<M>,
+    multicall_addr: Option<Address>,
+    block: u64,
+    ticks: &[i32],
+    scan: &TickScanConfig,
+    tick_info: F,
+    gross_net: fn(D) -> (u128, i128),
+) -> Result<Vec<(i32, BigInt)>, Box<dyn std::error::Error + Send + Sync>>
+where
+    M: Middleware + 'static,
+    D: Tokenizable + Send + 'static,
+    F: Fn(i32) -> ContractCall<M, D>,
+{
+    let chunks = ticks
+        .chunks(scan.tickinfo_chunk_size)
+        .map(|c| (c.to_vec(), c.iter().map(|t| tick_info(*t)).collect::<Vec<_>>()))
+        .collect::<Vec<_>>();
+
+    // Stream all chunks, but only `parallel_chunks` in flight at once.
+    let results: Vec<(Vec<i32>, Vec<D>)> = stream::iter(chunks.into_iter().map(|(ticks_chunk, calls)| {
+        let client = client.clone();
+        async move {
+            let mut mc = Multicall::new(client, multicall_addr).await?.block(block);
+            for call in calls {
+                mc.add_call(call, false);
+            }
+            let infos: Vec<D> = mc.call_array().await?;
+            Ok::<(Vec<i32>, Vec<D>), Box<dyn std::error::Error + Send + Sync>>((ticks_chunk, infos))
+        }
+    }))
+    .buffer_unordered(scan.parallel_chunks)
+    .try_collect()
+    .await?;
+
+    // Collect net liquidity deltas for non-zero gross ticks
+    let mut tick_data: Vec<(i32, BigInt)> = Vec::new();
+    for (ticks_chunk, infos_chunk) in results {
+        for (tick, info) in ticks_chunk.into_iter().zip(infos_chunk) {
+            let (liquidity_gross, liquidity_net) = gross_net(info);
+            if liquidity_gross > 0 {
+                tick_data.push((tick, BigInt::from(liquidity_net))); // i128 -> BigInt
+            }
+        }
+    }
+    Ok(tick_data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scanned_tick_range() {
+        // Word 0 ±24, spacing 60: every tick the bitmap scan can return lies inside
+        let (lo, hi) = scanned_tick_range(0, 60, 24);
+        assert_eq!(lo, -24 * 256 * 60);
+        assert_eq!(hi, (24 * 256 + 255) * 60);
+
+        let (lo, hi) = scanned_tick_range(-191_740, 10, 2);
+        let center = -191_740 / 10 / 256;
+        assert_eq!(lo, (center - 2) * 256 * 10);
+        assert_eq!(hi, ((center + 2) * 256 + 255) * 10);
+        assert!(lo <= -191_740 && -191_740 <= hi);
+    }
+
+    #[test]
+    fn test_widen_doubles_up_to_the_max() {
+        let scan = TickScanConfig::default();
+        let mut ranges = vec![scan.word_range];
+        while let Some(next) = scan.widen(*ranges.last().unwrap()) {
+            ranges.push(next);
+        }
+        assert_eq!(ranges, vec![24, 48, 96, 192]);
+
+        let odd = TickScanConfig { word_range: 5, max_word_range: 12, ..scan };
+        assert_eq!(odd.widen(5), Some(10));
+        assert_eq!(odd.widen(10), Some(12));
+        assert_eq!(odd.widen(12), None);
+        assert_eq!(TickScanConfig { max_word_range: i16::MAX, ..scan }.widen(20_000), Some(i16::MAX));
+    }
+
+    #[test]
+    fn test_initialized_ticks_from_bitmaps() {
+        let positions = scan_word_positions(-191_740, 60, 1);
+        assert_eq!(positions.len(), 3);
+
+        let mut words = vec![U256::zero(); 3];
+        words[0] = U256::one() | (U256::one() << 255); // first and last tick of the lower word
+        words[2] = U256::one() << 7;
+        let ticks = initialized_ticks(&positions, &words, 60);
+
+        let (lo_word, hi_word) = (positions[0] as i32, positions[2] as i32);
+        assert_eq!(ticks, vec![lo_word * 256 * 60, (lo_word * 256 + 255) * 60, (hi_word * 256 + 7) * 60]);
+        let (lo, hi) = scanned_tick_range(-191_740, 60, 1);
+        assert!(ticks.iter().all(|t| (lo..=hi).contains(t) && t % 60 == 0));
+
+        // Saturated word positions repeat; their ticks must not
+        let edge = vec![i16::MAX, i16::MAX];
+        assert_eq!(initialized_ticks(&edge, &[U256::one(), U256::one()], 1).len(), 1);
+    }
+}
@@ -0,0 +1,176 @@
+// src/chain/tokens.rs
+//
+// Token metadata for the pair being priced on each chain.
+// - Prices are "quote per base" (USDC per ETH for the default pair)
+// - Decimals are read from the token contracts, never assumed
+// - Native ETH (V4 currency address(0)) has no contract and is fixed at 18 decimals
+
+use ethers::prelude::*;
+use std::sync::Arc;
+
+use crate::math::amount::TokenAmount;
+
+abigen!(
+    Erc20Metadata,
+    r#"[
+        function decimals() view returns (uint8)
+    ]"#
+);
+
+/// Decimals of the native gas token on every EVM chain we support.
+pub const NATIVE_DECIMALS: u8 = 18;
+
+/// A token on one chain: address plus on-chain decimals.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TokenInfo {
+    pub address: Address,
+    pub decimals: u8,
+}
+
+impl TokenInfo {
+    pub const fn new(address: Address, decimals: u8) -> Self {
+        Self { address, decimals }
+    }
+
+    /// Native ETH as Uniswap V4 represents it (currency = address(0)).
+    pub fn native() -> Self {
+        Self::new(Address::zero(), NATIVE_DECIMALS)
+    }
+
+    pub fn is_native(&self) -> bool {
+        self.address.is_zero()
+    }
+
+    /// Raw smallest-unit amount of this token.
+    #[inline]
+    pub fn amount(&self, raw: U256) -> TokenAmount {
+        TokenAmount::new(raw, self.decimals)
+    }
+}
+
+/// The base/quote pair priced on a single chain.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TokenPair {
+    pub chain_id: u64,
+    pub base: TokenInfo,
+    pub quote: TokenInfo,
+}
+
+impl TokenPair {
+    pub const fn new(chain_id: u64, base: TokenInfo, quote: TokenInfo) -> Self {
+        Self { chain_id, base, quote }
+    }
+
+    /// Pools sort their tokens by address; true if the base token is token0.
+    pub fn base_is_token0(&self) -> bool {
+        self.base.address < self.quote.address
+    }
+
+    /// (token0, token1) as a pool would order them.
+    pub fn sorted_addresses(&self) -> (Address, Address) {
+        if self.base_is_token0() {
+            (self.base.address, self.quote.address)
+        } else {
+            (self.quote.address, self.base.address)
+        }
+    }
+
+    /// (decimals0, decimals1) for a pool whose token0 is/isn't the base token.
+    pub fn decimals_for(&self, token0_is_base: bool) -> (u8, u8) {
+        if token0_is_base {
+            (self.base.decimals, self.quote.decimals)
+        } else {
+            (self.quote.decimals, self.base.decimals)
+        }
+    }
+}
+
+/// Read `decimals()` for a token; native ETH short-circuits without an RPC.
+pub async fn load_token_info<M: Middleware + 'static>(
+    provider: Arc<M>,
+    address: Address,
+) -> Result<TokenInfo, Box<dyn std::error::Error + Send + Sync>> {
+    if address.is_zero() {
+        return Ok(TokenInfo::native());
+    }
+    let decimals = Erc20Metadata::new(address, provider)
+        .decimals()
+        .call()
+        .await
+        .map_err(|e| format!("decimals() failed for token {:?}: {}", address, e))?;
+    Ok(TokenInfo::new(address, decimals))
+}
+
+/// Load both sides of a pair in parallel.
+pub async fn load_token_pair<M: Middleware + 'static>(
+    provider: Arc<M>,
+    chain_id: u64,
+    base: Address,
+    quote: Address,
+) -> Result<TokenPair, Box<dyn std::error::Error + Send + Sync>> {
+    if base == quote {
+        return Err(format!("base and quote token are the same ({:?})", base).into());
+    }
+    let (base, quote) = tokio::try_join!(
+        load_token_info(provider.clone(), base),
+        load_token_info(provider, quote),
+    )?;
+    Ok(TokenPair::new(chain_id, base, quote))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usdc() -> TokenInfo {
+        TokenInfo::new(Address::from([0x11; 20]), 6)
+    }
+
+    #[test]
+    fn test_native_token() {
+        let eth = TokenInfo::native();
+        assert!(eth.is_native());
+        assert_eq!(eth.decimals, 18);
+        assert!(!usdc().is_native());
+    }
+
+    #[test]
+    fn test_amount_uses_decimals() {
+        assert_eq!(usdc().amount(U256::from(1_500_000u64)).to_f64(), 1.5);
+        assert_eq!(TokenInfo::native().amount(U256::from(2u8) * U256::exp10(18)).to_f64(), 2.0);
+        let btc = TokenInfo::new(Address::from([0x22; 20]), 8);
+        assert_eq!(btc.amount(U256::exp10(8)).to_string(), "1");
+    }
+
+    #[test]
+    fn test_pair_ordering_base_first() {
+        let pair = TokenPair::new(1, TokenInfo::native(), usdc());
+        assert!(pair.base_is_token0());
+        assert_eq!(pair.sorted_addresses(), (Address::zero(), usdc().address));
+        assert_eq!(pair.decimals_for(true), (18, 6));
+    }
+
+    #[test]
+    fn test_pair_ordering_quote_first() {
+        let base = TokenInfo::new(Address::from([0xAA; 20]), 8);
+        let pair = TokenPair::new(8453, base, usdc());
+        assert!(!pair.base_is_token0());
+        assert_eq!(pair.sorted_addresses(), (usdc().address, base.address));
+        assert_eq!(pair.decimals_for(false), (6, 8));
+    }
+
+    #[tokio::test]
+    async fn test_load_token_info_native_needs_no_rpc() {
+        // Unroutable endpoint: the native short-circuit must not touch it.
+        let provider = Arc::new(Provider::<Http>::try_from("http://127.0.0.1:1").unwrap());
+        let info = load_token_info(provider, Address::zero()).await.unwrap();
+        assert_eq!(info, TokenInfo::native());
+    }
+
+    #[tokio::test]
+    async fn test_load_token_pair_rejects_identical_tokens() {
+        let provider = Arc::new(Provider::<Http>::try_from("http://127.0.0.1:1").unwrap());
+        let addr = usdc().address;
+        assert!(load_token_pair(provider, 1, addr, addr).await.is_err());
+    }
+}
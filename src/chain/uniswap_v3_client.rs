@@ -0,0 +1,209 @@
+// src/chain/uniswap_v3_client.rs
+//
+// Uniswap V3 pools on Ethereum or Base, loaded into the same `PoolState` the V4 simulator runs on.
+// V3 keeps slot0, liquidity, tickBitmap and ticks on each pool contract; the tick math is the
+// one V4 inherited, so only the reads differ.
+// - Pools are keyed by (token0, token1, fee) in the per-chain factory
+// - Tokens are ERC-20s: V3 has no native ETH, so ETH pairs use WETH
+// - The protocol fee is taken out of the LP fee, so swappers pay `fee()` either way
+// - Reads are pinned to one block, recorded on the state
+
+use ethers::contract::{abigen, Multicall};
+use ethers::prelude::*;
+use ethers::providers::{Http, Provider};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::chain::registry::{CHAIN_ID_BASE, CHAIN_ID_ETHEREUM};
+use crate::chain::providers::latest_block_ref;
+use crate::chain::tick_scan::{
+    fetch_initialized_ticks, fetch_tick_liquidity, scanned_tick_range, synthetic_tick_data, TickScanConfig,
+};
+use crate::chain::tokens::TokenPair;
+use crate::chain::uniswap_v4_client::u256_to_bigint;
+use crate::math::uniswap_v4::{create_pool_with_real_data, PoolState};
+
+/// UniswapV3Factory on Ethereum mainnet.
+pub const UNISWAP_V3_FACTORY_ETHEREUM: &str = "0x1F98431c8aD98523631AE4a59f267346ea31F984";
+/// UniswapV3Factory on Base.
+pub const UNISWAP_V3_FACTORY_BASE: &str = "0x33128a8fC17869897dcE68Ed026d694621f6FDfD";
+
+abigen!(
+    UniswapV3Pool,
+    r#"[
+        function token0() view returns (address)
+        function token1() view returns (address)
+        function fee() view returns (uint24)
+        function tickSpacing() view returns (int24)
+        function liquidity() view returns (uint128)
+        function slot0() view returns (uint160 sqrtPriceX96, int24 tick, uint16 observationIndex, uint16 observationCardinality, uint16 observationCardinalityNext, uint8 feeProtocol, bool unlocked)
+        function tickBitmap(int16 wordPosition) view returns (uint256)
+        function ticks(int24 tick) view returns (uint128 liquidityGross, int128 liquidityNet, uint256 feeGrowthOutside0X128, uint256 feeGrowthOutside1X128, int56 tickCumulativeOutside, uint160 secondsPerLiquidityOutsideX128, uint32 secondsOutside, bool initialized)
+    ]"#
+);
+
+abigen!(
+    UniswapV3Factory,
+    r#"[
+        function getPool(address tokenA, address tokenB, uint24 fee) view returns (address)
+    ]"#
+);
+
+/// Raw slot0 row: (sqrtPriceX96, tick, observationIndex, observationCardinality, observationCardinalityNext, feeProtocol, unlocked)
+type Slot0Row = (U256, i32, u16, u16, u16, u8, bool);
+
+/// Raw ticks() row; only liquidityGross and liquidityNet are used.
+type TickRow = (u128, i128, U256, U256, i64, U256, u32, bool);
+
+/// The V3 factory deployed on `chain_id`, if we know of one.
+pub fn uniswap_v3_factory(chain_id: u64) -> Option<Address> {
+    let addr = match chain_id {
+        CHAIN_ID_ETHEREUM => UNISWAP_V3_FACTORY_ETHEREUM,
+        CHAIN_ID_BASE => UNISWAP_V3_FACTORY_BASE,
+        _ => return None,
+    };
+    Address::from_str(addr).ok()
+}
+
+/// Configured pool address, or the factory's pool for `pair` at `fee_ppm`.
+pub async fn resolve_v3_pool(
+    provider: Arc<Provider<Http>>,
+    pair: &TokenPair,
+    fee_ppm: u32,
+    pool_address: Option<Address>,
+) -> Result<Address, Box<dyn std::error::Error + Send + Sync>> {
+    if let Some(addr) = pool_address {
+        log::debug!("Using provided Uniswap V3 pool address: {}", addr);
+        return Ok(addr);
+    }
+    let factory_address = uniswap_v3_factory(pair.chain_id)
+        .ok_or_else(|| format!("no Uniswap V3 factory known for chain {}", pair.chain_id))?;
+    log::debug!("Discovering Uniswap V3 pool via the factory on chain {}", pair.chain_id);
+    let factory = UniswapV3Factory::new(factory_address, provider);
+    let addr = factory.get_pool(pair.base.address, pair.quote.address, fee_ppm).call().await?;
+    if addr == Address::zero() {
+        return Err(format!("Uniswap V3 pool with fee {} not found on chain {}", fee_ppm, pair.chain_id).into());
+    }
+    Ok(addr)
+}
+
+/// Load a V3 pool for `pair`. Every read is pinned to the latest block.
+/// Returns the pool and whether `pair.base` is token0.
+pub async fn load_v3_pool(
+    provider: Arc<Provider<Http>>,
+    pair: &TokenPair,
+    pool_address: Address,
+    multicall_addr: Option<Address>,
+) -> Result<(PoolState, bool), Box<dyn std::error::Error + Send + Sync>> {
+    let pool = UniswapV3Pool::new(pool_address, provider.clone());
+    let block = latest_block_ref(&provider).await?;
+
+    // 1) Tokens, fee, spacing, slot0 and liquidity in ONE multicall
+    let mut mc = Multicall::new(provider.clone(), multicall_addr).await?.block(block.number);
+    mc.add_call(pool.token_0(), false)
+        .add_call(pool.token_1(), false)
+        .add_call(pool.fee(), false)
+        .add_call(pool.tick_spacing(), false)
+        .add_call(pool.slot_0(), false)
+        .add_call(pool.liquidity(), false);
+    let (token0, token1, fee_pips, tick_spacing, slot0, liquidity): (Address, Address, u32, i32, Slot0Row, u128) =
+        mc.call().await?;
+    let (sqrt_price_x96, current_tick, ..) = slot0;
+
+    if (token0, token1) != pair.sorted_addresses() {
+        return Err(format!(
+            "Uniswap V3 pool {:?} holds {:?}/{:?}, not the configured pair",
+            pool_address, token0, token1
+        )
+        .into());
+    }
+    log::debug!("V3 state on chain {} at block {} — tick: {}, liquidity: {}, fee: {} pips",
+        pair.chain_id, block.number, current_tick, liquidity, fee_pips);
+
+    // 2) Tick data via bitmaps + ticks()
+    let scan = TickScanConfig::default();
+    let candidate_ticks = fetch_initialized_ticks(
+        provider.clone(), multicall_addr, block.number, current_tick, tick_spacing, scan.word_range,
+        |wp| pool.tick_bitmap(wp),
+    )
+    .await?;
+    let synthetic = candidate_ticks.is_empty();
+    let tick_data = if synthetic {
+        log::warn!("V3 pool {:?}: no initialized ticks within ±{} words; pricing on synthetic liquidity",
+            pool_address, scan.word_range);
+        synthetic_tick_data(current_tick)
+    } else {
+        fetch_tick_liquidity(
+            provider.clone(), multicall_addr, block.number, &candidate_ticks, &scan,
+            |t| pool.ticks(t),
+            |(liquidity_gross, liquidity_net, ..): TickRow| (liquidity_gross, liquidity_net),
+        )
+        .await?
+    };
+
+    // 3) Build pool (V3 has no hooks)
+    let mut state = create_pool_with_real_data(
+        token0,
+        token1,
+        fee_pips,
+        tick_spacing,
+        Address::zero(),
+        u256_to_bigint(sqrt_price_x96),
+        current_tick,
+        liquidity.into(),
+        tick_data,
+    );
+    state.block_number = block.number;
+    state.block_timestamp = block.timestamp;
+    state.chain_id = pair.chain_id;
+    state.synthetic_ticks = synthetic;
+    state.loaded_ticks = (!synthetic).then(|| scanned_tick_range(current_tick, tick_spacing, scan.word_range));
+
+    Ok((state, pair.base_is_token0()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::abi::{encode, AbiDecode, Token};
+    use ethers::utils::keccak256;
+
+    fn selector(signature: &str) -> [u8; 4] {
+        keccak256(signature)[..4].try_into().unwrap()
+    }
+
+    #[test]
+    fn test_abi_matches_v3_pool() {
+        assert_eq!(Slot0Call::selector(), selector("slot0()"));
+        assert_eq!(TicksCall::selector(), selector("ticks(int24)"));
+        assert_eq!(TickBitmapCall::selector(), selector("tickBitmap(int16)"));
+        assert_eq!(LiquidityCall::selector(), selector("liquidity()"));
+        assert_eq!(GetPoolCall::selector(), selector("getPool(address,address,uint24)"));
+    }
+
+    #[test]
+    fn test_slot0_row_decodes_fee_protocol() {
+        // V3's slot0 carries feeProtocol between the observation fields and `unlocked`
+        let raw = encode(&[
+            Token::Uint(U256::one() << 96),
+            Token::Int(I256::from(-200_000).into_raw()),
+            Token::Uint(1u64.into()),
+            Token::Uint(2u64.into()),
+            Token::Uint(3u64.into()),
+            Token::Uint(0x44u64.into()),
+            Token::Bool(true),
+        ]);
+        let row = Slot0Return::decode(raw).expect("slot0() row");
+        assert_eq!(row.sqrt_price_x96, U256::one() << 96);
+        assert_eq!(row.tick, -200_000);
+        assert_eq!(row.fee_protocol, 0x44);
+        assert!(row.unlocked);
+    }
+
+    #[test]
+    fn test_factories_per_chain() {
+        assert_eq!(uniswap_v3_factory(CHAIN_ID_ETHEREUM), UNISWAP_V3_FACTORY_ETHEREUM.parse().ok());
+        assert_eq!(uniswap_v3_factory(CHAIN_ID_BASE), UNISWAP_V3_FACTORY_BASE.parse().ok());
+        assert_eq!(uniswap_v3_factory(10), None);
+    }
+}
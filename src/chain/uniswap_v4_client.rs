@@ -3,10 +3,15 @@ use ethers::contract::{abigen, Multicall};
 use ethers::prelude::*;
 use ethers::providers::{Http, Provider};
 use ethers::utils::keccak256;
-use futures::{stream, StreamExt, TryStreamExt};
 use num_bigint::{BigInt, Sign};
 use std::sync::Arc;
 
+use crate::chain::providers::latest_block_ref;
+use crate::chain::tick_scan::{
+    fetch_initialized_ticks, fetch_tick_liquidity, scanned_tick_range, synthetic_tick_data, TickScanConfig,
+};
+use crate::chain::tokens::TokenPair;
+use crate::math::amount::TokenAmount;
 use crate::math::uniswap_v4::{create_pool_with_real_data, PoolState};
 
 abigen!(
@@ -19,7 +24,9 @@ abigen!(
         "inputs": [{"name": "poolId", "type": "bytes32"}],
         "outputs": [
           {"name": "sqrtPriceX96", "type": "uint160"},
-          {"name": "tick", "type": "int24"}
+          {"name": "tick", "type": "int24"},
+          {"name": "protocolFee", "type": "uint24"},
+          {"name": "lpFee", "type": "uint24"}
         ]
       },
       {
@@ -57,206 +64,209 @@ abigen!(
     ]"#
 );
 
-/// Backward-compatible entry (5 args). Auto-detects Multicall on the chain.
+/// The non-currency half of a V4 PoolKey.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct V4PoolParams {
+    pub fee_ppm: u32,
+    pub tick_spacing: i32,
+    pub hooks: Address,
+}
+
+/// keccak256(abi.encode(PoolKey)) — the poolId StateView and PoolManager events are keyed by.
+pub fn v4_pool_id(pair: &TokenPair, params: &V4PoolParams) -> [u8; 32] {
+    // Currency ordering (native ETH = address(0) sorts first)
+    let (currency0, currency1) = pair.sorted_addresses();
+    let tokens = vec![Token::Tuple(vec![
+        Token::Address(currency0),
+        Token::Address(currency1),
+        Token::Uint(U256::from(params.fee_ppm)),            // uint24 in practice
+        Token::Int(U256::from(params.tick_spacing as i64)), // int24
+        Token::Address(params.hooks),
+    ])];
+    keccak256(abi::encode(&tokens))
+}
+
+/// A full StateView load plus what it covered.
+#[derive(Clone, Debug)]
+pub struct V4PoolLoad {
+    pub pool: PoolState,
+    pub token0_is_base: bool,
+    /// Inclusive tick range whose bitmap words were scanned; None when the
+    /// bitmaps came back empty and synthetic liquidity was used instead.
+    pub scanned_ticks: Option<(i32, i32)>,
+}
+
+/// Default entry. Auto-detects Multicall on the chain.
+/// Returns the pool and whether `pair.base` is currency0.
 pub async fn load_v4_pool_snapshot(
     provider: Arc<Provider<Http>>,
     state_view_addr: Address,
-    usdc_addr: Address,
-    fee_ppm: u32,
-    tick_spacing: i32,
+    pair: &TokenPair,
+    params: &V4PoolParams,
 ) -> Result<(PoolState, bool), Box<dyn std::error::Error + Send + Sync>> {
-    load_v4_pool_snapshot_with_multicall(
-        provider,
-        state_view_addr,
-        usdc_addr,
-        fee_ppm,
-        tick_spacing,
-        None,
-    )
-    .await
+    load_v4_pool_snapshot_with_multicall(provider, state_view_addr, pair, params, None).await
 }
 
 /// Main entry with optional explicit Multicall address
 pub async fn load_v4_pool_snapshot_with_multicall(
     provider: Arc<Provider<Http>>,
     state_view_addr: Address,
-    usdc_addr: Address,
-    fee_ppm: u32,
-    tick_spacing: i32,
+    pair: &TokenPair,
+    params: &V4PoolParams,
     multicall_addr: Option<Address>,
 ) -> Result<(PoolState, bool), Box<dyn std::error::Error + Send + Sync>> {
+    let load = load_v4_pool(provider, state_view_addr, pair, params, None, multicall_addr).await?;
+    Ok((load.pool, load.token0_is_base))
+}
+
+/// Full load that also reports the scanned tick range (what event tracking can keep exact).
+/// slot0, liquidity, bitmaps and tick infos are all read at one block, recorded on the pool.
+/// With `max_trade` (in `pair.base`), the bitmap scan widens until buying and selling that much
+/// stays inside the loaded ticks, or `TickScanConfig::max_word_range` is reached.
+pub async fn load_v4_pool(
+    provider: Arc<Provider<Http>>,
+    state_view_addr: Address,
+    pair: &TokenPair,
+    params: &V4PoolParams,
+    max_trade: Option<TokenAmount>,
+    multicall_addr: Option<Address>,
+) -> Result<V4PoolLoad, Box<dyn std::error::Error + Send + Sync>> {
     let view = StateView::new(state_view_addr, provider.clone());
 
-    // Currency ordering with native ETH = address(0)
-    let eth = Address::zero();
-    let (currency0, currency1, token0_is_eth) = if eth < usdc_addr {
-        (eth, usdc_addr, true)
-    } else {
-        (usdc_addr, eth, false)
-    };
+    // Currency ordering (native ETH = address(0) sorts first)
+    let (currency0, currency1) = pair.sorted_addresses();
+    let token0_is_base = pair.base_is_token0();
 
-    let hooks = Address::zero();
+    let V4PoolParams { fee_ppm, tick_spacing, hooks } = *params;
 
-    // Encode V4 PoolKey -> poolId
-    let tokens = vec![Token::Tuple(vec![
-        Token::Address(currency0),
-        Token::Address(currency1),
-        Token::Uint(U256::from(fee_ppm)),            // uint24 in practice
-        Token::Int(U256::from(tick_spacing as i64)), // int24
-        Token::Address(hooks),
-    ])];
-    let pool_id = keccak256(abi::encode(&tokens));
+    let pool_id = v4_pool_id(pair, params);
 
-    // 1) slot0 + liquidity in ONE multicall
-    let ((sqrt_price_x96, current_tick), liquidity) =
-        fetch_core_state_multicall(provider.clone(), &view, pool_id, multicall_addr).await?;
-
-    log::debug!("V4 state — tick: {}, liquidity: {}", current_tick, liquidity);
-
-    let sqrt_bi = u256_to_bigint(sqrt_price_x96.into());
-    let liq_bi = u256_to_bigint(liquidity.into());
-
-    // 2) Tick data via bitmaps + tick infos (few multicalls)
-    let tick_data = fetch_tick_data_multicall(
-        provider.clone(),
-        &view,
-        pool_id,
-        current_tick,
-        tick_spacing,
-        24,     // word_range (±N words around current)
-        4096,   // tickinfo_chunk_size (try 8192 if your RPC allows)
-        6,      // parallel_chunks
-        multicall_addr,
-    )
-    .await?;
+    // 0) Pin every read below to one block, so slot0 and the ticks describe the same state
+    let block = latest_block_ref(&provider).await?;
 
-    // 3) Build pool
-    let pool = create_pool_with_real_data(
-        currency0, currency1, fee_ppm, tick_spacing, hooks, sqrt_bi, current_tick, liq_bi, tick_data,
-    );
+    // 1) slot0 + liquidity in ONE multicall
+    let ((sqrt_price_x96, current_tick, protocol_fee, lp_fee), liquidity) =
+        fetch_core_state_multicall(provider.clone(), &view, pool_id, multicall_addr, block.number).await?;
+
+    log::debug!("V4 state at block {} — tick: {}, liquidity: {}, lpFee: {}, protocolFee: {:#x}",
+        block.number, current_tick, liquidity, lp_fee, protocol_fee);
+
+    let sqrt_bi = u256_to_bigint(sqrt_price_x96);
+    let liq_bi = u256_to_bigint(liquidity);
+
+    // 2) Tick data via bitmaps + tick infos (few multicalls), widening the scan while it finds
+    //    nothing or the largest trade would run past it
+    let base_config = TickScanConfig::default();
+    let max_trade = max_trade.map(|size| size.rescale(pair.base.decimals).to_bigint());
+    let mut scan = base_config;
+    let (mut pool, scanned_ticks) = loop {
+        let fetched = fetch_tick_data_multicall(
+            provider.clone(),
+            &view,
+            pool_id,
+            current_tick,
+            tick_spacing,
+            &scan,
+            multicall_addr,
+            block.number,
+        )
+        .await?;
+
+        // 3) Build pool
+        let found = fetched.is_some();
+        let scanned = found.then(|| scanned_tick_range(current_tick, tick_spacing, scan.word_range));
+        let mut pool = create_pool_with_real_data(
+            currency0, currency1, fee_ppm, tick_spacing, hooks, sqrt_bi.clone(), current_tick, liq_bi.clone(),
+            fetched.unwrap_or_else(|| synthetic_tick_data(current_tick)),
+        );
+        pool.loaded_ticks = scanned;
+        pool.synthetic_ticks = !found;
+        // slot0's fees before the coverage check: a dynamic pool's key carries no fee of its own
+        pool.lp_fee_ppm = lp_fee;
+        pool.protocol_fee = protocol_fee;
+
+        let covered = found && max_trade.as_ref().is_none_or(|size| pool.covers_trade(token0_is_base, size));
+        match base_config.widen(scan.word_range) {
+            Some(wider) if !covered => {
+                log::debug!("V4 pool {}: ±{} bitmap words don't cover the largest trade; scanning ±{}",
+                    H256::from(pool_id), scan.word_range, wider);
+                scan.word_range = wider;
+            }
+            _ if !found => {
+                log::warn!("V4 pool {}: no initialized ticks within ±{} words; pricing on synthetic liquidity",
+                    H256::from(pool_id), scan.word_range);
+                break (pool, scanned);
+            }
+            _ => {
+                if !covered {
+                    log::warn!("V4 pool {}: the largest trade runs past the ±{} words loaded; such quotes are refused",
+                        H256::from(pool_id), scan.word_range);
+                }
+                break (pool, scanned);
+            }
+        }
+    };
+    pool.block_number = block.number;
+    pool.block_timestamp = block.timestamp;
+    pool.chain_id = pair.chain_id;
 
-    Ok((pool, token0_is_eth))
+    Ok(V4PoolLoad { pool, token0_is_base, scanned_ticks })
 }
 
+/// Raw getSlot0 row: (sqrtPriceX96, tick, protocolFee, lpFee)
+type Slot0Row = (U256, i32, u32, u32);
+
 /// ONE multicall for slot0 + liquidity
 async fn fetch_core_state_multicall<M: Middleware + 'static>(
     client: Arc<M>,
     view: &StateView<M>,
     pool_id: [u8; 32],
     multicall_addr: Option<Address>,
-) -> Result<((U256, i32), U256), Box<dyn std::error::Error + Send + Sync>> {
-    let mut mc = Multicall::new(client.clone(), multicall_addr).await?;
+    block: u64,
+) -> Result<(Slot0Row, U256), Box<dyn std::error::Error + Send + Sync>> {
+    let mut mc = Multicall::new(client.clone(), multicall_addr).await?.block(block);
     mc.add_call(view.get_slot_0(pool_id), false);
     mc.add_call(view.get_liquidity(pool_id), false);
-    let out: ((U256, i32), U256) = mc.call().await?;
+    let out: (Slot0Row, U256) = mc.call().await?;
     Ok(out)
 }
 
-/// Bitmaps + tick infos via Multicall. Few calls, **bounded-parallel** chunked tick infos.
+/// Raw getTickInfo row: (liquidityGross, liquidityNet, feeGrowthOutside0X128, feeGrowthOutside1X128)
+type TickInfoRow = (u128, i128, U256, U256);
+
+/// Bitmaps + tick infos via Multicall (see chain::tick_scan).
+/// `None` when every scanned bitmap word is empty.
+#[allow(clippy::too_many_arguments)]
 async fn fetch_tick_data_multicall<M: Middleware + 'static>(
     client: Arc<M>,
     view: &StateView<M>,
     pool_id: [u8; 32],
     current_tick: i32,
     tick_spacing: i32,
-    word_range: i16,
-    tickinfo_chunk_size: usize,
-    parallel_chunks: usize,
+    scan: &TickScanConfig,
     multicall_addr: Option<Address>,
-) -> Result<Vec<(i32, BigInt)>, Box<dyn std::error::Error + Send + Sync>> {
-    // Compute word positions around current tick
-    let current_word = current_tick / tick_spacing / 256;
-    let word_positions: Vec<i16> = (-word_range..=word_range)
-        .map(|off| (current_word as i16).saturating_add(off))
-        .collect();
-
-    // 2a) All bitmaps in ONE multicall (homogeneous => call_array())
-    let bitmaps: Vec<U256> = {
-        let mut mc = Multicall::new(client.clone(), multicall_addr).await?;
-        for wp in &word_positions {
-            mc.add_call(view.get_tick_bitmap(pool_id, *wp), false);
-        }
-        mc.call_array().await?
-    };
-
-    // 2b) Decode set bits -> candidate ticks
-    let mut candidate_ticks = Vec::new();
-    for (idx, word) in bitmaps.into_iter().enumerate() {
-        if word.is_zero() {
-            continue;
-        }
-        let word_pos = word_positions[idx] as i32;
-        for bit in 0..256usize {
-            if word.bit(bit) {
-                let t = word_pos * 256 * tick_spacing + (bit as i32) * tick_spacing;
-                candidate_ticks.push(t);
-            }
-        }
-    }
-
+    block: u64,
+) -> Result<Option<Vec<(i32, BigInt)>>, Box<dyn std::error::Error + Send + Sync>> {
+    let candidate_ticks = fetch_initialized_ticks(
+        client.clone(), multicall_addr, block, current_tick, tick_spacing, scan.word_range,
+        |wp| view.get_tick_bitmap(pool_id, wp),
+    )
+    .await?;
     if candidate_ticks.is_empty() {
-        // Fallback: synthetic wide ranges to keep downstream pricing working
-        let mut tick_data = Vec::new();
-        let wide_range = 12_000;
-        let synthetic_liq = BigInt::from(1_000_000_000_000_000_000_000_000u128);
-        for i in 0..10 {
-            let lower = current_tick - wide_range + (i * wide_range / 5);
-            let upper = current_tick + wide_range - (i * wide_range / 5);
-            let per = &synthetic_liq / BigInt::from(10u8);
-            tick_data.push((lower, per.clone()));
-            tick_data.push((upper, -per));
-        }
-        return Ok(tick_data);
+        return Ok(None);
     }
 
-    // 2c) Tick infos via Multicall, **chunked + bounded parallel**
-    candidate_ticks.sort_unstable();
-    candidate_ticks.dedup();
-
-    let chunked_ticks: Vec<Vec<i32>> = candidate_ticks
-        .chunks(tickinfo_chunk_size)
-        .map(|s| s.to_vec())
-        .collect();
-
-    // Stream all chunks, but only `parallel_chunks` in flight at once.
-    let results: Vec<(Vec<i32>, Vec<(u128, i128, U256, U256)>)> = stream::iter(
-        chunked_ticks.into_iter().map(|ticks_chunk| {
-            let client = client.clone();
-            let view = view.clone();
-            async move {
-                let mut mc = Multicall::new(client.clone(), multicall_addr).await?;
-                for t in &ticks_chunk {
-                    mc.add_call(view.get_tick_info(pool_id, *t), false);
-                }
-                let infos: Vec<(u128, i128, U256, U256)> = mc.call_array().await?;
-                Ok::<(Vec<i32>, Vec<(u128, i128, U256, U256)>), Box<dyn std::error::Error + Send + Sync>>(
-                    (ticks_chunk, infos)
-                )
-            }
-        }),
+    let tick_data = fetch_tick_liquidity(
+        client, multicall_addr, block, &candidate_ticks, scan,
+        |t| view.get_tick_info(pool_id, t),
+        |(liquidity_gross, liquidity_net, _f0, _f1): TickInfoRow| (liquidity_gross, liquidity_net),
     )
-    .buffer_unordered(parallel_chunks)
-    .try_collect()
     .await?;
-
-    // 2d) Collect net liquidity deltas for non-zero gross ticks
-    let mut tick_data: Vec<(i32, BigInt)> = Vec::new();
-    for (ticks_chunk, infos_chunk) in results {
-        for (i, (liquidity_gross, liquidity_net, _f0, _f1)) in
-            infos_chunk.into_iter().enumerate()
-        {
-            if liquidity_gross > 0u128 {
-                let tick = ticks_chunk[i];
-                tick_data.push((tick, BigInt::from(liquidity_net))); // i128 -> BigInt
-            }
-        }
-    }
-
-    Ok(tick_data)
+    Ok(Some(tick_data))
 }
 
 /// Helpers
-fn u256_to_bigint(u: U256) -> BigInt {
+pub(crate) fn u256_to_bigint(u: U256) -> BigInt {
     let mut buf = [0u8; 32];
     u.to_big_endian(&mut buf);
     BigInt::from_bytes_be(Sign::Plus, &buf)
@@ -265,6 +275,7 @@ fn u256_to_bigint(u: U256) -> BigInt {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::chain::tick_scan::scan_center_word;
 
     #[test]
     fn test_u256_to_bigint() {
@@ -315,6 +326,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn test_currency_ordering_logic() {
         // Test the currency ordering logic used in the main function
         let eth = Address::zero();
@@ -353,7 +365,7 @@ mod tests {
         let tick_spacing = 60;
         let word_range = 24i16;
 
-        let current_word = current_tick / tick_spacing / 256;
+        let current_word = scan_center_word(current_tick, tick_spacing);
         let word_positions: Vec<i16> = (-word_range..=word_range)
             .map(|off| (current_word as i16).saturating_add(off))
             .collect();
@@ -493,6 +505,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::useless_vec)]
     fn test_chunk_size_calculation() {
         // Test chunking logic for tick processing
         let candidate_ticks = vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10];
@@ -569,6 +582,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::assign_op_pattern)]
     fn test_bitmap_bit_extraction() {
         // Test bitmap bit extraction logic
         use ethers::types::U256;
@@ -671,4 +685,21 @@ mod tests {
         let result3 = normal.saturating_add(50);
         assert_eq!(result3, 150);
     }
+
+    #[test]
+    fn test_slot0_row_decodes_fees() {
+        use ethers::abi::{encode, AbiDecode, Token};
+
+        assert_eq!(GetSlot0Call::selector(), keccak256("getSlot0(bytes32)")[..4]);
+        let raw = encode(&[
+            Token::Uint(U256::one() << 96),
+            Token::Int(I256::from(-200_000).into_raw()),
+            Token::Uint(U256::from(0x0c8_0c8u64)), // 200 pips each way
+            Token::Uint(U256::from(2_500u64)),
+        ]);
+        let row = GetSlot0Return::decode(raw).expect("getSlot0 row");
+        assert_eq!(row.tick, -200_000);
+        assert_eq!(row.protocol_fee, 0x0c8_0c8);
+        assert_eq!(row.lp_fee, 2_500);
+    }
 }
@@ -0,0 +1,445 @@
+// src/chain/uniswap_v4_tracker.rs
+//
+// Keeps a V4 PoolState current from PoolManager events between full StateView loads.
+// - ModifyLiquidity: liquidityNet at both range ends, plus active liquidity when in range
+// - Swap: the event carries the post-swap sqrtPrice, active liquidity and tick, and the fee
+//   charged, which on a dynamic-fee pool is the LP fee currently in force
+// - Anything we can't apply exactly (reorged logs, positions or prices outside the
+//   scanned tick range) asks for a resync instead of guessing
+// Periodic full loads are compared against the tracked state to surface drift.
+// A full load is pinned at one block and takes a while; logs arriving meanwhile are buffered
+// in the pair's V4TrackerSlot and replayed onto the tracker the load produces.
+
+use ethers::contract::parse_log;
+use ethers::prelude::*;
+use num_bigint::BigInt;
+use std::fmt;
+
+use crate::chain::events::{v4_log_pool_id, PoolManagerEventsEvents, TrackerUpdate};
+use crate::chain::providers::BlockRef;
+use crate::chain::uniswap_v4_client::{u256_to_bigint, V4PoolLoad};
+use crate::math::uniswap_v4::{combined_swap_fee_ppm, PoolState};
+
+/// Differences between the tracked state and a fresh StateView load.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PoolDrift {
+    pub sqrt_price: bool,
+    pub tick: bool,
+    pub liquidity: bool,
+    pub ticks: usize, // initialized ticks whose liquidityNet differs, within both scanned ranges
+}
+
+impl PoolDrift {
+    pub fn is_clean(&self) -> bool {
+        *self == PoolDrift::default()
+    }
+}
+
+impl fmt::Display for PoolDrift {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "sqrtPrice {}, tick {}, liquidity {}, {} tick(s) with different liquidityNet",
+            if self.sqrt_price { "differs" } else { "ok" },
+            if self.tick { "differs" } else { "ok" },
+            if self.liquidity { "differs" } else { "ok" },
+            self.ticks
+        )
+    }
+}
+
+pub struct V4PoolTracker {
+    pool_id: H256,
+    state: PoolState,
+    token0_is_base: bool,
+    scanned_ticks: Option<(i32, i32)>,
+    synced_block: u64,                 // block of the full load
+    last_applied: Option<(u64, U256)>, // (block, log index)
+}
+
+impl V4PoolTracker {
+    /// Start from a full load; logs up to and including its block are skipped.
+    pub fn new(load: V4PoolLoad, pool_id: H256) -> Self {
+        Self {
+            pool_id,
+            synced_block: load.pool.block_number,
+            state: load.pool,
+            token0_is_base: load.token0_is_base,
+            scanned_ticks: load.scanned_ticks,
+            last_applied: None,
+        }
+    }
+
+    pub fn state(&self) -> &PoolState {
+        &self.state
+    }
+
+    pub fn token0_is_base(&self) -> bool {
+        self.token0_is_base
+    }
+
+    /// Latest block reflected in `state()`.
+    pub fn block(&self) -> BlockRef {
+        BlockRef { number: self.state.block_number, timestamp: self.state.block_timestamp }
+    }
+
+    fn in_scanned_range(&self, tick: i32) -> bool {
+        self.scanned_ticks.is_none_or(|(lo, hi)| lo <= tick && tick <= hi)
+    }
+
+    /// `block_timestamp` is that of the log's block (logs don't carry one).
+    pub fn apply_log(&mut self, log: &Log, block_timestamp: u64) -> TrackerUpdate {
+        if v4_log_pool_id(log) != Some(self.pool_id) {
+            return TrackerUpdate::Ignored;
+        }
+        if log.removed == Some(true) {
+            return TrackerUpdate::NeedsResync("log removed by a reorg".into());
+        }
+        let (Some(block), Some(index)) = (log.block_number, log.log_index) else {
+            return TrackerUpdate::Ignored; // pending logs carry no position
+        };
+        let position = (block.as_u64(), index);
+        if position.0 <= self.synced_block || self.last_applied.is_some_and(|last| position <= last) {
+            return TrackerUpdate::Ignored;
+        }
+
+        let event = match parse_log::<PoolManagerEventsEvents>(log.clone()) {
+            Ok(event) => event,
+            Err(e) => return TrackerUpdate::NeedsResync(format!("undecodable PoolManager log: {}", e)),
+        };
+
+        // Every check runs before the state is touched: a resync request leaves it as it was
+        match event {
+            PoolManagerEventsEvents::SwapFilter(swap) => {
+                if !self.in_scanned_range(swap.tick) {
+                    return TrackerUpdate::NeedsResync(format!("price moved to tick {} outside the scanned range", swap.tick));
+                }
+                self.state.sqrt_price_x96 = u256_to_bigint(swap.sqrt_price_x96);
+                self.state.liquidity = BigInt::from(swap.liquidity);
+                self.state.tick = swap.tick;
+                // A dynamic pool's hook may move the LP fee at any time; the swap paid the current one
+                if self.state.key.is_dynamic_fee() {
+                    let zero_for_one = swap.amount_0 < 0; // the swapper paid in token0
+                    if let Some(fee) = lp_fee_from_swap_fee(swap.fee, self.state.protocol_fee_ppm(zero_for_one)) {
+                        self.state.lp_fee_ppm = fee;
+                    }
+                }
+            }
+            PoolManagerEventsEvents::ModifyLiquidityFilter(m) => {
+                let delta = match i128::try_from(m.liquidity_delta) {
+                    Ok(d) => BigInt::from(d),
+                    Err(_) => return TrackerUpdate::NeedsResync("liquidityDelta out of int128 range".into()),
+                };
+                match self.scanned_ticks {
+                    Some(_) if !(self.in_scanned_range(m.tick_lower) && self.in_scanned_range(m.tick_upper)) => {
+                        // The far end's existing liquidityNet is unknown to us
+                        return TrackerUpdate::NeedsResync(format!(
+                            "position [{}, {}) reaches outside the scanned range", m.tick_lower, m.tick_upper
+                        ));
+                    }
+                    Some(_) => self.state.apply_liquidity_delta(m.tick_lower, m.tick_upper, &delta),
+                    // Synthetic ticks can't absorb real positions; keep active liquidity right at least
+                    None if m.tick_lower <= self.state.tick && self.state.tick < m.tick_upper => {
+                        self.state.liquidity += delta;
+                    }
+                    None => {}
+                }
+            }
+        }
+        self.last_applied = Some(position);
+        self.state.block_number = position.0;
+        self.state.block_timestamp = block_timestamp;
+        TrackerUpdate::Applied
+    }
+
+    /// Compare against a fresh full load (typically just before replacing this tracker).
+    pub fn drift(&self, fresh: &V4PoolLoad) -> PoolDrift {
+        let ours = &self.state;
+        let theirs = &fresh.pool;
+
+        // Only ticks both loads actually scanned are comparable
+        let ticks = match (self.scanned_ticks, fresh.scanned_ticks) {
+            (Some((lo_a, hi_a)), Some((lo_b, hi_b))) if lo_a.max(lo_b) <= hi_a.min(hi_b) => {
+                let (lo, hi) = (lo_a.max(lo_b), hi_a.min(hi_b));
+                let mut keys: Vec<i32> = ours.ticks.range(lo..=hi).map(|(t, _)| *t)
+                    .chain(theirs.ticks.range(lo..=hi).map(|(t, _)| *t))
+                    .collect();
+                keys.sort_unstable();
+                keys.dedup();
+                keys.into_iter()
+                    .filter(|t| ours.ticks.get(t).map(|i| &i.liquidity_net) != theirs.ticks.get(t).map(|i| &i.liquidity_net))
+                    .count()
+            }
+            _ => 0,
+        };
+
+        PoolDrift {
+            sqrt_price: ours.sqrt_price_x96 != theirs.sqrt_price_x96,
+            tick: ours.tick != theirs.tick,
+            liquidity: ours.liquidity != theirs.liquidity,
+            ticks,
+        }
+    }
+}
+
+/// The LP fee behind a Swap event's `fee`, which has the protocol fee of the swap's direction
+/// compounded in (the inverse of `combined_swap_fee_ppm`).
+fn lp_fee_from_swap_fee(swap_fee_ppm: u32, protocol_fee_ppm: u32) -> Option<u32> {
+    if protocol_fee_ppm == 0 {
+        return Some(swap_fee_ppm);
+    }
+    let (p, above) = (protocol_fee_ppm as u64, swap_fee_ppm.checked_sub(protocol_fee_ppm)? as u64);
+    let guess = (above * 1_000_000 / (1_000_000 - p)) as u32;
+    (guess..=guess + 1).find(|lp| combined_swap_fee_ppm(protocol_fee_ppm, *lp) == swap_fee_ppm)
+}
+
+/// Cap on logs buffered during one full load; past it the load's result is dropped for a resync.
+const MAX_PENDING_LOGS: usize = 10_000;
+
+/// A pair's tracker (None until the first load, or after a resync request) plus the logs
+/// seen while a full load is in flight.
+#[derive(Default)]
+pub struct V4TrackerSlot {
+    pub tracker: Option<V4PoolTracker>,
+    loading: bool,
+    pending: Vec<(Log, u64)>, // (log, block timestamp)
+    overflowed: bool,
+}
+
+impl V4TrackerSlot {
+    /// Start buffering logs for a full load that is about to be issued.
+    pub fn begin_load(&mut self) {
+        self.loading = true;
+        self.pending.clear();
+        self.overflowed = false;
+    }
+
+    /// The load failed: stop buffering, keep the current tracker.
+    pub fn abort_load(&mut self) {
+        self.loading = false;
+        self.pending.clear();
+        self.overflowed = false;
+    }
+
+    /// Keep a copy of the log for replay if a load is in flight. Returns whether it was kept.
+    pub fn buffer(&mut self, log: &Log, block_timestamp: u64) -> bool {
+        if !self.loading {
+            return false;
+        }
+        if self.pending.len() < MAX_PENDING_LOGS {
+            self.pending.push((log.clone(), block_timestamp));
+        } else {
+            self.overflowed = true;
+        }
+        true
+    }
+
+    /// Install the tracker built from a finished load and replay the logs buffered while it ran
+    /// (those at or before its block are skipped by the tracker itself). On a replayed log that
+    /// needs a resync the slot is left empty and the reason returned.
+    pub fn finish_load(&mut self, tracker: V4PoolTracker) -> Result<&V4PoolTracker, String> {
+        let pending = std::mem::take(&mut self.pending);
+        let overflowed = std::mem::replace(&mut self.overflowed, false);
+        self.loading = false;
+        self.tracker = None;
+        if overflowed {
+            return Err(format!("more than {} logs arrived during the load", MAX_PENDING_LOGS));
+        }
+
+        let mut tracker = tracker;
+        for (log, block_timestamp) in &pending {
+            if let TrackerUpdate::NeedsResync(why) = tracker.apply_log(log, *block_timestamp) {
+                return Err(why);
+            }
+        }
+        Ok(self.tracker.insert(tracker))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain::events::{ModifyLiquidityFilter, SwapFilter};
+    use crate::math::uniswap_v4::{create_pool_with_real_data, DYNAMIC_FEE_FLAG};
+    use ethers::abi::{encode, Token};
+
+    const POOL: H256 = H256::repeat_byte(0x42);
+    const TS: u64 = 1_700_000_012;
+
+    fn load() -> V4PoolLoad {
+        // One position [-600, 600) with L = 1e18 around tick 0
+        let l = BigInt::from(1_000_000_000_000_000_000u128);
+        let pool = create_pool_with_real_data(
+            Address::zero(), Address::from([0x11; 20]), 3000, 60, Address::zero(),
+            BigInt::from(1) << 96, 0, l.clone(), vec![(-600, l.clone()), (600, -l)],
+        );
+        let mut pool = pool;
+        pool.block_number = 100;
+        pool.block_timestamp = 1_700_000_000;
+        V4PoolLoad { pool, token0_is_base: true, scanned_ticks: Some((-6000, 6000)) }
+    }
+
+    fn log_at(block: u64, index: u64, topic0: H256, data: Vec<Token>) -> Log {
+        Log {
+            topics: vec![topic0, POOL, H256::zero()],
+            data: encode(&data).into(),
+            block_number: Some(block.into()),
+            log_index: Some(index.into()),
+            ..Default::default()
+        }
+    }
+
+    fn swap_log(block: u64, index: u64, sqrt_price: U256, liquidity: u128, tick: i32) -> Log {
+        swap_log_with_fee(block, index, sqrt_price, liquidity, tick, 3000)
+    }
+
+    fn swap_log_with_fee(block: u64, index: u64, sqrt_price: U256, liquidity: u128, tick: i32, fee: u32) -> Log {
+        log_at(block, index, SwapFilter::signature(), vec![
+            Token::Int(I256::from(-1000).into_raw()),
+            Token::Int(I256::from(990).into_raw()),
+            Token::Uint(sqrt_price),
+            Token::Uint(U256::from(liquidity)),
+            Token::Int(I256::from(tick).into_raw()),
+            Token::Uint(U256::from(fee)),
+        ])
+    }
+
+    fn modify_log(block: u64, index: u64, lower: i32, upper: i32, delta: i128) -> Log {
+        log_at(block, index, ModifyLiquidityFilter::signature(), vec![
+            Token::Int(I256::from(lower).into_raw()),
+            Token::Int(I256::from(upper).into_raw()),
+            Token::Int(I256::from(delta).into_raw()),
+            Token::FixedBytes(vec![0u8; 32]),
+        ])
+    }
+
+    #[test]
+    fn test_swap_sets_price_liquidity_and_tick() {
+        let mut t = V4PoolTracker::new(load(), POOL);
+        let sqrt = U256::from(2u64).pow(U256::from(96)) + U256::from(12345u64);
+        assert_eq!(t.apply_log(&swap_log(101, 0, sqrt, 7, 1), TS), TrackerUpdate::Applied);
+        assert_eq!(t.state().sqrt_price_x96, u256_to_bigint(sqrt));
+        assert_eq!(t.state().liquidity, BigInt::from(7));
+        assert_eq!(t.state().tick, 1);
+        assert_eq!(t.block(), BlockRef { number: 101, timestamp: TS });
+    }
+
+    #[test]
+    fn test_swap_updates_a_dynamic_pools_lp_fee() {
+        let sqrt = U256::from(2u64).pow(U256::from(96));
+        // A static pool keeps its key's fee whatever the event says
+        let mut t = V4PoolTracker::new(load(), POOL);
+        t.apply_log(&swap_log_with_fee(101, 0, sqrt, 7, 0, 500), TS);
+        assert_eq!(t.state().lp_fee_ppm, 3000);
+
+        let mut dynamic = load();
+        dynamic.pool.key.fee_ppm = DYNAMIC_FEE_FLAG;
+        dynamic.pool.lp_fee_ppm = 3000;
+        let mut t = V4PoolTracker::new(dynamic.clone(), POOL);
+        assert_eq!(t.apply_log(&swap_log_with_fee(101, 0, sqrt, 7, 0, 500), TS), TrackerUpdate::Applied);
+        assert_eq!(t.state().lp_fee_ppm, 500);
+
+        // Replayed after a load, the last swap's fee wins
+        let mut slot = V4TrackerSlot::default();
+        slot.begin_load();
+        slot.buffer(&swap_log_with_fee(101, 0, sqrt, 7, 0, 500), TS);
+        slot.buffer(&swap_log_with_fee(102, 0, sqrt, 7, 0, 2500), TS);
+        let tracker = slot.finish_load(V4PoolTracker::new(dynamic.clone(), POOL)).unwrap();
+        assert_eq!(tracker.state().lp_fee_ppm, 2500);
+
+        // With a protocol fee on the swap's side (token0 in), the event's fee has it compounded in
+        dynamic.pool.protocol_fee = 1000;
+        let mut t = V4PoolTracker::new(dynamic, POOL);
+        t.apply_log(&swap_log_with_fee(101, 0, sqrt, 7, 0, combined_swap_fee_ppm(1000, 500)), TS);
+        assert_eq!(t.state().lp_fee_ppm, 500);
+    }
+
+    #[test]
+    fn test_modify_liquidity_in_range() {
+        let mut t = V4PoolTracker::new(load(), POOL);
+        let before = t.state().liquidity.clone();
+        assert_eq!(t.apply_log(&modify_log(101, 3, -120, 120, 5_000), TS), TrackerUpdate::Applied);
+        assert_eq!(t.state().liquidity, before + 5_000);
+        assert_eq!(t.state().ticks[&-120].liquidity_net, BigInt::from(5_000));
+        assert_eq!(t.state().ticks[&120].liquidity_net, BigInt::from(-5_000));
+    }
+
+    #[test]
+    fn test_skips_old_duplicate_and_foreign_logs() {
+        let mut t = V4PoolTracker::new(load(), POOL);
+        let l = modify_log(100, 0, -120, 120, 5_000);
+        assert_eq!(t.apply_log(&l, TS), TrackerUpdate::Ignored); // already in the snapshot
+
+        let l = modify_log(101, 0, -120, 120, 5_000);
+        assert_eq!(t.apply_log(&l, TS), TrackerUpdate::Applied);
+        assert_eq!(t.apply_log(&l, TS), TrackerUpdate::Ignored); // replayed after a reconnect
+
+        let mut other = modify_log(102, 0, -120, 120, 5_000);
+        other.topics[1] = H256::repeat_byte(0x01);
+        assert_eq!(t.apply_log(&other, TS), TrackerUpdate::Ignored);
+    }
+
+    #[test]
+    fn test_resync_when_leaving_scanned_range() {
+        let mut t = V4PoolTracker::new(load(), POOL);
+        assert!(matches!(t.apply_log(&modify_log(101, 0, -120, 12_000, 1), TS), TrackerUpdate::NeedsResync(_)));
+        assert!(matches!(t.apply_log(&swap_log(101, 1, U256::one(), 1, 7_000), TS), TrackerUpdate::NeedsResync(_)));
+
+        let mut removed = modify_log(102, 0, -120, 120, 1);
+        removed.removed = Some(true);
+        assert!(matches!(t.apply_log(&removed, TS), TrackerUpdate::NeedsResync(_)));
+    }
+
+    #[test]
+    fn test_resync_leaves_state_untouched() {
+        let mut t = V4PoolTracker::new(load(), POOL);
+        assert_eq!(t.apply_log(&modify_log(101, 0, -120, 120, 5_000), TS), TrackerUpdate::Applied);
+        let (state, block) = (t.state().clone(), t.block());
+
+        assert!(matches!(t.apply_log(&swap_log(102, 0, U256::one(), 1, 7_000), TS + 12), TrackerUpdate::NeedsResync(_)));
+        assert_eq!(t.state().sqrt_price_x96, state.sqrt_price_x96);
+        assert_eq!(t.state().liquidity, state.liquidity);
+        assert_eq!(t.state().tick, state.tick);
+        assert_eq!(t.block(), block);
+
+        // The refused log is not recorded as applied
+        let sqrt = U256::from(2u64).pow(U256::from(96));
+        assert_eq!(t.apply_log(&swap_log(102, 0, sqrt, 7, 1), TS + 12), TrackerUpdate::Applied);
+    }
+
+    #[test]
+    fn test_slot_replays_logs_seen_during_a_load() {
+        let mut slot = V4TrackerSlot::default();
+        assert!(!slot.buffer(&modify_log(99, 0, -120, 120, 1), TS)); // no load in flight
+
+        slot.begin_load();
+        assert!(slot.buffer(&modify_log(100, 0, -120, 120, 1), TS)); // inside the load's block
+        assert!(slot.buffer(&modify_log(101, 0, -120, 120, 5_000), TS));
+        let tracker = slot.finish_load(V4PoolTracker::new(load(), POOL)).unwrap();
+        assert_eq!(tracker.state().liquidity, load().pool.liquidity + 5_000);
+        assert_eq!(tracker.block().number, 101);
+        assert!(!slot.buffer(&modify_log(102, 0, -120, 120, 1), TS)); // buffering stopped
+
+        slot.begin_load();
+        slot.buffer(&swap_log(102, 0, U256::one(), 1, 7_000), TS);
+        assert!(slot.finish_load(V4PoolTracker::new(load(), POOL)).is_err());
+        assert!(slot.tracker.is_none());
+    }
+
+    #[test]
+    fn test_drift_against_fresh_load() {
+        let mut t = V4PoolTracker::new(load(), POOL);
+        assert!(t.drift(&load()).is_clean());
+
+        // Fresh load saw a position we missed
+        let mut fresh = load();
+        fresh.pool.apply_liquidity_delta(-60, 60, &BigInt::from(9));
+        let drift = t.drift(&fresh);
+        assert!(drift.liquidity);
+        assert_eq!(drift.ticks, 2);
+
+        // Applying the same event closes the gap
+        t.apply_log(&modify_log(101, 0, -60, 60, 9), TS);
+        assert!(t.drift(&fresh).is_clean());
+    }
+}
@@ -1,24 +1,97 @@
 use std::env;
+use std::time::Duration;
+
+use crate::chain::gas::Eip1559Pricing;
+use crate::chain::registry::ChainConfig;
+
+/// How often the background poller refreshes each source.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PollIntervals {
+    pub cex_price: Duration,
+    pub gas: Duration,
+    pub uniswap_v4: Duration,  // ~ one Ethereum block
+    pub uniswap_v4_reconcile: Duration, // full reload when V4 state is tracked from WS events
+    pub aerodrome: Duration,   // ~ one Base block
+    pub bridge_fees: Duration, // Across quotes move slowly
+    pub routes: Duration,      // full loads of every routing pool
+    pub split_venues: Duration, // full loads of every split-mode pool
+}
+
+impl Default for PollIntervals {
+    fn default() -> Self {
+        Self {
+            cex_price: Duration::from_millis(2_000),
+            gas: Duration::from_millis(12_000),
+            uniswap_v4: Duration::from_millis(12_000),
+            uniswap_v4_reconcile: Duration::from_millis(300_000),
+            aerodrome: Duration::from_millis(2_000),
+            bridge_fees: Duration::from_millis(60_000),
+            routes: Duration::from_millis(12_000),
+            split_venues: Duration::from_millis(12_000),
+        }
+    }
+}
+
+impl PollIntervals {
+    fn from_env() -> Self {
+        // Missing, unparsable or zero values keep the default
+        let ms = |key: &str, default: Duration| {
+            env::var(key).ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .filter(|ms| *ms > 0)
+                .map(Duration::from_millis)
+                .unwrap_or(default)
+        };
+        let d = Self::default();
+        Self {
+            cex_price: ms("POLL_CEX_PRICE_MS", d.cex_price),
+            gas: ms("POLL_GAS_MS", d.gas),
+            uniswap_v4: ms("POLL_UNISWAP_V4_MS", d.uniswap_v4),
+            uniswap_v4_reconcile: ms("POLL_UNISWAP_V4_RECONCILE_MS", d.uniswap_v4_reconcile),
+            aerodrome: ms("POLL_AERODROME_MS", d.aerodrome),
+            bridge_fees: ms("POLL_BRIDGE_FEES_MS", d.bridge_fees),
+            routes: ms("POLL_ROUTES_MS", d.routes),
+            split_venues: ms("POLL_SPLIT_VENUES_MS", d.split_venues),
+        }
+    }
+}
+
+/// L1 gas pricing; missing or unparsable values keep the default.
+fn gas_pricing_from_env() -> Eip1559Pricing {
+    let d = Eip1559Pricing::default();
+    Eip1559Pricing {
+        priority_fee_percentile: env::var("GAS_PRIORITY_FEE_PERCENTILE").ok()
+            .and_then(|v| v.parse::<f64>().ok())
+            .filter(|p| (0.0..=100.0).contains(p))
+            .unwrap_or(d.priority_fee_percentile),
+        base_fee_blocks_ahead: env::var("GAS_BASE_FEE_BLOCKS_AHEAD").ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|n| (0..=64).contains(n))
+            .unwrap_or(d.base_fee_blocks_ahead),
+        fee_history_blocks: env::var("GAS_FEE_HISTORY_BLOCKS").ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|n| (1..=1024).contains(n))
+            .unwrap_or(d.fee_history_blocks),
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct Config {
-    pub ethereum_rpc_url: String,
-    pub base_rpc_url: String,
-    pub uniswap_state_view: String,
+    // Every chain with an RPC URL set: Ethereum and Base always, Arbitrum, Optimism and
+    // Unichain when configured. Each carries its WETH/USDC and V4 StateView addresses.
+    pub chains: Vec<ChainConfig>,
+    pub ethereum_ws_url: Option<String>, // enables event-driven refresh on Ethereum
+    pub base_ws_url: Option<String>,     // enables event-driven refresh on Base
     pub cex_api_url: String,
     pub port: u16,
-    
-    // Ethereum token addresses
-    pub eth_weth_address: String,
-    pub eth_usdc_address: String,
-    
-    // Base network token addresses
-    pub base_weth_address: String,
-    pub base_usdc_address: String,
+    pub watchlist_path: String,
+    pub poll_intervals: PollIntervals,
     
     // Protocol addresses
     pub uniswap_universal_router: String,
+    pub uniswap_pool_manager: String,
     pub aerodrome_factory_address: String,
+    pub aerodrome_cl_factory_address: String, // Slipstream pools
     pub aerodrome_weth_usdc_volatile_pool: Option<String>,
     
     // Gas constants
@@ -26,6 +99,8 @@ pub struct Config {
     pub gas_uniswap_v4_settle_take_overhead: u64,
     pub gas_uniswap_v4_hook_overhead: u64,
     pub gas_aerodrome_swap: u64,
+    pub gas_uniswap_v3_swap: u64,
+    pub gas_pricing: Eip1559Pricing, // L1 base fee projection and priority-fee percentile
 }
 
 impl Config {
@@ -37,34 +112,28 @@ impl Config {
         dotenv::dotenv().ok();
 
         Ok(Config {
-            ethereum_rpc_url: env::var("ETHEREUM_RPC_URL")
-                .map_err(|_| "ETHEREUM_RPC_URL must be set")?,
-            base_rpc_url: env::var("BASE_RPC_URL")
-                .map_err(|_| "BASE_RPC_URL must be set")?,
-            uniswap_state_view: env::var("UNISWAP_V4_STATE_VIEW")
-                .map_err(|_| "UNISWAP_V4_STATE_VIEW must be set")?,
+            chains: ChainConfig::all_from_env()?,
+            ethereum_ws_url: env::var("ETHEREUM_WS_URL").ok().filter(|u| !u.is_empty()),
+            base_ws_url: env::var("BASE_WS_URL").ok().filter(|u| !u.is_empty()),
             cex_api_url: env::var("CEX_API_URL")
                 .unwrap_or_else(|_| "https://api.coinbase.com/v2/exchange-rates?currency=ETH".to_string()),
             port: env::var("PORT")
                 .unwrap_or_else(|_| "8000".to_string())
                 .parse()
                 .unwrap_or(8000),
-                
-            // Token addresses from environment
-            eth_weth_address: env::var("ETH_WETH_ADDRESS")
-                .map_err(|_| "ETH_WETH_ADDRESS must be set")?,
-            eth_usdc_address: env::var("ETH_USDC_ADDRESS")
-                .map_err(|_| "ETH_USDC_ADDRESS must be set")?,
-            base_weth_address: env::var("BASE_WETH_ADDRESS")
-                .map_err(|_| "BASE_WETH_ADDRESS must be set")?,
-            base_usdc_address: env::var("BASE_USDC_ADDRESS")
-                .map_err(|_| "BASE_USDC_ADDRESS must be set")?,
+            watchlist_path: env::var("WATCHLIST_PATH")
+                .unwrap_or_else(|_| "watchlist.toml".to_string()),
+            poll_intervals: PollIntervals::from_env(),
                 
             // Protocol addresses
             uniswap_universal_router: env::var("UNISWAP_V4_UNIVERSAL_ROUTER")
                 .map_err(|_| "UNISWAP_V4_UNIVERSAL_ROUTER must be set")?,
+            uniswap_pool_manager: env::var("UNISWAP_V4_POOL_ADDRESS") // the singleton PoolManager
+                .unwrap_or_else(|_| crate::chain::events::UNISWAP_V4_POOL_MANAGER.to_string()),
             aerodrome_factory_address: env::var("AERODROME_FACTORY_ADDRESS")
                 .map_err(|_| "AERODROME_FACTORY_ADDRESS must be set")?,
+            aerodrome_cl_factory_address: env::var("AERODROME_CL_FACTORY_ADDRESS")
+                .unwrap_or_else(|_| crate::chain::aerodrome_slipstream_client::AERODROME_CL_FACTORY.to_string()),
             aerodrome_weth_usdc_volatile_pool: env::var("AERODROME_WETH_USDC_VOLATILE_POOL").ok(),
                 
             // Gas constants
@@ -76,6 +145,14 @@ impl Config {
                 .unwrap_or_else(|_| "0".to_string()).parse().unwrap_or(0),
             gas_aerodrome_swap: env::var("GAS_AERODROME_SWAP")
                 .unwrap_or_else(|_| "185000".to_string()).parse().unwrap_or(185000),
+            gas_uniswap_v3_swap: env::var("GAS_UNISWAP_V3_SWAP")
+                .unwrap_or_else(|_| "130000".to_string()).parse().unwrap_or(130000),
+            gas_pricing: gas_pricing_from_env(),
         })
     }
+
+    /// The configured chain with `chain_id`, if its RPC URL is set.
+    pub fn chain(&self, chain_id: u64) -> Option<&ChainConfig> {
+        self.chains.iter().find(|c| c.chain_id == chain_id)
+    }
 }
\ No newline at end of file
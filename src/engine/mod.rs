@@ -1,4 +1,5 @@
 pub mod optimizer;
+pub mod poller;
 pub mod pricing;
 pub mod service;
-
+pub mod subscriptions;
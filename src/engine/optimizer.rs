@@ -1,64 +1,255 @@
 // src/arbitrage_optimizer.rs
 // ============================================================================
 // Optimizer that searches the best ETH trade size for cross-venue WETH/USDC arb
-// using your existing modules:
-//   - Uniswap: BigInt v3/v4 simulator (simulate_exact_in_tokens, etc.)
-//   - Aerodrome: volatile xy=k math (simulate_exact_in_volatile, volatile_amount_out)
+// over any set of venues (math::venue::Venue):
+//   - Uniswap V4 or V3: BigInt tick simulator on PoolState
+//   - Aerodrome: volatile xy=k or stable x³y+xy³ math (VolatilePairState::amount_out),
+//     or a Slipstream CL pool run through the same tick simulator as Uniswap
 //
-// Direction A: Sell on Aerodrome (ETH->USDC), Buy on Uniswap (USDC->ETH exact-output)
-// Direction B: Sell on Uniswap (ETH->USDC), Buy on Aerodrome (USDC->ETH exact-output)
+// Every ordered pair of venues is a direction: sell ETH->USDC (exact input) on one,
+// buy it back USDC->ETH (exact output) on the other. The best direction wins.
+// Split mode (optimize_split) instead spreads each leg over several venues, equalising
+// their marginal prices, and reports the per-venue sizes.
 //
-// Profit in USD: P(x) = proceeds_usdc(x) - cost_usdc(x) - gas_eth_usd - gas_base_usd - bridge_cost_usd
+// Profit in USD: P(x) = (proceeds_usdc(x) - cost_usdc(x)) * quote_usd - gas_sell_usd - gas_buy_usd
+//                       - (bridge_cost_usd + bridge_usd_per_eth * x)
 //
 // Assumptions:
-// - Decimals: WETH=18, USDC=6.
-// - Pool directions are determined by whether WETH is token0 in each module.
-// - Uniswap simulator is single-pool (no multi-hop); fee tier comes from PoolState.key or override.
+// - "ETH"/"WETH" and "USDC" name the base and quote token of the configured pair.
+// - Decimals: read on-chain; each venue carries the pair's tokens on its own chain.
+// - Pool directions are determined by whether WETH is token0 in each pool.
+// - One pool per leg, or a multi-hop route (math::route) when PairVenue::routed is given
+//   routing pools; fee tiers come from the pool states.
+// - The bridge cost applies only when the two venues are on different chains; two venues on
+//   one chain trade in a single transaction, so their gas is bundled (gas::legs_cost_usd).
 //
 // This file is sync (no RPC); you feed it fresh pool snapshots from your network layer.
 //
 
+use std::cell::RefCell;
+use std::sync::Arc;
 
-use num_traits::ToPrimitive;
+use ethers::types::U256;
 
-use crate::math::aerodrome_volatile::{
-    VolatilePairState, SwapDirection as AeroDir, simulate_exact_in_volatile,
-    volatile_amount_out as aero_amount_out, to_raw as aero_to_raw,
-    from_raw as aero_from_raw, spot_price_out_per_in,
-};
+use crate::math::aerodrome_volatile::VolatilePairState;
+use crate::math::amount::TokenAmount;
+use crate::math::route::{Route, RoutePool, RouteVenue};
+use crate::math::uniswap_v4::PoolState as UniPoolState;
+use crate::math::venue::Venue;
 
-use crate::math::uniswap_v4::{
-    // Uniswap BigInt simulator types and helpers
-    PoolState as UniPoolState,
-    SwapDirection as UniDir,
-    SwapResult as UniSwapResult,
-    simulate_exact_in_tokens as uni_exact_in,
-};
+use crate::chain::gas::{legs_cost_usd, GasEstimate};
+use crate::chain::tokens::TokenPair;
 
-use ethers::types::U256;
-use crate::chain::gas::GasEstimate;
+/// Detail reported alongside P(x): (proceeds_usd, costs_usd, sell_px, buy_px, gas_usd).
+type ProfitSnapshot = (f64, f64, f64, f64, f64);
+
+/// The pool behind the Base legs: Aerodrome, or a Uniswap V3 pool configured in its place.
+#[derive(Clone, Debug)]
+pub enum AerodromePoolState {
+    /// Volatile or stable pair: reserves and curve.
+    Pair(VolatilePairState),
+    /// Slipstream or Uniswap V3 concentrated-liquidity pool, with the Base token decimals.
+    Concentrated { pool: UniPoolState, tokens: TokenPair },
+}
+
+impl Venue for AerodromePoolState {
+    fn quote_exact_in(&self, zero_for_one: bool, amount_in: U256) -> Option<U256> {
+        match self {
+            AerodromePoolState::Pair(pair) => pair.quote_exact_in(zero_for_one, amount_in),
+            AerodromePoolState::Concentrated { pool, .. } => pool.quote_exact_in(zero_for_one, amount_in),
+        }
+    }
+
+    fn quote_exact_out(&self, zero_for_one: bool, amount_out: U256) -> Option<U256> {
+        match self {
+            AerodromePoolState::Pair(pair) => pair.quote_exact_out(zero_for_one, amount_out),
+            AerodromePoolState::Concentrated { pool, .. } => pool.quote_exact_out(zero_for_one, amount_out),
+        }
+    }
+
+    fn spot_price(&self) -> f64 {
+        match self {
+            AerodromePoolState::Pair(pair) => Venue::spot_price(pair),
+            AerodromePoolState::Concentrated { pool, .. } => Venue::spot_price(pool),
+        }
+    }
+
+    fn gas_units(&self) -> u64 {
+        match self {
+            AerodromePoolState::Pair(pair) => pair.gas_units,
+            AerodromePoolState::Concentrated { pool, .. } => pool.gas_units,
+        }
+    }
+
+    fn chain_id(&self) -> u64 {
+        match self {
+            AerodromePoolState::Pair(pair) => pair.chain_id,
+            AerodromePoolState::Concentrated { pool, .. } => pool.chain_id,
+        }
+    }
+}
+
+/// One venue as the optimizer trades it: the pool, plus the pair's orientation and decimals on it.
+#[derive(Clone, Debug)]
+pub struct PairVenue {
+    pub id: String,              // names the venue in directions, e.g. "uniswap", "aerodrome"
+    pub pool: Arc<dyn Venue>,
+    pub tokens: TokenPair,       // base/quote decimals on the venue's chain
+    pub token0_is_weth: bool,    // true if the pool's token0 == WETH
+    pub gas: GasEstimate,        // one swap through this venue, on its chain
+    pub gas_usd: f64,            // cost of one swap through this venue
+    pub route: Option<Arc<RouteVenue>>, // set by `routed`: each quote takes its own path and gas
+}
+
+/// One leg quoted at one size: USDC and effective price, the gas of the swap that gives them,
+/// and on a routed leg the path taken.
+#[derive(Clone, Debug)]
+pub struct LegQuote {
+    pub usdc: f64,
+    pub px: f64,
+    pub gas: GasEstimate,
+    pub route: Option<Route>,
+}
+
+impl PairVenue {
+    /// Gas is priced from the pool's gas units on `gas` (that chain's estimate);
+    /// a pool with no gas units set pays the estimate's whole total.
+    pub fn new(
+        id: impl Into<String>,
+        pool: impl Venue + 'static,
+        tokens: TokenPair,
+        token0_is_weth: bool,
+        gas: &GasEstimate,
+    ) -> Self {
+        let gas = gas.repriced_for(pool.gas_units());
+        let gas_usd = gas.total_usd;
+        PairVenue { id: id.into(), pool: Arc::new(pool), tokens, token0_is_weth, gas, gas_usd, route: None }
+    }
+
+    /// The same leg, routed: the best path through `pools` and this venue's own pool (the
+    /// direct swap is the one-hop route). Paths are compared net of their own gas, converted to
+    /// the leg's tokens at `quote_usd` and the pool's spot price, and each quote pays the gas of
+    /// the path it took; `gas`/`gas_usd` are those of the best-priced path.
+    pub fn routed(self, pools: &[RoutePool], max_hops: usize, gas: &GasEstimate, quote_usd: f64) -> Self {
+        if pools.is_empty() {
+            return self;
+        }
+        // One gas unit in raw quote, then in raw base at the direct pool's spot price
+        let quote_per_gas = if quote_usd > 0.0 { gas.usd_per_gas_unit() / quote_usd } else { 0.0 };
+        let spot = self.spot_price();
+        let base_per_gas = if spot > 0.0 { quote_per_gas / spot } else { 0.0 };
+        let raw = |human: f64, decimals: u8| human * 10f64.powi(decimals as i32);
+        let (quote_cost, base_cost) = (raw(quote_per_gas, self.tokens.quote.decimals), raw(base_per_gas, self.tokens.base.decimals));
+
+        let (base, quote) = (self.tokens.base.address, self.tokens.quote.address);
+        let (token0, token1, cost0, cost1) = if self.token0_is_weth {
+            (base, quote, base_cost, quote_cost)
+        } else {
+            (quote, base, quote_cost, base_cost)
+        };
+        let mut route_pools = vec![RoutePool { id: self.id.clone(), token0, token1, pool: self.pool }];
+        route_pools.extend_from_slice(pools);
+        let route = Arc::new(RouteVenue::new(route_pools, token0, token1, max_hops).with_gas_cost(cost0, cost1));
+        let gas = gas.repriced_for(route.gas_units());
+        PairVenue {
+            id: self.id,
+            pool: route.clone(),
+            tokens: self.tokens,
+            token0_is_weth: self.token0_is_weth,
+            gas_usd: gas.total_usd,
+            gas,
+            route: Some(route),
+        }
+    }
+
+    pub fn chain_id(&self) -> u64 {
+        self.pool.chain_id()
+    }
+
+    /// Sell leg: ETH->USDC exact input. Returns (usdc_out, eff px usdc/eth).
+    /// The search runs on f64 sizes; each is rounded to whole base units before quoting.
+    pub fn sell(&self, eth_in: f64) -> Option<(f64, f64)> {
+        self.sell_quote(eth_in).map(|q| (q.usdc, q.px))
+    }
+
+    /// Buy leg: USDC->ETH exact output. Returns (usdc_in, implied buy px usdc/eth).
+    pub fn buy(&self, eth_out: f64) -> Option<(f64, f64)> {
+        self.buy_quote(eth_out).map(|q| (q.usdc, q.px))
+    }
+
+    /// Sell leg with its gas and, when routed, its path.
+    pub fn sell_quote(&self, eth_in: f64) -> Option<LegQuote> {
+        let amount_in = self.base_amount(eth_in);
+        if amount_in.is_zero() { return None; }
+        let (raw_out, route) = match &self.route {
+            Some(venue) => {
+                let route = venue.route_exact_in(self.token0_is_weth, amount_in.raw)?;
+                (route.amount_out(), Some(route))
+            }
+            None => (self.pool.quote_exact_in(self.token0_is_weth, amount_in.raw)?, None),
+        };
+        let out = self.tokens.quote.amount(raw_out);
+        Some(LegQuote { usdc: out.to_f64(), px: out.per(&amount_in), gas: self.gas_for(route.as_ref()), route })
+    }
+
+    /// Buy leg with its gas and, when routed, its path.
+    pub fn buy_quote(&self, eth_out: f64) -> Option<LegQuote> {
+        let amount_out = self.base_amount(eth_out);
+        if amount_out.is_zero() {
+            return Some(LegQuote { usdc: 0.0, px: 0.0, gas: self.gas.clone(), route: None });
+        }
+        let (raw_in, route) = match &self.route {
+            Some(venue) => {
+                let route = venue.route_exact_out(!self.token0_is_weth, amount_out.raw)?;
+                (route.amount_in(), Some(route))
+            }
+            None => (self.pool.quote_exact_out(!self.token0_is_weth, amount_out.raw)?, None),
+        };
+        let need = self.tokens.quote.amount(raw_in);
+        Some(LegQuote { usdc: need.to_f64(), px: need.per(&amount_out), gas: self.gas_for(route.as_ref()), route })
+    }
+
+    // The chain's estimate for the path taken; the venue's own gas when not routed
+    fn gas_for(&self, route: Option<&Route>) -> GasEstimate {
+        route.map_or_else(|| self.gas.clone(), |r| self.gas.repriced_for(r.gas_units))
+    }
+
+    // Zero for negative or non-finite sizes, which the legs treat as "nothing to trade"
+    fn base_amount(&self, eth: f64) -> TokenAmount {
+        let decimals = self.tokens.base.decimals;
+        TokenAmount::from_f64(eth, decimals).unwrap_or(TokenAmount::zero(decimals))
+    }
+
+    /// Marginal USDC per ETH, before fees.
+    pub fn spot_price(&self) -> f64 {
+        let raw = self.pool.spot_price(); // token1 per token0
+        let quote_per_base = if self.token0_is_weth { raw } else if raw > 0.0 { 1.0 / raw } else { 0.0 };
+        quote_per_base * 10f64.powi(self.tokens.base.decimals as i32 - self.tokens.quote.decimals as i32)
+    }
+}
 
 /// All inputs the optimizer needs for one run.
 #[derive(Clone, Debug)]
 pub struct OptimizerInputs {
-    // On-chain snapshots provided by your data layer
-    pub uni_pool: UniPoolState,           // Uniswap v3/v4-like pool (single pool)
-    pub uni_token0_is_weth: bool,         // true if pool.currency0 == WETH
-    pub uni_fee_ppm_override: Option<u32>,// override fee ppm (else use pool.key.fee_ppm)
-
-    pub aero_pair: VolatilePairState,     // Aerodrome volatile pool snapshot
-    pub aero_token0_is_weth: bool,        // true if pair.token0 == WETH
+    // On-chain snapshots provided by your data layer, with gas priced per venue
+    pub venues: Vec<PairVenue>,
 
     // Costs
-    pub gas_eth: GasEstimate,   // Ethereum (Uniswap side)
-    pub gas_base: GasEstimate,  // Base (Aerodrome side)
-    pub bridge_cost_usd: f64, // optional amortized bridge/rebalance cost per trade (can be 0.0)
+    pub bridge_cost_usd: f64,    // fixed bridge/rebalance cost per cross-chain trade (can be 0.0)
+    pub bridge_usd_per_eth: f64, // plus this much per ETH traded
+    pub quote_usd: f64,          // USD per quote token: legs quote in it, gas and bridge are in USD
 
     // Search configuration
     pub hint_size_eth: f64, // initial guess (e.g., 1.0)
     pub max_size_eth: f64,  // safety cap (e.g., 200.0)
 }
 
+impl OptimizerInputs {
+    pub fn venue(&self, id: &str) -> Option<&PairVenue> {
+        self.venues.iter().find(|v| v.id == id)
+    }
+}
+
 /// Result of the optimizer.
 #[derive(Clone, Debug)]
 pub struct OptimizeResult {
@@ -71,267 +262,317 @@ pub struct OptimizeResult {
     pub net_profit_usd: f64,
     pub eff_price_sell_usdc_per_eth: f64, // at optimal size, on the sell venue
     pub eff_price_buy_usdc_per_eth: f64,  // implied on buy venue (usdc needed / eth out)
+    pub sell_route: Option<Route>,        // path of a routed sell leg at the optimal size
+    pub buy_route: Option<Route>,         // path of a routed buy leg at the optimal size
 }
 
-/// Two arbitrage directions.
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
-pub enum ArbDirection {
-    SellAeroBuyUni, // Sell ETH->USDC on Aerodrome, buy USDC->ETH on Uniswap
-    SellUniBuyAero, // Sell ETH->USDC on Uniswap,  buy USDC->ETH on Aerodrome
+/// An arbitrage direction: sell ETH->USDC on `sell`, buy USDC->ETH on `buy` (venue ids).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ArbDirection {
+    pub sell: String,
+    pub buy: String,
+}
+
+impl ArbDirection {
+    pub fn new(sell: impl Into<String>, buy: impl Into<String>) -> Self {
+        ArbDirection { sell: sell.into(), buy: buy.into() }
+    }
+
+    /// API label, e.g. "SELL_AERODROME_BUY_UNISWAP".
+    pub fn label(&self) -> String {
+        format!("SELL_{}_BUY_{}", self.sell.to_uppercase(), self.buy.to_uppercase())
+    }
+}
+
+/// The two venues of one direction.
+#[derive(Clone, Copy)]
+struct Legs<'a> {
+    sell: &'a PairVenue,
+    buy: &'a PairVenue,
+}
+
+impl Legs<'_> {
+    // Chain 0 means "not from chain": assume the legs are apart
+    fn same_chain(&self) -> bool {
+        let chain = self.sell.chain_id();
+        chain != 0 && chain == self.buy.chain_id()
+    }
 }
 
 // ------------------------------ Public entry ---------------------------------
 
-/// Optimize both directions and return the best candidate.
-/// Returns None if both directions are non-profitable for all sizes.
+/// Optimize every ordered pair of venues and return the best candidate.
+/// Returns None if no direction is profitable for any size.
 pub fn optimize(inputs: &OptimizerInputs) -> Option<OptimizeResult> {
-    let a = maximize_direction(inputs, ArbDirection::SellAeroBuyUni);
-    let b = maximize_direction(inputs, ArbDirection::SellUniBuyAero);
-
-    let best = match (a, b) {
-        (Some(a_result), Some(b_result)) => {
-            if a_result.net_profit_usd >= b_result.net_profit_usd { a_result } else { b_result }
-        },
-        (Some(a_result), None) => a_result,
-        (None, Some(b_result)) => b_result,
-        (None, None) => return None,
-    };
+    let mut best: Option<OptimizeResult> = None;
+    for (i, sell) in inputs.venues.iter().enumerate() {
+        for (j, buy) in inputs.venues.iter().enumerate() {
+            if i == j { continue; }
+            let Some(candidate) = maximize_direction(inputs, Legs { sell, buy }) else { continue };
+            if best.as_ref().is_none_or(|b| candidate.net_profit_usd > b.net_profit_usd) {
+                best = Some(candidate);
+            }
+        }
+    }
 
     // Only return result if it's actually profitable
-    if best.net_profit_usd > 0.0 {
-        Some(best)
-    } else {
-        None
-    }
+    best.filter(|b| b.net_profit_usd > 0.0)
+}
+
+/// Optimize one direction only, for callers whose bridge cost depends on it.
+/// Returns None if it isn't profitable for any size, or names an unknown venue.
+pub fn optimize_direction(inputs: &OptimizerInputs, direction: &ArbDirection) -> Option<OptimizeResult> {
+    let legs = Legs { sell: inputs.venue(&direction.sell)?, buy: inputs.venue(&direction.buy)? };
+    maximize_direction(inputs, legs).filter(|r| r.net_profit_usd > 0.0)
 }
 
 // ------------------------------ Core maximize --------------------------------
 
-fn maximize_direction(inputs: &OptimizerInputs, dir: ArbDirection) -> Option<OptimizeResult> {
+fn maximize_direction(inputs: &OptimizerInputs, legs: Legs) -> Option<OptimizeResult> {
+    let eval = |x: f64| profit_with_snapshot(inputs, legs, x);
     // 1) Bracket with exponential growth from hint
-    let (l, r) = bracket_profit(inputs, dir, inputs.hint_size_eth, inputs.max_size_eth)?;
+    let (l, r) = bracket_profit(&eval, inputs.hint_size_eth, inputs.max_size_eth)?;
     // 2) Golden-section search (few evals, robust)
-    let (x_star, p_star, snapshot) = golden_search(inputs, dir, l, r)?;
+    let (x_star, p_star, snapshot) = golden_search(&eval, l, r)?;
     // 3) Build final struct
-    let (proceeds_usd, costs_usd, sell_px, buy_px) = snapshot;
-    let gas_total = inputs.gas_eth.total_usd + inputs.gas_base.total_usd;
-    let net = p_star;
+    let (proceeds_usd, costs_usd, sell_px, buy_px, gas_usd_total) = snapshot;
 
     Some(OptimizeResult {
-        direction: dir,
+        direction: ArbDirection::new(&legs.sell.id, &legs.buy.id),
         optimal_size_eth: x_star,
         proceeds_usd,
         costs_usd,
-        gas_usd_total: gas_total,
-        bridge_cost_usd: inputs.bridge_cost_usd,
-        net_profit_usd: net,
+        gas_usd_total,
+        bridge_cost_usd: bridge_cost(inputs, legs, x_star),
+        net_profit_usd: p_star,
         eff_price_sell_usdc_per_eth: sell_px,
         eff_price_buy_usdc_per_eth: buy_px,
+        sell_route: legs.sell.sell_quote(x_star).and_then(|q| q.route),
+        buy_route: legs.buy.buy_quote(x_star).and_then(|q| q.route),
     })
 }
 
 // ---------------------------- Profit evaluators ------------------------------
 
+fn bridge_cost(inputs: &OptimizerInputs, legs: Legs, x_eth: f64) -> f64 {
+    if legs.same_chain() { 0.0 } else { inputs.bridge_cost_usd + inputs.bridge_usd_per_eth * x_eth }
+}
+
 /// Evaluate P(x) and also return detail snapshot for reporting:
 /// (proceeds_usd, costs_usd, sell_px, buy_px).
-fn profit_with_snapshot(inputs: &OptimizerInputs, dir: ArbDirection, x_eth: f64)
-    -> Option<(f64 /*P*/, (f64,f64,f64,f64) /*snapshot*/)>
+fn profit_with_snapshot(inputs: &OptimizerInputs, legs: Legs, x_eth: f64)
+    -> Option<(f64 /*P*/, ProfitSnapshot)>
 {
     if x_eth <= 0.0 { return None; }
 
-    // gas + bridge
-    let gas_total = inputs.gas_eth.total_usd + inputs.gas_base.total_usd;
-    let bridge_cost = inputs.bridge_cost_usd;
-
-    match dir {
-        ArbDirection::SellAeroBuyUni => {
-            // 1) Sell on Aerodrome: ETH -> USDC (exact input)
-            let mut sell_px: f64 = 0.0; // USDC per ETH (effective)
-            let usdc_out = aero_usdc_out_for_weth_in(&inputs.aero_pair, inputs.aero_token0_is_weth, x_eth, &mut Some(&mut |px| { sell_px = px; }))?;
-            // 2) Buy on Uniswap: USDC -> WETH exact-output (need USDC in to get x ETH)
-            let (usdc_in, buy_px) = uni_usdc_in_for_weth_out(&inputs.uni_pool, inputs.uni_token0_is_weth, inputs.uni_fee_ppm_override, x_eth)?;
-            // 3) Profit
-            let proceeds = usdc_out;
-            let costs    = usdc_in;
-            let p = proceeds - costs - gas_total - bridge_cost;
-            Some((p, (proceeds, costs, sell_px, buy_px)))
-        }
-        ArbDirection::SellUniBuyAero => {
-            // 1) Sell on Uniswap: WETH -> USDC (exact input)
-            let (usdc_out, sell_px) = uni_usdc_out_for_weth_in(&inputs.uni_pool, inputs.uni_token0_is_weth, inputs.uni_fee_ppm_override, x_eth)?;
-            // 2) Buy on Aerodrome: USDC -> WETH exact-output (need USDC in to get x ETH)
-            let usdc_in = aero_usdc_in_for_weth_out(&inputs.aero_pair, inputs.aero_token0_is_weth, x_eth)?;
-            let buy_px = if x_eth > 0.0 { usdc_in / x_eth } else { 0.0 };
-            // 3) Profit
-            let proceeds = usdc_out;
-            let costs    = usdc_in;
-            let p = proceeds - costs - gas_total - bridge_cost;
-            Some((p, (proceeds, costs, sell_px, buy_px)))
-        }
-    }
+    // 1) Sell: ETH -> USDC (exact input)
+    let sell = legs.sell.sell_quote(x_eth)?;
+    // 2) Buy: USDC -> ETH exact-output (need USDC in to get x ETH back)
+    let buy = legs.buy.buy_quote(x_eth)?;
+    // 3) Profit, net of the gas of the paths taken + bridge
+    let (proceeds_usd, costs_usd) = (sell.usdc * inputs.quote_usd, buy.usdc * inputs.quote_usd);
+    let gas_usd = legs_cost_usd(&sell.gas, &buy.gas, legs.same_chain());
+    let p = proceeds_usd - costs_usd - gas_usd - bridge_cost(inputs, legs, x_eth);
+    Some((p, (proceeds_usd, costs_usd, sell.px, buy.px, gas_usd)))
 }
 
-// Aerodrome leg: ETH->USDC exact input (sell). Also compute effective price.
-fn aero_usdc_out_for_weth_in(
-    pair: &VolatilePairState,
-    token0_is_weth: bool,
-    eth_in: f64,
-    eff_px_sink: &mut Option<&mut dyn FnMut(f64)>
-) -> Option<f64> {
-    // Use the flag to determine direction, not address checks
-    let direction = if token0_is_weth { AeroDir::ZeroForOne } else { AeroDir::OneForZero };
-    let (_ain_raw, aout_raw, eff, _spot, _imp) = simulate_exact_in_volatile(pair, direction, eth_in);
-    if let Some(cb) = eff_px_sink.as_deref_mut() {
-        cb(eff); // USDC per ETH - eff is already tokenOut per tokenIn
-    }
-    // Output decimals depend on which token is USDC
-    let out_decimals = if token0_is_weth { pair.decimals1 } else { pair.decimals0 };
-    Some(aero_from_raw(aout_raw, out_decimals))
+// ------------------------------ Split mode -----------------------------------
+
+/// Steps of `max_size_eth` each leg is handed out in; marginal prices end up equal to within one step.
+const SPLIT_STEPS: usize = 100;
+
+/// One venue's share of a split leg.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VenueFill {
+    pub venue: String,
+    pub size_eth: f64,
+    pub usdc: f64, // received on a sell, paid on a buy
 }
 
-// Aerodrome leg: USDC->WETH exact output (buy): find USDC needed to receive target ETH.
-fn aero_usdc_in_for_weth_out(
-    pair: &VolatilePairState,
-    token0_is_weth: bool,
-    target_eth_out: f64,
-) -> Option<f64> {
-    // Map reserves for USDC->WETH (input = USDC, output = WETH)
-    // Use the flag consistently to determine which token is which
-    let (rin, rout, din, dout) = if token0_is_weth {
-        // token0=WETH, token1=USDC: USDC->WETH means OneForZero (token1 in -> token0 out)
-        (pair.reserve1, pair.reserve0, pair.decimals1, pair.decimals0)
-    } else {
-        // token1=WETH, token0=USDC: USDC->WETH means ZeroForOne (token0 in -> token1 out)
-        (pair.reserve0, pair.reserve1, pair.decimals0, pair.decimals1)
-    };
+/// Result of the split optimizer: both legs spread over several venues.
+#[derive(Clone, Debug)]
+pub struct SplitResult {
+    pub optimal_size_eth: f64,
+    pub sells: Vec<VenueFill>, // venues that got part of the sell leg
+    pub buys: Vec<VenueFill>,  // venues that got part of the buy leg
+    pub proceeds_usd: f64,
+    pub costs_usd: f64,
+    pub gas_usd_total: f64,    // one transaction per chain used, bundling its venues' swaps
+    pub bridge_cost_usd: f64,
+    pub net_profit_usd: f64,
+}
 
-    let target_raw = aero_to_raw(target_eth_out, dout);
-    if target_raw.is_zero() { return Some(0.0); }
+/// Split mode: sell on the richer venues and buy on the cheaper ones, each leg allocated across
+/// its venues by equalising marginal prices. Venues are ranked by spot price and every cut
+/// between sellers and buyers is tried. Returns None if no split is profitable.
+pub fn optimize_split(inputs: &OptimizerInputs) -> Option<SplitResult> {
+    optimize_split_with(inputs, |_, _, x_eth| inputs.bridge_cost_usd + inputs.bridge_usd_per_eth * x_eth)
+}
 
-    // Binary search on amountIn so that aero_amount_out(amountIn) >= target_raw.
-    let mut lo = U256::zero();
-    let mut hi = {
-        // conservative upper bound: start from spot*target, inflate 4x
-        let spot = spot_price_out_per_in(rin, rout, din, dout);
-        let approx_usdc = if spot.is_finite() && spot > 0.0 {
-            target_eth_out / spot
-        } else {
-            target_eth_out * 10_000.0
-        };
-        let guess = aero_to_raw(approx_usdc * 4.0, din);
-        let cap = U256::from_dec_str("1000000000000000000000000000000")
-            .unwrap_or_else(|_| U256::max_value()); // 1e30
-        if guess > cap { cap } else { guess }
-    };
+/// `optimize_split`, with each candidate's rebalance fee taken from
+/// `bridge_usd(sell_chains, buy_chains, size_eth)` for the chains its fills actually use,
+/// rather than from the inputs' single bridge fee.
+pub fn optimize_split_with(inputs: &OptimizerInputs, bridge_usd: impl Fn(&[u64], &[u64], f64) -> f64) -> Option<SplitResult> {
+    let step = inputs.max_size_eth / SPLIT_STEPS as f64;
+    if step.is_nan() || step <= 0.0 { return None; }
+    let mut ranked: Vec<&PairVenue> = inputs.venues.iter().collect();
+    ranked.sort_by(|a, b| b.spot_price().total_cmp(&a.spot_price()));
 
-    let fee = pair.fee_bps;
-    for _ in 0..64 {
-        if lo >= hi { break; }
-        let mid = (lo + hi) >> 1;
-        let out = aero_amount_out(mid, rin, rout, fee);
-        if out < target_raw { lo = mid + U256::from(1u8); } else { hi = mid; }
+    let mut best: Option<SplitResult> = None;
+    for cut in 1..ranked.len() {
+        let (sellers, buyers) = ranked.split_at(cut);
+        let ladders = RefCell::new((Ladder::new(sellers, Side::Sell, step), Ladder::new(buyers, Side::Buy, step)));
+        let eval = |x: f64| {
+            let (sell, buy) = &mut *ladders.borrow_mut();
+            split_profit(inputs, &bridge_usd, sell, buy, x)
+        };
+        let Some((l, r)) = bracket_profit(&eval, inputs.hint_size_eth, inputs.max_size_eth) else { continue };
+        let Some((_, _, candidate)) = golden_search(&eval, l, r) else { continue };
+        if best.as_ref().is_none_or(|b| candidate.net_profit_usd > b.net_profit_usd) {
+            best = Some(candidate);
+        }
     }
-    Some(aero_from_raw(hi, din))
+    best.filter(|b| b.net_profit_usd > 0.0)
 }
 
-// Uniswap leg: WETH->USDC exact input (sell)
-fn uni_usdc_out_for_weth_in(
-    pool: &UniPoolState,
-    token0_is_weth: bool,
-    fee_override_ppm: Option<u32>,
-    eth_in: f64,
-) -> Option<(f64 /*usdc_out*/, f64 /*eff px usdc/eth*/)> {
-    let direction = if token0_is_weth { UniDir::ZeroForOne } else { UniDir::OneForZero };
-    let res: UniSwapResult = uni_exact_in(pool, direction, fee_override_ppm, eth_in, 18, None).ok()?;
-    // Extract human amounts based on direction
-    let (in_eth, out_usdc) = if token0_is_weth {
-        // ZeroForOne: amount0 (WETH) is negative (in), amount1 (USDC) is positive (out)
-        ((-res.amount0.clone()).to_f64().unwrap_or(0.0) / 1e18,
-         res.amount1.clone().to_f64().unwrap_or(0.0) / 1e6)
-    } else {
-        // OneForZero: amount1 (WETH) is negative (in), amount0 (USDC) is positive (out)
-        ((-res.amount1.clone()).to_f64().unwrap_or(0.0) / 1e18,
-         res.amount0.clone().to_f64().unwrap_or(0.0) / 1e6)
+fn split_profit(
+    inputs: &OptimizerInputs,
+    bridge_usd: &impl Fn(&[u64], &[u64], f64) -> f64,
+    sell: &mut Ladder,
+    buy: &mut Ladder,
+    x_eth: f64,
+) -> Option<(f64, SplitResult)> {
+    if x_eth <= 0.0 { return None; }
+    let sells = sell.fill(x_eth)?;
+    let buys = buy.fill(x_eth)?;
+
+    let used: Vec<&PairVenue> = sell.venues.iter().chain(buy.venues)
+        .filter(|v| sells.iter().chain(&buys).any(|f| f.venue == v.id))
+        .copied()
+        .collect();
+    let gas_usd_total = bundled_gas_usd(&used);
+    // Chain 0 means "not from chain": assume the venues are apart
+    let one_chain = used.first().is_some_and(|v| v.chain_id() != 0 && used.iter().all(|u| u.chain_id() == v.chain_id()));
+    let chains = |ladder: &Ladder, fills: &[VenueFill]| -> Vec<u64> {
+        ladder.venues.iter().filter(|v| fills.iter().any(|f| f.venue == v.id)).map(|v| v.chain_id()).collect()
     };
-    let eff = if in_eth > 0.0 { out_usdc / in_eth } else { 0.0 };
-    Some((out_usdc, eff))
+    let bridge_cost_usd = if one_chain { 0.0 } else { bridge_usd(&chains(sell, &sells), &chains(buy, &buys), x_eth) };
+
+    let proceeds_usd = sells.iter().map(|f| f.usdc).sum::<f64>() * inputs.quote_usd;
+    let costs_usd = buys.iter().map(|f| f.usdc).sum::<f64>() * inputs.quote_usd;
+    let net_profit_usd = proceeds_usd - costs_usd - gas_usd_total - bridge_cost_usd;
+    Some((net_profit_usd, SplitResult {
+        optimal_size_eth: x_eth,
+        sells,
+        buys,
+        proceeds_usd,
+        costs_usd,
+        gas_usd_total,
+        bridge_cost_usd,
+        net_profit_usd,
+    }))
 }
 
-// Uniswap leg: USDC->WETH exact output (buy) — find USDC in so that WETH out >= target.
-fn uni_usdc_in_for_weth_out(
-    pool: &UniPoolState,
-    token0_is_weth: bool,
-    fee_override_ppm: Option<u32>,
-    target_eth_out: f64,
-) -> Option<(f64 /*usdc_in*/, f64 /*implied buy px usdc/eth*/)> {
-    // Simulate exact-input USDC->WETH repeatedly with binary search on USDC in.
-    // Direction for USDC->WETH:
-    let direction = if token0_is_weth { UniDir::OneForZero } else { UniDir::ZeroForOne };
-
-    if target_eth_out <= 0.0 { return Some((0.0, 0.0)); }
-
-    // Initial bracket on USDC input
-    let mut lo = 0.0_f64;
-    // spot guess: price USDC/ETH via pool.tick (we can approximate via a small simulation)
-    let spot_usdc_per_eth = spot_usdc_per_eth_uniswap(pool, token0_is_weth).max(1.0);
-    let mut hi = target_eth_out * spot_usdc_per_eth * 4.0; // generous
-
-    // Guard rails
-    hi = hi.min(1.0e12); // $1T cap to avoid runaway in degenerate pools
-
-    for _ in 0..64 {
-        let mid = (lo + hi) * 0.5;
-        let res = uni_exact_in(pool, direction, fee_override_ppm, mid, 6 /*USDC*/, None).ok()?;
-        // ETH out (depends on direction)
-        let eth_out = match direction {
-            UniDir::OneForZero => (res.amount0.clone()).to_f64().unwrap_or(0.0) / 1e18, // token0=WETH out
-            UniDir::ZeroForOne => (res.amount1.clone()).to_f64().unwrap_or(0.0) / 1e18, // token1=WETH out
-        };
-        if eth_out >= target_eth_out { hi = mid; } else { lo = mid; }
-        if (hi - lo) / hi.max(1.0) < 1e-4 { break; } // 1 bp in input
+// Gas of a swap on each of `venues`: the swaps on one chain go out as one transaction, bundled
+// as pair mode bundles two same-chain legs. Chain 0 ("not from chain") pays each swap alone.
+fn bundled_gas_usd(venues: &[&PairVenue]) -> f64 {
+    let mut per_chain: Vec<(u64, GasEstimate)> = Vec::new();
+    for v in venues {
+        match per_chain.iter_mut().find(|(chain, _)| *chain != 0 && *chain == v.chain_id()) {
+            Some((_, tx)) => *tx = tx.bundled_with(&v.gas),
+            None => per_chain.push((v.chain_id(), v.gas.clone())),
+        }
     }
-    let usdc_in = hi;
-    let buy_px = usdc_in / target_eth_out;
-    Some((usdc_in, buy_px))
+    per_chain.iter().map(|(_, tx)| tx.total_usd).sum()
 }
 
-fn spot_usdc_per_eth_uniswap(pool: &UniPoolState, token0_is_weth: bool) -> f64 {
-    // Use a tiny trade to sample effective price close to spot.
-    let tiny = 0.0001_f64;
-    let direction = if token0_is_weth { UniDir::ZeroForOne } else { UniDir::OneForZero };
-    let res = uni_exact_in(pool, direction, None, tiny, 18, None);
-    if let Ok(r) = res {
-        let (eth_in, usdc_out) = if token0_is_weth {
-            // ZeroForOne: WETH (token0) in, USDC (token1) out
-            ((-r.amount0.clone()).to_f64().unwrap_or(0.0) / 1e18,
-             r.amount1.clone().to_f64().unwrap_or(0.0) / 1e6)
-        } else {
-            // OneForZero: WETH (token1) in, USDC (token0) out
-            ((-r.amount1.clone()).to_f64().unwrap_or(0.0) / 1e18,
-             r.amount0.clone().to_f64().unwrap_or(0.0) / 1e6)
-        };
-        if eth_in > 0.0 { usdc_out / eth_in } else { 0.0 }
-    } else { 0.0 }
+#[derive(Clone, Copy)]
+enum Side { Sell, Buy }
+
+// USDC out for selling `size_eth` on `venue`, or in for buying it
+fn leg_quote(side: Side, venue: &PairVenue, size_eth: f64) -> Option<f64> {
+    match side {
+        Side::Sell => venue.sell(size_eth).map(|q| q.0),
+        Side::Buy => venue.buy(size_eth).map(|q| q.0),
+    }
+}
+
+/// One leg filled greedily in fixed steps: each step goes to the venue whose next step is worth
+/// most (most USDC out on a sell, least USDC in on a buy). The fill order doesn't depend on the
+/// total, so every size the search tries reads off one ladder, extended on demand.
+struct Ladder<'a> {
+    venues: &'a [&'a PairVenue],
+    side: Side,
+    step: f64,
+    rungs: Vec<Vec<(f64, f64)>>, // (eth, usdc) per venue after each step; rungs[0] is empty
+    next: Vec<Option<f64>>,      // usdc per venue after one more step; None once it can't take one
+}
+
+impl<'a> Ladder<'a> {
+    fn new(venues: &'a [&'a PairVenue], side: Side, step: f64) -> Self {
+        let next = venues.iter().map(|v| leg_quote(side, v, step)).collect();
+        Ladder { venues, side, step, rungs: vec![vec![(0.0, 0.0); venues.len()]], next }
+    }
+
+    // Give the next step to the best venue; None once no venue can take it
+    fn climb(&mut self) -> Option<()> {
+        let mut rung = self.rungs.last()?.clone();
+        let side = self.side;
+        let (i, _) = self.next.iter().enumerate()
+            .filter_map(|(i, usdc)| usdc.map(|u| (i, u - rung[i].1)))
+            .reduce(|a, b| {
+                let better = match side { Side::Sell => b.1 > a.1, Side::Buy => b.1 < a.1 };
+                if better { b } else { a }
+            })?;
+        rung[i] = (rung[i].0 + self.step, self.next[i]?);
+        self.next[i] = leg_quote(side, self.venues[i], rung[i].0 + self.step);
+        self.rungs.push(rung);
+        Some(())
+    }
+
+    /// Fills for `x_eth`: the whole steps below it, plus the rest on the venue next in line.
+    fn fill(&mut self, x_eth: f64) -> Option<Vec<VenueFill>> {
+        let whole = (x_eth / self.step).floor() as usize;
+        let rest = x_eth - whole as f64 * self.step;
+        let partial = rest > self.step * 1e-9;
+        while self.rungs.len() <= whole + usize::from(partial) {
+            self.climb()?;
+        }
+
+        let mut rung = self.rungs[whole].clone();
+        if partial {
+            let after = &self.rungs[whole + 1];
+            let i = (0..rung.len()).find(|&i| after[i].0 > rung[i].0)?;
+            rung[i] = (rung[i].0 + rest, leg_quote(self.side, self.venues[i], rung[i].0 + rest)?);
+        }
+        Some(self.venues.iter().zip(rung)
+            .filter(|(_, (eth, _))| *eth > 0.0)
+            .map(|(v, (size_eth, usdc))| VenueFill { venue: v.id.clone(), size_eth, usdc })
+            .collect())
+    }
 }
 
 // -------------------------- Bracket + golden search --------------------------
 
-fn bracket_profit(
-    inputs: &OptimizerInputs,
-    dir: ArbDirection,
+// Both searches maximize P(x) given by `eval`, which also returns the detail to report at x.
+
+fn bracket_profit<S>(
+    eval: &impl Fn(f64) -> Option<(f64, S)>,
     mut x0: f64,
     x_cap: f64,
 ) -> Option<(f64 /*left*/, f64 /*right*/)> {
-    x0 = x0.max(1e-9);
+    // A refused size (e.g. past a pool's loaded ticks) is the right edge, not the end of the search
+    let x_cap = quotable_cap(eval, x_cap)?;
+    x0 = x0.max(1e-9).min(x_cap);
     let mut best_x = x0;
-    let mut best_p = profit(inputs, dir, x0)?;
+    let mut best_p = profit(eval, x0)?;
     let mut l = (x0 * 0.5).max(1e-9);
     let mut r = x0;
 
     // Grow exponentially until profit starts dropping (or cap)
     for _ in 0..16 {
         let x_try = (r * 2.0).min(x_cap);
-        let p_try = profit(inputs, dir, x_try)?;
+        let p_try = profit(eval, x_try)?;
         if p_try > best_p {
             best_p = p_try;
             best_x = x_try;
@@ -347,31 +588,44 @@ fn bracket_profit(
     Some((best_x * 0.5, (best_x * 2.0).min(x_cap)))
 }
 
-fn golden_search(
-    inputs: &OptimizerInputs,
-    dir: ArbDirection,
+/// Largest size up to `x_cap` that `eval` still prices. Sizes a venue refuses stay refused as
+/// they grow (a fill past the loaded ticks or the reserves), so the edge is found by bisection.
+fn quotable_cap<S>(eval: &impl Fn(f64) -> Option<(f64, S)>, x_cap: f64) -> Option<f64> {
+    if profit(eval, x_cap).is_some() {
+        return Some(x_cap);
+    }
+    let (mut lo, mut hi) = (0.0, x_cap);
+    for _ in 0..24 {
+        let mid = 0.5 * (lo + hi);
+        if profit(eval, mid).is_some() { lo = mid } else { hi = mid }
+    }
+    (lo > 0.0).then_some(lo)
+}
+
+fn golden_search<S: Clone>(
+    eval: &impl Fn(f64) -> Option<(f64, S)>,
     mut a: f64,
     mut b: f64,
-) -> Option<(f64 /*x**/, f64 /*P**/, (f64,f64,f64,f64) /*snapshot*/)> {
+) -> Option<(f64 /*x**/, f64 /*P**/, S)> {
     let phi = 0.5 * (3.0_f64.sqrt() + 1.0); // golden ratio ~1.618
     let tol = 1e-3; // 0.1% relative interval width
     let mut c = b - (b - a) / phi;
     let mut d = a + (b - a) / phi;
 
-    let (mut pc, mut sc) = profit_with_snapshot_full(inputs, dir, c)?;
-    let (mut pd, mut sd) = profit_with_snapshot_full(inputs, dir, d)?;
+    let (mut pc, mut sc) = eval(c)?;
+    let (mut pd, mut sd) = eval(d)?;
 
     for _ in 0..24 {
         if (b - a) / b.max(1.0) < tol { break; }
         if pc > pd {
-            b = d; d = c; pd = pc; sd = sc;
+            b = d; d = c; pd = pc; sd = sc.clone();
             c = b - (b - a) / phi;
-            let (p, snap) = profit_with_snapshot_full(inputs, dir, c)?;
+            let (p, snap) = eval(c)?;
             pc = p; sc = snap;
         } else {
-            a = c; c = d; pc = pd; sc = sd;
+            a = c; c = d; pc = pd; sc = sd.clone();
             d = a + (b - a) / phi;
-            let (p, snap) = profit_with_snapshot_full(inputs, dir, d)?;
+            let (p, snap) = eval(d)?;
             pd = p; sd = snap;
         }
     }
@@ -380,19 +634,11 @@ fn golden_search(
 }
 
 #[inline]
-fn profit(inputs: &OptimizerInputs, dir: ArbDirection, x_eth: f64) -> Option<f64> {
-    let p = profit_with_snapshot(inputs, dir, x_eth)?.0;
+fn profit<S>(eval: &impl Fn(f64) -> Option<(f64, S)>, x_eth: f64) -> Option<f64> {
+    let p = eval(x_eth)?.0;
     if p.is_finite() { Some(p) } else { None }
 }
 
-fn profit_with_snapshot_full(
-    inputs: &OptimizerInputs,
-    dir: ArbDirection,
-    x_eth: f64,
-) -> Option<(f64, (f64,f64,f64,f64))> {
-    profit_with_snapshot(inputs, dir, x_eth)
-}
-
 
 #[cfg(test)]
 mod tests {
@@ -400,12 +646,13 @@ mod tests {
     use crate::math::aerodrome_volatile::VolatilePairState;
     use crate::math::uniswap_v4::PoolState as UniPoolState;
     use crate::chain::gas::GasEstimate;
+    use crate::chain::tokens::TokenInfo;
     use ethers::types::{Address, U256};
     use num_bigint::BigInt;
 
     fn create_test_uni_pool() -> UniPoolState {
         // Create a simple test pool with reasonable liquidity
-        crate::math::uniswap_v4::create_pool_with_real_data(
+        let mut pool = crate::math::uniswap_v4::create_pool_with_real_data(
             Address::zero(), // WETH
             Address::from([0x11; 20]), // USDC
             3000, // 0.3% fee
@@ -418,7 +665,17 @@ mod tests {
                 (-200000, BigInt::from(1000000000000000000u128)),
                 (-180000, -BigInt::from(1000000000000000000u128)),
             ],
-        )
+        );
+        pool.chain_id = 1;
+        pool
+    }
+
+    fn create_test_tokens() -> TokenPair {
+        TokenPair::new(1, TokenInfo::native(), TokenInfo::new(Address::from([0x11; 20]), 6))
+    }
+
+    fn create_base_tokens() -> TokenPair {
+        TokenPair::new(8453, TokenInfo::new(Address::zero(), 18), TokenInfo::new(Address::from([0x22; 20]), 6))
     }
 
     fn create_test_aero_pool() -> VolatilePairState {
@@ -432,6 +689,11 @@ mod tests {
             decimals0: 18,
             decimals1: 6,
             fee_bps: 30, // 0.3%
+            block_number: 0,
+            block_timestamp: 0,
+            stable: false,
+            chain_id: 8453,
+            gas_units: 0,
         }
     }
 
@@ -440,30 +702,467 @@ mod tests {
             gas_limit: U256::from(200_000),
             gas_price: U256::from(25_000_000_000u64), // 25 gwei
             l1_data_fee: U256::zero(),
+            l1_gas_units: U256::zero(),
+            scenarios: None,
             total_wei: U256::from(5_000_000_000_000_000u64), // 0.005 ETH
             total_eth: 0.005,
             total_usd: 2.0,
         }
     }
 
+    fn uni_venue(pool: UniPoolState, gas: &GasEstimate) -> PairVenue {
+        PairVenue::new("uniswap", pool, create_test_tokens(), true, gas)
+    }
+
+    fn aero_venue(pool: AerodromePoolState, gas: &GasEstimate) -> PairVenue {
+        PairVenue::new("aerodrome", pool, create_base_tokens(), true, gas)
+    }
+
+    fn sell_uni_buy_aero() -> ArbDirection {
+        ArbDirection::new("uniswap", "aerodrome")
+    }
+
+    fn sell_aero_buy_uni() -> ArbDirection {
+        ArbDirection::new("aerodrome", "uniswap")
+    }
+
+    #[test]
+    fn test_aerodrome_legs_on_stable_pool() {
+        // USDT/USDC-like sAMM pool: 5M / 5M, both 6 decimals, 1 bp
+        let pair = VolatilePairState {
+            token0: Address::from([0x01; 20]),
+            token1: Address::from([0x02; 20]),
+            reserve0: U256::from(5_000_000_000_000u64),
+            reserve1: U256::from(5_000_000_000_000u64),
+            decimals0: 6,
+            decimals1: 6,
+            fee_bps: 1,
+            block_number: 0,
+            block_timestamp: 0,
+            stable: true,
+            chain_id: 0,
+            gas_units: 0,
+        };
+        let tokens = TokenPair::new(8453, TokenInfo::new(pair.token0, 6), TokenInfo::new(pair.token1, 6));
+        let venue = PairVenue::new("aerodrome", AerodromePoolState::Pair(pair.clone()), tokens, true, &create_test_gas());
+
+        // Selling 100k base barely moves a stable curve (x*y=k would lose ~2%)
+        let (out, sell_px) = venue.sell(100_000.0).unwrap();
+        assert!(out > 99_980.0 && out < 100_000.0, "stable sell: {}", out);
+        assert!((sell_px - out / 100_000.0).abs() < 1e-12);
+        assert!((venue.spot_price() - 1.0).abs() < 1e-12);
+
+        // Exact-out buy searches the same curve: a bit more than 1:1, never less
+        let (cost, _) = venue.buy(100_000.0).unwrap();
+        assert!(cost > 100_000.0 && cost < 100_020.0, "stable buy: {}", cost);
+        let raw = |x: f64| TokenAmount::from_f64(x, 6).unwrap().raw;
+        let base_out = pair.amount_out(crate::math::aerodrome_volatile::SwapDirection::OneForZero, raw(cost));
+        assert!(base_out >= raw(100_000.0));
+    }
+
+    fn create_test_cl_pool(usdc_per_eth: f64) -> UniPoolState {
+        // Slipstream-style WETH/USDC pool, one deep position ±20k ticks around the price
+        let tick = crate::math::uniswap_v4::tick_from_price(usdc_per_eth, 18, 6) / 10 * 10;
+        let liquidity = BigInt::from(100_000_000_000_000_000_000u128);
+        crate::math::uniswap_v4::create_pool_with_real_data(
+            Address::zero(),
+            Address::from([0x22; 20]),
+            500,
+            10,
+            Address::zero(),
+            crate::math::uniswap_v4::get_sqrt_ratio_at_tick(tick),
+            tick,
+            liquidity.clone(),
+            vec![(tick - 20_000, liquidity.clone()), (tick + 20_000, -liquidity)],
+        )
+    }
+
+    #[test]
+    fn test_slipstream_pool_as_aerodrome_venue() {
+        // Uniswap at 3500, Slipstream 3% lower: buy on Aerodrome, sell on Uniswap
+        let gas = create_test_gas();
+        let mut cl_pool = create_test_cl_pool(3400.0);
+        cl_pool.chain_id = 8453;
+        let aero = aero_venue(AerodromePoolState::Concentrated { pool: cl_pool.clone(), tokens: create_base_tokens() }, &gas);
+
+        // The Base legs run on the tick simulator, same as a venue on the bare pool
+        let (out, sell_px) = aero.sell(1.0).unwrap();
+        let bare = PairVenue::new("slipstream", cl_pool.clone(), create_base_tokens(), true, &gas);
+        assert_eq!(Some((out, sell_px)), bare.sell(1.0));
+        let (_, buy_px) = aero.buy(1.0).unwrap();
+        assert!(sell_px < buy_px, "sell {} buy {}", sell_px, buy_px);
+        // Exact-out is solved on the ticks, not searched: the quoted input is the least that buys 1 ETH
+        assert!(buy_px > 3400.0 && buy_px < 3400.0 * 1.0016, "buy {}", buy_px); // 5 bp fee, < 10 ticks of rounding
+        let one_eth = U256::exp10(18);
+        let usdc_in = cl_pool.quote_exact_out(false, one_eth).unwrap();
+        assert!(cl_pool.quote_exact_in(false, usdc_in).unwrap() >= one_eth);
+        assert!(cl_pool.quote_exact_in(false, usdc_in - 1).unwrap() < one_eth);
+        assert!((aero.spot_price() / 3400.0 - 1.0).abs() < 0.01);
+
+        let mut uni_pool = create_test_cl_pool(3500.0);
+        uni_pool.chain_id = 1;
+        let inputs = OptimizerInputs {
+            venues: vec![uni_venue(uni_pool, &gas), aero],
+            bridge_cost_usd: 1.0,
+            bridge_usd_per_eth: 0.0,
+            quote_usd: 1.0,
+            hint_size_eth: 1.0,
+            max_size_eth: 100.0,
+        };
+        let res = optimize(&inputs).expect("a 3% gap is profitable");
+        assert_eq!(res.direction, sell_uni_buy_aero());
+        assert_eq!(res.direction.label(), "SELL_UNISWAP_BUY_AERODROME");
+        assert_eq!(res.bridge_cost_usd, 1.0);
+        assert!(res.eff_price_buy_usdc_per_eth < res.eff_price_sell_usdc_per_eth);
+    }
+
+    #[test]
+    fn test_profit_converted_from_quote_to_usd() {
+        // Same pools, quote token worth $0.50: the gap is half as many dollars, gas and bridge are not
+        let gas = create_test_gas();
+        let mut uni_pool = create_test_cl_pool(3500.0);
+        uni_pool.chain_id = 1;
+        let mut cl_pool = create_test_cl_pool(3400.0);
+        cl_pool.chain_id = 8453;
+        let mut inputs = OptimizerInputs {
+            venues: vec![
+                uni_venue(uni_pool, &gas),
+                aero_venue(AerodromePoolState::Concentrated { pool: cl_pool, tokens: create_base_tokens() }, &gas),
+            ],
+            bridge_cost_usd: 1.0,
+            bridge_usd_per_eth: 0.0,
+            quote_usd: 1.0,
+            hint_size_eth: 1.0,
+            max_size_eth: 10.0,
+        };
+        let at_par = optimize(&inputs).expect("a 3% gap is profitable");
+        inputs.quote_usd = 0.5;
+        let halved = optimize(&inputs).expect("still profitable");
+
+        assert!((halved.optimal_size_eth - at_par.optimal_size_eth).abs() < 1e-3); // capped either way
+        assert!((halved.proceeds_usd - at_par.proceeds_usd / 2.0).abs() < 1e-3 * at_par.proceeds_usd);
+        let fixed = halved.gas_usd_total + halved.bridge_cost_usd;
+        assert_eq!(fixed, at_par.gas_usd_total + at_par.bridge_cost_usd);
+        assert!((halved.net_profit_usd - (halved.proceeds_usd - halved.costs_usd - fixed)).abs() < 1e-6);
+        assert!(halved.eff_price_sell_usdc_per_eth > 3400.0); // prices stay in quote units
+    }
+
+    #[test]
+    fn test_bridge_fee_per_eth_moves_the_optimum() {
+        // A fee that grows with size pulls the optimum in; one direction can be searched alone
+        let gas = create_test_gas();
+        let mut uni_pool = create_test_cl_pool(3500.0);
+        uni_pool.chain_id = 1;
+        let mut inputs = OptimizerInputs {
+            venues: vec![uni_venue(uni_pool, &gas), aero_venue(AerodromePoolState::Pair(create_test_aero_pool()), &gas)],
+            bridge_cost_usd: 1.0,
+            bridge_usd_per_eth: 0.0,
+            quote_usd: 1.0,
+            hint_size_eth: 1.0,
+            max_size_eth: 1000.0,
+        };
+        let flat = optimize_direction(&inputs, &sell_uni_buy_aero()).expect("a 3% gap is profitable");
+        inputs.bridge_usd_per_eth = 20.0;
+        let scaled = optimize_direction(&inputs, &sell_uni_buy_aero()).expect("still profitable");
+
+        assert!(scaled.optimal_size_eth < flat.optimal_size_eth, "{} vs {}", scaled.optimal_size_eth, flat.optimal_size_eth);
+        assert!((scaled.bridge_cost_usd - (1.0 + 20.0 * scaled.optimal_size_eth)).abs() < 1e-9);
+        assert!(optimize_direction(&inputs, &sell_aero_buy_uni()).is_none());
+        assert!(optimize_direction(&inputs, &ArbDirection::new("uniswap", "nowhere")).is_none());
+    }
+
+    #[test]
+    fn test_max_size_past_the_loaded_ticks() {
+        // Only a few ticks around the Uniswap price were scanned: selling max_size_eth there is
+        // refused, but the smaller sizes it does cover are still searched
+        let gas = create_test_gas();
+        let mut uni_pool = create_test_cl_pool(3500.0);
+        uni_pool.chain_id = 1;
+        uni_pool.loaded_ticks = Some((uni_pool.tick - 1, uni_pool.tick + 1));
+        let mut cl_pool = create_test_cl_pool(3400.0);
+        cl_pool.chain_id = 8453;
+        let inputs = OptimizerInputs {
+            venues: vec![
+                uni_venue(uni_pool, &gas),
+                aero_venue(AerodromePoolState::Concentrated { pool: cl_pool, tokens: create_base_tokens() }, &gas),
+            ],
+            bridge_cost_usd: 1.0,
+            bridge_usd_per_eth: 0.0,
+            quote_usd: 1.0,
+            hint_size_eth: 50.0,
+            max_size_eth: 100.0,
+        };
+        let uni = inputs.venue("uniswap").unwrap();
+        assert!(uni.sell(inputs.max_size_eth).is_none());
+
+        let res = optimize(&inputs).expect("covered sizes are profitable");
+        assert_eq!(res.direction, sell_uni_buy_aero());
+        assert!(res.optimal_size_eth < inputs.max_size_eth);
+        assert!(uni.sell(res.optimal_size_eth).is_some());
+        assert!(res.net_profit_usd > 0.0);
+        assert!(optimize_split(&inputs).is_some());
+    }
+
+    #[test]
+    fn test_searches_every_ordered_venue_pair() {
+        // Three venues: the widest gap is between the two Base pools, which also skip the bridge
+        let gas = create_test_gas();
+        let mut uni_pool = create_test_cl_pool(3450.0);
+        uni_pool.chain_id = 1;
+        let mut cheap = create_test_cl_pool(3300.0);
+        cheap.chain_id = 8453;
+        let mut rich = create_test_cl_pool(3600.0);
+        rich.chain_id = 8453;
+        let inputs = OptimizerInputs {
+            venues: vec![
+                uni_venue(uni_pool, &gas),
+                PairVenue::new("cheap", cheap, create_base_tokens(), true, &gas),
+                PairVenue::new("rich", rich, create_base_tokens(), true, &gas),
+            ],
+            bridge_cost_usd: 50.0,
+            bridge_usd_per_eth: 0.0,
+            quote_usd: 1.0,
+            hint_size_eth: 1.0,
+            max_size_eth: 100.0,
+        };
+
+        let res = optimize(&inputs).expect("a 9% gap is profitable");
+        assert_eq!(res.direction, ArbDirection::new("rich", "cheap"));
+        assert_eq!(res.bridge_cost_usd, 0.0);
+        // Both swaps in one Base transaction: 2 x 200k gas, less one intrinsic 21k
+        assert!((res.gas_usd_total - 3.79).abs() < 1e-9);
+        assert!(inputs.venue("rich").is_some() && inputs.venue("sushi").is_none());
+    }
+
+    #[test]
+    fn test_base_v4_pool_against_aerodrome() {
+        // A V4 pool on Base 3% above Aerodrome: same chain, so no bridge and one transaction
+        let mut gas = create_test_gas();
+        gas.l1_data_fee = U256::from(100_000_000_000_000u64); // 1e-4 ETH, paid once per transaction
+        let mut v4 = create_test_cl_pool(3500.0);
+        v4.chain_id = 8453;
+        v4.gas_units = 140_000;
+        let mut aero_pool = create_test_cl_pool(3400.0);
+        aero_pool.chain_id = 8453;
+        aero_pool.gas_units = 185_000;
+        let inputs = OptimizerInputs {
+            venues: vec![
+                PairVenue::new("uniswap", v4, create_base_tokens(), true, &gas),
+                aero_venue(AerodromePoolState::Concentrated { pool: aero_pool, tokens: create_base_tokens() }, &gas),
+            ],
+            bridge_cost_usd: 50.0,
+            bridge_usd_per_eth: 0.0,
+            quote_usd: 1.0,
+            hint_size_eth: 1.0,
+            max_size_eth: 100.0,
+        };
+
+        let res = optimize(&inputs).expect("a 3% gap is profitable");
+        assert_eq!(res.direction, sell_uni_buy_aero());
+        assert_eq!(res.bridge_cost_usd, 0.0);
+        let (uni, aero) = (inputs.venue("uniswap").unwrap(), inputs.venue("aerodrome").unwrap());
+        assert!((res.gas_usd_total - gas.repriced_for(304_000).total_usd).abs() < 1e-9);
+        assert!(res.gas_usd_total < uni.gas_usd + aero.gas_usd);
+        assert!((res.net_profit_usd - (res.proceeds_usd - res.costs_usd - res.gas_usd_total)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_routed_leg_sells_through_a_richer_path() {
+        // Direct Ethereum pool at 3500, but ETH -> USDT -> USDC clears near 3700
+        let gas = create_test_gas();
+        let usdt = Address::from([0x33; 20]);
+        let pair = |token1: Address, r0: u64, d0: u8, r1: u64| VolatilePairState {
+            token0: Address::zero(),
+            token1,
+            reserve0: U256::from(r0) * U256::exp10(d0 as usize),
+            reserve1: U256::from(r1) * U256::exp10(6),
+            decimals0: d0,
+            decimals1: 6,
+            fee_bps: 5,
+            block_number: 0,
+            block_timestamp: 0,
+            stable: false,
+            chain_id: 1,
+            gas_units: 100_000,
+        };
+        let eth_usdt = pair(usdt, 10_000, 18, 37_000_000);
+        let usdc_usdt = VolatilePairState { token0: Address::from([0x11; 20]), ..pair(usdt, 50_000_000, 6, 50_000_000) };
+        let routing = [
+            RoutePool::new("eth-usdt", eth_usdt.token0, eth_usdt.token1, eth_usdt),
+            RoutePool::new("usdc-usdt", usdc_usdt.token0, usdc_usdt.token1, usdc_usdt),
+        ];
+
+        let mut uni_pool = create_test_cl_pool(3500.0);
+        uni_pool.chain_id = 1;
+        let direct = uni_venue(uni_pool, &gas);
+        let routed = direct.clone().routed(&routing, 2, &gas, 1.0);
+        assert_eq!(routed.id, "uniswap");
+        assert!((routed.spot_price() / 3700.0 - 1.0).abs() < 0.01);
+        assert!((routed.gas_usd - gas.cost_usd_for(200_000)).abs() < 1e-9);
+        assert!(routed.sell(10.0).unwrap().0 > direct.sell(10.0).unwrap().0);
+        // One hop allowed: back to the direct pool
+        assert_eq!(direct.clone().routed(&routing, 1, &gas, 1.0).sell(10.0), direct.sell(10.0));
+
+        let aero = base_cl_venue("aerodrome", 3400.0, &gas);
+        let profit = |eth_leg: PairVenue| {
+            let inputs = OptimizerInputs {
+                venues: vec![eth_leg, aero.clone()],
+                bridge_cost_usd: 1.0,
+                bridge_usd_per_eth: 0.0,
+                quote_usd: 1.0,
+                hint_size_eth: 1.0,
+                max_size_eth: 50.0,
+            };
+            optimize(&inputs).expect("profitable either way")
+        };
+        let (direct_res, routed_res) = (profit(direct.clone()), profit(routed));
+        assert_eq!(routed_res.direction, sell_uni_buy_aero());
+        assert!(routed_res.net_profit_usd > direct_res.net_profit_usd);
+        // The result shows the path taken, and charges its gas
+        let hops: Vec<_> = routed_res.sell_route.as_ref().expect("routed sell").hops.iter().map(|h| h.pool.clone()).collect();
+        assert_eq!(hops, ["eth-usdt", "usdc-usdt"]);
+        assert!(routed_res.buy_route.is_none());
+        assert!(direct_res.sell_route.is_none());
+
+        // Gas dear enough in quote terms: the extra hop no longer pays, so the leg trades
+        // direct and pays one hop's gas
+        let mut uni_pool = create_test_cl_pool(3500.0);
+        (uni_pool.chain_id, uni_pool.gas_units) = (1, 100_000);
+        let direct = uni_venue(uni_pool, &gas);
+        let routed = direct.clone().routed(&routing, 2, &gas, 1e-4);
+        let quote = routed.sell_quote(10.0).unwrap();
+        assert_eq!(quote.route.as_ref().unwrap().hops.len(), 1);
+        assert_eq!(Some((quote.usdc, quote.px)), direct.sell(10.0));
+        assert!((quote.gas.total_usd - gas.cost_usd_for(100_000)).abs() < 1e-9);
+    }
+
+    fn base_cl_venue(id: &str, usdc_per_eth: f64, gas: &GasEstimate) -> PairVenue {
+        let mut pool = create_test_cl_pool(usdc_per_eth);
+        pool.chain_id = 8453;
+        PairVenue::new(id, pool, create_base_tokens(), true, gas)
+    }
+
+    #[test]
+    fn test_split_spreads_a_leg_over_equal_pools() {
+        // One rich pool to sell into, two identical cheap pairs to buy from, all on Base
+        let gas = create_test_gas();
+        let cheap = AerodromePoolState::Pair(VolatilePairState {
+            reserve1: U256::from(3_300_000_000_000u64), // 3300 USDC per WETH
+            ..create_test_aero_pool()
+        });
+        let inputs = OptimizerInputs {
+            venues: vec![
+                aero_venue(cheap.clone(), &gas),
+                base_cl_venue("rich", 3600.0, &gas),
+                PairVenue::new("aerodrome_b", cheap, create_base_tokens(), true, &gas),
+            ],
+            bridge_cost_usd: 50.0,
+            bridge_usd_per_eth: 0.0,
+            quote_usd: 1.0,
+            hint_size_eth: 1.0,
+            max_size_eth: 100.0,
+        };
+
+        let split = optimize_split(&inputs).expect("a 9% gap is profitable");
+        assert_eq!(split.sells.len(), 1);
+        assert_eq!(split.sells[0].venue, "rich");
+        assert_eq!(split.buys.len(), 2);
+        let step = inputs.max_size_eth / SPLIT_STEPS as f64;
+        assert!((split.buys[0].size_eth - split.buys[1].size_eth).abs() <= step + 1e-9);
+        let bought: f64 = split.buys.iter().map(|f| f.size_eth).sum();
+        assert!((bought - split.optimal_size_eth).abs() < 1e-9);
+        assert!((split.sells[0].size_eth - split.optimal_size_eth).abs() < 1e-9);
+
+        // The three swaps go out as one Base transaction: 3 x 200k gas, less two intrinsic 21k;
+        // no bridge on a single chain, and never worse than one pair
+        assert!((split.gas_usd_total - 5.58).abs() < 1e-9);
+        assert_eq!(split.bridge_cost_usd, 0.0);
+        let expected = split.proceeds_usd - split.costs_usd - split.gas_usd_total;
+        assert!((split.net_profit_usd - expected).abs() < 1e-9);
+        let single = optimize(&inputs).expect("profitable as a pair too");
+        assert!(split.net_profit_usd > single.net_profit_usd);
+    }
+
+    #[test]
+    fn test_split_with_two_venues_is_the_pair_trade() {
+        let gas = create_test_gas();
+        let mut uni_pool = create_test_cl_pool(3500.0);
+        uni_pool.chain_id = 1;
+        let inputs = OptimizerInputs {
+            venues: vec![uni_venue(uni_pool, &gas), base_cl_venue("aerodrome", 3400.0, &gas)],
+            bridge_cost_usd: 1.0,
+            bridge_usd_per_eth: 0.0,
+            quote_usd: 1.0,
+            hint_size_eth: 1.0,
+            max_size_eth: 100.0,
+        };
+
+        let single = optimize(&inputs).expect("a 3% gap is profitable");
+        let split = optimize_split(&inputs).expect("same trade");
+        assert_eq!(split.sells[0].venue, "uniswap");
+        assert_eq!(split.buys[0].venue, "aerodrome");
+        assert_eq!(split.bridge_cost_usd, 1.0);
+        assert!((split.optimal_size_eth / single.optimal_size_eth - 1.0).abs() < 1e-9);
+        assert!((split.net_profit_usd - single.net_profit_usd).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_split_pays_the_bridge_of_the_chains_it_uses() {
+        let gas = create_test_gas();
+        let mut uni_pool = create_test_cl_pool(3500.0);
+        uni_pool.chain_id = 1;
+        let inputs = OptimizerInputs {
+            venues: vec![uni_venue(uni_pool, &gas), base_cl_venue("aerodrome", 3400.0, &gas)],
+            bridge_cost_usd: 0.0,
+            bridge_usd_per_eth: 0.0,
+            quote_usd: 1.0,
+            hint_size_eth: 1.0,
+            max_size_eth: 100.0,
+        };
+
+        // Selling on Ethereum and buying on Base pays that route's $7, not the dearer way back
+        let route_fee = |sells: &[u64], buys: &[u64], _: f64| if sells == [1] && buys == [8453] { 7.0 } else { 1e6 };
+        let split = optimize_split_with(&inputs, route_fee).expect("a 3% gap is profitable");
+        assert_eq!(split.bridge_cost_usd, 7.0);
+        let expected = split.proceeds_usd - split.costs_usd - split.gas_usd_total - 7.0;
+        assert!((split.net_profit_usd - expected).abs() < 1e-9);
+
+        // Once the chains' own fee takes every split's net below zero there is none to return
+        assert!(optimize_split_with(&inputs, |_, _, _| 1e9).is_none());
+    }
+
+    #[test]
+    fn test_venue_gas_priced_from_gas_units() {
+        // 0.005 ETH for 200k gas at $2 total: 100k units cost half
+        let gas = create_test_gas();
+        let mut pool = create_test_aero_pool();
+        assert_eq!(aero_venue(AerodromePoolState::Pair(pool.clone()), &gas).gas_usd, 2.0);
+        pool.gas_units = 100_000;
+        let venue = aero_venue(AerodromePoolState::Pair(pool), &gas);
+        assert!((venue.gas_usd - 1.0).abs() < 1e-9);
+        assert_eq!(venue.chain_id(), 8453);
+    }
+
     #[test]
     fn test_optimizer_basic() {
+        let gas_base = GasEstimate {
+            gas_limit: U256::from(150_000),
+            gas_price: U256::from(1_000_000_000u64), // 1 gwei
+            l1_data_fee: U256::zero(),
+            l1_gas_units: U256::zero(),
+            scenarios: None,
+            total_wei: U256::from(150_000_000_000_000u64),
+            total_eth: 0.00015,
+            total_usd: 0.5,
+        };
         let inputs = OptimizerInputs {
-            uni_pool: create_test_uni_pool(),
-            uni_token0_is_weth: true,
-            uni_fee_ppm_override: Some(3000),
-            aero_pair: create_test_aero_pool(),
-            aero_token0_is_weth: true,
-            gas_eth: create_test_gas(),
-            gas_base: GasEstimate {
-                gas_limit: U256::from(150_000),
-                gas_price: U256::from(1_000_000_000u64), // 1 gwei
-                l1_data_fee: U256::zero(),
-                total_wei: U256::from(150_000_000_000_000u64),
-                total_eth: 0.00015,
-                total_usd: 0.5,
-            },
+            venues: vec![
+                uni_venue(create_test_uni_pool(), &create_test_gas()),
+                aero_venue(AerodromePoolState::Pair(create_test_aero_pool()), &gas_base),
+            ],
             bridge_cost_usd: 5.0,
+            bridge_usd_per_eth: 0.0,
+            quote_usd: 1.0,
             hint_size_eth: 1.0,
             max_size_eth: 100.0,
         };
@@ -483,8 +1182,8 @@ mod tests {
             
             // Check direction is valid
             assert!(
-                res.direction == ArbDirection::SellAeroBuyUni ||
-                res.direction == ArbDirection::SellUniBuyAero
+                res.direction == sell_aero_buy_uni() ||
+                res.direction == sell_uni_buy_aero()
             );
         }
     }
@@ -492,39 +1191,49 @@ mod tests {
     #[test]
     fn test_optimizer_no_arbitrage() {
         // Create pools with identical prices and high costs
+        let gas_eth = GasEstimate {
+            gas_limit: U256::from(200_000),
+            gas_price: U256::from(500_000_000_000u64), // Very high gas
+            l1_data_fee: U256::zero(),
+            l1_gas_units: U256::zero(),
+            scenarios: None,
+            total_wei: U256::from(100_000_000_000_000_000u64),
+            total_eth: 0.1,
+            total_usd: 350.0, // Very high gas cost
+        };
+        let gas_base = GasEstimate {
+            gas_limit: U256::from(150_000),
+            gas_price: U256::from(100_000_000_000u64), // High gas
+            l1_data_fee: U256::zero(),
+            l1_gas_units: U256::zero(),
+            scenarios: None,
+            total_wei: U256::from(15_000_000_000_000_000u64),
+            total_eth: 0.015,
+            total_usd: 52.5, // High gas cost
+        };
         let inputs = OptimizerInputs {
-            uni_pool: create_test_uni_pool(),
-            uni_token0_is_weth: true,
-            uni_fee_ppm_override: Some(3000),
-            aero_pair: VolatilePairState {
-                token0: Address::zero(),
-                token1: Address::from([0x22; 20]),
-                        reserve0: U256::from_dec_str("1000000000000000000000")
-            .expect("Failed to parse reserve0"),
-        reserve1: U256::from_dec_str("3490000000000")
-            .expect("Failed to parse reserve1"), // Almost same price
-                decimals0: 18,
-                decimals1: 6,
-                fee_bps: 30,
-            },
-            aero_token0_is_weth: true,
-            gas_eth: GasEstimate {
-                gas_limit: U256::from(200_000),
-                gas_price: U256::from(500_000_000_000u64), // Very high gas
-                l1_data_fee: U256::zero(),
-                total_wei: U256::from(100_000_000_000_000_000u64),
-                total_eth: 0.1,
-                total_usd: 350.0, // Very high gas cost
-            },
-            gas_base: GasEstimate {
-                gas_limit: U256::from(150_000),
-                gas_price: U256::from(100_000_000_000u64), // High gas
-                l1_data_fee: U256::zero(),
-                total_wei: U256::from(15_000_000_000_000_000u64),
-                total_eth: 0.015,
-                total_usd: 52.5, // High gas cost
-            },
+            venues: vec![
+                uni_venue(create_test_uni_pool(), &gas_eth),
+                aero_venue(AerodromePoolState::Pair(VolatilePairState {
+                    token0: Address::zero(),
+                    token1: Address::from([0x22; 20]),
+                            reserve0: U256::from_dec_str("1000000000000000000000")
+                .expect("Failed to parse reserve0"),
+            reserve1: U256::from_dec_str("3490000000000")
+                .expect("Failed to parse reserve1"), // Almost same price
+                    decimals0: 18,
+                    decimals1: 6,
+                    fee_bps: 30,
+                    block_number: 0,
+                    block_timestamp: 0,
+                    stable: false,
+                    chain_id: 8453,
+                    gas_units: 0,
+                }), &gas_base),
+            ],
             bridge_cost_usd: 500.0, // Extremely high bridge cost
+            bridge_usd_per_eth: 0.0,
+            quote_usd: 1.0,
             hint_size_eth: 1.0,
             max_size_eth: 100.0,
         };
@@ -542,39 +1251,40 @@ mod tests {
     #[test]
     fn test_optimizer_direction_selection() {
         // Test SellUniBuyAero direction (Uni more expensive)
-        let mut inputs = OptimizerInputs {
-            uni_pool: create_test_uni_pool(), // ~3500 USDC/ETH
-            uni_token0_is_weth: true,
-            uni_fee_ppm_override: Some(3000),
-            aero_pair: VolatilePairState {
-                token0: Address::zero(),
-                token1: Address::from([0x22; 20]),
-                        reserve0: U256::from_dec_str("1000000000000000000000")
+        let cheap_gas = GasEstimate {
+            gas_limit: U256::from(100_000),
+            gas_price: U256::from(1_000_000_000u64),
+            l1_data_fee: U256::zero(),
+            l1_gas_units: U256::zero(),
+            scenarios: None,
+            total_wei: U256::from(100_000_000_000_000u64),
+            total_eth: 0.0001,
+            total_usd: 0.1,
+        };
+        let mut aero_pair = VolatilePairState {
+            token0: Address::zero(),
+            token1: Address::from([0x22; 20]),
+                    reserve0: U256::from_dec_str("1000000000000000000000")
             .expect("Failed to parse reserve0"),
         reserve1: U256::from_dec_str("3300000000000")
             .expect("Failed to parse reserve1"), // Cheaper at 3300
-                decimals0: 18,
-                decimals1: 6,
-                fee_bps: 30,
-            },
-            aero_token0_is_weth: true,
-            gas_eth: GasEstimate {
-                gas_limit: U256::from(100_000),
-                gas_price: U256::from(1_000_000_000u64),
-                l1_data_fee: U256::zero(),
-                total_wei: U256::from(100_000_000_000_000u64),
-                total_eth: 0.0001,
-                total_usd: 0.1,
-            },
-            gas_base: GasEstimate {
-                gas_limit: U256::from(100_000),
-                gas_price: U256::from(1_000_000_000u64),
-                l1_data_fee: U256::zero(),
-                total_wei: U256::from(100_000_000_000_000u64),
-                total_eth: 0.0001,
-                total_usd: 0.1,
-            },
+            decimals0: 18,
+            decimals1: 6,
+            fee_bps: 30,
+            block_number: 0,
+            block_timestamp: 0,
+            stable: false,
+            chain_id: 8453,
+            gas_units: 0,
+        };
+        let mut inputs = OptimizerInputs {
+            venues: vec![
+                uni_venue(create_test_uni_pool(), &cheap_gas), // ~3500 USDC/ETH
+                aero_venue(AerodromePoolState::Pair(aero_pair.clone()), &cheap_gas),
+            ],
             bridge_cost_usd: 1.0,
+            bridge_usd_per_eth: 0.0,
+            quote_usd: 1.0,
             hint_size_eth: 1.0,
             max_size_eth: 10.0,
         };
@@ -582,10 +1292,11 @@ mod tests {
         let result1 = optimize(&inputs);
         
         // Test SellAeroBuyUni direction (Aero more expensive)
-        inputs.aero_pair.reserve1 = U256::from_dec_str("3700000000000")
+        aero_pair.reserve1 = U256::from_dec_str("3700000000000")
             .expect("Failed to parse reserve1"); // Much more expensive at 3700
-        inputs.aero_pair.reserve0 = U256::from_dec_str("1000000000000000000000")
+        aero_pair.reserve0 = U256::from_dec_str("1000000000000000000000")
             .expect("Failed to parse reserve0");
+        inputs.venues[1] = aero_venue(AerodromePoolState::Pair(aero_pair), &cheap_gas);
         
         let result2 = optimize(&inputs);
         
@@ -607,14 +1318,13 @@ mod tests {
     #[test]
     fn test_optimizer_respects_max_size() {
         let inputs = OptimizerInputs {
-            uni_pool: create_test_uni_pool(),
-            uni_token0_is_weth: true,
-            uni_fee_ppm_override: Some(3000),
-            aero_pair: create_test_aero_pool(),
-            aero_token0_is_weth: true,
-            gas_eth: create_test_gas(),
-            gas_base: create_test_gas(),
+            venues: vec![
+                uni_venue(create_test_uni_pool(), &create_test_gas()),
+                aero_venue(AerodromePoolState::Pair(create_test_aero_pool()), &create_test_gas()),
+            ],
             bridge_cost_usd: 0.1,
+            bridge_usd_per_eth: 0.0,
+            quote_usd: 1.0,
             hint_size_eth: 1.0,
             max_size_eth: 5.0, // Small max size
         };
@@ -648,14 +1358,13 @@ mod tests {
     fn test_edge_cases() {
         // Test with zero trade size hint
         let mut inputs = OptimizerInputs {
-            uni_pool: create_test_uni_pool(),
-            uni_token0_is_weth: true,
-            uni_fee_ppm_override: Some(3000),
-            aero_pair: create_test_aero_pool(),
-            aero_token0_is_weth: true,
-            gas_eth: create_test_gas(),
-            gas_base: create_test_gas(),
+            venues: vec![
+                uni_venue(create_test_uni_pool(), &create_test_gas()),
+                aero_venue(AerodromePoolState::Pair(create_test_aero_pool()), &create_test_gas()),
+            ],
             bridge_cost_usd: 5.0,
+            bridge_usd_per_eth: 0.0,
+            quote_usd: 1.0,
             hint_size_eth: 0.0, // Zero hint
             max_size_eth: 100.0,
         };
@@ -668,6 +1377,9 @@ mod tests {
         inputs.max_size_eth = 0.001;
         
         let _ = optimize(&inputs);
+
+        // A single venue has no direction to trade
+        inputs.venues.truncate(1);
+        assert!(optimize(&inputs).is_none());
     }
 }
-
@@ -0,0 +1,1105 @@
+// src/engine/poller.rs
+//
+// Background market-state refresh into an in-memory cache.
+// - One tokio task per source: CEX prices and gas globally; V4 pool, Aerodrome pair and
+//   Across fees per watched pair
+// - The CEX task prices ETH (gas is paid in it everywhere) and every currency the watchlist
+//   values a pair's base or quote token in, and stores each pair's USD prices
+// - Each source runs on its own interval (config::PollIntervals), and can be woken early
+//   through PollWakers (engine::subscriptions does so on new blocks and pool events)
+// - Writers swap whole entries; handlers clone Arcs and compute without touching RPCs
+// - Every entry records when it was fetched and, for on-chain state, the block its reads
+//   were pinned to (number and timestamp)
+// - With an Ethereum WS, V4 pools are kept current from PoolManager events between full
+//   loads, which then run on the slower reconcile interval and report any drift
+// - Aerodrome token ordering and fee are read once; afterwards only reserves are refreshed
+//   (Sync events with a Base WS, getReserves polls otherwise). Slipstream pools have no
+//   reserves to track and are loaded in full on every Aerodrome poll
+// - A Uniswap V3 pool configured in place of either leg is loaded in full on that leg's
+//   poll interval; V3 pools are not event-tracked
+// - Routing pools are loaded in full on their own interval. They are optional: a snapshot
+//   is ready without them, and the legs trade direct until they arrive
+// - Split-mode pools likewise: until they load, split mode spreads over the two legs only
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::{Duration, Instant};
+
+use ethers::types::{Log, H256};
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+
+use crate::bootstrap::{AppState, PairEntry, SplitPool};
+use crate::chain::{
+    aerodrome_client::{load_pair_reserves, load_pair_snapshot},
+    aerodrome_slipstream_client::load_slipstream_pool,
+    aerodrome_tracker::AerodromePairTracker,
+    gas::GasEstimate,
+    tokens::TokenPair,
+    registry::{CHAIN_ID_BASE, CHAIN_ID_ETHEREUM},
+    providers::BlockRef,
+    uniswap_v3_client::load_v3_pool,
+    uniswap_v4_client::{load_v4_pool, v4_pool_id},
+    events::TrackerUpdate,
+    uniswap_v4_tracker::V4PoolTracker,
+};
+use crate::engine::optimizer::AerodromePoolState;
+use crate::engine::service::compute_bridge_fee_usd_for_direction;
+use crate::math::amount::TokenAmount;
+use crate::math::route::RoutePool;
+use crate::math::uniswap_v4::PoolState;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// A cached value plus when, and at which block, it was read.
+#[derive(Debug)]
+pub struct Cached<T> {
+    pub value: T,
+    pub fetched_at: Instant,
+    pub block: Option<BlockRef>, // None for off-chain sources
+}
+
+impl<T> Cached<T> {
+    pub fn new(value: T, block: Option<BlockRef>) -> Self {
+        Self { value, fetched_at: Instant::now(), block }
+    }
+
+    pub fn age(&self) -> Duration {
+        self.fetched_at.elapsed()
+    }
+
+    pub fn freshness(&self) -> SourceFreshness {
+        SourceFreshness {
+            age_ms: self.age().as_millis() as u64,
+            block_number: self.block.map(|b| b.number),
+            block_timestamp: self.block.map(|b| b.timestamp),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct UniswapSnapshot {
+    pub pool: PoolState,
+    pub token0_is_base: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct AerodromeSnapshot {
+    pub pool: AerodromePoolState,
+    pub token0_is_base: bool,
+}
+
+/// A split-mode pool as loaded. Every kind fits AerodromePoolState: V3, V4 and Slipstream as
+/// concentrated liquidity, Aerodrome volatile/stable as a pair.
+#[derive(Clone, Debug)]
+pub struct VenueSnapshot {
+    pub id: String,
+    pub pool: AerodromePoolState,
+    pub tokens: TokenPair,
+    pub token0_is_base: bool,
+}
+
+/// Routing pools per chain, each read at its own block.
+#[derive(Clone, Debug, Default)]
+pub struct RouteSnapshot {
+    pub ethereum: Vec<RoutePool>,
+    pub base: Vec<RoutePool>,
+    pub max_hops: usize,
+}
+
+/// Latest gas estimate of every configured chain, by chain id.
+#[derive(Clone, Debug, Default)]
+pub struct GasSnapshot {
+    pub chains: HashMap<u64, GasEstimate>,
+}
+
+impl GasSnapshot {
+    /// The estimate of the chain a leg trades on.
+    pub fn on(&self, chain_id: u64) -> Result<&GasEstimate, String> {
+        self.chains.get(&chain_id).ok_or_else(|| format!("no gas estimate for chain {}", chain_id))
+    }
+
+    /// This snapshot with each chain's fresh estimate swapped in; a chain whose estimate
+    /// failed keeps its last good one. Returns the failures too.
+    fn updated(&self, fresh: Vec<(u64, Result<GasEstimate, BoxError>)>) -> (GasSnapshot, Vec<String>) {
+        let mut next = self.clone();
+        let mut failed = Vec::new();
+        for (chain_id, estimate) in fresh {
+            match estimate {
+                Ok(estimate) => { next.chains.insert(chain_id, estimate); }
+                Err(e) => failed.push(format!("chain {}: {}", chain_id, e)),
+            }
+        }
+        (next, failed)
+    }
+}
+
+/// One route's Across fee: a fixed USD part (the relayer's gas) plus a fraction of the trade
+/// notional (LP and capital fees), fitted to quotes at two sizes.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BridgeFee {
+    pub fixed_usd: f64,
+    pub rate: f64,
+}
+
+impl BridgeFee {
+    /// Line through the USD fees quoted at two sizes (base units, `small` < `large`), valued at
+    /// `base_usd`; neither part goes negative.
+    pub fn fit(small: (f64, f64), large: (f64, f64), base_usd: f64) -> Self {
+        let ((x0, fee0), (x1, fee1)) = (small, large);
+        let per_eth = ((fee1 - fee0) / (x1 - x0)).max(0.0);
+        BridgeFee { fixed_usd: (fee0 - per_eth * x0).max(0.0), rate: per_eth / base_usd }
+    }
+
+    pub fn usd(&self, size_eth: f64, base_usd: f64) -> f64 {
+        self.fixed_usd + self.rate * size_eth * base_usd
+    }
+}
+
+/// Across rebalancing fee per (sell chain, buy chain), quoted at two trade sizes. A direction
+/// Across has never quoted is kept in `no_route` with the reason, rather than left unpublished.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BridgeFeeRates {
+    pub by_route: HashMap<(u64, u64), BridgeFee>,
+    pub no_route: HashMap<(u64, u64), String>,
+}
+
+impl BridgeFeeRates {
+    /// Fee model for selling on `sell_chain_id` and buying on `buy_chain_id`. Same-chain trades
+    /// need no rebalance; chains without a quoted route are prohibitive.
+    pub fn route(&self, sell_chain_id: u64, buy_chain_id: u64) -> BridgeFee {
+        if sell_chain_id == buy_chain_id {
+            return BridgeFee::default();
+        }
+        self.by_route
+            .get(&(sell_chain_id, buy_chain_id))
+            .copied()
+            .unwrap_or(BridgeFee { fixed_usd: f64::INFINITY, rate: 0.0 })
+    }
+
+    /// Why selling on `sell_chain_id` and buying on `buy_chain_id` can't be rebalanced, if it can't.
+    pub fn unavailable(&self, sell_chain_id: u64, buy_chain_id: u64) -> Option<&str> {
+        self.no_route.get(&(sell_chain_id, buy_chain_id)).map(String::as_str)
+    }
+
+    /// The reasons, when neither direction between the two chains can be rebalanced.
+    pub fn unroutable(&self, chain_a: u64, chain_b: u64) -> Option<String> {
+        match (self.unavailable(chain_a, chain_b), self.unavailable(chain_b, chain_a)) {
+            (Some(there), Some(back)) if there == back => Some(there.to_string()),
+            (Some(there), Some(back)) => Some(format!("{}; {}", there, back)),
+            _ => None,
+        }
+    }
+
+    /// Fee for a trade of `size_eth` on that route; nothing traded, nothing to rebalance.
+    pub fn fee_usd(&self, sell_chain_id: u64, buy_chain_id: u64, size_eth: f64, base_usd: f64) -> f64 {
+        if size_eth <= 0.0 {
+            return 0.0;
+        }
+        self.route(sell_chain_id, buy_chain_id).usd(size_eth, base_usd)
+    }
+}
+
+/// USD value of one base and one quote token of a pair, from its `[pair.cex]` currencies.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct UsdPrices {
+    pub base: f64,
+    pub quote: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SourceFreshness {
+    pub age_ms: u64,
+    pub block_number: Option<u64>,
+    pub block_timestamp: Option<u64>,
+}
+
+/// Age and block of every source behind one response.
+#[derive(Clone, Copy, Debug)]
+pub struct SnapshotInfo {
+    pub cex_price: SourceFreshness,
+    pub gas: SourceFreshness,
+    pub uniswap_v4: SourceFreshness,
+    pub aerodrome: SourceFreshness,
+    pub bridge_fees: SourceFreshness,
+}
+
+/// Everything a handler needs to price one pair, as of the last refreshes.
+#[derive(Clone)]
+pub struct PairSnapshot {
+    pub cex_price: Arc<Cached<f64>>, // ETH, for gas
+    pub usd: Arc<Cached<UsdPrices>>,
+    pub gas: Arc<Cached<GasSnapshot>>,
+    pub uniswap: Arc<Cached<UniswapSnapshot>>,
+    pub aerodrome: Arc<Cached<AerodromeSnapshot>>,
+    pub bridge_fees: Arc<Cached<BridgeFeeRates>>,
+    pub routes: Option<Arc<Cached<RouteSnapshot>>>, // None until loaded, or without routing
+    pub venues: Option<Arc<Cached<Vec<VenueSnapshot>>>>, // split-mode pools; None until loaded, or without any
+}
+
+impl PairSnapshot {
+    pub fn info(&self) -> SnapshotInfo {
+        SnapshotInfo {
+            cex_price: self.cex_price.freshness(),
+            gas: self.gas.freshness(),
+            uniswap_v4: self.uniswap.freshness(),
+            aerodrome: self.aerodrome.freshness(),
+            bridge_fees: self.bridge_fees.freshness(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct PairSlots {
+    usd: Option<Arc<Cached<UsdPrices>>>,
+    uniswap: Option<Arc<Cached<UniswapSnapshot>>>,
+    aerodrome: Option<Arc<Cached<AerodromeSnapshot>>>,
+    bridge_fees: Option<Arc<Cached<BridgeFeeRates>>>,
+    routes: Option<Arc<Cached<RouteSnapshot>>>,
+    venues: Option<Arc<Cached<Vec<VenueSnapshot>>>>,
+}
+
+#[derive(Default)]
+struct Slots {
+    cex_price: Option<Arc<Cached<f64>>>,
+    gas: Option<Arc<Cached<GasSnapshot>>>,
+    pairs: HashMap<String, PairSlots>,
+}
+
+/// Latest value of every polled source.
+#[derive(Default)]
+pub struct MarketCache {
+    slots: RwLock<Slots>,
+}
+
+impl MarketCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // A panicking writer only ever leaves a complete entry behind, so poisoning is harmless
+    fn read(&self) -> RwLockReadGuard<'_, Slots> {
+        self.slots.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, Slots> {
+        self.slots.write().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn cex_price(&self) -> Option<Arc<Cached<f64>>> {
+        self.read().cex_price.clone()
+    }
+
+    pub fn set_cex_price(&self, price: Cached<f64>) {
+        self.write().cex_price = Some(Arc::new(price));
+    }
+
+    pub fn usd_prices(&self, pair: &str) -> Option<Arc<Cached<UsdPrices>>> {
+        self.read().pairs.get(pair).and_then(|p| p.usd.clone())
+    }
+
+    pub fn set_usd_prices(&self, pair: &str, prices: Cached<UsdPrices>) {
+        self.write().pairs.entry(pair.to_string()).or_default().usd = Some(Arc::new(prices));
+    }
+
+    pub fn gas(&self) -> Option<Arc<Cached<GasSnapshot>>> {
+        self.read().gas.clone()
+    }
+
+    pub fn set_gas(&self, gas: Cached<GasSnapshot>) {
+        self.write().gas = Some(Arc::new(gas));
+    }
+
+    pub fn set_uniswap(&self, pair: &str, snapshot: Cached<UniswapSnapshot>) {
+        self.write().pairs.entry(pair.to_string()).or_default().uniswap = Some(Arc::new(snapshot));
+    }
+
+    pub fn set_aerodrome(&self, pair: &str, snapshot: Cached<AerodromeSnapshot>) {
+        self.write().pairs.entry(pair.to_string()).or_default().aerodrome = Some(Arc::new(snapshot));
+    }
+
+    pub fn bridge_fees(&self, pair: &str) -> Option<Arc<Cached<BridgeFeeRates>>> {
+        self.read().pairs.get(pair).and_then(|p| p.bridge_fees.clone())
+    }
+
+    pub fn set_bridge_fees(&self, pair: &str, rates: Cached<BridgeFeeRates>) {
+        self.write().pairs.entry(pair.to_string()).or_default().bridge_fees = Some(Arc::new(rates));
+    }
+
+    pub fn set_routes(&self, pair: &str, routes: Cached<RouteSnapshot>) {
+        self.write().pairs.entry(pair.to_string()).or_default().routes = Some(Arc::new(routes));
+    }
+
+    pub fn set_venues(&self, pair: &str, venues: Cached<Vec<VenueSnapshot>>) {
+        self.write().pairs.entry(pair.to_string()).or_default().venues = Some(Arc::new(venues));
+    }
+
+    /// Latest snapshot for a pair; errors until every required source has been fetched once.
+    pub fn pair_snapshot(&self, pair: &str) -> Result<PairSnapshot, String> {
+        let slots = self.read();
+        let empty = PairSlots::default();
+        let p = slots.pairs.get(pair).unwrap_or(&empty);
+
+        match (&slots.cex_price, &p.usd, &slots.gas, &p.uniswap, &p.aerodrome, &p.bridge_fees) {
+            (Some(cex_price), Some(usd), Some(gas), Some(uniswap), Some(aerodrome), Some(bridge_fees)) => {
+                Ok(PairSnapshot {
+                    cex_price: cex_price.clone(),
+                    usd: usd.clone(),
+                    gas: gas.clone(),
+                    uniswap: uniswap.clone(),
+                    aerodrome: aerodrome.clone(),
+                    bridge_fees: bridge_fees.clone(),
+                    routes: p.routes.clone(),
+                    venues: p.venues.clone(),
+                })
+            }
+            _ => {
+                let missing: Vec<&str> = [
+                    ("cex_price", slots.cex_price.is_none()),
+                    ("usd_prices", p.usd.is_none()),
+                    ("gas", slots.gas.is_none()),
+                    ("uniswap_v4", p.uniswap.is_none()),
+                    ("aerodrome", p.aerodrome.is_none()),
+                    ("bridge_fees", p.bridge_fees.is_none()),
+                ]
+                .into_iter()
+                .filter_map(|(name, absent)| absent.then_some(name))
+                .collect();
+                Err(format!("market data for '{}' not ready yet (waiting for: {})", pair, missing.join(", ")))
+            }
+        }
+    }
+}
+
+/// ETH first (gas needs it), then each other currency the watchlist names, once. A pair is
+/// repriced when both its currencies came back; failures are reported after the rest is stored.
+pub async fn refresh_cex_price(app: &AppState) -> Result<(), BoxError> {
+    let eth = app.cex_client.get_coinbase_price().await
+        .map_err(|e| -> BoxError { e.into() })?;
+    app.market.set_cex_price(Cached::new(eth, None));
+
+    let mut prices = HashMap::from([("ETH".to_string(), eth), ("USD".to_string(), 1.0)]);
+    let mut others: Vec<String> = app.pairs.iter()
+        .flat_map(|p| [p.cex.base.to_ascii_uppercase(), p.cex.quote.to_ascii_uppercase()])
+        .filter(|c| !prices.contains_key(c))
+        .collect();
+    others.sort_unstable();
+    others.dedup();
+    let fetched = futures::future::join_all(others.into_iter().map(|currency| async move {
+        let price = app.cex_client.get_usd_price(&currency).await;
+        (currency, price)
+    }))
+    .await;
+    let mut failed = Vec::new();
+    for (currency, price) in fetched {
+        match price {
+            Ok(price) => { prices.insert(currency, price); }
+            Err(e) => failed.push(format!("{}: {:#}", currency, e)),
+        }
+    }
+
+    for pair in &app.pairs {
+        let price = |currency: &str| prices.get(&currency.to_ascii_uppercase()).copied();
+        if let (Some(base), Some(quote)) = (price(&pair.cex.base), price(&pair.cex.quote)) {
+            app.market.set_usd_prices(&pair.name, Cached::new(UsdPrices { base, quote }, None));
+        }
+    }
+    if failed.is_empty() { Ok(()) } else { Err(format!("CEX price lookup failed ({})", failed.join("; ")).into()) }
+}
+
+/// Gas is priced in USD, so it waits for the first CEX price.
+pub async fn refresh_gas(app: &AppState) -> Result<(), BoxError> {
+    let cex_price = app.market.cex_price().ok_or("waiting for the first CEX price")?.value;
+    // Quoted at a typical swap of the chain (Aerodrome on Base, V4 elsewhere); legs reprice by their pool's gas units
+    // Chains are estimated independently: one failing RPC doesn't hold back the others
+    let estimates = futures::future::join_all(app.chains.configs().into_iter().map(|chain| async move {
+        let estimate = async {
+            let gas_units = if chain.chain_id == CHAIN_ID_BASE { app.gas_aerodrome_swap } else { app.gas_uniswap_v4_total };
+            let provider = app.chains.provider(chain.chain_id)?;
+            app.chains.gas_model(chain.chain_id)?.estimate(provider, gas_units, cex_price).await
+        };
+        (chain.chain_id, estimate.await)
+    }))
+    .await;
+
+    let succeeded = estimates.iter().any(|(_, e)| e.is_ok());
+    let previous = app.market.gas().map(|g| g.value.clone()).unwrap_or_default();
+    let (gas, failed) = previous.updated(estimates);
+    if succeeded {
+        app.market.set_gas(Cached::new(gas, None));
+    }
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("gas estimates failed, keeping the last good ones: {}", failed.join("; ")).into())
+    }
+}
+
+// Same reasoning as MarketCache: a tracker is only ever replaced or updated whole
+fn lock<T>(m: &Mutex<T>) -> MutexGuard<'_, T> {
+    m.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn publish_tracked(app: &AppState, pair: &PairEntry, tracker: &V4PoolTracker) {
+    let snapshot = UniswapSnapshot { pool: tracker.state().clone(), token0_is_base: tracker.token0_is_base() };
+    app.market.set_uniswap(&pair.name, Cached::new(snapshot, Some(tracker.block())));
+}
+
+/// Full StateView load, from the StateView of the chain the V4 pool is on. When V4 events are
+/// tracked this is the reconciliation step: the tracked state is compared with the fresh load,
+/// then replaced by it, with the logs that arrived during the load replayed on top.
+/// A V3 pool configured on Ethereum is loaded instead.
+pub async fn refresh_uniswap(app: &AppState, pair: &PairEntry) -> Result<(), BoxError> {
+    if let Some(v3) = pair.uniswap_v3_on(CHAIN_ID_ETHEREUM) {
+        let (mut pool, token0_is_base) = load_v3_pool(app.provider(CHAIN_ID_ETHEREUM)?, &v3.tokens, v3.pool, None).await?;
+        pool.gas_units = app.gas_uniswap_v3_swap;
+        let block = BlockRef { number: pool.block_number, timestamp: pool.block_timestamp };
+        app.market.set_uniswap(&pair.name, Cached::new(UniswapSnapshot { pool, token0_is_base }, Some(block)));
+        return Ok(());
+    }
+
+    // Scan ticks wide enough for the largest trade the pair is priced at
+    let max_trade = TokenAmount::from_f64(pair.trade_size.max, pair.eth_pair.base.decimals);
+    let chain_id = pair.eth_pair.chain_id;
+    let state_view = app.uniswap_state_view(chain_id)?;
+    let tracker_slot = app.v4_trackers.get(&pair.name).filter(|_| app.ethereum_ws_url.is_some() && pair.v4_tracked());
+    if let Some(slot) = tracker_slot {
+        lock(slot).begin_load();
+    }
+    let load = load_v4_pool(app.provider(chain_id)?, state_view, &pair.eth_pair, &pair.v4_pool, max_trade, None).await;
+    let mut load = match load {
+        Ok(load) => load,
+        Err(e) => {
+            if let Some(slot) = tracker_slot {
+                lock(slot).abort_load();
+            }
+            return Err(e);
+        }
+    };
+    load.pool.gas_units = app.gas_uniswap_v4_total;
+    load.pool.before_swap_fee_ppm = pair.v4_before_swap_fee_ppm;
+
+    match tracker_slot {
+        Some(slot) => {
+            let mut slot = lock(slot);
+            if let Some(tracked) = slot.tracker.as_ref() {
+                let drift = tracked.drift(&load);
+                if drift.is_clean() {
+                    log::debug!("{}: tracked V4 state matches StateView", pair.name);
+                } else {
+                    log::warn!("{}: tracked V4 state drifted from StateView ({}); resynced", pair.name, drift);
+                }
+            }
+            let pool_id = H256::from(v4_pool_id(&pair.eth_pair, &pair.v4_pool));
+            let tracker = slot.finish_load(V4PoolTracker::new(load, pool_id))
+                .map_err(|why| format!("logs during the V4 load need another resync: {}", why))?;
+            publish_tracked(app, pair, tracker);
+        }
+        None => {
+            let block = BlockRef { number: load.pool.block_number, timestamp: load.pool.block_timestamp };
+            let snapshot = UniswapSnapshot { pool: load.pool, token0_is_base: load.token0_is_base };
+            app.market.set_uniswap(&pair.name, Cached::new(snapshot, Some(block)));
+        }
+    }
+    Ok(())
+}
+
+/// Apply a PoolManager log to the pair's tracker and publish the result.
+/// Returns true when the tracker needs a full reload first.
+pub fn apply_v4_log(app: &AppState, pair: &PairEntry, log: &Log, block_timestamp: u64) -> bool {
+    let Some(slot) = app.v4_trackers.get(&pair.name) else { return true };
+    let mut slot = lock(slot);
+    // A load in flight gets the log too; it's replayed onto the tracker the load produces
+    let buffered = slot.buffer(log, block_timestamp);
+    let Some(tracker) = slot.tracker.as_mut() else { return !buffered }; // no tracker yet
+
+    match tracker.apply_log(log, block_timestamp) {
+        TrackerUpdate::Applied => {
+            publish_tracked(app, pair, tracker);
+            false
+        }
+        TrackerUpdate::Ignored => false,
+        TrackerUpdate::NeedsResync(why) => {
+            log::info!("{}: V4 tracker needs a resync: {}", pair.name, why);
+            slot.tracker = None;
+            true
+        }
+    }
+}
+
+fn publish_aerodrome(app: &AppState, pair: &PairEntry, tracker: &AerodromePairTracker) {
+    let snapshot = AerodromeSnapshot {
+        pool: AerodromePoolState::Pair(tracker.state().clone()),
+        token0_is_base: tracker.token0_is_base(),
+    };
+    app.market.set_aerodrome(&pair.name, Cached::new(snapshot, Some(tracker.block())));
+}
+
+/// First run (or after a resync request): full load including token ordering and fee.
+/// Afterwards: reserves only, plus the fee when there is no Base WS to report SetCustomFee.
+/// Slipstream pools, and a V3 pool configured on Base: a full pinned load every time.
+pub async fn refresh_aerodrome(app: &AppState, pair: &PairEntry) -> Result<(), BoxError> {
+    if !pair.aerodrome_tracked() {
+        let provider = app.provider(CHAIN_ID_BASE)?;
+        let ((mut pool, token0_is_base), tokens, gas_units) = match pair.uniswap_v3_on(CHAIN_ID_BASE) {
+            Some(v3) => (load_v3_pool(provider, &v3.tokens, v3.pool, None).await?, v3.tokens, app.gas_uniswap_v3_swap),
+            None => (
+                load_slipstream_pool(provider, &pair.base_pair, pair.aerodrome_pool, None).await?,
+                pair.base_pair,
+                app.gas_aerodrome_swap,
+            ),
+        };
+        pool.gas_units = gas_units;
+        let block = BlockRef { number: pool.block_number, timestamp: pool.block_timestamp };
+        let snapshot = AerodromeSnapshot { pool: AerodromePoolState::Concentrated { pool, tokens }, token0_is_base };
+        app.market.set_aerodrome(&pair.name, Cached::new(snapshot, Some(block)));
+        return Ok(());
+    }
+
+    let slot = app.aerodrome_trackers.get(&pair.name).ok_or("no Aerodrome tracker for pair")?;
+    let synced = lock(slot).is_some();
+
+    if !synced {
+        let (mut state, token0_is_base) = load_pair_snapshot(
+            app.provider(CHAIN_ID_BASE)?,
+            &pair.base_pair,
+            app.aerodrome_factory_address,
+            pair.aerodrome_stable,
+            Some(pair.aerodrome_pool),
+        )
+        .await?;
+        state.gas_units = app.gas_aerodrome_swap;
+        let mut slot = lock(slot);
+        let tracker = slot.insert(AerodromePairTracker::new(pair.aerodrome_pool, state, token0_is_base));
+        publish_aerodrome(app, pair, tracker);
+        return Ok(());
+    }
+
+    let with_fee = app.base_ws_url.is_none();
+    let reading = load_pair_reserves(
+        app.provider(CHAIN_ID_BASE)?,
+        app.aerodrome_factory_address,
+        pair.aerodrome_pool,
+        pair.aerodrome_stable,
+        with_fee,
+    )
+    .await?;
+    let mut slot = lock(slot);
+    if let Some(tracker) = slot.as_mut() {
+        if tracker.apply_reserves(reading) == TrackerUpdate::Applied {
+            publish_aerodrome(app, pair, tracker);
+        }
+    }
+    Ok(())
+}
+
+/// Apply a Sync/SetCustomFee log to the pair's tracker and publish the result.
+/// Returns true when a full reload is needed (the tracker is dropped so the next refresh does one).
+pub fn apply_aerodrome_log(app: &AppState, pair: &PairEntry, log: &Log, block_timestamp: u64) -> bool {
+    let Some(slot) = app.aerodrome_trackers.get(&pair.name) else { return true };
+    let mut slot = lock(slot);
+    let Some(tracker) = slot.as_mut() else { return true };
+
+    match tracker.apply_log(log, block_timestamp) {
+        TrackerUpdate::Applied => {
+            publish_aerodrome(app, pair, tracker);
+            false
+        }
+        TrackerUpdate::Ignored => false,
+        TrackerUpdate::NeedsResync(why) => {
+            log::info!("{}: Aerodrome tracker needs a full reload: {}", pair.name, why);
+            *slot = None;
+            true
+        }
+    }
+}
+
+/// Across fees are quoted at the default trade size and this multiple of it.
+const BRIDGE_FEE_SIZE_RATIO: f64 = 4.0;
+
+/// Quote Across at two sizes and store each route's fee as a fixed part plus a fraction of notional.
+/// A direction that fails keeps its previous fee rather than caching a prohibitive one; one that
+/// was never quoted is stored as having no route, so the pair still becomes ready and says why.
+/// Same-chain pairs never bridge, so they store zero rates without asking Across.
+pub async fn refresh_bridge_fees(app: &AppState, pair: &PairEntry) -> Result<(), BoxError> {
+    if pair.same_chain() {
+        app.market.set_bridge_fees(&pair.name, Cached::new(BridgeFeeRates::default(), None));
+        return Ok(());
+    }
+    let usd = app.market.usd_prices(&pair.name).ok_or("waiting for the first CEX prices")?.value;
+    // Quoted at the default size and four times it, to split the fixed part from the variable one
+    let size = if pair.trade_size.default > 0.0 { pair.trade_size.default } else { pair.trade_size.max.max(1.0) };
+    let sizes = [size, size * BRIDGE_FEE_SIZE_RATIO];
+    if usd.base <= 0.0 {
+        return Err("no base-token price to value Across fees".into());
+    }
+    let tokens = pair.base_leg_tokens();
+    let amount = |size: f64| TokenAmount::from_f64(size, tokens.base.decimals)
+        .ok_or("default trade size is not a valid token amount");
+    let (small, large) = (amount(sizes[0])?, amount(sizes[1])?);
+
+    let (uni, aero) = (pair.ethereum_leg_tokens(), pair.base_leg_tokens());
+    let (uni_chain, aero_chain) = (uni.chain_id, aero.chain_id);
+    let quote = |amount, sell, buy| compute_bridge_fee_usd_for_direction(&app.chains, sell, buy, &pair.bridge, amount, usd);
+    let ((uni_to_aero_small, uni_to_aero_large), (aero_to_uni_small, aero_to_uni_large)) = futures::future::join(
+        futures::future::join(quote(small, uni, aero), quote(large, uni, aero)),
+        futures::future::join(quote(small, aero, uni), quote(large, aero, uni)),
+    )
+    .await;
+
+    let previous = app.market.bridge_fees(&pair.name);
+    let mut rates = BridgeFeeRates::default();
+    let mut failed = Vec::new();
+    for (route, small_fee, large_fee) in [
+        ((uni_chain, aero_chain), uni_to_aero_small, uni_to_aero_large),
+        ((aero_chain, uni_chain), aero_to_uni_small, aero_to_uni_large),
+    ] {
+        match (small_fee, large_fee) {
+            (Ok(small_fee), Ok(large_fee)) => {
+                rates.by_route.insert(route, BridgeFee::fit((sizes[0], small_fee), (sizes[1], large_fee), usd.base));
+            }
+            (Err(e), _) | (_, Err(e)) => {
+                match previous.as_ref().and_then(|p| p.value.by_route.get(&route)) {
+                    Some(fee) => {
+                        rates.by_route.insert(route, *fee);
+                    }
+                    None => {
+                        rates.no_route.insert(route, e.clone());
+                    }
+                }
+                failed.push(format!("{} -> {}: {}", route.0, route.1, e));
+            }
+        }
+    }
+    app.market.set_bridge_fees(&pair.name, Cached::new(rates, None));
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("Across fee lookup failed, keeping any previous rate: {}", failed.join("; ")).into())
+    }
+}
+
+/// Load every routing pool of the pair and replace its route snapshot.
+pub async fn refresh_routes(app: &AppState, pair: &PairEntry) -> Result<(), BoxError> {
+    let Some(routing) = &pair.routing else { return Ok(()) };
+
+    let ethereum = futures::future::try_join_all(routing.uniswap_v4.iter().map(|(tokens, params, before_swap_fee)| async move {
+        let state_view = app.uniswap_state_view(CHAIN_ID_ETHEREUM)?;
+        let mut load = load_v4_pool(app.provider(CHAIN_ID_ETHEREUM)?, state_view, tokens, params, None, None).await?;
+        load.pool.gas_units = app.gas_uniswap_v4_total;
+        load.pool.before_swap_fee_ppm = *before_swap_fee;
+        let id = format!("uniswap_v4:{:?}", H256::from(v4_pool_id(tokens, params)));
+        let (token0, token1) = (load.pool.key.currency0, load.pool.key.currency1);
+        Ok::<_, BoxError>(RoutePool::new(id, token0, token1, load.pool))
+    }));
+    let base = futures::future::try_join_all(routing.aerodrome.iter().map(|(tokens, pool, stable)| async move {
+        let (mut state, _) = load_pair_snapshot(
+            app.provider(CHAIN_ID_BASE)?,
+            tokens,
+            app.aerodrome_factory_address,
+            *stable,
+            Some(*pool),
+        )
+        .await?;
+        state.gas_units = app.gas_aerodrome_swap;
+        let (token0, token1) = (state.token0, state.token1);
+        Ok::<_, BoxError>(RoutePool::new(format!("aerodrome:{:?}", pool), token0, token1, state))
+    }));
+    let (ethereum, base) = futures::future::try_join(ethereum, base).await?;
+
+    let routes = RouteSnapshot { ethereum, base, max_hops: routing.max_hops };
+    app.market.set_routes(&pair.name, Cached::new(routes, None));
+    Ok(())
+}
+
+/// Load every split-mode pool of the pair in full and replace its venue snapshot.
+pub async fn refresh_split_venues(app: &AppState, pair: &PairEntry) -> Result<(), BoxError> {
+    let venues = futures::future::try_join_all(pair.split.iter().map(|venue| async move {
+        let (pool, tokens, token0_is_base) = match &venue.pool {
+            SplitPool::UniswapV4 { tokens, params, before_swap_fee } => {
+                let max_trade = TokenAmount::from_f64(pair.trade_size.max, tokens.base.decimals);
+                let state_view = app.uniswap_state_view(tokens.chain_id)?;
+                let mut load = load_v4_pool(app.provider(tokens.chain_id)?, state_view, tokens, params, max_trade, None).await?;
+                load.pool.gas_units = app.gas_uniswap_v4_total;
+                load.pool.before_swap_fee_ppm = *before_swap_fee;
+                (AerodromePoolState::Concentrated { pool: load.pool, tokens: *tokens }, *tokens, load.token0_is_base)
+            }
+            SplitPool::UniswapV3(v3) => {
+                let (mut pool, token0_is_base) = load_v3_pool(app.provider(v3.tokens.chain_id)?, &v3.tokens, v3.pool, None).await?;
+                pool.gas_units = app.gas_uniswap_v3_swap;
+                (AerodromePoolState::Concentrated { pool, tokens: v3.tokens }, v3.tokens, token0_is_base)
+            }
+            SplitPool::Aerodrome { tokens, pool, tick_spacing: Some(_), .. } => {
+                let (mut pool, token0_is_base) = load_slipstream_pool(app.provider(CHAIN_ID_BASE)?, tokens, *pool, None).await?;
+                pool.gas_units = app.gas_aerodrome_swap;
+                (AerodromePoolState::Concentrated { pool, tokens: *tokens }, *tokens, token0_is_base)
+            }
+            SplitPool::Aerodrome { tokens, pool, stable, tick_spacing: None } => {
+                let (mut state, token0_is_base) = load_pair_snapshot(
+                    app.provider(CHAIN_ID_BASE)?, tokens, app.aerodrome_factory_address, *stable, Some(*pool),
+                )
+                .await?;
+                state.gas_units = app.gas_aerodrome_swap;
+                (AerodromePoolState::Pair(state), *tokens, token0_is_base)
+            }
+        };
+        Ok::<_, BoxError>(VenueSnapshot { id: venue.id.clone(), pool, tokens, token0_is_base })
+    }))
+    .await?;
+    app.market.set_venues(&pair.name, Cached::new(venues, None));
+    Ok(())
+}
+
+/// Per-pair refresh triggers, index-aligned with `AppState::pairs`.
+#[derive(Default)]
+pub struct PairWakers {
+    pub uniswap: Arc<Notify>,
+    pub aerodrome: Arc<Notify>,
+    pub bridge_fees: Arc<Notify>,
+    pub routes: Arc<Notify>,
+    pub split_venues: Arc<Notify>,
+}
+
+/// Wake a source ahead of its next tick. Wakes arriving mid-refresh coalesce into one rerun.
+#[derive(Default)]
+pub struct PollWakers {
+    pub cex_price: Arc<Notify>,
+    pub gas: Arc<Notify>,
+    pub pairs: Vec<PairWakers>,
+}
+
+impl PollWakers {
+    pub fn new(pair_count: usize) -> Self {
+        Self {
+            pairs: (0..pair_count).map(|_| PairWakers::default()).collect(),
+            ..Self::default()
+        }
+    }
+}
+
+fn spawn_loop<F, Fut>(source: String, every: Duration, wake: Arc<Notify>, mut refresh: F) -> JoinHandle<()>
+where
+    F: FnMut() -> Fut + Send + 'static,
+    Fut: Future<Output = Result<(), BoxError>> + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(every);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            // The interval is the staleness bound; a wake restarts it
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = wake.notified() => ticker.reset(),
+            }
+            let start = Instant::now();
+            match refresh().await {
+                Ok(()) => log::debug!("{} refreshed in {:?}", source, start.elapsed()),
+                Err(e) => log::warn!("{} refresh failed: {}", source, e),
+            }
+        }
+    })
+}
+
+/// Start one refresh loop per source. The first tick fires immediately, so the cache
+/// fills as soon as the slowest source answers.
+pub fn spawn(app: Arc<AppState>, wakers: &PollWakers) -> Vec<JoinHandle<()>> {
+    let every = app.poll_intervals;
+    let mut handles = Vec::new();
+
+    let a = app.clone();
+    handles.push(spawn_loop("cex_price".into(), every.cex_price, wakers.cex_price.clone(), move || {
+        let a = a.clone();
+        async move { refresh_cex_price(&a).await }
+    }));
+
+    let a = app.clone();
+    handles.push(spawn_loop("gas".into(), every.gas, wakers.gas.clone(), move || {
+        let a = a.clone();
+        async move { refresh_gas(&a).await }
+    }));
+
+    for (i, (pair, wake)) in app.pairs.iter().zip(&wakers.pairs).enumerate() {
+        // Tracked from events, the full V4 load only has to catch drift
+        let tracked = app.ethereum_ws_url.is_some() && pair.v4_tracked();
+        let uniswap_every = if tracked { every.uniswap_v4_reconcile } else { every.uniswap_v4 };
+        let venue = if pair.v4_priced() { "uniswap_v4" } else { "uniswap_v3" };
+
+        let a = app.clone();
+        handles.push(spawn_loop(format!("{}/{}", pair.name, venue), uniswap_every, wake.uniswap.clone(), move || {
+            let a = a.clone();
+            async move { refresh_uniswap(&a, &a.pairs[i]).await }
+        }));
+
+        let a = app.clone();
+        handles.push(spawn_loop(format!("{}/aerodrome", pair.name), every.aerodrome, wake.aerodrome.clone(), move || {
+            let a = a.clone();
+            async move { refresh_aerodrome(&a, &a.pairs[i]).await }
+        }));
+
+        let a = app.clone();
+        handles.push(spawn_loop(format!("{}/bridge_fees", pair.name), every.bridge_fees, wake.bridge_fees.clone(), move || {
+            let a = a.clone();
+            async move { refresh_bridge_fees(&a, &a.pairs[i]).await }
+        }));
+
+        if pair.routing.is_some() {
+            let a = app.clone();
+            handles.push(spawn_loop(format!("{}/routes", pair.name), every.routes, wake.routes.clone(), move || {
+                let a = a.clone();
+                async move { refresh_routes(&a, &a.pairs[i]).await }
+            }));
+        }
+
+        if !pair.split.is_empty() {
+            let a = app.clone();
+            handles.push(spawn_loop(format!("{}/split_venues", pair.name), every.split_venues, wake.split_venues.clone(), move || {
+                let a = a.clone();
+                async move { refresh_split_venues(&a, &a.pairs[i]).await }
+            }));
+        }
+    }
+
+    log::info!("Started {} market-state pollers", handles.len());
+    handles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain::gas::create_test_gas_estimate;
+    use crate::chain::registry::{CHAIN_ID_ARBITRUM, CHAIN_ID_OPTIMISM};
+    use crate::math::aerodrome_volatile::VolatilePairState;
+    use ethers::types::Address;
+    use num_bigint::BigInt;
+
+    fn uniswap_snapshot() -> UniswapSnapshot {
+        UniswapSnapshot {
+            pool: crate::math::uniswap_v4::create_pool_with_real_data(
+                Address::zero(), Address::from([0x11; 20]), 3000, 60, Address::zero(),
+                BigInt::from(1) << 96, 0, BigInt::from(1_000_000u64), vec![],
+            ),
+            token0_is_base: true,
+        }
+    }
+
+    fn aerodrome_snapshot() -> AerodromeSnapshot {
+        AerodromeSnapshot {
+            pool: AerodromePoolState::Pair(VolatilePairState {
+                token0: Address::zero(),
+                token1: Address::from([0x11; 20]),
+                reserve0: ethers::types::U256::from(1_000u64),
+                reserve1: ethers::types::U256::from(4_000_000u64),
+                decimals0: 18,
+                decimals1: 6,
+                fee_bps: 30,
+                block_number: 0,
+                block_timestamp: 0,
+                stable: false,
+                chain_id: 0,
+                gas_units: 0,
+            }),
+            token0_is_base: true,
+        }
+    }
+
+    fn test_bridge_rates() -> BridgeFeeRates {
+        let rate = |rate| BridgeFee { fixed_usd: 0.0, rate };
+        BridgeFeeRates {
+            by_route: HashMap::from([((CHAIN_ID_ETHEREUM, CHAIN_ID_BASE), rate(0.001)), ((CHAIN_ID_BASE, CHAIN_ID_ETHEREUM), rate(0.002))]),
+            ..Default::default()
+        }
+    }
+
+    fn fill(cache: &MarketCache, pair: &str) {
+        cache.set_cex_price(Cached::new(4000.0, None));
+        cache.set_usd_prices(pair, Cached::new(UsdPrices { base: 4000.0, quote: 1.0 }, None));
+        cache.set_gas(Cached::new(GasSnapshot {
+            chains: HashMap::from([
+                (CHAIN_ID_ETHEREUM, create_test_gas_estimate(20, 200_000, 4000.0)),
+                (CHAIN_ID_BASE, create_test_gas_estimate(1, 150_000, 4000.0)),
+            ]),
+        }, None));
+        cache.set_uniswap(pair, Cached::new(uniswap_snapshot(), Some(BlockRef { number: 21_000_000, timestamp: 1_730_000_000 })));
+        cache.set_aerodrome(pair, Cached::new(aerodrome_snapshot(), Some(BlockRef { number: 30_000_000, timestamp: 1_730_000_004 })));
+        cache.set_bridge_fees(pair, Cached::new(test_bridge_rates(), None));
+    }
+
+    #[test]
+    fn test_snapshot_not_ready_lists_missing_sources() {
+        let cache = MarketCache::new();
+        cache.set_cex_price(Cached::new(4000.0, None));
+        cache.set_uniswap("ETH-USDC", Cached::new(uniswap_snapshot(), Some(BlockRef { number: 1, timestamp: 0 })));
+
+        let err = cache.pair_snapshot("ETH-USDC").err().expect("incomplete cache must not yield a snapshot");
+        assert!(err.contains("gas"));
+        assert!(err.contains("aerodrome"));
+        assert!(err.contains("bridge_fees"));
+        assert!(!err.contains("cex_price"));
+        assert!(err.contains("usd_prices"));
+        assert!(!err.contains("uniswap_v4"));
+    }
+
+    #[test]
+    fn test_snapshot_reports_blocks_and_age() {
+        let cache = MarketCache::new();
+        fill(&cache, "ETH-USDC");
+
+        let snap = cache.pair_snapshot("ETH-USDC").expect("cache is complete");
+        let info = snap.info();
+        assert_eq!(info.uniswap_v4.block_number, Some(21_000_000));
+        assert_eq!(info.aerodrome.block_number, Some(30_000_000));
+        assert_eq!(info.aerodrome.block_timestamp, Some(1_730_000_004));
+        assert_eq!(info.cex_price.block_number, None);
+        assert_eq!(info.cex_price.block_timestamp, None);
+        assert!(info.gas.age_ms < 60_000);
+
+        // Pair slots are independent
+        assert!(cache.pair_snapshot("CBBTC-USDC").is_err());
+    }
+
+    #[test]
+    fn test_routes_are_optional() {
+        let cache = MarketCache::new();
+        fill(&cache, "ETH-USDC");
+        assert!(cache.pair_snapshot("ETH-USDC").unwrap().routes.is_none());
+
+        let AerodromePoolState::Pair(state) = aerodrome_snapshot().pool else { unreachable!() };
+        let pool = RoutePool::new("aerodrome:test", state.token0, state.token1, state);
+        let routes = RouteSnapshot { ethereum: vec![], base: vec![pool], max_hops: 2 };
+        cache.set_routes("ETH-USDC", Cached::new(routes, None));
+        let snap = cache.pair_snapshot("ETH-USDC").unwrap();
+        assert_eq!(snap.routes.expect("routes cached").value.base[0].id, "aerodrome:test");
+    }
+
+    #[test]
+    fn test_writers_replace_entries_without_touching_readers() {
+        let cache = MarketCache::new();
+        fill(&cache, "ETH-USDC");
+        let before = cache.pair_snapshot("ETH-USDC").unwrap();
+
+        cache.set_cex_price(Cached::new(4100.0, None));
+        let after = cache.pair_snapshot("ETH-USDC").unwrap();
+
+        assert_eq!(before.cex_price.value, 4000.0); // a held snapshot is never mutated
+        assert_eq!(after.cex_price.value, 4100.0);
+        assert!(Arc::ptr_eq(&before.uniswap, &after.uniswap));
+    }
+
+    #[tokio::test]
+    async fn test_wake_triggers_refresh_before_the_interval() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let runs = Arc::new(AtomicUsize::new(0));
+        let wake = Arc::new(Notify::new());
+        let counter = runs.clone();
+        let handle = spawn_loop("test".into(), Duration::from_secs(3600), wake.clone(), move || {
+            let counter = counter.clone();
+            async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            }
+        });
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(runs.load(Ordering::SeqCst), 1); // first tick is immediate
+
+        wake.notify_one();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(runs.load(Ordering::SeqCst), 2); // woken long before the hour is up
+
+        handle.abort();
+    }
+
+    #[test]
+    fn test_bridge_fee_rates_scale_with_notional() {
+        let rates = test_bridge_rates();
+        assert!((rates.fee_usd(CHAIN_ID_ETHEREUM, CHAIN_ID_BASE, 10.0, 4000.0) - 40.0).abs() < 1e-9);
+        assert!((rates.fee_usd(CHAIN_ID_BASE, CHAIN_ID_ETHEREUM, 5.0, 4000.0) - 40.0).abs() < 1e-9);
+        assert_eq!(rates.fee_usd(CHAIN_ID_ETHEREUM, CHAIN_ID_BASE, 0.0, 4000.0), 0.0);
+        // Both legs on one chain: nothing to rebalance
+        assert_eq!(rates.fee_usd(CHAIN_ID_BASE, CHAIN_ID_BASE, 10.0, 4000.0), 0.0);
+        assert_eq!(rates.fee_usd(CHAIN_ID_ETHEREUM, 10, 10.0, 4000.0), f64::INFINITY);
+        assert_eq!(BridgeFeeRates::default().fee_usd(CHAIN_ID_ETHEREUM, CHAIN_ID_BASE, 10.0, 4000.0), f64::INFINITY);
+    }
+
+    #[test]
+    fn test_unquoted_route_is_published_with_its_reason() {
+        let cache = MarketCache::new();
+        fill(&cache, "CBBTC-USDC");
+        let why = "no Across quote from ethereum to base (Base: 404 Not Found)";
+        let rates = BridgeFeeRates {
+            by_route: HashMap::from([((CHAIN_ID_BASE, CHAIN_ID_ETHEREUM), BridgeFee { fixed_usd: 1.0, rate: 0.0 })]),
+            no_route: HashMap::from([((CHAIN_ID_ETHEREUM, CHAIN_ID_BASE), why.to_string())]),
+        };
+        cache.set_bridge_fees("CBBTC-USDC", Cached::new(rates, None));
+
+        // The pair is ready; the missing direction is prohibitive and says why
+        let snap = cache.pair_snapshot("CBBTC-USDC").expect("a route without quotes still publishes");
+        let rates = &snap.bridge_fees.value;
+        assert_eq!(rates.unavailable(CHAIN_ID_ETHEREUM, CHAIN_ID_BASE), Some(why));
+        assert_eq!(rates.fee_usd(CHAIN_ID_ETHEREUM, CHAIN_ID_BASE, 1.0, 4000.0), f64::INFINITY);
+        assert_eq!(rates.unavailable(CHAIN_ID_BASE, CHAIN_ID_ETHEREUM), None);
+        assert_eq!(rates.unroutable(CHAIN_ID_ETHEREUM, CHAIN_ID_BASE), None);
+
+        let both = BridgeFeeRates {
+            no_route: HashMap::from([((CHAIN_ID_ETHEREUM, CHAIN_ID_BASE), why.to_string()), ((CHAIN_ID_BASE, CHAIN_ID_ETHEREUM), why.to_string())]),
+            ..Default::default()
+        };
+        assert_eq!(both.unroutable(CHAIN_ID_BASE, CHAIN_ID_ETHEREUM).as_deref(), Some(why));
+    }
+
+    #[test]
+    fn test_bridge_fee_keeps_its_fixed_part() {
+        // $3 of relayer gas plus 5 bp: $23 at 10 ETH and $83 at 40 ETH, with ETH at $4000
+        let fee = BridgeFee::fit((10.0, 23.0), (40.0, 83.0), 4000.0);
+        assert!((fee.fixed_usd - 3.0).abs() < 1e-9);
+        assert!((fee.rate - 0.0005).abs() < 1e-12);
+        // A small trade pays mostly the fixed part, rather than a tenth of the 10 ETH fee
+        assert!((fee.usd(1.0, 4000.0) - 5.0).abs() < 1e-9);
+        assert!((fee.usd(100.0, 4000.0) - 203.0).abs() < 1e-9);
+
+        // Quotes falling with size, or a fee below its share: neither part goes negative
+        assert_eq!(BridgeFee::fit((10.0, 20.0), (40.0, 10.0), 4000.0).rate, 0.0);
+        assert_eq!(BridgeFee::fit((10.0, 1.0), (40.0, 100.0), 4000.0).fixed_usd, 0.0);
+
+        let rates = BridgeFeeRates { by_route: HashMap::from([((CHAIN_ID_ETHEREUM, CHAIN_ID_BASE), fee)]), ..Default::default() };
+        assert!((rates.fee_usd(CHAIN_ID_ETHEREUM, CHAIN_ID_BASE, 1.0, 4000.0) - 5.0).abs() < 1e-9);
+        assert_eq!(rates.route(CHAIN_ID_BASE, CHAIN_ID_BASE), BridgeFee::default());
+        assert!(rates.route(CHAIN_ID_BASE, CHAIN_ID_ETHEREUM).fixed_usd.is_infinite());
+    }
+
+    #[test]
+    fn test_failed_gas_estimate_keeps_the_last_good_one() {
+        let previous = GasSnapshot {
+            chains: HashMap::from([
+                (CHAIN_ID_ETHEREUM, create_test_gas_estimate(20, 200_000, 4000.0)),
+                (CHAIN_ID_ARBITRUM, create_test_gas_estimate(1, 100_000, 4000.0)),
+            ]),
+        };
+        let (next, failed) = previous.updated(vec![
+            (CHAIN_ID_ETHEREUM, Ok(create_test_gas_estimate(30, 200_000, 4000.0))),
+            (CHAIN_ID_BASE, Ok(create_test_gas_estimate(1, 150_000, 4000.0))),
+            (CHAIN_ID_ARBITRUM, Err("rpc down".into())),
+        ]);
+        assert_eq!(next.on(CHAIN_ID_ETHEREUM).unwrap().gas_price, ethers::types::U256::from(30_000_000_000u64));
+        assert!(next.on(CHAIN_ID_BASE).is_ok());
+        assert_eq!(next.on(CHAIN_ID_ARBITRUM).unwrap().gas_limit, ethers::types::U256::from(100_000u64));
+        assert_eq!(failed.len(), 1);
+        assert!(failed[0].contains("42161") && failed[0].contains("rpc down"));
+
+        // Never estimated and failing: still missing
+        let (next, _) = GasSnapshot::default().updated(vec![(CHAIN_ID_OPTIMISM, Err("rpc down".into()))]);
+        assert!(next.on(CHAIN_ID_OPTIMISM).is_err());
+    }
+
+    #[test]
+    fn test_gas_snapshot_by_chain() {
+        let cache = MarketCache::new();
+        fill(&cache, "ETH-USDC");
+        let snap = cache.pair_snapshot("ETH-USDC").unwrap();
+        assert_eq!(snap.gas.value.on(CHAIN_ID_BASE).unwrap().gas_limit, ethers::types::U256::from(150_000u64));
+        assert!(snap.gas.value.on(CHAIN_ID_ARBITRUM).unwrap_err().contains("42161"));
+    }
+}
@@ -1,14 +1,20 @@
 // pricing.rs
+//
+// Quotes are "quote token per base token" for the configured pair. The `usdc`/`eth`
+// names below refer to the quote/base side of the default WETH/USDC pair. Trade sizes come in
+// as a base-token `TokenAmount` and are rescaled to each pool's own base decimals (`TokenPair`
+// for Uniswap, `VolatilePairState` for Aerodrome); only the resulting prices are f64.
+// Aerodrome Slipstream and Uniswap V3 pools share the Uniswap tick simulator and are quoted through it.
 use crate::math::uniswap_v4::{
-    PoolState as UniPoolState, simulate_exact_in_tokens, SwapDirection as UniDir,
-};
-use crate::math::aerodrome_volatile::{
-    VolatilePairState, simulate_exact_in_volatile, SwapDirection as AeroDir,
-    volatile_amount_out as aero_amount_out, to_raw as aero_to_raw, from_raw as aero_from_raw,
+    PoolState as UniPoolState, simulate_exact_in_tokens, simulate_exact_out_tokens, SwapDirection as UniDir,
+    SwapResult as UniSwapResult,
 };
+use crate::math::aerodrome_volatile::{VolatilePairState, simulate_exact_in_volatile, SwapDirection as AeroDir};
+use crate::math::amount::TokenAmount;
 use crate::chain::gas::GasEstimate;
-use ethers::types::U256;
-use num_traits::ToPrimitive;
+use crate::chain::tokens::TokenPair;
+use crate::engine::optimizer::AerodromePoolState;
+use crate::math::venue::Venue;
 
 #[derive(Default)]
 pub struct SideQuote {
@@ -24,76 +30,71 @@ pub struct VenueQuotes {
 
 // ---------- UNISWAP V4 ----------
 
+// (base_in, quote_out) for a base->quote swap result
+fn uniswap_base_in_quote_out(res: &UniSwapResult, tokens: &TokenPair, token0_is_weth: bool) -> (TokenAmount, TokenAmount) {
+    let dir = if token0_is_weth { UniDir::ZeroForOne } else { UniDir::OneForZero };
+    res.amounts(dir, tokens.base.decimals, tokens.quote.decimals)
+}
+
+// A fill past the loaded ticks (or on synthetic ones) priced liquidity nobody has read
+fn within_loaded_ticks(res: UniSwapResult) -> Result<UniSwapResult, Box<dyn std::error::Error + Send + Sync>> {
+    if res.beyond_loaded_ticks {
+        return Err("trade runs past the pool's loaded ticks; liquidity there is unknown".into());
+    }
+    Ok(res)
+}
+
 // SELL: ETH->USDC exact-in (you already do this)
 fn uniswap_sell_price_usdc_per_eth(
     pool: &UniPoolState,
+    tokens: &TokenPair,
     token0_is_weth: bool,
-    eth_in: f64,
+    eth_in: TokenAmount,
     fee_ppm: Option<u32>,
 ) -> Result<f64, Box<dyn std::error::Error + Send + Sync>> {
     let dir = if token0_is_weth { UniDir::ZeroForOne } else { UniDir::OneForZero };
-    let res = simulate_exact_in_tokens(pool, dir, fee_ppm, eth_in, 18, None)?;
-    let (ein, uout) = if token0_is_weth {
-        ((-res.amount0.clone()).to_f64().unwrap_or(0.0)/1e18,
-         res.amount1.clone().to_f64().unwrap_or(0.0)/1e6)
-    } else {
-        ((-res.amount1.clone()).to_f64().unwrap_or(0.0)/1e18,
-         res.amount0.clone().to_f64().unwrap_or(0.0)/1e6)
-    };
-    Ok(if ein > 0.0 { uout / ein } else { 0.0 })
+    let res = within_loaded_ticks(simulate_exact_in_tokens(pool, dir, fee_ppm, eth_in.rescale(tokens.base.decimals), None)?)?;
+    let (ein, uout) = uniswap_base_in_quote_out(&res, tokens, token0_is_weth);
+    Ok(uout.per(&ein))
 }
 
-// BUY: USDC->ETH exact-out via binary search on USDC-in
+// BUY: USDC->ETH exact-out, one tick walk
 fn uniswap_buy_price_usdc_per_eth(
     pool: &UniPoolState,
+    tokens: &TokenPair,
     token0_is_weth: bool,
-    eth_out_target: f64,
+    eth_out_target: TokenAmount,
     fee_ppm: Option<u32>,
 ) -> Result<f64, Box<dyn std::error::Error + Send + Sync>> {
-    if eth_out_target <= 0.0 { return Ok(0.0); }
+    let eth_out = eth_out_target.rescale(tokens.base.decimals);
+    if eth_out.is_zero() { return Ok(0.0); }
     let dir = if token0_is_weth { UniDir::OneForZero } else { UniDir::ZeroForOne };
-    // bracket USDC-in; start from a rough guess using a tiny trade as spot proxy
-    let spot_guess = uniswap_spot_proxy(pool, token0_is_weth).max(1.0);
-    let mut lo = 0.0_f64;
-    let mut hi = (eth_out_target * spot_guess * 4.0).min(1.0e12); // $1T cap
-
-    for _ in 0..64 {
-        let mid = 0.5 * (lo + hi);
-        let res = simulate_exact_in_tokens(pool, dir, fee_ppm, mid, 6 /* USDC decimals */, None)?;
-        let eth_out = match dir {
-            UniDir::OneForZero => (res.amount0.clone()).to_f64().unwrap_or(0.0) / 1e18, // token0=WETH out
-            UniDir::ZeroForOne => (res.amount1.clone()).to_f64().unwrap_or(0.0) / 1e18, // token1=WETH out
-        };
-        if eth_out >= eth_out_target { hi = mid; } else { lo = mid; }
-        if hi > 0.0 && (hi - lo) / hi < 1e-4 { break; } // ~1bp in input
-    }
-    Ok(hi / eth_out_target) // USDC per ETH
+    let res = within_loaded_ticks(simulate_exact_out_tokens(pool, dir, fee_ppm, eth_out, None)?)?;
+    let (usdc_in, _) = res.amounts(dir, tokens.quote.decimals, tokens.base.decimals);
+    Ok(usdc_in.per(&eth_out)) // USDC per ETH
 }
 
 // tiny-trade proxy for spot (still pays fee, but fine since you said "ignore mid")
-fn uniswap_spot_proxy(pool: &UniPoolState, token0_is_weth: bool) -> f64 {
-    let tiny = 0.0001_f64;
+fn uniswap_spot_proxy(pool: &UniPoolState, tokens: &TokenPair, token0_is_weth: bool) -> f64 {
+    let Some(tiny) = TokenAmount::from_f64(0.0001, tokens.base.decimals) else { return 0.0 };
     let dir = if token0_is_weth { UniDir::ZeroForOne } else { UniDir::OneForZero };
-    if let Ok(r) = simulate_exact_in_tokens(pool, dir, None, tiny, 18, None) {
-        let (ein, uout) = if token0_is_weth {
-            ((-r.amount0).to_f64().unwrap_or(0.0)/1e18, (r.amount1).to_f64().unwrap_or(0.0)/1e6)
-        } else {
-            ((-r.amount1).to_f64().unwrap_or(0.0)/1e18, (r.amount0).to_f64().unwrap_or(0.0)/1e6)
-        };
-        if ein > 0.0 { uout / ein } else { 0.0 }
+    if let Ok(r) = simulate_exact_in_tokens(pool, dir, None, tiny, None) {
+        let (ein, uout) = uniswap_base_in_quote_out(&r, tokens, token0_is_weth);
+        uout.per(&ein)
     } else { 0.0 }
 }
 
 // One call that returns both sides for Uniswap
 pub fn quote_uniswap_v4_both(
     pool: &UniPoolState,
+    tokens: &TokenPair,
     token0_is_weth: bool,
-    trade_size_eth: f64,
+    trade_size_eth: TokenAmount,
     gas_cost: &GasEstimate,
     fee_ppm: Option<u32>,
 ) -> Result<VenueQuotes, Box<dyn std::error::Error + Send + Sync>> {
-    let sell = uniswap_sell_price_usdc_per_eth(pool, token0_is_weth, trade_size_eth, fee_ppm)?;
-    let buy  = uniswap_buy_price_usdc_per_eth(pool, token0_is_weth, trade_size_eth, fee_ppm)?;
+    let sell = uniswap_sell_price_usdc_per_eth(pool, tokens, token0_is_weth, trade_size_eth, fee_ppm)?;
+    let buy  = uniswap_buy_price_usdc_per_eth(pool, tokens, token0_is_weth, trade_size_eth, fee_ppm)?;
     Ok(VenueQuotes {
         sell: SideQuote { price_usdc_per_eth: sell, estimated_gas_cost_usd: gas_cost.total_usd },
         buy:  SideQuote { price_usdc_per_eth: buy,  estimated_gas_cost_usd: gas_cost.total_usd },
@@ -106,64 +107,63 @@ pub fn quote_uniswap_v4_both(
 fn aerodrome_sell_price_usdc_per_eth(
     pair: &VolatilePairState,
     token0_is_weth: bool,
-    eth_in: f64,
+    eth_in: TokenAmount,
 ) -> f64 {
     let dir = if token0_is_weth { AeroDir::ZeroForOne } else { AeroDir::OneForZero };
     let (_ain_raw, _aout_raw, exec_price, _spot, _impact) = simulate_exact_in_volatile(pair, dir, eth_in);
     exec_price // already USDC per ETH
 }
 
-// BUY: USDC->ETH exact-out via binary search on USDC-in (xy=k with fee)
+// BUY: USDC->ETH exact-out; getAmountIn on volatile pairs, a search of the curve on stable ones
 fn aerodrome_buy_price_usdc_per_eth(
     pair: &VolatilePairState,
     token0_is_weth: bool,
-    eth_out_target: f64,
-) -> f64 {
-    if eth_out_target <= 0.0 { return 0.0; }
-
-    // Map reserves/decimals so that rin = USDC reserve, rout = WETH reserve
-    let (rin, rout, din, dout) = if token0_is_weth {
-        (pair.reserve1, pair.reserve0, pair.decimals1, pair.decimals0) // token1=USDC -> token0=WETH
+    eth_out_target: TokenAmount,
+) -> Result<f64, Box<dyn std::error::Error + Send + Sync>> {
+    // Direction/decimals for USDC in, WETH out
+    let (zero_for_one, din, dout) = if token0_is_weth {
+        (false, pair.decimals1, pair.decimals0) // token1=USDC -> token0=WETH
     } else {
-        (pair.reserve0, pair.reserve1, pair.decimals0, pair.decimals1) // token0=USDC -> token1=WETH
+        (true, pair.decimals0, pair.decimals1) // token0=USDC -> token1=WETH
     };
 
-    let target_raw = aero_to_raw(eth_out_target, dout);
-    if target_raw.is_zero() { return 0.0; }
-
-    // rough bound: (ETH target)/(WETH per USDC at spot) * 4
-    let spot = crate::math::aerodrome_volatile::spot_price_out_per_in(rin, rout, din, dout); // WETH per USDC
-    let approx_usdc = if spot > 0.0 { eth_out_target / spot } else { eth_out_target * 10_000.0 };
-    let mut lo = U256::zero();
-    let mut hi = {
-        let g = aero_to_raw(approx_usdc * 4.0, din);
-        let cap = U256::from_dec_str("1000000000000000000000000000000")
-            .unwrap_or_else(|_| U256::max_value()); // 1e30 guard, fallback to max
-        if g > cap { cap } else { g }
-    };
+    let target = eth_out_target.rescale(dout);
+    if target.is_zero() { return Ok(0.0); }
 
-    let fee_bps = pair.fee_bps;
-    for _ in 0..64 {
-        let mid = (lo + hi) >> 1;
-        let out = aero_amount_out(mid, rin, rout, fee_bps);
-        if out < target_raw { lo = mid + U256::from(1u8); } else { hi = mid; }
-    }
-    let usdc_in = aero_from_raw(hi, din);
-    usdc_in / eth_out_target
+    let usdc_in = pair.quote_exact_out(zero_for_one, target.raw).ok_or_else(|| {
+        let reserve_out = if zero_for_one { pair.reserve1 } else { pair.reserve0 };
+        format!("pair can only deliver less than {} of the {} units requested", reserve_out, target.raw)
+    })?;
+    Ok(TokenAmount::new(usdc_in, din).per(&target))
 }
 
 // One call that returns both sides for Aerodrome
 pub fn quote_aerodrome_both(
     pair: &VolatilePairState,
     token0_is_weth: bool,
-    trade_size_eth: f64,
+    trade_size_eth: TokenAmount,
     gas_cost: &GasEstimate,
-) -> VenueQuotes {
+) -> Result<VenueQuotes, Box<dyn std::error::Error + Send + Sync>> {
     let sell = aerodrome_sell_price_usdc_per_eth(pair, token0_is_weth, trade_size_eth);
-    let buy  = aerodrome_buy_price_usdc_per_eth(pair, token0_is_weth, trade_size_eth);
-    VenueQuotes {
+    let buy  = aerodrome_buy_price_usdc_per_eth(pair, token0_is_weth, trade_size_eth)?;
+    Ok(VenueQuotes {
         sell: SideQuote { price_usdc_per_eth: sell, estimated_gas_cost_usd: gas_cost.total_usd },
         buy:  SideQuote { price_usdc_per_eth: buy,  estimated_gas_cost_usd: gas_cost.total_usd },
+    })
+}
+
+// Any Base-leg pool; Slipstream and V3 quotes run on the Uniswap simulator
+pub fn quote_aerodrome_pool_both(
+    pool: &AerodromePoolState,
+    token0_is_weth: bool,
+    trade_size_eth: TokenAmount,
+    gas_cost: &GasEstimate,
+) -> Result<VenueQuotes, Box<dyn std::error::Error + Send + Sync>> {
+    match pool {
+        AerodromePoolState::Pair(pair) => quote_aerodrome_both(pair, token0_is_weth, trade_size_eth, gas_cost),
+        AerodromePoolState::Concentrated { pool, tokens } => {
+            quote_uniswap_v4_both(pool, tokens, token0_is_weth, trade_size_eth, gas_cost, None)
+        }
     }
 }
 
@@ -186,8 +186,9 @@ pub struct AerodromeQuote {
 
 pub fn quote_uniswap_v4(
     pool: &UniPoolState,
+    tokens: &TokenPair,
     token0_is_weth: bool,
-    trade_size_eth: f64,
+    trade_size_eth: TokenAmount,
     gas_cost: &GasEstimate,
 ) -> Result<UniswapQuote, Box<dyn std::error::Error + Send + Sync>> {
     let direction = if token0_is_weth {
@@ -196,18 +197,12 @@ pub fn quote_uniswap_v4(
         UniDir::OneForZero
     };
     
-    let result = simulate_exact_in_tokens(pool, direction, Some(3000), trade_size_eth, 18, None)?;
+    let result = within_loaded_ticks(simulate_exact_in_tokens(pool, direction, None, trade_size_eth.rescale(tokens.base.decimals), None)?)?;
 
-    let (eth_in, usdc_out) = if token0_is_weth {
-        ((-result.amount0.clone()).to_f64().unwrap_or(0.0) / 1e18, 
-         result.amount1.clone().to_f64().unwrap_or(0.0) / 1e6)
-    } else {
-        ((-result.amount1.clone()).to_f64().unwrap_or(0.0) / 1e18, 
-         result.amount0.clone().to_f64().unwrap_or(0.0) / 1e6)
-    };
+    let (eth_in, usdc_out) = uniswap_base_in_quote_out(&result, tokens, token0_is_weth);
 
-    let effective_price = if eth_in > 0.0 { usdc_out / eth_in } else { 0.0 };
-    let spot_price = uniswap_spot_proxy(pool, token0_is_weth);
+    let effective_price = usdc_out.per(&eth_in);
+    let spot_price = uniswap_spot_proxy(pool, tokens, token0_is_weth);
     let price_impact_percent = if spot_price > 0.0 {
         ((effective_price - spot_price) / spot_price) * 100.0
     } else {
@@ -224,7 +219,7 @@ pub fn quote_uniswap_v4(
 pub fn quote_aerodrome(
     pair: &VolatilePairState,
     token0_is_weth: bool,
-    trade_size_eth: f64,
+    trade_size_eth: TokenAmount,
     gas_cost: &GasEstimate,
 ) -> Result<AerodromeQuote, Box<dyn std::error::Error + Send + Sync>> {
     let direction = if token0_is_weth {
@@ -249,9 +244,29 @@ pub fn quote_aerodrome(
     })
 }
 
+pub fn quote_aerodrome_pool(
+    pool: &AerodromePoolState,
+    token0_is_weth: bool,
+    trade_size_eth: TokenAmount,
+    gas_cost: &GasEstimate,
+) -> Result<AerodromeQuote, Box<dyn std::error::Error + Send + Sync>> {
+    match pool {
+        AerodromePoolState::Pair(pair) => quote_aerodrome(pair, token0_is_weth, trade_size_eth, gas_cost),
+        AerodromePoolState::Concentrated { pool, tokens } => {
+            let q = quote_uniswap_v4(pool, tokens, token0_is_weth, trade_size_eth, gas_cost)?;
+            Ok(AerodromeQuote {
+                effective_price_usd: q.effective_price_usd,
+                price_impact_percent: q.price_impact_percent,
+                estimated_gas_cost_usd: q.estimated_gas_cost_usd,
+            })
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::chain::tokens::TokenInfo;
     use crate::math::uniswap_v4::PoolState as UniPoolState;
     use crate::math::aerodrome_volatile::VolatilePairState;
     use ethers::types::{Address, U256};
@@ -274,6 +289,10 @@ mod tests {
         )
     }
 
+    fn test_tokens() -> TokenPair {
+        TokenPair::new(1, TokenInfo::native(), TokenInfo::new(Address::from([0x11; 20]), 6))
+    }
+
     fn create_test_aero_pool() -> VolatilePairState {
         VolatilePairState {
             token0: Address::zero(), // WETH
@@ -285,14 +304,25 @@ mod tests {
             decimals0: 18,
             decimals1: 6,
             fee_bps: 30, // 0.3%
+            block_number: 0,
+            block_timestamp: 0,
+            stable: false,
+            chain_id: 0,
+            gas_units: 0,
         }
     }
 
+    fn eth(x: f64) -> TokenAmount {
+        TokenAmount::from_f64(x, 18).unwrap()
+    }
+
     fn create_test_gas() -> GasEstimate {
         GasEstimate {
             gas_limit: U256::from(200_000),
             gas_price: U256::from(25_000_000_000u64), // 25 gwei
             l1_data_fee: U256::zero(),
+            l1_gas_units: U256::zero(),
+            scenarios: None,
             total_wei: U256::from(5_000_000_000_000_000u64), // 0.005 ETH
             total_eth: 0.005,
             total_usd: 17.5, // 0.005 * 3500
@@ -302,9 +332,9 @@ mod tests {
     #[test]
     fn test_uniswap_sell_price_basic() {
         let pool = create_test_uni_pool();
-        let trade_size = 1.0; // 1 ETH
+        let trade_size = eth(1.0); // 1 ETH
         
-        let result = uniswap_sell_price_usdc_per_eth(&pool, true, trade_size, Some(3000));
+        let result = uniswap_sell_price_usdc_per_eth(&pool, &test_tokens(), true, trade_size, Some(3000));
         
         assert!(result.is_ok());
         let price = result.expect("Failed to get sell price");
@@ -317,9 +347,9 @@ mod tests {
     #[test]
     fn test_uniswap_buy_price_basic() {
         let pool = create_test_uni_pool();
-        let trade_size = 1.0; // 1 ETH
+        let trade_size = eth(1.0); // 1 ETH
         
-        let result = uniswap_buy_price_usdc_per_eth(&pool, true, trade_size, Some(3000));
+        let result = uniswap_buy_price_usdc_per_eth(&pool, &test_tokens(), true, trade_size, Some(3000));
         
         assert!(result.is_ok());
         let price = result.expect("Failed to get buy price");
@@ -332,18 +362,20 @@ mod tests {
     #[test]
     fn test_uniswap_token_direction() {
         let pool = create_test_uni_pool();
-        let trade_size = 1.0;
+        let trade_size = eth(1.0);
         
         // Test with WETH as token0
-        let sell_price_0 = uniswap_sell_price_usdc_per_eth(&pool, true, trade_size, Some(3000))
+        let sell_price_0 = uniswap_sell_price_usdc_per_eth(&pool, &test_tokens(), true, trade_size, Some(3000))
             .expect("Failed to get sell price with WETH as token0");
-        let buy_price_0 = uniswap_buy_price_usdc_per_eth(&pool, true, trade_size, Some(3000))
+        let buy_price_0 = uniswap_buy_price_usdc_per_eth(&pool, &test_tokens(), true, trade_size, Some(3000))
             .expect("Failed to get buy price with WETH as token0");
         
-        // Test with WETH as token1
-        let sell_price_1 = uniswap_sell_price_usdc_per_eth(&pool, false, trade_size, Some(3000))
+        // Test with WETH as token1: read that way the pool holds only ~0.1 "WETH" of token1,
+        // so exact-out reports the shortfall instead of pricing an unfillable buy
+        let sell_price_1 = uniswap_sell_price_usdc_per_eth(&pool, &test_tokens(), false, trade_size, Some(3000))
             .expect("Failed to get sell price with WETH as token1");
-        let buy_price_1 = uniswap_buy_price_usdc_per_eth(&pool, false, trade_size, Some(3000))
+        assert!(uniswap_buy_price_usdc_per_eth(&pool, &test_tokens(), false, trade_size, Some(3000)).is_err());
+        let buy_price_1 = uniswap_buy_price_usdc_per_eth(&pool, &test_tokens(), false, eth(0.01), Some(3000))
             .expect("Failed to get buy price with WETH as token1");
         
         // Prices should be positive in both cases
@@ -356,24 +388,61 @@ mod tests {
         // Buy price should generally be higher than sell price but test data may vary
     }
 
+    #[test]
+    fn test_uniswap_refuses_trades_past_loaded_ticks() {
+        // The scan covered 60 ticks either side of the price: 0.001 ETH stays inside, 1 ETH does not
+        let mut pool = create_test_uni_pool();
+        let tick = crate::math::uniswap_v4::get_tick_at_sqrt_ratio(&pool.sqrt_price_x96);
+        pool.loaded_ticks = Some((tick - 60, tick + 60));
+        assert!(uniswap_sell_price_usdc_per_eth(&pool, &test_tokens(), true, eth(0.001), None).is_ok());
+        assert!(uniswap_buy_price_usdc_per_eth(&pool, &test_tokens(), true, eth(0.001), None).is_ok());
+        let err = uniswap_sell_price_usdc_per_eth(&pool, &test_tokens(), true, eth(1.0), None).unwrap_err();
+        assert!(err.to_string().contains("loaded ticks"), "{}", err);
+        assert!(uniswap_buy_price_usdc_per_eth(&pool, &test_tokens(), true, eth(1.0), None).is_err());
+        assert!(quote_uniswap_v4(&pool, &test_tokens(), true, eth(1.0), &create_test_gas()).is_err());
+
+        // Synthetic ticks price nothing
+        pool.loaded_ticks = None;
+        pool.synthetic_ticks = true;
+        assert!(uniswap_sell_price_usdc_per_eth(&pool, &test_tokens(), true, eth(0.001), None).is_err());
+        assert!(uniswap_buy_price_usdc_per_eth(&pool, &test_tokens(), true, eth(0.001), None).is_err());
+    }
+
     #[test]
     fn test_uniswap_zero_trade_size() {
         let pool = create_test_uni_pool();
         
-        let buy_result = uniswap_buy_price_usdc_per_eth(&pool, true, 0.0, Some(3000));
+        let buy_result = uniswap_buy_price_usdc_per_eth(&pool, &test_tokens(), true, eth(0.0), Some(3000));
         
         assert!(buy_result.is_ok());
         assert_eq!(buy_result.expect("Failed to get buy result"), 0.0); // Buy price should be 0 for 0 target
         
         // Sell with 0 might have different behavior, so just test it doesn't panic
-        let _sell_result = uniswap_sell_price_usdc_per_eth(&pool, true, 0.0, Some(3000));
+        let _sell_result = uniswap_sell_price_usdc_per_eth(&pool, &test_tokens(), true, eth(0.0), Some(3000));
+    }
+
+    #[test]
+    fn test_uniswap_price_uses_pair_decimals() {
+        let pool = create_test_uni_pool();
+        let six = test_tokens();
+        let mut eighteen = six;
+        eighteen.quote.decimals = 18;
+
+        let p6 = uniswap_sell_price_usdc_per_eth(&pool, &six, true, eth(1.0), Some(3000))
+            .expect("sell with 6-decimal quote");
+        let p18 = uniswap_sell_price_usdc_per_eth(&pool, &eighteen, true, eth(1.0), Some(3000))
+            .expect("sell with 18-decimal quote");
+
+        // Same raw output, 12 fewer orders of magnitude when the quote has 18 decimals
+        assert!(p6 > 0.0);
+        assert!(((p6 / p18) / 1e12 - 1.0).abs() < 1e-9);
     }
 
     #[test]
     fn test_uniswap_spot_proxy() {
         let pool = create_test_uni_pool();
         
-        let spot_price = uniswap_spot_proxy(&pool, true);
+        let spot_price = uniswap_spot_proxy(&pool, &test_tokens(), true);
         
         assert!(spot_price >= 0.0, "Spot price should be non-negative");
         // Spot price might be 0 if simulation fails, which is acceptable for test pool
@@ -383,9 +452,9 @@ mod tests {
     fn test_quote_uniswap_v4_both() {
         let pool = create_test_uni_pool();
         let gas = create_test_gas();
-        let trade_size = 1.0;
+        let trade_size = eth(1.0);
         
-        let result = quote_uniswap_v4_both(&pool, true, trade_size, &gas, Some(3000));
+        let result = quote_uniswap_v4_both(&pool, &test_tokens(), true, trade_size, &gas, Some(3000));
         
         assert!(result.is_ok());
         let quotes = result.expect("Failed to get quotes");
@@ -405,7 +474,7 @@ mod tests {
     #[test]
     fn test_aerodrome_sell_price_basic() {
         let pair = create_test_aero_pool();
-        let trade_size = 1.0; // 1 ETH
+        let trade_size = eth(1.0); // 1 ETH
         
         let price = aerodrome_sell_price_usdc_per_eth(&pair, true, trade_size);
         
@@ -417,9 +486,9 @@ mod tests {
     #[test]
     fn test_aerodrome_buy_price_basic() {
         let pair = create_test_aero_pool();
-        let trade_size = 1.0; // 1 ETH
+        let trade_size = eth(1.0); // 1 ETH
         
-        let price = aerodrome_buy_price_usdc_per_eth(&pair, true, trade_size);
+        let price = aerodrome_buy_price_usdc_per_eth(&pair, true, trade_size).expect("Failed to get Aerodrome buy price");
         
         assert!(price > 3000.0, "Buy price should be above 3000 USDC/ETH");
         assert!(price < 4000.0, "Buy price should be below 4000 USDC/ETH");
@@ -428,15 +497,15 @@ mod tests {
     #[test]
     fn test_aerodrome_token_direction() {
         let pair = create_test_aero_pool();
-        let trade_size = 1.0;
+        let trade_size = eth(1.0);
         
         // Test with WETH as token0
         let sell_price_0 = aerodrome_sell_price_usdc_per_eth(&pair, true, trade_size);
-        let buy_price_0 = aerodrome_buy_price_usdc_per_eth(&pair, true, trade_size);
+        let buy_price_0 = aerodrome_buy_price_usdc_per_eth(&pair, true, trade_size).expect("Failed to get Aerodrome buy price");
         
         // Test with WETH as token1
         let sell_price_1 = aerodrome_sell_price_usdc_per_eth(&pair, false, trade_size);
-        let buy_price_1 = aerodrome_buy_price_usdc_per_eth(&pair, false, trade_size);
+        let buy_price_1 = aerodrome_buy_price_usdc_per_eth(&pair, false, trade_size).expect("Failed to get Aerodrome buy price");
         
         // All prices should be positive
         assert!(sell_price_0 > 0.0);
@@ -453,8 +522,8 @@ mod tests {
     fn test_aerodrome_zero_trade_size() {
         let pair = create_test_aero_pool();
         
-        let sell_price = aerodrome_sell_price_usdc_per_eth(&pair, true, 0.0);
-        let buy_price = aerodrome_buy_price_usdc_per_eth(&pair, true, 0.0);
+        let sell_price = aerodrome_sell_price_usdc_per_eth(&pair, true, eth(0.0));
+        let buy_price = aerodrome_buy_price_usdc_per_eth(&pair, true, eth(0.0)).expect("Failed to get Aerodrome buy price");
         
         // Sell with 0 should return 0 or reasonable value
         assert!(sell_price >= 0.0);
@@ -466,9 +535,9 @@ mod tests {
     fn test_quote_aerodrome_both() {
         let pair = create_test_aero_pool();
         let gas = create_test_gas();
-        let trade_size = 1.0;
+        let trade_size = eth(1.0);
         
-        let quotes = quote_aerodrome_both(&pair, true, trade_size, &gas);
+        let quotes = quote_aerodrome_both(&pair, true, trade_size, &gas).expect("Failed to get Aerodrome quotes");
         
         // Test sell side
         assert!(quotes.sell.price_usdc_per_eth > 0.0);
@@ -486,9 +555,9 @@ mod tests {
     fn test_legacy_quote_uniswap_v4() {
         let pool = create_test_uni_pool();
         let gas = create_test_gas();
-        let trade_size = 1.0;
+        let trade_size = eth(1.0);
         
-        let result = quote_uniswap_v4(&pool, true, trade_size, &gas);
+        let result = quote_uniswap_v4(&pool, &test_tokens(), true, trade_size, &gas);
         
         assert!(result.is_ok());
         let quote = result.expect("Failed to get Uniswap quote");
@@ -505,7 +574,7 @@ mod tests {
     fn test_legacy_quote_aerodrome() {
         let pair = create_test_aero_pool();
         let gas = create_test_gas();
-        let trade_size = 1.0;
+        let trade_size = eth(1.0);
         
         let result = quote_aerodrome(&pair, true, trade_size, &gas);
         
@@ -526,17 +595,17 @@ mod tests {
         let pair = create_test_aero_pool();
         let gas = create_test_gas();
         
-        let sizes = vec![0.1, 1.0, 5.0];
+        let sizes = vec![eth(0.1), eth(1.0), eth(5.0)];
         
         for size in sizes {
             // Uniswap
-            let uni_quotes = quote_uniswap_v4_both(&pool, true, size, &gas, Some(3000))
+            let uni_quotes = quote_uniswap_v4_both(&pool, &test_tokens(), true, size, &gas, Some(3000))
                 .expect("Failed to get Uniswap quotes");
             assert!(uni_quotes.sell.price_usdc_per_eth > 0.0);
             assert!(uni_quotes.buy.price_usdc_per_eth > 0.0);
             
             // Aerodrome
-            let aero_quotes = quote_aerodrome_both(&pair, true, size, &gas);
+            let aero_quotes = quote_aerodrome_both(&pair, true, size, &gas).expect("Failed to get Aerodrome quotes");
             assert!(aero_quotes.sell.price_usdc_per_eth > 0.0);
             assert!(aero_quotes.buy.price_usdc_per_eth > 0.0);
             
@@ -551,11 +620,11 @@ mod tests {
         let pool = create_test_uni_pool();
         let pair = create_test_aero_pool();
         let gas = create_test_gas();
-        let trade_size = 1.0;
+        let trade_size = eth(1.0);
         
-        let uni_quotes = quote_uniswap_v4_both(&pool, true, trade_size, &gas, Some(3000))
+        let uni_quotes = quote_uniswap_v4_both(&pool, &test_tokens(), true, trade_size, &gas, Some(3000))
             .expect("Failed to get Uniswap quotes");
-        let aero_quotes = quote_aerodrome_both(&pair, true, trade_size, &gas);
+        let aero_quotes = quote_aerodrome_both(&pair, true, trade_size, &gas).expect("Failed to get Aerodrome quotes");
         
         // Calculate potential arbitrage spreads
         let spread_sell_uni_buy_aero = uni_quotes.sell.price_usdc_per_eth - aero_quotes.buy.price_usdc_per_eth;
@@ -591,11 +660,11 @@ mod tests {
         let pool = create_test_uni_pool();
         let pair = create_test_aero_pool();
         let gas = create_test_gas();
-        let large_size = 100.0; // 100 ETH
+        let large_size = eth(100.0); // 100 ETH
         
         // Should handle large trades without panicking
-        let uni_result = quote_uniswap_v4_both(&pool, true, large_size, &gas, Some(3000));
-        let aero_quotes = quote_aerodrome_both(&pair, true, large_size, &gas);
+        let uni_result = quote_uniswap_v4_both(&pool, &test_tokens(), true, large_size, &gas, Some(3000));
+        let aero_quotes = quote_aerodrome_both(&pair, true, large_size, &gas).expect("Failed to get Aerodrome quotes");
         
         if let Ok(uni_quotes) = uni_result {
             assert!(uni_quotes.sell.price_usdc_per_eth >= 0.0);
@@ -1,21 +1,24 @@
-use std::sync::Arc;
-use ethers::prelude::*;
+use crate::bootstrap::{AppState, PairEntry};
 use crate::chain::{
-    uniswap_v4_client::load_v4_pool_snapshot,
-    aerodrome_client::load_volatile_pair_snapshot,
-    gas::estimate_simple_gas_costs,
-    cex_client::CexClient,
-    across_fees::{
-        get_weth_fee_base_to_eth, get_weth_fee_eth_to_base,
-        get_usdc_fee_base_to_eth, get_usdc_fee_eth_to_base,
-    },
+    gas::{legs_cost_usd, GasEstimate},
+    tokens::TokenPair,
+    across_fees::get_bridge_fee,
+    registry::ChainRegistry,
 };
 use crate::engine::{
-    optimizer::{optimize, OptimizerInputs, ArbDirection},
-    pricing::{quote_uniswap_v4, quote_aerodrome, quote_uniswap_v4_both, quote_aerodrome_both},
+    optimizer::{optimize_direction, optimize_split_with, ArbDirection, OptimizerInputs, PairVenue, VenueFill},
+    poller::{BridgeFeeRates, PairSnapshot, SnapshotInfo, UsdPrices},
+    pricing::{quote_uniswap_v4, quote_aerodrome_pool, quote_uniswap_v4_both, quote_aerodrome_pool_both},
 };
+use crate::math::amount::TokenAmount;
+use crate::math::route::Route;
+use crate::math::venue::Venue;
+use crate::watchlist::PairToken;
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
 
 pub struct ArbitrageAnalysis {
+    pub pair: String,
     pub timestamp_utc: String,
     pub trade_size_eth: f64,
     pub reference_cex_price_usd: f64,
@@ -41,210 +44,155 @@ pub struct ArbitrageAnalysis {
     pub bridge_cost_usd:                 f64, // best direction bridge cost
     pub net_profit_best_usd:             f64,
     pub recommended_action:              String,
-}
 
-#[inline]
-fn scale_amount_to_smallest_units(amount: f64, decimals: u32) -> String {
-    // Defensive and saturating conversion from f64 -> token smallest units, as a decimal string.
-    // - Negative, NaN, or non-finite => "0"
-    // - Rounds to nearest integer of smallest units
-    // - Clamps to u128::MAX to avoid UB on cast
-    if !amount.is_finite() || amount <= 0.0 {
-        return "0".to_string();
-    }
+    pub snapshot: SnapshotInfo, // age and block of the cached state this was priced from
+}
 
-    // 10^decimals (decimals in {6,18} for USDC/WETH) is safe in f64.
-    let factor = 10f64.powi(decimals as i32);
-    let scaled = amount * factor;
+/// Convert an API trade size (human base units) to the Ethereum leg's base token, exactly.
+fn trade_size_amount(pair: &PairEntry, trade_size_eth: f64) -> Result<TokenAmount, Box<dyn std::error::Error + Send + Sync>> {
+    TokenAmount::from_f64(trade_size_eth, pair.ethereum_leg_tokens().base.decimals)
+        .ok_or_else(|| format!("trade size {} is not a valid token amount", trade_size_eth).into())
+}
 
-    // Round to nearest integer smallest-unit
-    let v = if scaled.is_finite() { scaled.round() } else { 0.0 };
+// Gas of each leg's swap, priced on the chain it trades on: (Uniswap leg, Aerodrome leg).
+fn leg_gas(snap: &PairSnapshot, pair: &PairEntry) -> Result<(GasEstimate, GasEstimate), String> {
+    let gas = &snap.gas.value;
+    let uni = gas.on(pair.ethereum_leg_tokens().chain_id)?.repriced_for(snap.uniswap.value.pool.gas_units);
+    let aero = gas.on(pair.base_leg_tokens().chain_id)?.repriced_for(snap.aerodrome.value.pool.gas_units());
+    Ok((uni, aero))
+}
 
-    // Clamp to [0, u128::MAX]
-    if v <= 0.0 {
-        return "0".to_string();
+// A cross-chain pair Across can't rebalance either way is refused, with what the poller was told.
+fn check_rebalance_route(pair: &PairEntry, rates: &BridgeFeeRates) -> Result<(), String> {
+    let (uni, aero) = (pair.ethereum_leg_tokens().chain_id, pair.base_leg_tokens().chain_id);
+    match rates.unroutable(uni, aero) {
+        Some(why) => Err(format!("pair '{}' has no Across route to rebalance over: {}", pair.name, why)),
+        None => Ok(()),
     }
-    // Floor of u128::MAX as f64 (approx 3.4e38) — safe to cast back down after clamp.
-    let max_u128_f64 = (u128::MAX as f64).floor();
-    let clamped = if v > max_u128_f64 { max_u128_f64 } else { v };
+}
 
-    let as_u128 = clamped as u128;
-    as_u128.to_string()
+// The two amounts a rebalance would bridge: the base traded, and its quote value at the CEX price
+// (quote rounded half away from zero). The quote side is zero if the product leaves Decimal's range.
+fn bridge_amounts(tokens: &TokenPair, trade_size: TokenAmount, quote_per_base: f64) -> (TokenAmount, TokenAmount) {
+    let base = trade_size.rescale(tokens.base.decimals);
+    let quote = trade_size
+        .to_decimal()
+        .zip(Decimal::from_f64(quote_per_base))
+        .and_then(|(size, px)| size.checked_mul(px))
+        .and_then(|value| TokenAmount::from_decimal(value, tokens.quote.decimals))
+        .unwrap_or(TokenAmount::zero(tokens.quote.decimals));
+    (base, quote)
 }
 
 // Compute “rebalancing bridge fee” in USD for a given direction & size:
-// We try bridging **WETH** and **USDC** (the two assets that become imbalanced),
-// pick the cheaper USD fee. All calls are concurrent.
-async fn compute_bridge_fee_usd_for_direction(
-    trade_size_eth: f64,
-    cex_price_usd: f64,
-    direction: ArbDirection,
-) -> f64 {
-    // WETH amount to rebalance ≈ trade_size_eth (bridge in opposite direction of where ETH piled up)
-    let weth_amount_wei = scale_amount_to_smallest_units(trade_size_eth, 18);
-
-    // USDC imbalance ≈ trade_size_eth * price (bridge USDC the other way)
-    let usdc_amount_6 = scale_amount_to_smallest_units(trade_size_eth * cex_price_usd, 6);
-
-    use futures::future;
-
-    // For SELL_UNI_BUY_AERO:
-    // - ETH piles up on Base → bridge WETH Base→Ethereum OR
-    // - USDC piles up on Ethereum → bridge USDC Ethereum→Base
-    //
-    // For SELL_AERO_BUY_UNI:
-    // - ETH piles up on Ethereum → bridge WETH Ethereum→Base OR
-    // - USDC piles up on Base → bridge USDC Base→Ethereum
-    match direction {
-        ArbDirection::SellUniBuyAero => {
-            let weth_b2e = get_weth_fee_base_to_eth(&weth_amount_wei);
-            let usdc_e2b = get_usdc_fee_eth_to_base(&usdc_amount_6);
-
-            // Run both in parallel; if one fails, keep the other
-            let (weth_fee_res, usdc_fee_res) = future::join(weth_b2e, usdc_e2b).await;
-
-            let weth_fee_usd = weth_fee_res
-                .ok()
-                .and_then(|f| f.total_relay_fee.total_in_usd(18, cex_price_usd).ok())
-                .unwrap_or(f64::INFINITY);
-
-            let usdc_fee_usd = usdc_fee_res
-                .ok()
-                .and_then(|f| f.total_relay_fee.total_in_usd(6, 1.0).ok()) // USDC ~ $1.0
-                .unwrap_or(f64::INFINITY);
-
-            if !weth_fee_usd.is_finite() && !usdc_fee_usd.is_finite() {
-                log::warn!("Both bridge fee lookups failed for SellUniBuyAero; treating as prohibitive");
-            }
-
-            weth_fee_usd.min(usdc_fee_usd)
-        }
-        ArbDirection::SellAeroBuyUni => {
-            let weth_e2b = get_weth_fee_eth_to_base(&weth_amount_wei);
-            let usdc_b2e = get_usdc_fee_base_to_eth(&usdc_amount_6);
-
-            let (weth_fee_res, usdc_fee_res) = future::join(weth_e2b, usdc_b2e).await;
-
-            let weth_fee_usd = weth_fee_res
-                .ok()
-                .and_then(|f| f.total_relay_fee.total_in_usd(18, cex_price_usd).ok())
-                .unwrap_or(f64::INFINITY);
-
-            let usdc_fee_usd = usdc_fee_res
-                .ok()
-                .and_then(|f| f.total_relay_fee.total_in_usd(6, 1.0).ok())
-                .unwrap_or(f64::INFINITY);
-
-            if !weth_fee_usd.is_finite() && !usdc_fee_usd.is_finite() {
-                log::warn!("Both bridge fee lookups failed for SellAeroBuyUni; treating as prohibitive");
-            }
-
-            weth_fee_usd.min(usdc_fee_usd)
+// We try bridging each of the pair's bridgeable tokens (the two sides that become imbalanced),
+// on each chain at the address that leg trades, and pick the cheaper USD fee. All calls are concurrent.
+// The direction is the leg the base token is sold on and the one it is bought back on, any two
+// configured chains; both legs on one chain need no rebalance.
+// Errs, with every lookup's reason, when no token could be quoted.
+// Called by the poller; handlers use the cached rate it derives from this.
+pub(crate) async fn compute_bridge_fee_usd_for_direction(
+    chains: &ChainRegistry,
+    sell_tokens: &TokenPair,
+    buy_tokens: &TokenPair,
+    bridged: &[PairToken],
+    trade_size: TokenAmount,
+    usd: UsdPrices,
+) -> Result<f64, String> {
+    if sell_tokens.chain_id == buy_tokens.chain_id {
+        return Ok(0.0);
+    }
+    if bridged.is_empty() {
+        return Err("no token of the pair is bridgeable".to_string());
+    }
+    let sell_chain = chains.config(sell_tokens.chain_id)?;
+    let buy_chain = chains.config(buy_tokens.chain_id)?;
+
+    // Base amount to rebalance ≈ trade size, in the buy leg's base decimals
+    // Quote imbalance ≈ trade size * price, in the sell leg's quote decimals
+    let quote_per_base = usd.base / usd.quote;
+    let (base_amount, _) = bridge_amounts(buy_tokens, trade_size, quote_per_base);
+    let (_, quote_amount) = bridge_amounts(sell_tokens, trade_size, quote_per_base);
+
+    // Selling on one chain and buying on the other:
+    // - base piles up on the buy chain → bridge base buy→sell OR
+    // - quote piles up on the sell chain → bridge quote sell→buy
+    // Each fee is charged in the token bridged, so it is valued in that token's origin decimals
+    let fees = futures::future::join_all(bridged.iter().map(|token| {
+        let (origin, from, dest, to, amount, price) = match token {
+            PairToken::Base => (buy_chain, buy_tokens.base, sell_chain, sell_tokens.base, base_amount, usd.base),
+            PairToken::Quote => (sell_chain, sell_tokens.quote, buy_chain, buy_tokens.quote, quote_amount, usd.quote),
+        };
+        async move {
+            get_bridge_fee(origin, from.address, dest, to.address, &amount.raw.to_string())
+                .await
+                .and_then(|f| f.total_relay_fee.total_in_usd(from.decimals, price))
+                .map_err(|e| format!("{:?}: {}", token, e))
         }
+    }))
+    .await;
+
+    // If one fails, keep the other
+    let (quoted, failed): (Vec<_>, Vec<_>) = fees.into_iter().partition(|f| f.is_ok());
+    let fee_usd = quoted.into_iter().flatten().fold(f64::INFINITY, f64::min);
+    if fee_usd.is_finite() {
+        Ok(fee_usd)
+    } else {
+        let reasons: Vec<String> = failed.into_iter().filter_map(Result::err).collect();
+        Err(format!("no Across quote from {} to {} ({})", sell_chain.name, buy_chain.name, reasons.join("; ")))
     }
 }
 
-pub async fn analyze_arbitrage(
-    eth_provider: Arc<Provider<Http>>,
-    base_provider: Arc<Provider<Http>>,
-    state_view_addr: Address,
-    cex_client: &CexClient,
+pub fn analyze_arbitrage(
+    app: &AppState,
+    pair: &PairEntry,
     trade_size_eth: f64,
-    eth_usdc_address: Address,
-    base_weth_address: Address,
-    base_usdc_address: Address,
-    aerodrome_factory_address: Address,
-    aerodrome_pool_address: Option<Address>,
-    gas_uniswap_units: u64,
-    gas_aerodrome_units: u64,
 ) -> Result<ArbitrageAnalysis, Box<dyn std::error::Error + Send + Sync>> {
-    use std::time::Instant;
-
-    // PARALLEL EXECUTION: Run all independent data fetches concurrently
-    let parallel_start = Instant::now();
-    log::info!("Starting parallel data fetch");
-
-    let (cex_price, (uni_pool, uni_token0_is_eth), (aero_pair, aero_token0_is_weth)) = tokio::try_join!(
-        async {
-            let start = Instant::now();
-            let result = cex_client.get_coinbase_price().await
-                .map_err(|e| -> Box<dyn std::error::Error + Send + Sync> { e.into() });
-            log::debug!("CEX price fetch completed in {:?}", start.elapsed());
-            result
-        },
-        async {
-            let start = Instant::now();
-            let result = load_v4_pool_snapshot(
-                eth_provider.clone(),
-                state_view_addr,
-                eth_usdc_address,
-                3000,
-                60,
-            ).await;
-            log::debug!("Uniswap V4 snapshot completed in {:?}", start.elapsed());
-            result
-        },
-        async {
-            let start = Instant::now();
-            let result = load_volatile_pair_snapshot(
-                base_provider.clone(),
-                base_weth_address,
-                base_usdc_address,
-                aerodrome_factory_address,
-                aerodrome_pool_address,
-            ).await;
-            log::debug!("Aerodrome snapshot completed in {:?}", start.elapsed());
-            result
-        }
-    )?;
-
-    log::info!("Parallel data fetch completed in {:?}", parallel_start.elapsed());
-
-    // Gas estimation (depends on cex_price, so runs after parallel fetch)
-    let gas_start = Instant::now();
-    log::debug!("Starting gas estimation");
-    let (gas_eth, gas_base) = estimate_simple_gas_costs(
-        eth_provider.clone(),
-        base_provider.clone(),
-        cex_price,
-        gas_uniswap_units,
-        gas_aerodrome_units,
-    ).await?;
-    log::debug!("Gas estimation completed in {:?}", gas_start.elapsed());
+    // Latest background-refreshed state; no RPCs on the request path
+    let snap = app.market.pair_snapshot(&pair.name)?;
+    check_rebalance_route(pair, &snap.bridge_fees.value)?;
+    let usd = snap.usd.value;
+    let (uni_pool, uni_token0_is_eth) = (&snap.uniswap.value.pool, snap.uniswap.value.token0_is_base);
+    let (aero_pool, aero_token0_is_weth) = (&snap.aerodrome.value.pool, snap.aerodrome.value.token0_is_base);
+    let (gas_eth, gas_base) = leg_gas(&snap, pair)?;
+    let trade_size = trade_size_amount(pair, trade_size_eth)?;
 
     // 4. Quotes (both sides per venue)
     log::debug!("Starting Uniswap V4 bidirectional quote");
-    let uni = quote_uniswap_v4_both(&uni_pool, uni_token0_is_eth, trade_size_eth, &gas_eth, Some(3000))
+    let uni = quote_uniswap_v4_both(uni_pool, pair.ethereum_leg_tokens(), uni_token0_is_eth, trade_size, &gas_eth, None)
         .map_err(|e| { log::error!("Uniswap V4 quote failed: {:?}", e); e })?;
 
     log::debug!("Starting Aerodrome bidirectional quote");
-    let aero = quote_aerodrome_both(&aero_pair, aero_token0_is_weth, trade_size_eth, &gas_base);
+    let aero = quote_aerodrome_pool_both(aero_pool, aero_token0_is_weth, trade_size, &gas_base)
+        .map_err(|e| { log::error!("Aerodrome quote failed: {:?}", e); e })?;
 
     // Legacy quotes for price impact calculation
     log::debug!("Starting legacy Uniswap V4 quote");
-    let uni_quote = quote_uniswap_v4(&uni_pool, uni_token0_is_eth, trade_size_eth, &gas_eth)
+    let uni_quote = quote_uniswap_v4(uni_pool, pair.ethereum_leg_tokens(), uni_token0_is_eth, trade_size, &gas_eth)
         .map_err(|e| { log::error!("Legacy Uniswap V4 quote failed: {:?}", e); e })?;
 
     log::debug!("Starting legacy Aerodrome quote");
-    let aero_quote = quote_aerodrome(&aero_pair, aero_token0_is_weth, trade_size_eth, &gas_base)
+    let aero_quote = quote_aerodrome_pool(aero_pool, aero_token0_is_weth, trade_size, &gas_base)
         .map_err(|e| { log::error!("Legacy Aerodrome quote failed: {:?}", e); e })?;
 
     // 5. Directional arbitrage math (USDC/ETH prices)
     let spread_uni_to_aero = uni.sell.price_usdc_per_eth - aero.buy.price_usdc_per_eth;
     let spread_aero_to_uni = aero.sell.price_usdc_per_eth - uni.buy.price_usdc_per_eth;
 
-    let gross_uni_to_aero = spread_uni_to_aero * trade_size_eth;
-    let gross_aero_to_uni = spread_aero_to_uni * trade_size_eth;
+    // Spreads are in quote units; gas and bridge fees are in USD
+    let gross_uni_to_aero = spread_uni_to_aero * trade_size_eth * usd.quote;
+    let gross_aero_to_uni = spread_aero_to_uni * trade_size_eth * usd.quote;
 
-    // Compute live Across bridge fees (USD) for rebalancing in each direction (concurrently)
-    use futures::future;
-    let (fee_uni_to_aero_usd, fee_aero_to_uni_usd) = future::join(
-        compute_bridge_fee_usd_for_direction(trade_size_eth, cex_price, ArbDirection::SellUniBuyAero),
-        compute_bridge_fee_usd_for_direction(trade_size_eth, cex_price, ArbDirection::SellAeroBuyUni),
-    ).await;
+    // Across bridge fees (USD) for rebalancing in each direction, from the cached rates
+    let bridge = &snap.bridge_fees.value;
+    let (eth_chain, base_chain) = (pair.ethereum_leg_tokens().chain_id, pair.base_leg_tokens().chain_id);
+    let fee_uni_to_aero_usd = bridge.fee_usd(eth_chain, base_chain, trade_size_eth, usd.base);
+    let fee_aero_to_uni_usd = bridge.fee_usd(base_chain, eth_chain, trade_size_eth, usd.base);
 
-    let total_cost_uni_to_aero = gas_eth.total_usd + gas_base.total_usd + fee_uni_to_aero_usd;
-    let total_cost_aero_to_uni = gas_eth.total_usd + gas_base.total_usd + fee_aero_to_uni_usd;
+    // Both legs on one chain go out as one transaction
+    let gas_usd = legs_cost_usd(&gas_eth, &gas_base, eth_chain == base_chain);
+    let total_cost_uni_to_aero = gas_usd + fee_uni_to_aero_usd;
+    let total_cost_aero_to_uni = gas_usd + fee_aero_to_uni_usd;
 
     let net1 = gross_uni_to_aero - total_cost_uni_to_aero; // SELL_UNI_BUY_AERO
     let net2 = gross_aero_to_uni - total_cost_aero_to_uni; // SELL_AERO_BUY_UNI
@@ -256,9 +204,10 @@ pub async fn analyze_arbitrage(
     };
 
     Ok(ArbitrageAnalysis {
+        pair: pair.name.clone(),
         timestamp_utc: chrono::Utc::now().to_rfc3339(),
         trade_size_eth,
-        reference_cex_price_usd: cex_price,
+        reference_cex_price_usd: usd.base,
 
         uni_sell_price: uni.sell.price_usdc_per_eth,
         uni_buy_price:  uni.buy.price_usdc_per_eth,
@@ -278,10 +227,13 @@ pub async fn analyze_arbitrage(
         bridge_cost_usd:                if net1 >= net2 { fee_uni_to_aero_usd } else { fee_aero_to_uni_usd },
         net_profit_best_usd:            net_best,
         recommended_action:             action,
+
+        snapshot:                       snap.info(),
     })
 }
 
 pub struct OptimalArbitrageAnalysis {
+    pub pair: String,
     pub timestamp_utc: String,
     pub reference_cex_price_usd: f64,
     pub optimal_trade_size_eth: f64,
@@ -294,133 +246,99 @@ pub struct OptimalArbitrageAnalysis {
     pub gas_cost_usd: f64,
     pub bridge_cost_usd: f64,
     pub recommended_action: String,
+    pub venue_fills: Option<SplitFills>, // split mode only: each venue's share of the legs
+    pub sell_route: Option<Route>,       // routed legs only: path and per-hop amounts taken
+    pub buy_route: Option<Route>,
+    pub snapshot: SnapshotInfo,
+}
+
+/// Per-venue shares of a split trade.
+pub struct SplitFills {
+    pub sells: Vec<VenueFill>,
+    pub buys: Vec<VenueFill>,
 }
 
-pub async fn find_optimal_arbitrage(
-    eth_provider: Arc<Provider<Http>>,
-    base_provider: Arc<Provider<Http>>,
-    state_view_addr: Address,
-    cex_client: &CexClient,
+pub fn find_optimal_arbitrage(
+    app: &AppState,
+    pair: &PairEntry,
     max_size_eth: f64,
-    eth_usdc_address: Address,
-    base_weth_address: Address,
-    base_usdc_address: Address,
-    aerodrome_factory_address: Address,
-    aerodrome_pool_address: Option<Address>,
-    gas_uniswap_units: u64,
-    gas_aerodrome_units: u64,
 ) -> Result<OptimalArbitrageAnalysis, Box<dyn std::error::Error + Send + Sync>> {
-    use std::time::Instant;
-
-    // PARALLEL EXECUTION: Run all independent data fetches concurrently
-    let parallel_start = Instant::now();
-    log::info!("Starting parallel data fetch for optimal arbitrage");
-
-    let (cex_price, (uni_pool, uni_token0_is_eth), (aero_pair, aero_token0_is_weth)) = tokio::try_join!(
-        async {
-            let start = Instant::now();
-            let result = cex_client.get_coinbase_price().await
-                .map_err(|e| -> Box<dyn std::error::Error + Send + Sync> { e.into() });
-            log::debug!("CEX price fetch completed in {:?}", start.elapsed());
-            result
-        },
-        async {
-            let start = Instant::now();
-            let result = load_v4_pool_snapshot(
-                eth_provider.clone(),
-                state_view_addr,
-                eth_usdc_address,
-                3000,
-                60,
-            ).await;
-            log::debug!("Uniswap V4 snapshot completed in {:?}", start.elapsed());
-            result
-        },
-        async {
-            let start = Instant::now();
-            let result = load_volatile_pair_snapshot(
-                base_provider.clone(),
-                base_weth_address,
-                base_usdc_address,
-                aerodrome_factory_address,
-                aerodrome_pool_address,
-            ).await;
-            log::debug!("Aerodrome snapshot completed in {:?}", start.elapsed());
-            result
-        }
-    )?;
-
-    log::info!("Parallel data fetch completed in {:?}", parallel_start.elapsed());
-
-    // 3. Fetch gas costs using predefined constants
-    let (gas_eth, gas_base) = estimate_simple_gas_costs(
-        eth_provider.clone(),
-        base_provider.clone(),
-        cex_price,
-        gas_uniswap_units,
-        gas_aerodrome_units,
-    ).await?;
+    // Latest background-refreshed state; no RPCs on the request path
+    let snap = app.market.pair_snapshot(&pair.name)?;
+    check_rebalance_route(pair, &snap.bridge_fees.value)?;
+    let usd = snap.usd.value;
+    let (uni_pool, uni_token0_is_eth) = (&snap.uniswap.value.pool, snap.uniswap.value.token0_is_base);
+    let (aero_pool, aero_token0_is_weth) = (&snap.aerodrome.value.pool, snap.aerodrome.value.token0_is_base);
+    let (gas_eth, gas_base) = leg_gas(&snap, pair)?;
+
+    let mut eth_leg = PairVenue::new(pair.ethereum_leg_id(), uni_pool.clone(), *pair.ethereum_leg_tokens(), uni_token0_is_eth, &gas_eth);
+    let mut base_leg = PairVenue::new(pair.base_leg_id(), aero_pool.clone(), *pair.base_leg_tokens(), aero_token0_is_weth, &gas_base);
+    // With routing pools loaded, either leg may take a multi-hop route instead
+    if let Some(routes) = &snap.routes {
+        let routes = &routes.value;
+        eth_leg = eth_leg.routed(&routes.ethereum, routes.max_hops, &gas_eth, usd.quote);
+        base_leg = base_leg.routed(&routes.base, routes.max_hops, &gas_base, usd.quote);
+    }
 
-    // 4. Run optimizer (bridge_cost_usd is a placeholder; we’ll recompute live below)
-    let inputs = OptimizerInputs {
-        uni_pool: uni_pool.clone(),
-        uni_token0_is_weth: uni_token0_is_eth,
-        uni_fee_ppm_override: Some(3000),
-        aero_pair: aero_pair.clone(),
-        aero_token0_is_weth,
-        gas_eth: gas_eth.clone(),
-        gas_base: gas_base.clone(),
-        bridge_cost_usd: 10.0, // placeholder
-        hint_size_eth: max_size_eth / 2.0,
-        max_size_eth,
-    };
+    // 4. Run the optimizer per direction, each with its route's cached Across fee (fixed part
+    //    plus a rate), so the size is searched against the fee it will pay
+    let rates = &snap.bridge_fees.value;
+    let directions = [
+        (ArbDirection::new(&eth_leg.id, &base_leg.id), eth_leg.chain_id(), base_leg.chain_id()),
+        (ArbDirection::new(&base_leg.id, &eth_leg.id), base_leg.chain_id(), eth_leg.chain_id()),
+    ];
+    let venues = vec![eth_leg, base_leg];
+    let best = directions
+        .into_iter()
+        .filter_map(|(direction, sell_chain, buy_chain)| {
+            let fee = rates.route(sell_chain, buy_chain);
+            let inputs = OptimizerInputs {
+                venues: venues.clone(),
+                bridge_cost_usd: fee.fixed_usd,
+                bridge_usd_per_eth: fee.rate * usd.base,
+                quote_usd: usd.quote,
+                hint_size_eth: max_size_eth / 2.0,
+                max_size_eth,
+            };
+            optimize_direction(&inputs, &direction)
+        })
+        .max_by(|a, b| a.net_profit_usd.total_cmp(&b.net_profit_usd));
 
-    match optimize(&inputs) {
+    match best {
         Some(result) => {
-            // Compute **live** bridge fee for the optimizer’s optimal size & direction
-            let live_bridge_fee_usd = compute_bridge_fee_usd_for_direction(
-                result.optimal_size_eth,
-                cex_price,
-                match result.direction {
-                    ArbDirection::SellAeroBuyUni => ArbDirection::SellAeroBuyUni,
-                    ArbDirection::SellUniBuyAero => ArbDirection::SellUniBuyAero,
-                },
-            ).await;
-
-            // Recompute totals replacing placeholder bridge cost with live fee
-            let corrected_total_costs = result.costs_usd - result.bridge_cost_usd + live_bridge_fee_usd;
-            let corrected_net = result.proceeds_usd - corrected_total_costs;
-
-            let direction_str = match result.direction {
-                ArbDirection::SellAeroBuyUni => "SELL_AERODROME_BUY_UNISWAP",
-                ArbDirection::SellUniBuyAero => "SELL_UNISWAP_BUY_AERODROME",
-            };
+            let gross_profit_usd = result.proceeds_usd - result.costs_usd;
+            let total_costs_usd = result.gas_usd_total + result.bridge_cost_usd;
 
-            let action = if corrected_net > 0.0 {
+            let action = if result.net_profit_usd > 0.0 {
                 "PROFITABLE_ARBITRAGE_FOUND"
             } else {
                 "NO_PROFITABLE_ARBITRAGE"
             };
 
             Ok(OptimalArbitrageAnalysis {
+                pair: pair.name.clone(),
                 timestamp_utc: chrono::Utc::now().to_rfc3339(),
-                reference_cex_price_usd: cex_price,
+                reference_cex_price_usd: usd.base,
                 optimal_trade_size_eth: result.optimal_size_eth,
-                optimal_direction: direction_str.to_string(),
-                net_profit_usd: corrected_net,
-                gross_profit_usd: result.proceeds_usd,
-                total_costs_usd: corrected_total_costs,
+                optimal_direction: result.direction.label(),
+                net_profit_usd: result.net_profit_usd,
+                gross_profit_usd,
+                total_costs_usd,
                 effective_sell_price_usdc_per_eth: result.eff_price_sell_usdc_per_eth,
                 effective_buy_price_usdc_per_eth: result.eff_price_buy_usdc_per_eth,
                 gas_cost_usd: result.gas_usd_total,
-                bridge_cost_usd: live_bridge_fee_usd,
+                bridge_cost_usd: result.bridge_cost_usd,
                 recommended_action: action.to_string(),
+                venue_fills: None,
+                sell_route: result.sell_route,
+                buy_route: result.buy_route,
+                snapshot: snap.info(),
             })
         }
         None => {
             // Still show market prices at a small test size for reference
-            let test_size = 1.0; // 1 ETH for price discovery
-            let uni = quote_uniswap_v4_both(&uni_pool, uni_token0_is_eth, test_size, &gas_eth, Some(3000))
+            let test_size = trade_size_amount(pair, 1.0)?; // 1 ETH for price discovery
+            let uni = quote_uniswap_v4_both(uni_pool, pair.ethereum_leg_tokens(), uni_token0_is_eth, test_size, &gas_eth, None)
                 .unwrap_or_else(|_| {
                     log::warn!("Failed to get Uniswap V4 quotes for test size, using defaults");
                     crate::engine::pricing::VenueQuotes {
@@ -434,66 +352,149 @@ pub async fn find_optimal_arbitrage(
                         },
                     }
                 });
-            let aero = quote_aerodrome_both(&aero_pair, aero_token0_is_weth, test_size, &gas_base);
+            let aero = quote_aerodrome_pool_both(aero_pool, aero_token0_is_weth, test_size, &gas_base)
+                .unwrap_or_else(|_| {
+                    log::warn!("Failed to get Aerodrome quotes for test size, using defaults");
+                    crate::engine::pricing::VenueQuotes::default()
+                });
 
             // Determine which direction would be better (even if unprofitable)
             let spread_uni_to_aero = uni.sell.price_usdc_per_eth - aero.buy.price_usdc_per_eth;
             let spread_aero_to_uni = aero.sell.price_usdc_per_eth - uni.buy.price_usdc_per_eth;
 
+            let (uni_id, aero_id) = (pair.ethereum_leg_id(), pair.base_leg_id());
             let (direction, sell_price, buy_price) = if spread_uni_to_aero > spread_aero_to_uni {
-                ("SELL_UNISWAP_BUY_AERODROME", uni.sell.price_usdc_per_eth, aero.buy.price_usdc_per_eth)
+                (ArbDirection::new(uni_id, aero_id), uni.sell.price_usdc_per_eth, aero.buy.price_usdc_per_eth)
             } else {
-                ("SELL_AERODROME_BUY_UNISWAP", aero.sell.price_usdc_per_eth, uni.buy.price_usdc_per_eth)
+                (ArbDirection::new(aero_id, uni_id), aero.sell.price_usdc_per_eth, uni.buy.price_usdc_per_eth)
             };
 
             Ok(OptimalArbitrageAnalysis {
+                pair: pair.name.clone(),
                 timestamp_utc: chrono::Utc::now().to_rfc3339(),
-                reference_cex_price_usd: cex_price,
+                reference_cex_price_usd: usd.base,
                 optimal_trade_size_eth: 0.0,
-                optimal_direction: direction.to_string(),
+                optimal_direction: direction.label(),
                 net_profit_usd: 0.0,
                 gross_profit_usd: 0.0,
                 total_costs_usd: 0.0,
                 effective_sell_price_usdc_per_eth: sell_price,
                 effective_buy_price_usdc_per_eth: buy_price,
-                gas_cost_usd: gas_eth.total_usd + gas_base.total_usd,
+                gas_cost_usd: legs_cost_usd(&gas_eth, &gas_base, pair.same_chain()),
                 bridge_cost_usd: 0.0,
                 recommended_action: "NO_ARBITRAGE_OPPORTUNITY".to_string(),
+                venue_fills: None,
+                sell_route: None,
+                buy_route: None,
+                snapshot: snap.info(),
             })
         }
     }
 }
 
+// Rebalance fee of a split trade: the dearest route between any chain it sells on and any it
+// buys on, since each chain's surplus has to move.
+fn split_bridge_fee_usd(rates: &BridgeFeeRates, sells: &[u64], buys: &[u64], size_eth: f64, base_usd: f64) -> f64 {
+    sells.iter()
+        .flat_map(|&s| buys.iter().map(move |&b| rates.fee_usd(s, b, size_eth, base_usd)))
+        .fold(0.0, f64::max)
+}
+
+/// Split mode: both legs spread over the pair's two legs and its `[pair.split]` pools.
+/// Without a profitable split this gives the pair-mode answer, with no venue fills.
+pub fn find_optimal_split(
+    app: &AppState,
+    pair: &PairEntry,
+    max_size_eth: f64,
+) -> Result<OptimalArbitrageAnalysis, Box<dyn std::error::Error + Send + Sync>> {
+    let snap = app.market.pair_snapshot(&pair.name)?;
+    check_rebalance_route(pair, &snap.bridge_fees.value)?;
+    let usd = snap.usd.value;
+    let (gas_eth, gas_base) = leg_gas(&snap, pair)?;
+
+    let mut venues = vec![
+        PairVenue::new(pair.ethereum_leg_id(), snap.uniswap.value.pool.clone(), *pair.ethereum_leg_tokens(), snap.uniswap.value.token0_is_base, &gas_eth),
+        PairVenue::new(pair.base_leg_id(), snap.aerodrome.value.pool.clone(), *pair.base_leg_tokens(), snap.aerodrome.value.token0_is_base, &gas_base),
+    ];
+    // Split pools join once loaded; until then the split is over the two legs
+    for v in snap.venues.iter().flat_map(|v| v.value.iter()) {
+        let gas = snap.gas.value.on(v.tokens.chain_id)?;
+        venues.push(PairVenue::new(v.id.clone(), v.pool.clone(), v.tokens, v.token0_is_base, gas));
+    }
+
+    // Each candidate split pays the bridge fee of the chains its fills use, not a single one
+    let inputs = OptimizerInputs {
+        venues,
+        bridge_cost_usd: 0.0,
+        bridge_usd_per_eth: 0.0,
+        quote_usd: usd.quote,
+        hint_size_eth: max_size_eth / 2.0,
+        max_size_eth,
+    };
+    let rates = &snap.bridge_fees.value;
+    let Some(result) = optimize_split_with(&inputs, |sells, buys, size_eth| split_bridge_fee_usd(rates, sells, buys, size_eth, usd.base)) else {
+        return find_optimal_arbitrage(app, pair, max_size_eth);
+    };
+
+    let gross_profit_usd = result.proceeds_usd - result.costs_usd;
+    let total_costs_usd = result.gas_usd_total + result.bridge_cost_usd;
+    let net_profit_usd = result.net_profit_usd;
+
+    let ids = |fills: &[VenueFill]| fills.iter().map(|f| f.venue.as_str()).collect::<Vec<_>>().join("+");
+    let per_eth = |fills: &[VenueFill]| fills.iter().map(|f| f.usdc).sum::<f64>() / result.optimal_size_eth;
+    let action = if net_profit_usd > 0.0 { "PROFITABLE_ARBITRAGE_FOUND" } else { "NO_PROFITABLE_ARBITRAGE" };
+
+    Ok(OptimalArbitrageAnalysis {
+        pair: pair.name.clone(),
+        timestamp_utc: chrono::Utc::now().to_rfc3339(),
+        reference_cex_price_usd: usd.base,
+        optimal_trade_size_eth: result.optimal_size_eth,
+        optimal_direction: ArbDirection::new(ids(&result.sells), ids(&result.buys)).label(),
+        net_profit_usd,
+        gross_profit_usd,
+        total_costs_usd,
+        effective_sell_price_usdc_per_eth: per_eth(&result.sells),
+        effective_buy_price_usdc_per_eth: per_eth(&result.buys),
+        gas_cost_usd: result.gas_usd_total,
+        bridge_cost_usd: result.bridge_cost_usd,
+        recommended_action: action.to_string(),
+        venue_fills: Some(SplitFills { sells: result.sells, buys: result.buys }),
+        sell_route: None,
+        buy_route: None,
+        snapshot: snap.info(),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use ethers::types::U256;
 
     #[test]
-    fn test_scale_amount_to_smallest_units() {
-        // Test ETH (18 decimals)
-        assert_eq!(scale_amount_to_smallest_units(1.0, 18), "1000000000000000000");
-        assert_eq!(scale_amount_to_smallest_units(0.000001, 18), "1000000000000");
-        assert_eq!(scale_amount_to_smallest_units(1.5, 18), "1500000000000000000");
-
-        // Test USDC (6 decimals)
-        assert_eq!(scale_amount_to_smallest_units(1.0, 6), "1000000");
-        assert_eq!(scale_amount_to_smallest_units(1000.0, 6), "1000000000");
-        assert_eq!(scale_amount_to_smallest_units(0.000001, 6), "1");
-
-        // Test edge cases
-        assert_eq!(scale_amount_to_smallest_units(0.0, 18), "0");
-        assert_eq!(scale_amount_to_smallest_units(-10.0, 18), "0"); // negative clamped to 0
-        assert_eq!(scale_amount_to_smallest_units(f64::NAN, 18), "0"); // NaN handled
-        assert_eq!(scale_amount_to_smallest_units(f64::INFINITY, 18), "0"); // Infinity handled
-
-        // Test rounding
-        assert_eq!(scale_amount_to_smallest_units(1.4999999999, 6), "1500000"); // rounds to 1.5
-        assert_eq!(scale_amount_to_smallest_units(1.5000000001, 6), "1500000"); // rounds to 1.5
-
-        // Very large (saturates safely rather than panic)
-        let huge = scale_amount_to_smallest_units(1e40, 18);
-        assert!(!huge.is_empty());
+    fn test_bridge_amounts_in_smallest_units() {
+        use crate::chain::tokens::TokenInfo;
+        use ethers::types::Address;
+        let tokens = TokenPair::new(8453, TokenInfo::native(), TokenInfo::new(Address::from([0x11; 20]), 6));
+        let eth = |x: f64| TokenAmount::from_f64(x, 18).unwrap();
+        let raw = |(base, quote): (TokenAmount, TokenAmount)| (base.raw.to_string(), quote.raw.to_string());
+
+        // ETH (18 decimals) and its USDC (6 decimals) value
+        assert_eq!(raw(bridge_amounts(&tokens, eth(1.0), 3500.0)), ("1000000000000000000".into(), "3500000000".into()));
+        assert_eq!(raw(bridge_amounts(&tokens, eth(0.000001), 1000.0)), ("1000000000000".into(), "1000".into()));
+
+        // 0.1 ETH is exactly 10^17 wei and 0.1 * 3000.3 is exactly 300.03 USDC (f64 gives 300.03000000000003)
+        assert_eq!(raw(bridge_amounts(&tokens, eth(0.1), 3000.3)), ("100000000000000000".into(), "300030000".into()));
+
+        // Quote rounds half away from zero at 6 decimals
+        assert_eq!(raw(bridge_amounts(&tokens, eth(1.0), 1.4999995)), ("1000000000000000000".into(), "1500000".into()));
+
+        // Zero size, and a price Decimal cannot hold, bridge nothing on the quote side
+        assert_eq!(raw(bridge_amounts(&tokens, TokenAmount::zero(18), 3500.0)), ("0".into(), "0".into()));
+        assert_eq!(bridge_amounts(&tokens, eth(1.0), f64::NAN).1, TokenAmount::zero(6));
+        assert_eq!(bridge_amounts(&tokens, eth(1.0), 1e40).1, TokenAmount::zero(6));
+
+        // Trade sizes are carried in the base token's own decimals
+        assert_eq!(bridge_amounts(&tokens, TokenAmount::from_f64(1.5, 8).unwrap(), 1.0).0, eth(1.5));
     }
 
     #[tokio::test]
@@ -511,6 +512,8 @@ mod tests {
             gas_limit: U256::from(200_000),
             gas_price: U256::from(25_000_000_000u64), // 25 gwei
             l1_data_fee: U256::zero(),
+            l1_gas_units: U256::zero(),
+            scenarios: None,
             total_wei: U256::from(5_000_000_000_000_000u64), // 0.005 ETH
             total_eth: 0.005,
             total_usd: 2.0,
@@ -520,6 +523,8 @@ mod tests {
             gas_limit: U256::from(150_000),
             gas_price: U256::from(1_000_000_000u64), // 1 gwei
             l1_data_fee: U256::zero(),
+            l1_gas_units: U256::zero(),
+            scenarios: None,
             total_wei: U256::from(150_000_000_000_000u64),
             total_eth: 0.00015,
             total_usd: 0.5,
@@ -579,12 +584,15 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::if_same_then_else)]
     fn test_gas_cost_selection() {
         // Test that we select the correct gas costs based on direction
         let gas_eth = crate::chain::gas::GasEstimate {
             gas_limit: U256::from(200_000),
             gas_price: U256::from(25_000_000_000u64),
             l1_data_fee: U256::zero(),
+            l1_gas_units: U256::zero(),
+            scenarios: None,
             total_wei: U256::from(5_000_000_000_000_000u64),
             total_eth: 0.005,
             total_usd: 2.0,
@@ -594,6 +602,8 @@ mod tests {
             gas_limit: U256::from(150_000),
             gas_price: U256::from(1_000_000_000u64),
             l1_data_fee: U256::zero(),
+            l1_gas_units: U256::zero(),
+            scenarios: None,
             total_wei: U256::from(150_000_000_000_000u64),
             total_eth: 0.00015,
             total_usd: 0.5,
@@ -612,6 +622,19 @@ mod tests {
         assert_eq!(total_gas, 2.5);
     }
 
+    #[test]
+    fn test_split_bridge_fee_takes_the_dearest_route() {
+        use crate::engine::poller::BridgeFee;
+        let rate = |rate| BridgeFee { fixed_usd: 0.0, rate };
+        let rates = BridgeFeeRates { by_route: [((1, 8453), rate(0.001)), ((8453, 1), rate(0.002))].into_iter().collect(), ..Default::default() };
+        // All on one chain: nothing to rebalance
+        assert_eq!(split_bridge_fee_usd(&rates, &[1], &[1], 10.0, 3000.0), 0.0);
+        assert!((split_bridge_fee_usd(&rates, &[1], &[8453], 10.0, 3000.0) - 30.0).abs() < 1e-9);
+        // Selling on both chains: the Base surplus still has to cross
+        assert!((split_bridge_fee_usd(&rates, &[1, 8453], &[1], 10.0, 3000.0) - 60.0).abs() < 1e-9);
+        assert!(split_bridge_fee_usd(&rates, &[10], &[1], 10.0, 3000.0).is_infinite());
+    }
+
     #[test]
     fn test_bridge_cost_selection() {
         // Test that we select the correct bridge cost based on best direction
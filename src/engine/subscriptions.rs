@@ -0,0 +1,194 @@
+// src/engine/subscriptions.rs
+//
+// Optional WS listeners that wake the poller when on-chain state changes.
+// - newHeads on either chain      -> gas
+// - V4 Swap / ModifyLiquidity     -> applied to that pair's V4 tracker (full reload if it can't)
+// - Aerodrome Sync / SetCustomFee -> applied to that pair's Aerodrome tracker (full reload on fee changes);
+//   Slipstream pools emit neither and stay on their poll interval, as do V3 pools on either chain
+// The poll intervals stay in place as a fallback; a dropped socket reconnects with backoff,
+// and every (re)subscription triggers a full refresh to cover logs missed while down.
+// Logs carry no timestamp, so each one is stamped with its block's newHeads timestamp.
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use ethers::prelude::*;
+use futures::StreamExt;
+use tokio::task::JoinHandle;
+
+use crate::bootstrap::AppState;
+use crate::chain::events::{aerodrome_events_filter, v4_log_pool_id, v4_pool_events_filter};
+use crate::chain::providers::create_ws_provider;
+use crate::chain::uniswap_v4_client::v4_pool_id;
+use crate::engine::poller::{apply_aerodrome_log, apply_v4_log, PollWakers};
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+const RECONNECT_MIN: Duration = Duration::from_secs(1);
+const RECONNECT_MAX: Duration = Duration::from_secs(60);
+const RECENT_HEADS: usize = 64;
+
+/// Timestamps of the last few heads, to stamp logs from the same blocks.
+#[derive(Default)]
+struct RecentHeads(VecDeque<(u64, u64)>);
+
+impl RecentHeads {
+    fn push(&mut self, number: u64, timestamp: u64) {
+        if self.0.len() == RECENT_HEADS {
+            self.0.pop_front();
+        }
+        self.0.push_back((number, timestamp));
+    }
+
+    /// The block's head timestamp; arrival time when its head hasn't been seen (yet).
+    fn timestamp_of(&self, number: Option<U64>) -> u64 {
+        number
+            .and_then(|n| self.0.iter().rev().find(|(b, _)| *b == n.as_u64()))
+            .map(|(_, ts)| *ts)
+            .unwrap_or_else(|| SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()))
+    }
+}
+
+/// Subscribe once, then forward heads and logs until either stream ends.
+async fn listen(
+    chain: &str,
+    url: &str,
+    filter: &Filter,
+    on_subscribed: &(dyn Fn() + Send + Sync),
+    on_head: &(dyn Fn() + Send + Sync),
+    on_log: &(dyn Fn(&Log, u64) + Send + Sync),
+) -> Result<(), BoxError> {
+    let provider = create_ws_provider(url).await?;
+    let mut heads = provider.subscribe_blocks().await?;
+    let mut logs = provider.subscribe_logs(filter).await?;
+    log::info!("{} WS subscribed to newHeads and pool logs", chain);
+    on_subscribed();
+
+    let mut recent = RecentHeads::default();
+    loop {
+        tokio::select! {
+            head = heads.next() => match head {
+                Some(head) => {
+                    if let Some(number) = head.number {
+                        recent.push(number.as_u64(), head.timestamp.as_u64());
+                    }
+                    on_head()
+                }
+                None => return Err("newHeads stream closed".into()),
+            },
+            log = logs.next() => match log {
+                Some(log) => on_log(&log, recent.timestamp_of(log.block_number)),
+                None => return Err("log stream closed".into()),
+            },
+        }
+    }
+}
+
+struct Handlers<S, H, L> {
+    on_subscribed: S,
+    on_head: H,
+    on_log: L,
+}
+
+fn spawn_listener<S, H, L>(chain: &'static str, url: String, filter: Filter, handlers: Handlers<S, H, L>) -> JoinHandle<()>
+where
+    S: Fn() + Send + Sync + 'static,
+    H: Fn() + Send + Sync + 'static,
+    L: Fn(&Log, u64) + Send + Sync + 'static,
+{
+    tokio::spawn(async move {
+        let mut backoff = RECONNECT_MIN;
+        loop {
+            let started = Instant::now();
+            let Handlers { on_subscribed, on_head, on_log } = &handlers;
+            if let Err(e) = listen(chain, &url, &filter, on_subscribed, on_head, on_log).await {
+                log::warn!("{} WS listener stopped: {}; reconnecting in {:?}", chain, e, backoff);
+            }
+            // A connection that held for a while starts the backoff over
+            if started.elapsed() > RECONNECT_MAX {
+                backoff = RECONNECT_MIN;
+            }
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(RECONNECT_MAX);
+        }
+    })
+}
+
+/// Start a listener per chain with a WS URL configured; chains without one rely on polling.
+pub fn spawn(app: Arc<AppState>, wakers: Arc<PollWakers>) -> Vec<JoinHandle<()>> {
+    let mut handles = Vec::new();
+
+    if let Some(url) = app.ethereum_ws_url.clone() {
+        // Pairs priced on a V3 pool get an id no PoolManager log carries
+        let pool_ids: Vec<Option<H256>> = app.pairs.iter()
+            .map(|p| p.v4_tracked().then(|| H256::from(v4_pool_id(&p.eth_pair, &p.v4_pool))))
+            .collect();
+        let tracked_ids: Vec<H256> = pool_ids.iter().flatten().copied().collect();
+        let filter = v4_pool_events_filter(app.uniswap_pool_manager, &tracked_ids);
+
+        let w = wakers.clone();
+        let on_subscribed = move || w.pairs.iter().for_each(|p| p.uniswap.notify_one());
+        let w = wakers.clone();
+        let on_head = move || w.gas.notify_one();
+        let w = wakers.clone();
+        let a = app.clone();
+        let on_log = move |log: &Log, block_timestamp: u64| {
+            let Some(id) = v4_log_pool_id(log) else { return };
+            for ((pool_id, pair), wake) in pool_ids.iter().zip(&a.pairs).zip(&w.pairs) {
+                if *pool_id == Some(id) && apply_v4_log(&a, pair, log, block_timestamp) {
+                    wake.uniswap.notify_one();
+                }
+            }
+        };
+        handles.push(spawn_listener("Ethereum", url, filter, Handlers { on_subscribed, on_head, on_log }));
+    }
+
+    if let Some(url) = app.base_ws_url.clone() {
+        let pools: Vec<Address> = app.pairs.iter()
+            .filter(|p| p.aerodrome_tracked())
+            .map(|p| p.aerodrome_pool)
+            .collect();
+        let filter = aerodrome_events_filter(&pools, app.aerodrome_factory_address);
+
+        let w = wakers.clone();
+        let on_subscribed = move || w.pairs.iter().for_each(|p| p.aerodrome.notify_one());
+        let w = wakers.clone();
+        let on_head = move || w.gas.notify_one();
+        let w = wakers;
+        let a = app.clone();
+        let on_log = move |log: &Log, block_timestamp: u64| {
+            // Trackers filter for their own pool (Sync by address, SetCustomFee by topic)
+            for (pair, wake) in a.pairs.iter().zip(&w.pairs).filter(|(p, _)| p.aerodrome_tracked()) {
+                if apply_aerodrome_log(&a, pair, log, block_timestamp) {
+                    wake.aerodrome.notify_one();
+                }
+            }
+        };
+        handles.push(spawn_listener("Base", url, filter, Handlers { on_subscribed, on_head, on_log }));
+    }
+
+    if handles.is_empty() {
+        log::info!("No WS URLs configured; market state refreshes on poll intervals only");
+    }
+    handles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recent_heads_stamp_logs() {
+        let mut heads = RecentHeads::default();
+        for n in 0..(RECENT_HEADS as u64 + 10) {
+            heads.push(n, 1_700_000_000 + 12 * n);
+        }
+        assert_eq!(heads.timestamp_of(Some(U64::from(70))), 1_700_000_840);
+
+        // Evicted or not yet seen: falls back to the current time
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        assert!(heads.timestamp_of(Some(U64::from(0))) >= now);
+        assert!(heads.timestamp_of(None) >= now);
+    }
+}
@@ -1,6 +1,7 @@
-// lib.rs - Library exports for integration tests
+// lib.rs - Library exports for the binary and integration tests
 
 pub mod config;
+pub mod watchlist;
 pub mod bootstrap;
 pub mod chain;
 pub mod math;
@@ -1,14 +1,9 @@
 use rocket::{launch, routes};
 use std::sync::Arc;
 
-mod config;
-mod bootstrap;
-mod chain;
-mod math;
-mod engine;
-mod web;
-
-use crate::web::routes::{arbitrage_opportunity, optimal_arbitrage_opportunity, health, metrics};
+use arrakis_arbitrage::{bootstrap, config};
+use arrakis_arbitrage::engine::{poller, subscriptions};
+use arrakis_arbitrage::web::routes::{arbitrage_opportunity, optimal_arbitrage_opportunity, health, metrics};
 
 #[launch]
 async fn rocket() -> _ {
@@ -21,9 +16,16 @@ async fn rocket() -> _ {
     // Build application state
     let app_state = Arc::new(
         bootstrap::AppState::new(&config)
+            .await
             .expect("Failed to initialize application state")
     );
 
+    // Keep market state warm in the background; handlers read from the cache.
+    // With WS URLs configured, new blocks and pool events trigger refreshes early.
+    let wakers = Arc::new(poller::PollWakers::new(app_state.pairs.len()));
+    poller::spawn(app_state.clone(), &wakers);
+    subscriptions::spawn(app_state.clone(), wakers);
+
     // Configure Rocket
     let figment = rocket::Config::figment()
         .merge(("port", config.port))
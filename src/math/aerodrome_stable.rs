@@ -0,0 +1,238 @@
+// Aerodrome (Solidly/Velodrome) — Stable Pool Math Module
+// --------------------------------------------------------
+// Focus: the sAMM invariant x³y + xy³ = k, with getAmountOut ported line by line from
+// Pool.sol so results match the contract to the wei. Used for stablecoin and LST pairs.
+//
+// Notes:
+// - Reserves are first normalized to 1e18 precision (reserve * 1e18 / 10^decimals), exactly
+//   like the contract, so mixed-decimal pairs (e.g. USDC/DAI) use the same curve.
+// - The output reserve is solved with the contract's Newton iteration (_get_y), including
+//   its rounding nudges and the 255-iteration cap; a swap the pool would revert on yields 0.
+// - Fee is taken off the input first: amountIn -= amountIn * fee / 10_000 (bps).
+// - Integer math on U256 throughout; f64 only for spot-price reporting. Products are checked:
+//   an input so large the contract's math would overflow (and revert) quotes 0.
+
+use ethers::types::U256;
+
+use crate::math::aerodrome_volatile::{from_raw, SwapDirection};
+
+const MAX_ITERATIONS: usize = 255; // Pool.sol reverts with "!y" past this
+
+#[inline]
+fn e18() -> U256 {
+    U256::exp10(18)
+}
+
+/// 10^decimals, the contract's `decimals0/1`.
+#[inline]
+fn unit(decimals: u8) -> U256 {
+    U256::exp10(decimals as usize)
+}
+
+/// x³y + xy³ on 1e18-normalized amounts (Pool._f); None where the contract would overflow.
+#[inline]
+fn f(x0: U256, y: U256) -> Option<U256> {
+    let a = x0.checked_mul(y)? / e18();
+    let b = (x0.checked_mul(x0)? / e18()).checked_add(y.checked_mul(y)? / e18())?;
+    Some(a.checked_mul(b)? / e18())
+}
+
+/// ∂f/∂y = 3x0y² + x0³ on 1e18-normalized amounts (Pool._d); None on overflow.
+#[inline]
+fn d(x0: U256, y: U256) -> Option<U256> {
+    let a = U256::from(3u8).checked_mul(x0)?.checked_mul(y.checked_mul(y)? / e18())? / e18();
+    let b = (x0.checked_mul(x0)? / e18()).checked_mul(x0)? / e18();
+    a.checked_add(b)
+}
+
+/// Pool._k on raw reserves (normalized internally); None on overflow.
+fn checked_k(reserve0: U256, reserve1: U256, decimals0: u8, decimals1: u8) -> Option<U256> {
+    let x = reserve0.checked_mul(e18())? / unit(decimals0);
+    let y = reserve1.checked_mul(e18())? / unit(decimals1);
+    f(x, y)
+}
+
+/// Pool._k for a stable pool: invariant from raw reserves (normalized internally).
+/// Saturates at U256::MAX for reserves no real pool holds.
+pub fn stable_k(reserve0: U256, reserve1: U256, decimals0: u8, decimals1: u8) -> U256 {
+    checked_k(reserve0, reserve1, decimals0, decimals1).unwrap_or(U256::MAX)
+}
+
+/// Pool._get_y: smallest y with f(x0, y) >= xy, by Newton iteration from `y`.
+/// The contract's convergence check calls `_k(x0, y + 1)`, which normalizes the already
+/// normalized values again with the pool's decimals0/decimals1; kept as-is for parity.
+/// None where the contract reverts: no convergence, or an overflowing step.
+fn get_y(x0: U256, xy: U256, mut y: U256, decimals0: u8, decimals1: u8) -> Option<U256> {
+    for _ in 0..MAX_ITERATIONS {
+        let k = f(x0, y)?;
+        let slope = d(x0, y).filter(|d| !d.is_zero())?;
+        if k < xy {
+            let mut dy = (xy - k).checked_mul(e18())? / slope;
+            if dy.is_zero() {
+                if k == xy {
+                    return Some(y);
+                }
+                if checked_k(x0, y + 1, decimals0, decimals1)? > xy {
+                    return Some(y + 1);
+                }
+                dy = U256::one();
+            }
+            y = y.checked_add(dy)?;
+        } else {
+            let mut dy = (k - xy).checked_mul(e18())? / slope;
+            if dy.is_zero() {
+                if k == xy || f(x0, y.checked_sub(U256::one())?)? < xy {
+                    return Some(y);
+                }
+                dy = U256::one();
+            }
+            y = y.checked_sub(dy)?;
+        }
+    }
+    None
+}
+
+/// Exact stable-pool getAmountOut (Pool.getAmountOut + _getAmountOut), in pool token order.
+/// Raw units in and out; 0 where the pool would revert (no convergence, empty reserves).
+pub fn stable_amount_out(
+    amount_in: U256,
+    reserve0: U256,
+    reserve1: U256,
+    decimals0: u8,
+    decimals1: u8,
+    direction: SwapDirection,
+    fee_bps: u32,
+) -> U256 {
+    if amount_in.is_zero() || reserve0.is_zero() || reserve1.is_zero() {
+        return U256::zero();
+    }
+    checked_amount_out(amount_in, reserve0, reserve1, decimals0, decimals1, direction, fee_bps).unwrap_or_default()
+}
+
+fn checked_amount_out(
+    amount_in: U256,
+    reserve0: U256,
+    reserve1: U256,
+    decimals0: u8,
+    decimals1: u8,
+    direction: SwapDirection,
+    fee_bps: u32,
+) -> Option<U256> {
+    let amount_in = amount_in - amount_in.checked_mul(U256::from(fee_bps.min(10_000)))? / U256::from(10_000u32);
+
+    let xy = checked_k(reserve0, reserve1, decimals0, decimals1)?;
+    let r0 = reserve0.checked_mul(e18())? / unit(decimals0);
+    let r1 = reserve1.checked_mul(e18())? / unit(decimals1);
+    let (reserve_a, reserve_b, dec_in, dec_out) = match direction {
+        SwapDirection::ZeroForOne => (r0, r1, decimals0, decimals1),
+        SwapDirection::OneForZero => (r1, r0, decimals1, decimals0),
+    };
+    let amount_in = amount_in.checked_mul(e18())? / unit(dec_in);
+
+    let y = get_y(amount_in.checked_add(reserve_a)?, xy, reserve_b, decimals0, decimals1)?;
+    (y <= reserve_b).then(|| (reserve_b - y) * unit(dec_out) / e18())
+}
+
+/// Marginal price (tokenOut per tokenIn, human units) at the current reserves, before fees:
+/// dy/dx = (3x²y + y³) / (x³ + 3xy²) on decimal-normalized reserves.
+pub fn stable_spot_price_out_per_in(reserve_in: U256, reserve_out: U256, dec_in: u8, dec_out: u8) -> f64 {
+    let x = from_raw(reserve_in, dec_in);
+    let y = from_raw(reserve_out, dec_out);
+    let den = x * x * x + 3.0 * x * y * y;
+    if den <= 0.0 { 0.0 } else { (3.0 * x * x * y + y * y * y) / den }
+}
+
+// ---------------------------------- Tests ------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::aerodrome_volatile::{to_raw, volatile_amount_out};
+
+    use SwapDirection::{OneForZero, ZeroForOne};
+
+    fn usdc(x: f64) -> U256 { to_raw(x, 6) }
+    fn dai(x: f64) -> U256 { to_raw(x, 18) }
+
+    #[test]
+    fn test_balanced_pool_trades_near_one_to_one() {
+        // token0 = USDC, token1 = DAI; 10M each, 1000 USDC in at 5 bps
+        let out = stable_amount_out(usdc(1_000.0), usdc(1e7), dai(1e7), 6, 18, ZeroForOne, 5);
+        let out_h = from_raw(out, 18);
+        assert!(out_h <= 1_000.0 * (1.0 - 0.0005), "fee must be charged: {}", out_h);
+        assert!(out_h > 999.4, "flat curve near balance: {}", out_h);
+    }
+
+    #[test]
+    fn test_invariant_holds_after_swap() {
+        let (r0, r1) = (usdc(4_000_000.0), dai(6_000_000.0));
+        let k_before = stable_k(r0, r1, 6, 18);
+
+        let amount_in = usdc(250_000.0);
+        let out = stable_amount_out(amount_in, r0, r1, 6, 18, ZeroForOne, 0);
+        assert!(!out.is_zero());
+        assert!(stable_k(r0 + amount_in, r1 - out, 6, 18) >= k_before, "pool must not lose value");
+
+        let amount_in = dai(250_000.0);
+        let out = stable_amount_out(amount_in, r0, r1, 6, 18, OneForZero, 0);
+        assert!(!out.is_zero());
+        assert!(stable_k(r0 - out, r1 + amount_in, 6, 18) >= k_before);
+    }
+
+    #[test]
+    fn test_matches_continuous_curve() {
+        // Solve x³y + xy³ = k for the new y in f64 and compare
+        let (x, y) = (3_000_000.0f64, 5_000_000.0f64);
+        let k = x * x * x * y + x * y * y * y;
+        let dx = 400_000.0;
+        let x1 = x + dx;
+        let (mut lo, mut hi) = (0.0f64, y);
+        for _ in 0..200 {
+            let mid = (lo + hi) / 2.0;
+            if x1 * x1 * x1 * mid + x1 * mid * mid * mid < k { lo = mid } else { hi = mid }
+        }
+        let expected = y - hi;
+
+        let out = stable_amount_out(usdc(dx), usdc(x), dai(y), 6, 18, ZeroForOne, 0);
+        assert!((from_raw(out, 18) - expected).abs() / expected < 1e-9);
+    }
+
+    #[test]
+    fn test_flatter_than_constant_product() {
+        // Same reserves and fee: a large trade moves the stable curve far less
+        let (r0, r1) = (usdc(1_000_000.0), usdc(1_000_000.0));
+        let amount_in = usdc(200_000.0);
+        let stable = stable_amount_out(amount_in, r0, r1, 6, 6, ZeroForOne, 5);
+        let volatile = volatile_amount_out(amount_in, r0, r1, 5);
+        assert!(stable > volatile);
+        assert!(from_raw(stable, 6) > 195_000.0);
+    }
+
+    #[test]
+    fn test_direction_symmetry() {
+        let (r0, r1) = (usdc(2_000_000.0), dai(2_000_000.0));
+        let a = stable_amount_out(usdc(10_000.0), r0, r1, 6, 18, ZeroForOne, 0);
+        let b = stable_amount_out(dai(10_000.0), r0, r1, 6, 18, OneForZero, 0);
+        // Balanced pool: both directions give the same human amount (to rounding)
+        assert!((from_raw(a, 18) - from_raw(b, 6)).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_spot_price() {
+        assert!((stable_spot_price_out_per_in(usdc(1e6), dai(1e6), 6, 18) - 1.0).abs() < 1e-12);
+        // More of the output token in the pool => it's cheaper, so more out per in
+        let p = stable_spot_price_out_per_in(usdc(1e6), dai(3e6), 6, 18);
+        assert!(p > 1.0);
+        // Matches a tiny fee-free swap
+        let tiny = stable_amount_out(usdc(1.0), usdc(1e6), dai(3e6), 6, 18, ZeroForOne, 0);
+        assert!((from_raw(tiny, 18) - p).abs() / p < 1e-4);
+    }
+
+    #[test]
+    fn test_degenerate_inputs() {
+        assert_eq!(stable_amount_out(U256::zero(), usdc(1.0), dai(1.0), 6, 18, ZeroForOne, 5), U256::zero());
+        assert_eq!(stable_amount_out(usdc(1.0), U256::zero(), dai(1.0), 6, 18, ZeroForOne, 5), U256::zero());
+        assert_eq!(stable_amount_out(usdc(1.0), usdc(1.0), U256::zero(), 6, 18, ZeroForOne, 5), U256::zero());
+        assert_eq!(stable_spot_price_out_per_in(U256::zero(), dai(1.0), 6, 18), 0.0);
+    }
+}
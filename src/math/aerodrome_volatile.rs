@@ -1,7 +1,8 @@
 // Aerodrome (Solidly/Velodrome) — Volatile Pool Math Module
 // ----------------------------------------------------------
 // Focus: exact constant-product (x*y=k) swap math with fee, using integer arithmetic.
-// This simulates the pool's getAmountOut for volatile pools, off-chain, without RPC calls.
+// This simulates the pool's getAmountOut (and its inverse, getAmountIn) for volatile pools,
+// off-chain, without RPC calls.
 // It is optimized for WETH/USDC, but works for any 2 tokens given reserves/decimals/fee.
 //
 // Cargo.toml deps (examples):
@@ -10,16 +11,23 @@
 //   serde = { version = "1", features = ["derive"], optional = true }
 //
 // Notes:
-// - All internal math is done in raw token units (integers). No floating point until reporting.
+// - All internal math is done in raw token units (integers). No floating point until reporting;
+//   amounts cross the module boundary as math::amount::TokenAmount.
 // - Fee is expressed in basis points (bps). γ = (10_000 - fee_bps) / 10_000.
 // - We never assume token order. Direction is explicit and reserves are mapped accordingly.
-// - For price/impact reporting we normalize by the respective token decimals.
+// - For price/impact reporting we normalize by the respective token decimals. Products of
+//   reserves/amounts and 10^decimals are held in 512 bits (FullMath-style mulDiv), so pools with
+//   reserves past u128 neither panic nor misreport.
 //
 // If you later want to validate parity with chain, do one staticcall to
 // pair.getAmountOut(amountIn, tokenIn) right before execution (good hygiene).
 
 use std::cmp::min;
-use ethers::types::{Address, U256};
+use ethers::types::{Address, U256, U512};
+
+use crate::math::aerodrome_stable::{stable_amount_out, stable_spot_price_out_per_in};
+use crate::math::amount::TokenAmount;
+use crate::math::uniswap_v4_fixed::{mul_div, mul_div_rounding_up};
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
@@ -42,6 +50,11 @@ pub struct VolatilePairState {
     pub decimals0: u8,         // e.g., WETH=18
     pub decimals1: u8,         // e.g., USDC=6
     pub fee_bps: u32,          // e.g., 5 for 0.05%
+    pub block_number: u64,     // block the state was read at; 0 when not from chain
+    pub block_timestamp: u64,  // unix seconds of that block
+    pub stable: bool,          // sAMM pool (x³y+xy³, math::aerodrome_stable) instead of x*y=k
+    pub chain_id: u64,         // chain the pool lives on; 0 when not from chain
+    pub gas_units: u64,        // gas of one swap through the pool; 0 until priced
 }
 
 impl VolatilePairState {
@@ -50,6 +63,27 @@ impl VolatilePairState {
     pub fn is_zero_liquidity(&self) -> bool {
         self.reserve0.is_zero() || self.reserve1.is_zero()
     }
+
+    /// The pool's getAmountOut for raw `amount_in`, on whichever curve the pool uses.
+    pub fn amount_out(&self, direction: SwapDirection, amount_in: U256) -> U256 {
+        if self.stable {
+            return stable_amount_out(
+                amount_in, self.reserve0, self.reserve1, self.decimals0, self.decimals1, direction, self.fee_bps,
+            );
+        }
+        let (reserve_in, reserve_out, _, _) = map_direction(self, direction);
+        volatile_amount_out(amount_in, reserve_in, reserve_out, self.fee_bps)
+    }
+
+    /// Marginal price (tokenOut per tokenIn, human units) before fees.
+    pub fn spot_price(&self, direction: SwapDirection) -> f64 {
+        let (reserve_in, reserve_out, dec_in, dec_out) = map_direction(self, direction);
+        if self.stable {
+            stable_spot_price_out_per_in(reserve_in, reserve_out, dec_in, dec_out)
+        } else {
+            spot_price_out_per_in(reserve_in, reserve_out, dec_in, dec_out)
+        }
+    }
 }
 
 // ------------------------------- Core math -----------------------------------
@@ -71,15 +105,39 @@ pub fn volatile_amount_out(
     let num = U256::from(10_000u32 - fee_bps);
     let den = U256::from(10_000u32);
 
-    let amount_in_after_fee = amount_in * num / den;
-    // out = (amount_in' * R_out) / (R_in + amount_in')
-    (amount_in_after_fee * reserve_out) / (reserve_in + amount_in_after_fee)
+    let amount_in_after_fee = mul_div(amount_in, num, den).unwrap_or_default(); // <= amount_in
+    // out = (amount_in' * R_out) / (R_in + amount_in'), product in 512 bits; always < R_out
+    mul_div(amount_in_after_fee, reserve_out, reserve_in.saturating_add(amount_in_after_fee)).unwrap_or_default()
+}
+
+/// Inverse of `volatile_amount_out`: the smallest raw input whose output is at least
+/// `amount_out` (getAmountIn, rounding up through the fee). None if it would drain the reserve.
+/// Formula: in' = ceil( (out * R_in) / (R_out - out) ), in = ceil( in' / γ )
+#[inline]
+pub fn volatile_amount_in(
+    amount_out: U256,
+    reserve_in: U256,
+    reserve_out: U256,
+    fee_bps: u32,
+) -> Option<U256> {
+    if amount_out.is_zero() {
+        return Some(U256::zero());
+    }
+    if reserve_in.is_zero() || amount_out >= reserve_out {
+        return None;
+    }
+    let fee_bps = min(fee_bps, 9_999); // same γ as volatile_amount_out
+    let num = U256::from(10_000u32 - fee_bps);
+    let den = U256::from(10_000u32);
+
+    let amount_in_after_fee = mul_div_rounding_up(amount_out, reserve_in, reserve_out - amount_out)?;
+    mul_div_rounding_up(amount_in_after_fee, den, num)
 }
 
 /// Map direction to (reserve_in, reserve_out) and decimals for reporting.
 #[inline]
-pub fn map_direction<'a>(
-    pair: &'a VolatilePairState,
+pub fn map_direction(
+    pair: &VolatilePairState,
     direction: SwapDirection,
 ) -> (U256, U256, u8, u8) {
     match direction {
@@ -98,30 +156,42 @@ pub fn spot_price_out_per_in(
     dec_in: u8,
     dec_out: u8,
 ) -> f64 {
-    if reserve_in.is_zero() { return 0.0; }
     // price = (R_out / 10^d_out) / (R_in / 10^d_in)
-    //       = (R_out * 10^d_in) / (R_in * 10^d_out)
-    let pow_in  = pow10(dec_in);
-    let pow_out = pow10(dec_out);
-    let num = mul_div_to_f64(reserve_out, pow_in);
-    let den = mul_div_to_f64(reserve_in, pow_out);
-    if den == 0.0 { 0.0 } else { num / den }
+    price_out_per_in(reserve_in, reserve_out, dec_in, dec_out)
 }
 
-/// Convert human -> raw integer units (rounded).
+/// Execution price of a fill (tokenOut per tokenIn), normalized by decimals.
+#[inline]
+pub fn execution_price_of_fill(
+    amount_in: U256,
+    amount_out: U256,
+    dec_in: u8,
+    dec_out: u8,
+) -> f64 {
+    price_out_per_in(amount_in, amount_out, dec_in, dec_out)
+}
+
+// (out * 10^d_in) / (in * 10^d_out) with both products exact in 512 bits; 0 if `amount_in` is 0.
+// Whole and fractional parts of the quotient are converted separately, so the result is within
+// an ulp or two of the true ratio at any size.
+fn price_out_per_in(amount_in: U256, amount_out: U256, dec_in: u8, dec_out: u8) -> f64 {
+    if amount_in.is_zero() { return 0.0; }
+    let num = amount_out.full_mul(pow10(dec_in));
+    let den = amount_in.full_mul(pow10(dec_out));
+    let (whole, frac) = num.div_mod(den);
+    u512_to_f64(whole) + u512_to_f64(frac) / u512_to_f64(den)
+}
+
+/// Convert human -> raw integer units (exact, via TokenAmount); zero for negative or non-finite input.
 #[inline]
 pub fn to_raw(amount_human: f64, decimals: u8) -> U256 {
-    if amount_human <= 0.0 { return U256::zero(); }
-    let scale = 10f64.powi(decimals as i32);
-    let v = (amount_human * scale).round();
-    if v <= 0.0 { U256::zero() } else { U256::from(v as u128) }
+    TokenAmount::from_f64(amount_human, decimals).map_or(U256::zero(), |a| a.raw)
 }
 
 /// Convert raw integer units -> human f64.
 #[inline]
 pub fn from_raw(amount: U256, decimals: u8) -> f64 {
-    let s = 10f64.powi(decimals as i32);
-    (amount.as_u128() as f64) / s
+    TokenAmount::new(amount, decimals).to_f64()
 }
 
 #[inline]
@@ -131,40 +201,31 @@ fn pow10(dec: u8) -> U256 {
 }
 
 #[inline]
-fn mul_div_to_f64(a: U256, b: U256) -> f64 {
-    // Convert smallish U256 products to f64 for reporting only.
-    // For ERC20 reserves this is safe for WETH/USDC scales; if you expect giant pools,
-    // switch reporting to decimal/bigdecimal to avoid precision loss.
-    let _ah = (a >> 128).low_u128();
-    let _al = a.low_u128();
-    let _bh = (b >> 128).low_u128();
-    let _bl = b.low_u128();
-    // naive: (a*b) ~ (ah<<128 + al) * (bh<<128 + bl) — we only need f64, so simplify:
-    let approx = (a.as_u128() as f64) * (b.as_u128() as f64);
-    approx
+fn u512_to_f64(v: U512) -> f64 {
+    // 2^512 is well inside f64's range
+    v.0.iter().rev().fold(0.0, |acc, limb| acc * 18_446_744_073_709_551_616.0 + *limb as f64)
 }
 
 // ----------------------------- Public simulator ------------------------------
 
-/// Simulate exact-input swap on a volatile pool.
-/// Returns (amount_in_raw, amount_out_raw, effective_price_out_per_in, spot_price, price_impact_pct).
+/// Simulate exact-input swap on the pool (volatile, or stable when `pair.stable`).
+/// `amount_in` is taken in the input token's decimals (rescaled if it comes in others).
+/// Returns (amount_in, amount_out, effective_price_out_per_in, spot_price, price_impact_pct).
 pub fn simulate_exact_in_volatile(
     pair: &VolatilePairState,
     direction: SwapDirection,
-    amount_in_human: f64,
-) -> (U256, U256, f64, f64, f64) {
-    let (reserve_in, reserve_out, dec_in, dec_out) = map_direction(pair, direction);
-    let amount_in_raw = to_raw(amount_in_human, dec_in);
-    let amount_out_raw = volatile_amount_out(amount_in_raw, reserve_in, reserve_out, pair.fee_bps);
+    amount_in: TokenAmount,
+) -> (TokenAmount, TokenAmount, f64, f64, f64) {
+    let (_, _, dec_in, dec_out) = map_direction(pair, direction);
+    let amount_in = amount_in.rescale(dec_in);
+    let amount_out = TokenAmount::new(pair.amount_out(direction, amount_in.raw), dec_out);
 
     // reporting
-    let in_h  = from_raw(amount_in_raw, dec_in);
-    let out_h = from_raw(amount_out_raw, dec_out);
-    let eff   = if in_h <= 0.0 { 0.0 } else { out_h / in_h };
-    let spot  = spot_price_out_per_in(reserve_in, reserve_out, dec_in, dec_out);
+    let eff   = execution_price_of_fill(amount_in.raw, amount_out.raw, dec_in, dec_out);
+    let spot  = pair.spot_price(direction);
     let impact_pct = if spot > 0.0 { (eff / spot - 1.0) * 100.0 } else { 0.0 };
 
-    (amount_in_raw, amount_out_raw, eff, spot, impact_pct)
+    (amount_in, amount_out, eff, spot, impact_pct)
 }
 
 /// Convenience: compute execution price only (tokenOut per tokenIn, human units).
@@ -173,16 +234,16 @@ pub fn simulate_exact_in_volatile(
 pub fn execution_price_out_per_in(
     pair: &VolatilePairState,
     direction: SwapDirection,
-    amount_in_human: f64,
+    amount_in: TokenAmount,
 ) -> f64 {
-    let (_, _, eff, _, _) = simulate_exact_in_volatile(pair, direction, amount_in_human);
+    let (_, _, eff, _, _) = simulate_exact_in_volatile(pair, direction, amount_in);
     eff
 }
 
 // ------------------------------ Sanity helpers -------------------------------
 
 /// Update reserves after a hypothetical swap (pure math), useful for iterative sims.
-/// NOTE: This models *volatile* math only. Not accounting for tax tokens, etc.
+/// NOTE: Not accounting for tax tokens, etc.
 #[allow(dead_code)]
 pub fn apply_swap_to_reserves(
     pair: &VolatilePairState,
@@ -190,7 +251,7 @@ pub fn apply_swap_to_reserves(
     amount_in_raw: U256,
 ) -> (U256, U256) {
     let (rin, rout, _, _) = map_direction(pair, direction);
-    let out = volatile_amount_out(amount_in_raw, rin, rout, pair.fee_bps);
+    let out = pair.amount_out(direction, amount_in_raw);
     let fee_num = U256::from(10_000u32 - min(pair.fee_bps, 9_999));
     let fee_den = U256::from(10_000u32);
     let in_after_fee = amount_in_raw * fee_num / fee_den;
@@ -216,6 +277,7 @@ pub fn apply_swap_to_reserves(
 mod tests {
     use super::*;
     fn addr(x: u8) -> Address { Address::from([x; 20]) }
+    fn weth(x: f64) -> TokenAmount { TokenAmount::from_f64(x, 18).unwrap() }
 
     fn mock_weth_usdc_pool(_price_usdc_per_weth: f64, weth: f64, usdc: f64, fee_bps: u32) -> VolatilePairState {
         // token0=WETH(18), token1=USDC(6) layout (common but not assumed by math)
@@ -238,6 +300,11 @@ mod tests {
             decimals0,
             decimals1,
             fee_bps,
+            block_number: 0,
+            block_timestamp: 0,
+            stable: false,
+            chain_id: 0,
+            gas_units: 0,
         }
     }
 
@@ -256,10 +323,25 @@ mod tests {
         assert!(o1 < o2 && o2 < o3, "output should increase with input");
     }
 
+    #[test]
+    fn volatile_amount_in_is_the_smallest_sufficient_input() {
+        let pair = mock_weth_usdc_pool(3000.0, 5_000.0, 15_000_000.0, 30);
+        for dir in [SwapDirection::ZeroForOne, SwapDirection::OneForZero] {
+            let (r_in, r_out, _, _) = map_direction(&pair, dir);
+            for out in [U256::one(), U256::from(7_777u64), r_out / 1_000, r_out / 3, r_out - 1] {
+                let need = volatile_amount_in(out, r_in, r_out, pair.fee_bps).expect("below the reserve");
+                assert!(volatile_amount_out(need, r_in, r_out, pair.fee_bps) >= out);
+                assert!(volatile_amount_out(need - 1, r_in, r_out, pair.fee_bps) < out);
+            }
+            assert_eq!(volatile_amount_in(r_out, r_in, r_out, pair.fee_bps), None);
+        }
+        assert_eq!(volatile_amount_in(U256::zero(), U256::zero(), U256::zero(), 30), Some(U256::zero()));
+    }
+
     #[test]
     fn execution_price_reasonable() {
         let pair = mock_weth_usdc_pool(3000.0, 5_000.0, 15_000_000.0, 5);
-        let px_small = execution_price_out_per_in(&pair, SwapDirection::ZeroForOne, 0.1);
+        let px_small = execution_price_out_per_in(&pair, SwapDirection::ZeroForOne, weth(0.1));
         let spot     = spot_price_out_per_in(pair.reserve0, pair.reserve1, pair.decimals0, pair.decimals1);
         assert!(px_small > 0.0 && spot > 0.0);
         // at small size, effective should be close to spot but slightly worse
@@ -269,8 +351,8 @@ mod tests {
     #[test]
     fn price_impact_sign_and_magnitude() {
         let pair = mock_weth_usdc_pool(3000.0, 10_000.0, 30_000_000.0, 5);
-        let (_, _, eff_small, spot, imp_small) = simulate_exact_in_volatile(&pair, SwapDirection::ZeroForOne, 0.1);
-        let (_, _, eff_big,   _,   imp_big  ) = simulate_exact_in_volatile(&pair, SwapDirection::ZeroForOne, 50.0);
+        let (_, _, eff_small, spot, imp_small) = simulate_exact_in_volatile(&pair, SwapDirection::ZeroForOne, weth(0.1));
+        let (_, _, eff_big,   _,   imp_big  ) = simulate_exact_in_volatile(&pair, SwapDirection::ZeroForOne, weth(50.0));
 
         assert!(eff_small < spot && imp_small < 0.0, "buying out of pool worsens price");
         assert!(eff_big   < eff_small, "bigger trade => worse execution");
@@ -281,8 +363,8 @@ mod tests {
     fn reserves_update_consistency() {
         let mut pair = mock_weth_usdc_pool(3000.0, 5_000.0, 15_000_000.0, 5);
         let amount_in = to_raw(10.0, pair.decimals0);
-        let (out_in_raw, out_raw, ..) = simulate_exact_in_volatile(&pair, SwapDirection::ZeroForOne, 10.0);
-        assert_eq!(out_in_raw, amount_in);
+        let (out_in, out, ..) = simulate_exact_in_volatile(&pair, SwapDirection::ZeroForOne, weth(10.0));
+        assert_eq!(out_in.raw, amount_in);
 
         let (new_r0, new_r1) = apply_swap_to_reserves(&pair, SwapDirection::ZeroForOne, amount_in);
         // reflect update with correct mapping
@@ -291,10 +373,65 @@ mod tests {
 
         // Next small trade should execute slightly worse after reserves move
         let px_before = execution_price_out_per_in(&mock_weth_usdc_pool(3000.0, 5_000.0, 15_000_000.0, 5),
-                                                  SwapDirection::ZeroForOne, 1.0);
-        let px_after  = execution_price_out_per_in(&pair, SwapDirection::ZeroForOne, 1.0);
+                                                  SwapDirection::ZeroForOne, weth(1.0));
+        let px_after  = execution_price_out_per_in(&pair, SwapDirection::ZeroForOne, weth(1.0));
         assert!(px_after < px_before);
         // out must be positive
-        assert!(!out_raw.is_zero());
+        assert!(!out.is_zero());
+    }
+
+    fn big(v: U256) -> num_bigint::BigInt { crate::math::uniswap_v4_fixed::to_bigint(v) }
+
+    #[test]
+    fn prices_are_exact_above_u128() {
+        // 10^12 * 2^110 WETH-wei (~2^150) against 3000 * 2^110 USDC-units: exactly 3000 USDC/WETH
+        let r_weth = U256::exp10(12) << 110;
+        let r_usdc = U256::from(3_000u64) << 110;
+        assert!(r_weth.bits() > 128);
+        assert_eq!(spot_price_out_per_in(r_weth, r_usdc, 18, 6), 3_000.0);
+        assert_eq!(spot_price_out_per_in(r_usdc, r_weth, 6, 18), 1.0 / 3_000.0);
+
+        // R * 10^18 no longer fits in 256 bits
+        let (r_in, r_out) = (U256::one() << 249, U256::one() << 250);
+        assert_eq!(spot_price_out_per_in(r_in, r_out, 18, 18), 2.0);
+        assert_eq!(spot_price_out_per_in(r_in, r_out + 1, 18, 18), 2.0); // +1 is far below an ulp
+        assert!((spot_price_out_per_in(U256::MAX, U256::MAX, 77, 0) / 1e77 - 1.0).abs() < 1e-15);
+        assert!((spot_price_out_per_in(U256::MAX, U256::MAX, 0, 77) / 1e-77 - 1.0).abs() < 1e-15);
+        assert_eq!(spot_price_out_per_in(U256::zero(), r_out, 18, 18), 0.0);
+
+        // Execution price of a fill, against BigInt
+        let (amount_in, amount_out) = (U256::from(7u8) << 200, U256::MAX / 3);
+        let expected = (big(amount_out) * big(U256::exp10(6))).to_string().parse::<f64>().unwrap()
+            / (big(amount_in) * big(U256::exp10(18))).to_string().parse::<f64>().unwrap();
+        assert!((execution_price_of_fill(amount_in, amount_out, 6, 18) / expected - 1.0).abs() < 1e-15);
+        assert_eq!(execution_price_of_fill(U256::zero(), amount_out, 6, 18), 0.0);
+    }
+
+    #[test]
+    fn swaps_do_not_overflow_above_u128() {
+        // amount_in' * R_out is ~2^450: the old U256 product panicked
+        let (r_in, r_out) = (U256::one() << 249, U256::one() << 250);
+        let amount_in = U256::one() << 200;
+        let out = volatile_amount_out(amount_in, r_in, r_out, 30);
+        let in_after_fee = big(amount_in) * 9_970 / 10_000;
+        let expected = &in_after_fee * big(r_out) / (big(r_in) + &in_after_fee);
+        assert_eq!(big(out), expected);
+
+        let need = volatile_amount_in(out, r_in, r_out, 30).expect("below the reserve");
+        assert!(volatile_amount_out(need, r_in, r_out, 30) >= out);
+        assert!(volatile_amount_out(need - 1, r_in, r_out, 30) < out);
+
+        // The simulator reports an execution price just under spot * (1 - fee)
+        let pair = VolatilePairState {
+            reserve0: U256::exp10(12) << 110,
+            reserve1: U256::from(3_000u64) << 110,
+            ..mock_weth_usdc_pool(3000.0, 1.0, 3_000.0, 30)
+        };
+        let (_, out, eff, spot, impact) =
+            simulate_exact_in_volatile(&pair, SwapDirection::ZeroForOne, TokenAmount::new(U256::one() << 129, 18));
+        assert!(!out.is_zero());
+        assert_eq!(spot, 3_000.0);
+        assert!(eff < spot * 0.997 && eff > spot * 0.997 * (1.0 - 1e-6), "eff {}", eff);
+        assert!((impact + 0.3).abs() < 1e-3, "impact {}", impact);
     }
 }
\ No newline at end of file
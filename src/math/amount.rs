@@ -0,0 +1,168 @@
+// src/math/amount.rs
+//
+// A token amount as the chain sees it: an integer in the token's smallest unit plus the
+// token's decimals. Math, pricing, the optimizer and bridge fees pass these around; f64 and
+// Decimal only appear where a number leaves the service (API responses, USD figures) or
+// where a search needs a continuous variable.
+// - Human -> raw goes through rust_decimal, so 0.1 ETH is exactly 10^17 wei
+// - Raw -> human splits whole and fractional units, so large amounts never truncate to u128
+
+use std::fmt;
+
+use ethers::types::U256;
+use num_bigint::BigInt;
+use rust_decimal::prelude::{FromPrimitive, RoundingStrategy};
+use rust_decimal::Decimal;
+
+use crate::math::uniswap_v4_fixed::{to_bigint, to_u256};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TokenAmount {
+    pub raw: U256,    // smallest units
+    pub decimals: u8, // of the token `raw` is counted in
+}
+
+/// Lossy U256 -> f64.
+pub fn u256_to_f64(v: U256) -> f64 {
+    v.0.iter().rev().fold(0.0, |acc, limb| acc * 18_446_744_073_709_551_616.0 + *limb as f64)
+}
+
+impl TokenAmount {
+    pub const fn new(raw: U256, decimals: u8) -> Self {
+        Self { raw, decimals }
+    }
+
+    pub fn zero(decimals: u8) -> Self {
+        Self::new(U256::zero(), decimals)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.raw.is_zero()
+    }
+
+    /// Exact human -> raw, rounding half away from zero past `decimals`. None if negative or too large.
+    pub fn from_decimal(value: Decimal, decimals: u8) -> Option<Self> {
+        if value.is_sign_negative() && !value.is_zero() {
+            return None;
+        }
+        let rounded = value.round_dp_with_strategy(decimals as u32, RoundingStrategy::MidpointAwayFromZero);
+        let mantissa = U256::from(rounded.mantissa().unsigned_abs());
+        let shift = decimals as usize - rounded.scale() as usize; // round_dp leaves scale <= decimals
+        let raw = mantissa.checked_mul(U256::from(10u8).checked_pow(U256::from(shift))?)?;
+        Some(Self::new(raw, decimals))
+    }
+
+    /// Human f64 -> raw, through the shortest decimal that round-trips the float (1.1 is 1.1,
+    /// not 1.100000000000000088). None for negative or non-finite input.
+    pub fn from_f64(value: f64, decimals: u8) -> Option<Self> {
+        if !value.is_finite() {
+            return None;
+        }
+        Self::from_decimal(Decimal::from_f64(value)?, decimals)
+    }
+
+    pub fn from_bigint(raw: &BigInt, decimals: u8) -> Option<Self> {
+        Some(Self::new(to_u256(raw)?, decimals))
+    }
+
+    pub fn to_bigint(&self) -> BigInt {
+        to_bigint(self.raw)
+    }
+
+    /// Human units as f64 (lossy), for API responses and USD figures.
+    pub fn to_f64(&self) -> f64 {
+        let Some(unit) = U256::from(10u8).checked_pow(U256::from(self.decimals)) else {
+            return 0.0;
+        };
+        let (whole, frac) = self.raw.div_mod(unit);
+        u256_to_f64(whole) + u256_to_f64(frac) / u256_to_f64(unit)
+    }
+
+    /// Human units as a Decimal; None beyond Decimal's 96-bit mantissa or 28 decimal places.
+    pub fn to_decimal(&self) -> Option<Decimal> {
+        if self.raw.bits() > 96 {
+            return None;
+        }
+        Decimal::try_from_i128_with_scale(self.raw.as_u128() as i128, self.decimals as u32)
+            .ok()
+            .map(|d| d.normalize())
+    }
+
+    /// The same amount counted in another decimals; exact going up, floored going down.
+    pub fn rescale(&self, decimals: u8) -> Self {
+        let ten = U256::from(10u8);
+        let raw = if decimals >= self.decimals {
+            self.raw.saturating_mul(ten.checked_pow(U256::from(decimals - self.decimals)).unwrap_or(U256::MAX))
+        } else {
+            ten.checked_pow(U256::from(self.decimals - decimals)).map_or(U256::zero(), |d| self.raw / d)
+        };
+        Self::new(raw, decimals)
+    }
+
+    /// Human-unit ratio self / other (e.g. quote out per base in); 0 if `other` is zero.
+    pub fn per(&self, other: &TokenAmount) -> f64 {
+        if other.is_zero() { 0.0 } else { self.to_f64() / other.to_f64() }
+    }
+}
+
+impl fmt::Display for TokenAmount {
+    /// Exact decimal, trailing zeros trimmed: 1500000 at 6 decimals is "1.5".
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = self.raw.to_string();
+        let decimals = self.decimals as usize;
+        if decimals == 0 {
+            return f.write_str(&digits);
+        }
+        let padded = format!("{:0>width$}", digits, width = decimals + 1);
+        let (whole, frac) = padded.split_at(padded.len() - decimals);
+        let frac = frac.trim_end_matches('0');
+        if frac.is_empty() { write!(f, "{}", whole) } else { write!(f, "{}.{}", whole, frac) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_human_amounts_are_exact() {
+        assert_eq!(TokenAmount::from_f64(0.1, 18).unwrap().raw, U256::exp10(17));
+        assert_eq!(TokenAmount::from_f64(1.1, 18).unwrap().raw, U256::from(11u64) * U256::exp10(17));
+        assert_eq!(TokenAmount::from_f64(0.000001, 6).unwrap().raw, U256::one());
+        assert_eq!(TokenAmount::from_f64(1.5e-7, 6).unwrap().raw, U256::zero()); // rounds past 6 dp
+        assert_eq!(TokenAmount::from_f64(2.5e-6, 6).unwrap().raw, U256::from(3u8)); // half away from zero
+        assert_eq!(TokenAmount::from_f64(0.0, 18).unwrap(), TokenAmount::zero(18));
+        assert!(TokenAmount::from_f64(-1.0, 18).is_none());
+        assert!(TokenAmount::from_f64(f64::NAN, 18).is_none());
+
+        let d = Decimal::from_str("123456789.123456789123456789").unwrap();
+        let a = TokenAmount::from_decimal(d, 18).unwrap();
+        assert_eq!(a.to_string(), "123456789.123456789123456789");
+        assert_eq!(a.to_decimal(), Some(d));
+    }
+
+    #[test]
+    fn test_raw_to_human_does_not_truncate_to_u128() {
+        let huge = TokenAmount::new(U256::one() << 200, 18);
+        let expected = 2f64.powi(200) / 1e18;
+        assert!((huge.to_f64() / expected - 1.0).abs() < 1e-15);
+        assert_eq!(huge.to_decimal(), None);
+
+        assert_eq!(TokenAmount::new(U256::from(1_500_000u64), 6).to_f64(), 1.5);
+        assert_eq!(TokenAmount::new(U256::from(42u8), 0).to_string(), "42");
+        assert_eq!(TokenAmount::new(U256::from(5u8), 6).to_string(), "0.000005");
+    }
+
+    #[test]
+    fn test_rescale_and_ratio() {
+        let usdc = TokenAmount::from_f64(3_500.25, 6).unwrap();
+        assert_eq!(usdc.rescale(18).raw, U256::from(350_025u64) * U256::exp10(16));
+        assert_eq!(usdc.rescale(18).rescale(6), usdc);
+        assert_eq!(usdc.rescale(2).raw, U256::from(350_025u64));
+
+        let eth = TokenAmount::from_f64(2.0, 18).unwrap();
+        assert_eq!(usdc.per(&eth), 1_750.125);
+        assert_eq!(usdc.per(&TokenAmount::zero(18)), 0.0);
+    }
+}
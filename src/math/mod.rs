@@ -1,3 +1,8 @@
+pub mod amount;
 pub mod uniswap_v4;
+pub mod uniswap_v4_fixed;
 pub mod aerodrome_volatile;
+pub mod aerodrome_stable;
 
+pub mod venue;
+pub mod route;
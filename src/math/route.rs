@@ -0,0 +1,372 @@
+// src/math/route.rs
+//
+// Multi-hop routes over a set of pools on one chain (ETH→USDT→USDC, ETH→wstETH→USDC, ...).
+// Pools are `Venue`s tagged with their tokens, so V4, V3, Slipstream and Aerodrome pairs mix.
+// - Paths are simple: no token is visited twice, so no pool is used twice
+// - Depth is bounded by `max_hops`; the direct pool is just the one-hop path
+// - Exact-in picks the path with the most output, exact-out the one with the least input, each
+//   net of the path's own gas once a gas cost in token units is given: an extra hop has to pay
+//   for itself
+// - `RouteVenue` wraps the search as a `Venue`, so either optimizer leg can route
+
+use std::sync::Arc;
+
+use ethers::types::{Address, U256};
+
+use crate::math::amount::u256_to_f64;
+use crate::math::venue::Venue;
+
+/// A pool a route may pass through.
+#[derive(Debug, Clone)]
+pub struct RoutePool {
+    pub id: String,
+    pub token0: Address,
+    pub token1: Address,
+    pub pool: Arc<dyn Venue>,
+}
+
+impl RoutePool {
+    pub fn new(id: impl Into<String>, token0: Address, token1: Address, pool: impl Venue + 'static) -> Self {
+        Self { id: id.into(), token0, token1, pool: Arc::new(pool) }
+    }
+}
+
+/// One swap along a route, in raw token units.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hop {
+    pub pool: String,
+    pub token_in: Address,
+    pub token_out: Address,
+    pub amount_in: U256,
+    pub amount_out: U256,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Route {
+    pub hops: Vec<Hop>,
+    /// Sum of the hops' swap gas.
+    pub gas_units: u64,
+}
+
+impl Route {
+    pub fn amount_in(&self) -> U256 {
+        self.hops.first().map_or(U256::zero(), |h| h.amount_in)
+    }
+
+    pub fn amount_out(&self) -> U256 {
+        self.hops.last().map_or(U256::zero(), |h| h.amount_out)
+    }
+}
+
+/// (pool index, zero_for_one) per hop.
+type Path = Vec<(usize, bool)>;
+
+/// Every simple path from `from` to `to` of at most `max_hops` hops.
+fn paths(pools: &[RoutePool], from: Address, to: Address, max_hops: usize) -> Vec<Path> {
+    fn walk(pools: &[RoutePool], at: Address, to: Address, left: usize, seen: &mut Vec<Address>, path: &mut Path, out: &mut Vec<Path>) {
+        if at == to {
+            out.push(path.clone());
+            return;
+        }
+        if left == 0 {
+            return;
+        }
+        for (i, p) in pools.iter().enumerate() {
+            let (zero_for_one, next) = if p.token0 == at {
+                (true, p.token1)
+            } else if p.token1 == at {
+                (false, p.token0)
+            } else {
+                continue;
+            };
+            if seen.contains(&next) {
+                continue;
+            }
+            seen.push(next);
+            path.push((i, zero_for_one));
+            walk(pools, next, to, left - 1, seen, path, out);
+            path.pop();
+            seen.pop();
+        }
+    }
+
+    let mut out = Vec::new();
+    if from != to {
+        walk(pools, from, to, max_hops, &mut vec![from], &mut Vec::new(), &mut out);
+    }
+    out
+}
+
+fn hop(pool: &RoutePool, zero_for_one: bool, amount_in: U256, amount_out: U256) -> Hop {
+    let (token_in, token_out) = if zero_for_one { (pool.token0, pool.token1) } else { (pool.token1, pool.token0) };
+    Hop { pool: pool.id.clone(), token_in, token_out, amount_in, amount_out }
+}
+
+fn route(pools: &[RoutePool], hops: Vec<Hop>, path: &Path) -> Route {
+    let gas_units = path.iter().map(|(i, _)| pools[*i].pool.gas_units()).sum();
+    Route { hops, gas_units }
+}
+
+// Gas of a route in raw token units, at `gas_cost` per gas unit
+fn gas_in_token(route: &Route, gas_cost: f64) -> f64 {
+    route.gas_units as f64 * gas_cost
+}
+
+/// The path turning exactly `amount_in` of `token_in` into the most `token_out`, net of its gas
+/// at `gas_cost` raw `token_out` per gas unit (0 ranks by output alone).
+pub fn best_route_exact_in(
+    pools: &[RoutePool],
+    token_in: Address,
+    token_out: Address,
+    amount_in: U256,
+    max_hops: usize,
+    gas_cost: f64,
+) -> Option<Route> {
+    let net = |r: &Route| u256_to_f64(r.amount_out()) - gas_in_token(r, gas_cost);
+    let mut best: Option<Route> = None;
+    'paths: for path in paths(pools, token_in, token_out, max_hops) {
+        let mut hops = Vec::with_capacity(path.len());
+        let mut amount = amount_in;
+        for &(i, zero_for_one) in &path {
+            let Some(out) = pools[i].pool.quote_exact_in(zero_for_one, amount) else { continue 'paths };
+            hops.push(hop(&pools[i], zero_for_one, amount, out));
+            amount = out;
+        }
+        let candidate = route(pools, hops, &path);
+        if best.as_ref().is_none_or(|b| net(&candidate) > net(b)) {
+            best = Some(candidate);
+        }
+    }
+    best
+}
+
+/// The path delivering at least `amount_out` of `token_out` for the least `token_in`, gas
+/// included at `gas_cost` raw `token_in` per gas unit.
+/// Quotes run backwards from the output, so each hop's input is the next hop's required output.
+pub fn best_route_exact_out(
+    pools: &[RoutePool],
+    token_in: Address,
+    token_out: Address,
+    amount_out: U256,
+    max_hops: usize,
+    gas_cost: f64,
+) -> Option<Route> {
+    let total = |r: &Route| u256_to_f64(r.amount_in()) + gas_in_token(r, gas_cost);
+    let mut best: Option<Route> = None;
+    'paths: for path in paths(pools, token_in, token_out, max_hops) {
+        let mut hops = Vec::with_capacity(path.len());
+        let mut amount = amount_out;
+        for &(i, zero_for_one) in path.iter().rev() {
+            let Some(needed) = pools[i].pool.quote_exact_out(zero_for_one, amount) else { continue 'paths };
+            hops.push(hop(&pools[i], zero_for_one, needed, amount));
+            amount = needed;
+        }
+        hops.reverse();
+        let candidate = route(pools, hops, &path);
+        if best.as_ref().is_none_or(|b| total(&candidate) < total(b)) {
+            best = Some(candidate);
+        }
+    }
+    best
+}
+
+/// Routes between `token0` and `token1` through `pools`, quoted like a single pool.
+#[derive(Debug, Clone)]
+pub struct RouteVenue {
+    pub pools: Vec<RoutePool>,
+    pub token0: Address,
+    pub token1: Address,
+    pub max_hops: usize,
+    /// Cost of one gas unit in raw token0 and token1; paths are ranked net of it.
+    pub gas_cost: [f64; 2],
+}
+
+impl RouteVenue {
+    pub fn new(pools: Vec<RoutePool>, token0: Address, token1: Address, max_hops: usize) -> Self {
+        Self { pools, token0, token1, max_hops, gas_cost: [0.0; 2] }
+    }
+
+    /// Rank paths net of their gas, at this many raw token0 and token1 per gas unit.
+    pub fn with_gas_cost(self, token0_per_gas: f64, token1_per_gas: f64) -> Self {
+        Self { gas_cost: [token0_per_gas, token1_per_gas], ..self }
+    }
+
+    fn tokens(&self, zero_for_one: bool) -> (Address, Address) {
+        if zero_for_one { (self.token0, self.token1) } else { (self.token1, self.token0) }
+    }
+
+    /// Best exact-in route net of gas, with per-hop amounts.
+    pub fn route_exact_in(&self, zero_for_one: bool, amount_in: U256) -> Option<Route> {
+        let (token_in, token_out) = self.tokens(zero_for_one);
+        let gas_cost = self.gas_cost[zero_for_one as usize]; // paid in the output token
+        best_route_exact_in(&self.pools, token_in, token_out, amount_in, self.max_hops, gas_cost)
+    }
+
+    /// Best exact-out route net of gas, with per-hop amounts.
+    pub fn route_exact_out(&self, zero_for_one: bool, amount_out: U256) -> Option<Route> {
+        let (token_in, token_out) = self.tokens(zero_for_one);
+        let gas_cost = self.gas_cost[!zero_for_one as usize]; // paid in the input token
+        best_route_exact_out(&self.pools, token_in, token_out, amount_out, self.max_hops, gas_cost)
+    }
+
+    /// The token0 -> token1 path with the best marginal price: (price, gas units).
+    fn best_spot(&self) -> Option<(f64, u64)> {
+        paths(&self.pools, self.token0, self.token1, self.max_hops)
+            .into_iter()
+            .map(|path| {
+                path.iter().fold((1.0, 0), |(px, gas), &(i, zero_for_one)| {
+                    let pool = &self.pools[i].pool;
+                    let spot = pool.spot_price();
+                    (px * if zero_for_one { spot } else { 1.0 / spot }, gas + pool.gas_units())
+                })
+            })
+            .filter(|(px, _)| px.is_finite())
+            .max_by(|a, b| a.0.total_cmp(&b.0))
+    }
+}
+
+impl Venue for RouteVenue {
+    fn quote_exact_in(&self, zero_for_one: bool, amount_in: U256) -> Option<U256> {
+        self.route_exact_in(zero_for_one, amount_in).map(|r| r.amount_out())
+    }
+
+    fn quote_exact_out(&self, zero_for_one: bool, amount_out: U256) -> Option<U256> {
+        self.route_exact_out(zero_for_one, amount_out).map(|r| r.amount_in())
+    }
+
+    /// Marginal price of the best token0 -> token1 path (the one selling token0 dearest).
+    fn spot_price(&self) -> f64 {
+        self.best_spot().map_or(0.0, |(px, _)| px)
+    }
+
+    /// Gas of the best-priced path: a default for callers that price a leg's gas once. Quotes
+    /// may take another path; `Route::gas_units` is the gas of the one taken.
+    fn gas_units(&self) -> u64 {
+        self.best_spot().map_or(0, |(_, gas)| gas)
+    }
+
+    fn chain_id(&self) -> u64 {
+        self.pools.first().map_or(0, |p| p.pool.chain_id())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::aerodrome_volatile::VolatilePairState;
+
+    const WETH: Address = Address::repeat_byte(0x01);
+    const USDC: Address = Address::repeat_byte(0x02);
+    const USDT: Address = Address::repeat_byte(0x03);
+    const WSTETH: Address = Address::repeat_byte(0x04);
+
+    /// Constant-product pool holding `r0` whole token0 and `r1` whole token1.
+    fn pair(id: &str, token0: Address, token1: Address, r0: u64, d0: u8, r1: u64, d1: u8) -> RoutePool {
+        let state = VolatilePairState {
+            token0,
+            token1,
+            reserve0: U256::from(r0) * U256::exp10(d0 as usize),
+            reserve1: U256::from(r1) * U256::exp10(d1 as usize),
+            decimals0: d0,
+            decimals1: d1,
+            fee_bps: 5,
+            block_number: 0,
+            block_timestamp: 0,
+            stable: false,
+            chain_id: 1,
+            gas_units: 100_000,
+        };
+        RoutePool::new(id, token0, token1, state)
+    }
+
+    /// A thin direct WETH/USDC pool, and deep WETH/USDT + USDT/USDC pools beside it.
+    fn pools() -> Vec<RoutePool> {
+        vec![
+            pair("weth-usdc", WETH, USDC, 100, 18, 300_000, 6),
+            pair("weth-usdt", WETH, USDT, 10_000, 18, 30_000_000, 6),
+            pair("usdc-usdt", USDC, USDT, 50_000_000, 6, 50_000_000, 6),
+            pair("wsteth-usdc", WSTETH, USDC, 1_000, 18, 3_500_000, 6),
+        ]
+    }
+
+    #[test]
+    fn test_two_hops_beat_a_thin_direct_pool() {
+        let pools = pools();
+        let ten_eth = U256::from(10u64) * U256::exp10(18);
+        let route = best_route_exact_in(&pools, WETH, USDC, ten_eth, 2, 0.0).expect("routes exist");
+
+        let ids: Vec<_> = route.hops.iter().map(|h| h.pool.as_str()).collect();
+        assert_eq!(ids, ["weth-usdt", "usdc-usdt"]);
+        assert_eq!(route.amount_in(), ten_eth);
+        assert_eq!(route.hops[0].amount_out, route.hops[1].amount_in);
+        assert_eq!((route.hops[0].token_out, route.hops[1].token_in), (USDT, USDT));
+        assert_eq!(route.gas_units, 200_000);
+
+        let direct = pools[0].pool.quote_exact_in(true, ten_eth).unwrap();
+        assert!(route.amount_out() > direct);
+
+        // One hop allowed: only the direct pool is left
+        let direct_only = best_route_exact_in(&pools, WETH, USDC, ten_eth, 1, 0.0).unwrap();
+        assert_eq!(direct_only.hops.len(), 1);
+        assert_eq!(direct_only.amount_out(), direct);
+    }
+
+    #[test]
+    fn test_extra_hop_has_to_pay_for_its_gas() {
+        let pools = pools();
+        let ten_eth = U256::from(10u64) * U256::exp10(18);
+        let two_hops = best_route_exact_in(&pools, WETH, USDC, ten_eth, 2, 0.0).unwrap();
+        let direct = pools[0].pool.quote_exact_in(true, ten_eth).unwrap();
+        let edge = u256_to_f64(two_hops.amount_out() - direct); // raw USDC the second hop gains
+
+        // Gas worth less than the edge per extra 100k: the route still wins
+        let cheap = best_route_exact_in(&pools, WETH, USDC, ten_eth, 2, edge / 200_000.0).unwrap();
+        assert_eq!(cheap.hops.len(), 2);
+        // Worth more: the direct pool wins, and the route carries its 1-hop gas
+        let dear = best_route_exact_in(&pools, WETH, USDC, ten_eth, 2, edge / 50_000.0).unwrap();
+        assert_eq!(dear.hops.len(), 1);
+        assert_eq!(dear.gas_units, 100_000);
+
+        // Exact out pays gas in the input token
+        let want = U256::from(5u64) * U256::exp10(18);
+        let routed = best_route_exact_out(&pools, USDC, WETH, want, 2, 0.0).unwrap();
+        let direct_in = pools[0].pool.quote_exact_out(false, want).unwrap();
+        let saving = u256_to_f64(direct_in - routed.amount_in());
+        assert_eq!(best_route_exact_out(&pools, USDC, WETH, want, 2, saving / 50_000.0).unwrap().hops.len(), 1);
+
+        // RouteVenue takes the gas cost per side
+        let venue = RouteVenue::new(pools.clone(), WETH, USDC, 2).with_gas_cost(0.0, edge / 50_000.0);
+        assert_eq!(venue.route_exact_in(true, ten_eth).unwrap().hops.len(), 1);
+    }
+
+    #[test]
+    fn test_exact_out_route_delivers_the_target() {
+        let pools = pools();
+        let want = U256::from(5u64) * U256::exp10(18); // buy 5 WETH with USDC
+        let route = best_route_exact_out(&pools, USDC, WETH, want, 2, 0.0).expect("routes exist");
+        assert_eq!(route.hops.len(), 2);
+        assert_eq!(route.amount_out(), want);
+
+        // Replaying the route forward reaches the target
+        let replay = best_route_exact_in(&pools, USDC, WETH, route.amount_in(), 2, 0.0).unwrap();
+        assert!(replay.amount_out() >= want);
+    }
+
+    #[test]
+    fn test_route_venue_quotes_like_a_pool() {
+        let venue = RouteVenue::new(pools(), WETH, USDC, 2);
+        let one_eth = U256::exp10(18);
+        let out = venue.quote_exact_in(true, one_eth).unwrap();
+        assert_eq!(Some(out), venue.route_exact_in(true, one_eth).map(|r| r.amount_out()));
+
+        // Both legs price near 3000 USDC per WETH, raw units
+        assert!((venue.spot_price() * 1e12 / 3000.0 - 1.0).abs() < 0.01);
+        assert_eq!(venue.chain_id(), 1);
+        assert!(venue.gas_units() >= 100_000);
+
+        // Unconnected tokens have no route
+        let lonely = RouteVenue::new(pools(), WETH, Address::repeat_byte(0x09), 3);
+        assert_eq!(lonely.quote_exact_in(true, one_eth), None);
+        assert_eq!(lonely.spot_price(), 0.0);
+    }
+}
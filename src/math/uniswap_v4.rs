@@ -1,4 +1,4 @@
-// Single-file BigInt Uniswap v3/v4 math + exact-input swap simulator + best-pool selection
+// Single-file BigInt Uniswap v3/v4 math + exact-input/exact-output swap simulator + best-pool selection
 // ----------------------------------------------------------------------------------------
 // Cargo.toml dependencies:
 //   num-bigint = "0.4"
@@ -8,11 +8,12 @@
 // Notes:
 // - This uses BigInt end-to-end to avoid overflow/rounding bugs and to mirror the reference
 //   implementations cleanly. Rounding semantics match Uniswap (two-step ceil for token0).
+// - `simulate_swap` runs the fixed-width port in math::uniswap_v4_fixed and only falls back to
+//   `simulate_swap_bigint` when a pool or amount doesn't fit; the BigInt code is the reference.
 // - You provide the pool(s). This file does not use Quoter; it reads pool state you pass in.
 // - Amount signs in results: negative = spent, positive = received.
+// - `amount_specified` > 0 is an exact input, < 0 an exact output (both walk the ticks once).
 //
-// If you want byte-for-byte parity with EVM fixed-width later, you can switch intermediates
-// to U256/U512 after you’re satisfied with correctness here.
 
 use std::cmp::min;
 use std::collections::BTreeMap;
@@ -21,9 +22,14 @@ use ethers::types::Address;
 use num_bigint::BigInt;
 use num_traits::{One, Zero, ToPrimitive, Signed};
 
-const MIN_TICK: i32 = -887_272;
-const MAX_TICK: i32 =  887_272;
+use crate::math::amount::TokenAmount;
+use crate::math::uniswap_v4_fixed;
+
+pub(crate) const MIN_TICK: i32 = -887_272;
+pub(crate) const MAX_TICK: i32 =  887_272;
 const FEE_DENOMINATOR_PPM: i64 = 1_000_000; // ppm
+/// PoolKey fee marking a dynamic-fee pool: the hook sets the LP fee, kept in slot0's lpFee.
+pub const DYNAMIC_FEE_FLAG: u32 = 0x80_0000;
 const Q96_U128: u128 = 1u128 << 96;
 
 // --------------------------------- Helpers ---------------------------------
@@ -88,7 +94,7 @@ pub fn get_sqrt_ratio_at_tick(tick: i32) -> BigInt {
     ms!("31be135f97d08fd981231505542fcfa6", (abs_tick & 0x8000)  != 0);
     ms!("09aa508b5b7a84e1c677de54f3e99bc9", (abs_tick & 0x10000) != 0);
     ms!("05d6af8dedb81196699c329225ee604",  (abs_tick & 0x20000) != 0);
-    ms!("01dcdc6f2d7c3395a2ed4f8b7feaf38",  (abs_tick & 0x40000) != 0);
+    ms!("2216e584f5fa1ea926041bedfe98",     (abs_tick & 0x40000) != 0);
     ms!("48a170391f7dc42444e8fa2",          (abs_tick & 0x80000) != 0);
 
     if tick > 0 {
@@ -176,7 +182,7 @@ pub fn amount1_delta(
 
 // ----------------------------- Next price helpers -----------------------------
 
-fn default_limit(direction: SwapDirection) -> BigInt {
+pub(crate) fn default_limit(direction: SwapDirection) -> BigInt {
     match direction {
         SwapDirection::ZeroForOne => get_sqrt_ratio_at_tick(MIN_TICK + 1),
         SwapDirection::OneForZero => get_sqrt_ratio_at_tick(MAX_TICK - 1),
@@ -184,7 +190,7 @@ fn default_limit(direction: SwapDirection) -> BigInt {
 }
 
 #[inline]
-fn next_sqrt_from_input_zero_for_one(
+pub(crate) fn next_sqrt_from_input_zero_for_one(
     liquidity: &BigInt,
     sqrt_p_x96: &BigInt,
     amount_in_net: &BigInt,
@@ -201,13 +207,27 @@ fn next_sqrt_from_input_zero_for_one(
 }
 
 #[inline]
-fn next_sqrt_from_input_one_for_zero(liquidity: &BigInt, sqrt_p_x96: &BigInt, amount_in_net: &BigInt) -> BigInt {
+pub(crate) fn next_sqrt_from_input_one_for_zero(liquidity: &BigInt, sqrt_p_x96: &BigInt, amount_in_net: &BigInt) -> BigInt {
     // sqrtQ = P + floor( amountIn * Q96 / L )
     if amount_in_net.is_zero() || liquidity.is_zero() { return sqrt_p_x96.clone(); }
     let inc = (amount_in_net * bu128(Q96_U128)) / liquidity;
     sqrt_p_x96 + inc
 }
 
+#[inline]
+pub(crate) fn next_sqrt_from_output(liquidity: &BigInt, sqrt_p_x96: &BigInt, amount_out: &BigInt, zero_for_one: bool) -> BigInt {
+    if amount_out.is_zero() || liquidity.is_zero() { return sqrt_p_x96.clone(); }
+    if zero_for_one {
+        // getNextSqrtPriceFromAmount1RoundingDown (token1 out): P - ceil( amountOut * Q96 / L )
+        sqrt_p_x96 - ceil_div(&(amount_out << 96), liquidity)
+    } else {
+        // getNextSqrtPriceFromAmount0RoundingUp (token0 out): ceil( (L<<96) * P / ( (L<<96) - amountOut * P ) )
+        let numerator1 = liquidity << 96;
+        let denominator = &numerator1 - amount_out * sqrt_p_x96;
+        ceil_div(&(&numerator1 * sqrt_p_x96), &denominator)
+    }
+}
+
 // ------------------------------- Data types ----------------------------------
 
 #[derive(Clone, Debug)]
@@ -220,6 +240,10 @@ pub struct PoolKey {
     pub hooks: Address,
 }
 impl PoolKey {
+    pub fn is_dynamic_fee(&self) -> bool {
+        self.fee_ppm == DYNAMIC_FEE_FLAG
+    }
+
     #[allow(dead_code)]
     pub fn pool_id(&self) -> String {
         format!("{:x?}-{:x?}-{}-{}-{:x?}", self.currency0, self.currency1, self.fee_ppm, self.tick_spacing, self.hooks)
@@ -240,6 +264,76 @@ pub struct PoolState {
     pub tick: i32,
     pub liquidity: BigInt, // non-negative
     pub ticks: BTreeMap<i32, TickInfo>, // initialized ticks
+    pub block_number: u64,              // block the state was read at; 0 when not from chain
+    pub block_timestamp: u64,           // unix seconds of that block
+    pub chain_id: u64,                  // chain the pool lives on; 0 when not from chain
+    pub gas_units: u64,                 // gas of one swap through the pool; 0 until priced
+    pub lp_fee_ppm: u32,                // slot0 lpFee: the key's fee, or the hook's current one on dynamic-fee pools
+    pub protocol_fee: u32,              // slot0 protocolFee, packed (oneForZero << 12 | zeroForOne), pips
+    pub before_swap_fee_ppm: Option<u32>, // LP fee a beforeSwap hook overrides every swap with (configured)
+    pub loaded_ticks: Option<(i32, i32)>, // inclusive range `ticks` is complete for; None = every tick
+    pub synthetic_ticks: bool,          // no initialized ticks were found and `ticks` is a made-up fallback
+}
+
+impl PoolState {
+    /// Whether a swap ending at `sqrt_price_x96` went past the ticks this state knows: outside
+    /// `loaded_ticks`, or anywhere off the current price when the ticks are synthetic.
+    pub fn past_loaded_ticks(&self, sqrt_price_x96: &BigInt) -> bool {
+        if self.synthetic_ticks {
+            return *sqrt_price_x96 != self.sqrt_price_x96;
+        }
+        match self.loaded_ticks {
+            None => false,
+            Some((lo, hi)) => {
+                *sqrt_price_x96 < get_sqrt_ratio_at_tick(lo.max(MIN_TICK))
+                    || *sqrt_price_x96 > get_sqrt_ratio_at_tick(hi.min(MAX_TICK))
+            }
+        }
+    }
+
+    /// Whether selling `base_amount` into the pool (exact input) and buying it back out (exact
+    /// output) both stay inside the loaded ticks, at the pool's own fee.
+    pub fn covers_trade(&self, base_is_token0: bool, base_amount: &BigInt) -> bool {
+        let (sell, buy) = if base_is_token0 {
+            (SwapDirection::ZeroForOne, SwapDirection::OneForZero)
+        } else {
+            (SwapDirection::OneForZero, SwapDirection::ZeroForOne)
+        };
+        [(sell, base_amount.clone()), (buy, -base_amount)].into_iter().all(|(direction, amount)| {
+            // A swap that cannot move the price (already at the limit) stays where it is
+            !simulate_tokens(self, direction, None, amount, None).is_ok_and(|r| r.beyond_loaded_ticks)
+        })
+    }
+
+    /// protocolFee for one direction, in pips (slot0 packs zeroForOne in the low 12 bits).
+    pub fn protocol_fee_ppm(&self, zero_for_one: bool) -> u32 {
+        if zero_for_one { self.protocol_fee & 0xfff } else { (self.protocol_fee >> 12) & 0xfff }
+    }
+
+    /// The hook behaviour swaps on this pool are simulated with.
+    pub fn hook_fee(&self) -> Box<dyn HookFee> {
+        match self.before_swap_fee_ppm {
+            Some(fee_ppm) => Box::new(BeforeSwapFee { fee_ppm }),
+            None if self.key.is_dynamic_fee() => Box::new(DynamicFee),
+            None => Box::new(NoHook),
+        }
+    }
+
+    /// Apply a ModifyLiquidity(tickLower, tickUpper, liquidityDelta) the way the PoolManager does:
+    /// net liquidity moves at both ends, and active liquidity moves if the range holds the current tick.
+    /// Ticks whose net reaches zero are dropped (crossing them would be a no-op).
+    pub fn apply_liquidity_delta(&mut self, tick_lower: i32, tick_upper: i32, delta: &BigInt) {
+        for (tick, d) in [(tick_lower, delta.clone()), (tick_upper, -delta)] {
+            let info = self.ticks.entry(tick).or_insert_with(|| TickInfo { tick, liquidity_net: BigInt::zero() });
+            info.liquidity_net += d;
+            if info.liquidity_net.is_zero() {
+                self.ticks.remove(&tick);
+            }
+        }
+        if tick_lower <= self.tick && self.tick < tick_upper {
+            self.liquidity += delta;
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -248,7 +342,7 @@ pub enum SwapDirection { ZeroForOne, OneForZero }
 #[derive(Clone, Debug)]
 pub struct SwapParams {
     pub direction: SwapDirection,
-    pub amount_specified: BigInt,     // exact input (>0)
+    pub amount_specified: BigInt,     // exact input (>0) or exact output (<0)
     pub sqrt_price_limit_x96: BigInt, // bound
     pub fee_ppm: u32,
 }
@@ -262,18 +356,45 @@ pub struct SwapResult {
     pub tick: i32,
     pub liquidity: BigInt,
     pub crossed_ticks: usize,
+    pub amount_to_protocol: BigInt, // input-token protocol fee, carved out of the swap fee
+    pub beyond_loaded_ticks: bool,  // ended outside the pool's loaded ticks: liquidity there is unknown
+}
+
+/// ProtocolFeeLibrary.calculateSwapFee: the protocol fee is taken first and the LP fee
+/// applies to the rest, so the swapper pays p + l - p*l/1e6 (pips, rounded down).
+pub fn combined_swap_fee_ppm(protocol_fee_ppm: u32, lp_fee_ppm: u32) -> u32 {
+    if protocol_fee_ppm == 0 {
+        return lp_fee_ppm;
+    }
+    let (p, l) = (protocol_fee_ppm as u64, lp_fee_ppm as u64);
+    (p + l - p * l / FEE_DENOMINATOR_PPM as u64) as u32
 }
 
 // Hook fee (optional)
 pub trait HookFee {
-    fn adjust_fee_ppm(&self, _pool: &PoolState, _params: &SwapParams, _remaining_in: &BigInt) -> u32 { 0 }
+    /// LP fee the swap pays; `params.fee_ppm` unless the hook decides it.
+    fn lp_fee_ppm(&self, _pool: &PoolState, params: &SwapParams) -> u32 { params.fee_ppm }
+    /// Extra fee on top of the LP fee, re-evaluated every step with the amount (input or output) still to fill.
+    fn adjust_fee_ppm(&self, _pool: &PoolState, _params: &SwapParams, _remaining: &BigInt) -> u32 { 0 }
 }
 pub struct NoHook;
 impl HookFee for NoHook {}
 
+/// Dynamic-fee pool: the LP fee is whatever the hook last set, as read from slot0's lpFee.
+pub struct DynamicFee;
+impl HookFee for DynamicFee {
+    fn lp_fee_ppm(&self, pool: &PoolState, _params: &SwapParams) -> u32 { pool.lp_fee_ppm }
+}
+
+/// Hook returning a fixed LP fee override from beforeSwap on every swap (dynamic-fee pools only).
+pub struct BeforeSwapFee { pub fee_ppm: u32 }
+impl HookFee for BeforeSwapFee {
+    fn lp_fee_ppm(&self, _pool: &PoolState, _params: &SwapParams) -> u32 { self.fee_ppm }
+}
+
 // ------------------------------- Swap math step -------------------------------
 
-fn compute_swap_step(
+pub(crate) fn compute_swap_step(
     sqrt_price_x96: &BigInt,
     sqrt_price_target_x96: &BigInt,
     liquidity: &BigInt,
@@ -292,46 +413,70 @@ fn compute_swap_step(
     if zero_for_one {
         // input needed (net) to reach target
         let amount_in_to_target = amount0_delta(sqrt_price_target_x96, sqrt_price_x96, liquidity, true);
-        // gross with fee
-        let gross_to_target = ceil_div(&( &amount_in_to_target * &denom ), &fee_complement);
 
-        if &gross_to_target <= amount_remaining {
+        if amount_remaining_less_fee >= amount_in_to_target {
             // reach target
             let amount_out = amount1_delta(sqrt_price_target_x96, sqrt_price_x96, liquidity, false);
-            let fee_amt = &gross_to_target - &amount_in_to_target;
+            let fee_amt = ceil_div(&(&amount_in_to_target * &fee_ppm_bi), &fee_complement);
             (sqrt_price_target_x96.clone(), amount_in_to_target, amount_out, fee_amt)
         } else {
-            // partial move
+            // partial move: all that's left goes in, the part not swapped is fee
             let sqrt_q = next_sqrt_from_input_zero_for_one(liquidity, sqrt_price_x96, &amount_remaining_less_fee);
-            let amount_in_used  = amount0_delta(&sqrt_q, sqrt_price_x96, liquidity, true);
             let amount_out_recv = amount1_delta(&sqrt_q, sqrt_price_x96, liquidity, false);
-            let gross_used = ceil_div(&( &amount_in_used * &denom ), &fee_complement);
-            let fee_amt = &gross_used - &amount_in_used;
-            (sqrt_q, amount_in_used, amount_out_recv, fee_amt)
+            let fee_amt = amount_remaining - &amount_remaining_less_fee;
+            (sqrt_q, amount_remaining_less_fee, amount_out_recv, fee_amt)
         }
     } else {
         // one for zero
         let amount_in_to_target = amount1_delta(sqrt_price_x96, sqrt_price_target_x96, liquidity, true);
-        let gross_to_target = ceil_div(&( &amount_in_to_target * &denom ), &fee_complement);
 
-        if &gross_to_target <= amount_remaining {
+        if amount_remaining_less_fee >= amount_in_to_target {
             let amount_out = amount0_delta(sqrt_price_x96, sqrt_price_target_x96, liquidity, false);
-            let fee_amt = &gross_to_target - &amount_in_to_target;
+            let fee_amt = ceil_div(&(&amount_in_to_target * &fee_ppm_bi), &fee_complement);
             (sqrt_price_target_x96.clone(), amount_in_to_target, amount_out, fee_amt)
         } else {
             let sqrt_q = next_sqrt_from_input_one_for_zero(liquidity, sqrt_price_x96, &amount_remaining_less_fee);
-            let amount_in_used  = amount1_delta(sqrt_price_x96, &sqrt_q, liquidity, true);
             let amount_out_recv = amount0_delta(sqrt_price_x96, &sqrt_q, liquidity, false);
-            let gross_used = ceil_div(&( &amount_in_used * &denom ), &fee_complement);
-            let fee_amt = &gross_used - &amount_in_used;
-            (sqrt_q, amount_in_used, amount_out_recv, fee_amt)
+            let fee_amt = amount_remaining - &amount_remaining_less_fee;
+            (sqrt_q, amount_remaining_less_fee, amount_out_recv, fee_amt)
         }
     }
 }
 
+/// SwapMath.computeSwapStep, exact-output branch: `amount_remaining` is the output still owed.
+pub(crate) fn compute_swap_step_exact_out(
+    sqrt_price_x96: &BigInt,
+    sqrt_price_target_x96: &BigInt,
+    liquidity: &BigInt,
+    amount_remaining: &BigInt,
+    fee_ppm: u32,
+    zero_for_one: bool,
+) -> (BigInt /*sqrtQ*/, BigInt /*amountIn*/, BigInt /*amountOut*/, BigInt /*fee*/ )
+{
+    // output available before the target
+    let out_to_target = if zero_for_one {
+        amount1_delta(sqrt_price_target_x96, sqrt_price_x96, liquidity, false)
+    } else {
+        amount0_delta(sqrt_price_x96, sqrt_price_target_x96, liquidity, false)
+    };
+    let (sqrt_q, amount_out) = if amount_remaining >= &out_to_target {
+        (sqrt_price_target_x96.clone(), out_to_target)
+    } else {
+        (next_sqrt_from_output(liquidity, sqrt_price_x96, amount_remaining, zero_for_one), amount_remaining.clone())
+    };
+    let amount_in = if zero_for_one {
+        amount0_delta(&sqrt_q, sqrt_price_x96, liquidity, true)
+    } else {
+        amount1_delta(sqrt_price_x96, &sqrt_q, liquidity, true)
+    };
+    // fee = ceil( amountIn * fee / (1e6 - fee) )
+    let fee_amt = ceil_div(&(&amount_in * bi(fee_ppm as i64)), &bi(FEE_DENOMINATOR_PPM - fee_ppm as i64));
+    (sqrt_q, amount_in, amount_out, fee_amt)
+}
+
 // -------------------------------- Simulator ---------------------------------
 
-fn next_initialized_tick(
+pub(crate) fn next_initialized_tick(
     ticks: &BTreeMap<i32, TickInfo>,
     current_tick: i32,
     direction: SwapDirection,
@@ -346,40 +491,59 @@ fn next_initialized_tick(
     }
 }
 
-pub fn simulate_swap(pool: &PoolState, params: &SwapParams, hook: &dyn HookFee) -> Result<SwapResult, String> {
-    if params.amount_specified <= BigInt::zero() {
-        return Err("amount_specified must be positive (exact input)".into());
+fn check_swap(pool: &PoolState, params: &SwapParams) -> Result<(), String> {
+    if params.amount_specified.is_zero() {
+        return Err("amount_specified must be non-zero (>0 exact input, <0 exact output)".into());
     }
-
-    let mut amount_remaining = params.amount_specified.clone();
-    let mut sqrt_price       = pool.sqrt_price_x96.clone();
-    let mut liquidity        = pool.liquidity.clone();
-    let mut current_tick     = pool.tick;
-
-    let mut amount0_total = BigInt::zero();
-    let mut amount1_total = BigInt::zero();
-    let mut ticks_crossed = 0usize;
-
     // price limit sanity
     match params.direction {
         SwapDirection::ZeroForOne => {
-            if params.sqrt_price_limit_x96 >= sqrt_price {
+            if params.sqrt_price_limit_x96 >= pool.sqrt_price_x96 {
                 return Err("price limit must be < current sqrt for ZeroForOne".into());
             }
         }
         SwapDirection::OneForZero => {
-            if params.sqrt_price_limit_x96 <= sqrt_price {
+            if params.sqrt_price_limit_x96 <= pool.sqrt_price_x96 {
                 return Err("price limit must be > current sqrt for OneForZero".into());
             }
         }
     }
+    Ok(())
+}
+
+/// Walk the ticks for one swap. Runs on fixed-width integers (math::uniswap_v4_fixed), with
+/// the same result as `simulate_swap_bigint` but without the allocations; state that doesn't
+/// fit uint160/uint128 is simulated on BigInt instead.
+pub fn simulate_swap(pool: &PoolState, params: &SwapParams, hook: &dyn HookFee) -> Result<SwapResult, String> {
+    check_swap(pool, params)?;
+    match uniswap_v4_fixed::simulate_swap(pool, params, hook) {
+        Some(res) => Ok(res),
+        None => simulate_swap_bigint(pool, params, hook),
+    }
+}
 
+/// The BigInt reference simulator.
+pub fn simulate_swap_bigint(pool: &PoolState, params: &SwapParams, hook: &dyn HookFee) -> Result<SwapResult, String> {
+    check_swap(pool, params)?;
+
+    let exact_in = params.amount_specified.is_positive();
+    let mut amount_remaining = params.amount_specified.abs(); // input left to spend, or output left to receive
+    let mut sqrt_price       = pool.sqrt_price_x96.clone();
+    let mut liquidity        = pool.liquidity.clone();
+    let mut current_tick     = pool.tick;
+
+    let mut amount0_total = BigInt::zero();
+    let mut amount1_total = BigInt::zero();
+    let mut amount_to_protocol = BigInt::zero();
+    let mut ticks_crossed = 0usize;
+
+    let lp_fee_ppm = hook.lp_fee_ppm(pool, params);
+    let protocol_fee_ppm = pool.protocol_fee_ppm(matches!(params.direction, SwapDirection::ZeroForOne));
     while amount_remaining > BigInt::zero() && liquidity > BigInt::zero() {
         let hook_adj = hook.adjust_fee_ppm(pool, params, &amount_remaining);
-        let eff_fee_ppm = min(
-            (FEE_DENOMINATOR_PPM - 1) as u32,
-            params.fee_ppm.saturating_add(hook_adj)
-        );
+        let max_fee = (FEE_DENOMINATOR_PPM - 1) as u32;
+        let step_lp_fee = min(max_fee, lp_fee_ppm.saturating_add(hook_adj));
+        let eff_fee_ppm = min(max_fee, combined_swap_fee_ppm(protocol_fee_ppm, step_lp_fee));
 
         let (next_tick, has_next) = next_initialized_tick(&pool.ticks, current_tick, params.direction);
         let sqrt_next = if has_next { get_sqrt_ratio_at_tick(next_tick) } else {
@@ -392,8 +556,11 @@ pub fn simulate_swap(pool: &PoolState, params: &SwapParams, hook: &dyn HookFee)
         };
 
         let zero_for_one = matches!(params.direction, SwapDirection::ZeroForOne);
-        let (sqrt_q, used_in, got_out, fee_amt) =
-            compute_swap_step(&sqrt_price, &sqrt_target_bound, &liquidity, &amount_remaining, eff_fee_ppm, zero_for_one);
+        let (sqrt_q, used_in, got_out, fee_amt) = if exact_in {
+            compute_swap_step(&sqrt_price, &sqrt_target_bound, &liquidity, &amount_remaining, eff_fee_ppm, zero_for_one)
+        } else {
+            compute_swap_step_exact_out(&sqrt_price, &sqrt_target_bound, &liquidity, &amount_remaining, eff_fee_ppm, zero_for_one)
+        };
 
         if zero_for_one {
             amount0_total -= &used_in + &fee_amt; // spent token0 (gross)
@@ -402,7 +569,15 @@ pub fn simulate_swap(pool: &PoolState, params: &SwapParams, hook: &dyn HookFee)
             amount1_total -= &used_in + &fee_amt; // spent token1 (gross)
             amount0_total += &got_out;            // received token0
         }
-        amount_remaining -= &used_in + &fee_amt;
+        if protocol_fee_ppm > 0 {
+            // Pool.swap: the protocol's cut is on the gross input, the LPs keep the rest of the fee
+            amount_to_protocol += if eff_fee_ppm == protocol_fee_ppm {
+                fee_amt.clone()
+            } else {
+                (&used_in + &fee_amt) * bi(protocol_fee_ppm as i64) / bi(FEE_DENOMINATOR_PPM)
+            };
+        }
+        amount_remaining -= if exact_in { &used_in + &fee_amt } else { got_out };
         sqrt_price = sqrt_q;
 
         let crossed = has_next && sqrt_price == sqrt_next;
@@ -434,30 +609,60 @@ pub fn simulate_swap(pool: &PoolState, params: &SwapParams, hook: &dyn HookFee)
     Ok(SwapResult {
         amount0: amount0_total,
         amount1: amount1_total,
+        beyond_loaded_ticks: pool.past_loaded_ticks(&sqrt_price),
         sqrt_price_x96: sqrt_price,
         tick: current_tick,
         liquidity,
         crossed_ticks: ticks_crossed,
+        amount_to_protocol,
     })
 }
 
 // ----------------------- Convenience + Best-pool picker ----------------------
 
+/// Spend exactly `amount_in` (raw units of the input token).
 pub fn simulate_exact_in_tokens(
     pool: &PoolState,
     direction: SwapDirection,
     fee_ppm_override: Option<u32>,
-    amount_in_tokens: f64,
-    input_decimals: u8,
+    amount_in: TokenAmount,
     price_limit: Option<BigInt>,
 ) -> Result<SwapResult, String> {
-    let scale = 10f64.powi(input_decimals as i32);
-    let amount_units = (amount_in_tokens * scale).round();
-    if amount_units < 0.0 { return Err("amount_in_tokens must be >= 0".into()); }
-    let amount_bi = BigInt::from(amount_units as i128);
+    simulate_tokens(pool, direction, fee_ppm_override, amount_in.to_bigint(), price_limit)
+}
 
+/// Exact-output twin of `simulate_exact_in_tokens`: receive exactly `amount_out`.
+/// Errors if the pool runs out of liquidity (or hits `price_limit`) first.
+pub fn simulate_exact_out_tokens(
+    pool: &PoolState,
+    direction: SwapDirection,
+    fee_ppm_override: Option<u32>,
+    amount_out: TokenAmount,
+    price_limit: Option<BigInt>,
+) -> Result<SwapResult, String> {
+    if amount_out.is_zero() { return Err("amount_out must be > 0".into()); }
+    let wanted = amount_out.to_bigint();
+    let res = simulate_tokens(pool, direction, fee_ppm_override, -wanted.clone(), price_limit)?;
+    let received = match direction {
+        SwapDirection::ZeroForOne => &res.amount1,
+        SwapDirection::OneForZero => &res.amount0,
+    };
+    if *received < wanted {
+        return Err(format!("pool can only deliver {} of the {} units requested", received, wanted));
+    }
+    Ok(res)
+}
+
+fn simulate_tokens(
+    pool: &PoolState,
+    direction: SwapDirection,
+    fee_ppm_override: Option<u32>,
+    amount_bi: BigInt,
+    price_limit: Option<BigInt>,
+) -> Result<SwapResult, String> {
     let limit = price_limit.unwrap_or_else(|| default_limit(direction));
 
+    // An explicit fee wins over whatever the pool's hook would charge
     let fee = fee_ppm_override.unwrap_or(pool.key.fee_ppm);
     let params = SwapParams {
         direction,
@@ -465,7 +670,24 @@ pub fn simulate_exact_in_tokens(
         sqrt_price_limit_x96: limit,
         fee_ppm: fee,
     };
-    simulate_swap(pool, &params, &NoHook)
+    match fee_ppm_override {
+        Some(_) => simulate_swap(pool, &params, &NoHook),
+        None => simulate_swap(pool, &params, &*pool.hook_fee()),
+    }
+}
+
+impl SwapResult {
+    /// (input spent, output received) for a swap in `direction`, in the tokens' decimals.
+    pub fn amounts(&self, direction: SwapDirection, in_decimals: u8, out_decimals: u8) -> (TokenAmount, TokenAmount) {
+        let (spent, received) = match direction {
+            SwapDirection::ZeroForOne => (-&self.amount0, &self.amount1),
+            SwapDirection::OneForZero => (-&self.amount1, &self.amount0),
+        };
+        (
+            TokenAmount::from_bigint(&spent, in_decimals).unwrap_or(TokenAmount::zero(in_decimals)),
+            TokenAmount::from_bigint(received, out_decimals).unwrap_or(TokenAmount::zero(out_decimals)),
+        )
+    }
 }
 
 #[allow(dead_code)]
@@ -475,36 +697,23 @@ pub fn execution_price_out_per_in(
     in_decimals: u8,
     out_decimals: u8,
 ) -> f64 {
-    let si = 10f64.powi(in_decimals as i32);
-    let so = 10f64.powi(out_decimals as i32);
-    match direction {
-        SwapDirection::ZeroForOne => {
-            let input  = (-res.amount0.clone()).to_f64().unwrap_or(0.0) / si;
-            let output = ( res.amount1.clone()).to_f64().unwrap_or(0.0) / so;
-            if input <= 0.0 { 0.0 } else { output / input }
-        }
-        SwapDirection::OneForZero => {
-            let input  = (-res.amount1.clone()).to_f64().unwrap_or(0.0) / si;
-            let output = ( res.amount0.clone()).to_f64().unwrap_or(0.0) / so;
-            if input <= 0.0 { 0.0 } else { output / input }
-        }
-    }
+    let (input, output) = res.amounts(direction, in_decimals, out_decimals);
+    output.per(&input)
 }
 
 #[allow(dead_code)]
 pub fn best_pool_for_exact_in(
     pools: &[PoolState],
     direction: SwapDirection,
-    amount_in_tokens: f64,
-    in_decimals: u8,
+    amount_in: TokenAmount,
     out_decimals: u8,
     fee_ppm_override: Option<u32>,
 ) -> Option<(PoolState, SwapResult, f64)> {
     let mut best: Option<(PoolState, SwapResult, f64)> = None;
     for p in pools {
-        let fee = fee_ppm_override.unwrap_or(p.key.fee_ppm);
-        let sim = simulate_exact_in_tokens(p, direction, Some(fee), amount_in_tokens, in_decimals, None).ok()?;
-        let px  = execution_price_out_per_in(&sim, direction, in_decimals, out_decimals);
+        // Without an override each pool charges its own fee, a dynamic pool's through its hook
+        let sim = simulate_exact_in_tokens(p, direction, fee_ppm_override, amount_in, None).ok()?;
+        let px  = execution_price_out_per_in(&sim, direction, amount_in.decimals, out_decimals);
         match &best {
             Some((_, _, best_px)) if px <= *best_px => {}
             _ => best = Some((p.clone(), sim, px)),
@@ -513,11 +722,10 @@ pub fn best_pool_for_exact_in(
     best
 }
 
-// --- helper: f64 -> token units ---
+// --- helper: human -> token units ---
 #[allow(dead_code)]
-fn units(amount: f64, decimals: u32) -> BigInt {
-    let scale = 10f64.powi(decimals as i32);
-    BigInt::from((amount * scale).round() as i128)
+fn units(amount: f64, decimals: u8) -> BigInt {
+    TokenAmount::from_f64(amount, decimals).map_or_else(BigInt::zero, |a| a.to_bigint())
 }
 
 // --- compute L from reserves & range ---
@@ -559,6 +767,7 @@ pub fn create_standard_weth_usdc_pools(
 }
 
 // Create pool with real on-chain data
+#[allow(clippy::too_many_arguments)]
 pub fn create_pool_with_real_data(
     currency0: Address,
     currency1: Address,
@@ -592,6 +801,15 @@ pub fn create_pool_with_real_data(
         tick: current_tick,
         liquidity,
         ticks,
+        block_number: 0,
+        block_timestamp: 0,
+        chain_id: 0,
+        gas_units: 0,
+        lp_fee_ppm: if fee_ppm == DYNAMIC_FEE_FLAG { 0 } else { fee_ppm }, // until slot0 says otherwise
+        protocol_fee: 0,
+        before_swap_fee_ppm: None,
+        loaded_ticks: None,
+        synthetic_ticks: false,
     }
 }
 
@@ -643,6 +861,15 @@ fn mock_pool_with_addresses(
         tick: center_tick,
         liquidity: concentrated_liquidity,
         ticks,
+        block_number: 0,
+        block_timestamp: 0,
+        chain_id: 0,
+        gas_units: 0,
+        lp_fee_ppm: if fee_ppm == DYNAMIC_FEE_FLAG { 0 } else { fee_ppm },
+        protocol_fee: 0,
+        before_swap_fee_ppm: None,
+        loaded_ticks: None,
+        synthetic_ticks: false,
     }
 }
 
@@ -670,6 +897,7 @@ mod tests {
     use super::*;
 
     fn addr(x: u8) -> Address { Address::from([x;20]) }
+    fn amt(x: f64, decimals: u8) -> TokenAmount { TokenAmount::from_f64(x, decimals).unwrap() }
 
     fn mock_pool(price_token1_per_token0: f64, fee_ppm: u32, tick_spacing: i32) -> PoolState {
         mock_pool_with_addresses(addr(1), addr(2), price_token1_per_token0, fee_ppm, tick_spacing)
@@ -728,7 +956,7 @@ mod tests {
         let requested_amount = BigInt::from(1e18 as i128);
         log::debug!("  requested amount: {} wei = 1.0 ETH", requested_amount);
         
-        let res = simulate_exact_in_tokens(&p, SwapDirection::ZeroForOne, None, 1.0, 18, None).unwrap();
+        let res = simulate_exact_in_tokens(&p, SwapDirection::ZeroForOne, None, amt(1.0, 18), None).unwrap();
         let px  = execution_price_out_per_in(&res, SwapDirection::ZeroForOne, 18, 6);
         
         log::debug!("Swap result:");
@@ -749,10 +977,174 @@ mod tests {
         let p2 = mock_pool(3000.0, 3000, 60);  
         let p3 = mock_pool(3000.0,10000, 200);
         let pools = vec![p1,p2,p3];
-        let (pool, res, px) = best_pool_for_exact_in(&pools, SwapDirection::ZeroForOne, 1.0, 18, 6, None).unwrap();
+        let (pool, res, px) = best_pool_for_exact_in(&pools, SwapDirection::ZeroForOne, amt(1.0, 18), 6, None).unwrap();
         assert!(res.amount0 < BigInt::zero() && res.amount1 > BigInt::zero());
         assert!(px > 2800.0 && px < 3200.0, "px {}", px);
         // lower fee tier usually wins at small size
         assert_eq!(pool.key.fee_ppm, 500);
+
+        // The 5 bp pool made dynamic at 1 bp beats them all, rather than being priced at its flag
+        let mut dynamic = mock_pool(3000.0, 500, 10);
+        dynamic.key.fee_ppm = DYNAMIC_FEE_FLAG;
+        dynamic.key.hooks = addr(9);
+        dynamic.lp_fee_ppm = 100;
+        let pools = [pools, vec![dynamic]].concat();
+        let (pool, _, _) = best_pool_for_exact_in(&pools, SwapDirection::ZeroForOne, amt(1.0, 18), 6, None).unwrap();
+        assert!(pool.key.is_dynamic_fee());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn hooked_pools_take_their_lp_fee_from_the_hook() {
+        let fixed = mock_pool(3000.0, 3000, 60);
+        let out = |p: &PoolState, fee: Option<u32>| {
+            simulate_exact_in_tokens(p, SwapDirection::ZeroForOne, fee, amt(1.0, 18), None).unwrap().amount1
+        };
+
+        // Dynamic-fee pool: the key's fee is only the flag, slot0's lpFee is charged
+        let mut dynamic = fixed.clone();
+        dynamic.key.fee_ppm = DYNAMIC_FEE_FLAG;
+        dynamic.key.hooks = addr(9);
+        dynamic.lp_fee_ppm = 3000;
+        assert!(dynamic.key.is_dynamic_fee() && !fixed.key.is_dynamic_fee());
+        assert_eq!(out(&dynamic, None), out(&fixed, None));
+
+        // A beforeSwap override replaces it, as does an explicit fee
+        dynamic.before_swap_fee_ppm = Some(500);
+        assert_eq!(out(&dynamic, None), out(&fixed, Some(500)));
+        assert_eq!(out(&dynamic, Some(3000)), out(&fixed, None));
+        assert!(out(&dynamic, None) > out(&fixed, None));
+    }
+
+    #[test]
+    fn protocol_fee_compounds_with_the_lp_fee() {
+        assert_eq!(combined_swap_fee_ppm(0, 3000), 3000);
+        assert_eq!(combined_swap_fee_ppm(1000, 3000), 3997); // 1000 + 3000 - 3
+        assert_eq!(combined_swap_fee_ppm(1000, 0), 1000);
+
+        let plain = mock_pool(3000.0, 3000, 60);
+        let mut taxed = plain.clone();
+        taxed.protocol_fee = (500 << 12) | 1000; // 1000 pips selling token0, 500 selling token1
+        assert_eq!((taxed.protocol_fee_ppm(true), taxed.protocol_fee_ppm(false)), (1000, 500));
+
+        let sim = |p: &PoolState, dir: SwapDirection, fee: Option<u32>, amount: f64, dec: u8| {
+            simulate_exact_in_tokens(p, dir, fee, amt(amount, dec), None).unwrap()
+        };
+        // The swapper pays the combined fee, per direction
+        let sell = sim(&taxed, SwapDirection::ZeroForOne, None, 1.0, 18);
+        assert_eq!(sell.amount1, sim(&plain, SwapDirection::ZeroForOne, Some(3997), 1.0, 18).amount1);
+        let buy = sim(&taxed, SwapDirection::OneForZero, None, 3000.0, 6);
+        assert_eq!(buy.amount0, sim(&plain, SwapDirection::OneForZero, Some(combined_swap_fee_ppm(500, 3000)), 3000.0, 6).amount0);
+
+        // The protocol takes its pips of the gross input
+        assert_eq!(sell.amount_to_protocol, BigInt::from(10u64).pow(18) * 1000 / 1_000_000);
+        assert!(sim(&plain, SwapDirection::ZeroForOne, None, 1.0, 18).amount_to_protocol.is_zero());
+    }
+
+    #[test]
+    fn exact_out_pays_what_exact_in_would_need() {
+        let p = mock_pool(3000.0, 3000, 60);
+        // Buy half the position's 100 WETH with USDC: a large move within one step
+        let want = BigInt::from(50u64) * BigInt::from(10u64).pow(18);
+        let res = simulate_exact_out_tokens(&p, SwapDirection::OneForZero, None, amt(50.0, 18), None).unwrap();
+        assert_eq!(res.amount0, want);
+        let paid = -res.amount1.clone();
+        assert!(paid > BigInt::zero());
+
+        // Feeding that input back exact-in delivers the target, within rounding
+        let back = simulate_swap(&p, &SwapParams {
+            direction: SwapDirection::OneForZero,
+            amount_specified: paid.clone(),
+            sqrt_price_limit_x96: default_limit(SwapDirection::OneForZero),
+            fee_ppm: 3000,
+        }, &NoHook).unwrap();
+        assert!(back.amount0 >= &want - 1);
+        assert!(&back.amount0 - &want < &want / 1_000_000_000u64); // sub-ppb: sqrt-price rounding only
+
+        // Selling token0 for an exact token1 amount works the same way
+        let res = simulate_exact_out_tokens(&p, SwapDirection::ZeroForOne, None, amt(3000.0, 6), None).unwrap();
+        assert_eq!(res.amount1, BigInt::from(3_000_000_000u64));
+        let eth_in = (-res.amount0.clone()).to_f64().unwrap() / 1e18;
+        assert!(eth_in > 1.0 && eth_in < 1.01, "paid {} WETH for 3000 USDC", eth_in);
+
+        // Across initialized ticks: a narrow range stacked on a wide one
+        let tick = p.tick / 60 * 60;
+        let l = BigInt::from(10u64).pow(15); // ~0.1 WETH within ±120 ticks
+        let stacked = create_pool_with_real_data(
+            addr(1), addr(2), 3000, 60, Address::zero(), get_sqrt_ratio_at_tick(tick), tick, &l * 2,
+            vec![(tick - 6000, l.clone()), (tick - 120, l.clone()), (tick + 120, -l.clone()), (tick + 6000, -l.clone())],
+        );
+        let res = simulate_exact_out_tokens(&stacked, SwapDirection::OneForZero, None, amt(0.5, 18), None).unwrap();
+        assert!(res.crossed_ticks >= 1);
+        let back = simulate_swap(&stacked, &SwapParams {
+            direction: SwapDirection::OneForZero,
+            amount_specified: -res.amount1.clone(),
+            sqrt_price_limit_x96: default_limit(SwapDirection::OneForZero),
+            fee_ppm: 3000,
+        }, &NoHook).unwrap();
+        assert!(back.amount0 >= res.amount0 - 1);
+        assert_eq!(back.crossed_ticks, res.crossed_ticks);
+
+        // More than the pool holds is an error, zero is not a swap
+        assert!(simulate_exact_out_tokens(&p, SwapDirection::OneForZero, None, amt(1e6, 18), None).is_err());
+        assert!(simulate_exact_out_tokens(&p, SwapDirection::OneForZero, None, amt(0.0, 18), None).is_err());
+    }
+
+    #[test]
+    fn swaps_report_running_past_loaded_ticks() {
+        let mut p = mock_pool(3000.0, 3000, 60);
+        p.loaded_ticks = Some((p.tick - 120, p.tick + 120));
+        let swap = |p: &PoolState, direction, amount: BigInt| {
+            let params = SwapParams {
+                direction,
+                amount_specified: amount,
+                sqrt_price_limit_x96: default_limit(direction),
+                fee_ppm: 3000,
+            };
+            let fixed = simulate_swap(p, &params, &NoHook).unwrap();
+            let reference = simulate_swap_bigint(p, &params, &NoHook).unwrap();
+            assert_eq!(fixed.beyond_loaded_ticks, reference.beyond_loaded_ticks);
+            fixed.beyond_loaded_ticks
+        };
+
+        // A small sell stays within 120 ticks; 10 ETH out of 100 does not, either way
+        assert!(!swap(&p, SwapDirection::ZeroForOne, units(0.001, 18)));
+        assert!(swap(&p, SwapDirection::ZeroForOne, units(10.0, 18)));
+        assert!(!swap(&p, SwapDirection::OneForZero, -units(0.001, 18)));
+        assert!(swap(&p, SwapDirection::OneForZero, -units(10.0, 18)));
+        assert!(p.covers_trade(true, &units(0.001, 18)));
+        assert!(!p.covers_trade(true, &units(10.0, 18)));
+
+        // Complete tick data never runs out; synthetic data runs out at once
+        p.loaded_ticks = None;
+        assert!(!swap(&p, SwapDirection::ZeroForOne, units(10.0, 18)));
+        assert!(p.covers_trade(true, &units(10.0, 18)));
+        p.synthetic_ticks = true;
+        assert!(swap(&p, SwapDirection::ZeroForOne, units(0.001, 18)));
+        assert!(!p.covers_trade(false, &units(0.001, 18)));
+    }
+
+    #[test]
+    fn apply_liquidity_delta_updates_ticks_and_active_liquidity() {
+        let mut p = mock_pool(3000.0, 3000, 60);
+        let l0 = p.liquidity.clone();
+        let (lo, hi) = ((p.tick / 60 - 10) * 60, (p.tick / 60 + 10) * 60);
+        let d = BigInt::from(1_000_000_000_000u64);
+
+        // In-range mint: both ends and active liquidity move
+        p.apply_liquidity_delta(lo, hi, &d);
+        assert_eq!(p.liquidity, &l0 + &d);
+        assert_eq!(p.ticks[&lo].liquidity_net, d);
+        assert_eq!(p.ticks[&hi].liquidity_net, -d.clone());
+
+        // Burning it again restores the pool and drops the now-empty ticks
+        p.apply_liquidity_delta(lo, hi, &-d.clone());
+        assert_eq!(p.liquidity, l0);
+        assert!(!p.ticks.contains_key(&lo) && !p.ticks.contains_key(&hi));
+
+        // Out-of-range position leaves active liquidity alone
+        let above = (p.tick / 60 + 20) * 60;
+        p.apply_liquidity_delta(above, above + 600, &d);
+        assert_eq!(p.liquidity, l0);
+        assert_eq!(p.ticks[&above].liquidity_net, d);
+    }
+}
@@ -0,0 +1,573 @@
+// Fixed-width Uniswap v3/v4 swap math: TickMath, SqrtPriceMath and SwapMath on U256/I256
+// ----------------------------------------------------------------------------------------
+// A port of math::uniswap_v4 to the contracts' integer widths, with U512 only inside mulDiv.
+// The BigInt module stays the reference: every primitive here is bit-exact with its BigInt
+// counterpart (see the property tests below), and `simulate_swap` walks the ticks step for
+// step the way uniswap_v4::simulate_swap_bigint does, just without allocating.
+//
+// Notes:
+// - Sqrt prices are Q64.96 in a U256, liquidity is a u128, tick liquidityNet an i128.
+// - Anything that would overflow those widths returns None instead of wrapping;
+//   uniswap_v4::simulate_swap then reruns the swap on BigInt, so callers never see the difference.
+
+use std::cmp::{max, min};
+
+use ethers::types::{Sign, I256, U256, U512};
+use num_bigint::BigInt;
+use num_traits::{Signed, ToPrimitive};
+
+use crate::math::uniswap_v4::{
+    combined_swap_fee_ppm, next_initialized_tick, HookFee, PoolState, SwapDirection, SwapParams, SwapResult,
+    MAX_TICK, MIN_TICK,
+};
+
+const FEE_DENOMINATOR_PPM: u32 = 1_000_000; // ppm
+
+// --------------------------------- Helpers ---------------------------------
+
+#[inline]
+fn q96() -> U256 { U256::one() << 96 }
+
+/// FullMath.mulDiv: floor(a * b / d) with a 512-bit product; None if d = 0 or the result overflows.
+#[inline]
+pub fn mul_div(a: U256, b: U256, d: U256) -> Option<U256> {
+    if d.is_zero() { return None; }
+    U256::try_from(a.full_mul(b) / U512::from(d)).ok()
+}
+
+/// FullMath.mulDivRoundingUp: ceil(a * b / d).
+#[inline]
+pub fn mul_div_rounding_up(a: U256, b: U256, d: U256) -> Option<U256> {
+    if d.is_zero() { return None; }
+    let (q, r) = a.full_mul(b).div_mod(U512::from(d));
+    let q = U256::try_from(q).ok()?;
+    if r.is_zero() { Some(q) } else { q.checked_add(U256::one()) }
+}
+
+/// UnsafeMath.divRoundingUp (d > 0).
+#[inline]
+fn div_rounding_up(a: U256, d: U256) -> U256 {
+    let (q, r) = a.div_mod(d);
+    if r.is_zero() { q } else { q + 1 }
+}
+
+/// Non-negative BigInt -> U256; None if negative or wider than 256 bits.
+pub fn to_u256(v: &BigInt) -> Option<U256> {
+    let (sign, bytes) = v.to_bytes_be();
+    if sign == num_bigint::Sign::Minus || bytes.len() > 32 {
+        return None;
+    }
+    Some(U256::from_big_endian(&bytes))
+}
+
+pub fn to_bigint(v: U256) -> BigInt {
+    let mut bytes = [0u8; 32];
+    v.to_big_endian(&mut bytes);
+    BigInt::from_bytes_be(num_bigint::Sign::Plus, &bytes)
+}
+
+fn i256_to_bigint(v: I256) -> BigInt {
+    let (sign, abs) = v.into_sign_and_abs();
+    match sign {
+        Sign::Negative => -to_bigint(abs),
+        Sign::Positive => to_bigint(abs),
+    }
+}
+
+// -------------------------------- Tick Math --------------------------------
+
+// TickMath.getSqrtPriceAtTick: Q128.128 factor for each bit of |tick| above the first
+const RATIO_FACTORS: [(u32, u128); 18] = [
+    (0x2,     0xfff97272373d413259a46990580e213a),
+    (0x4,     0xfff2e50f5f656932ef12357cf3c7fdcc),
+    (0x8,     0xffe5caca7e10e4e61c3624eaa0941cd0),
+    (0x10,    0xffcb9843d60f6159c9db58835c926644),
+    (0x20,    0xff973b41fa98c081472e6896dfb254c0),
+    (0x40,    0xff2ea16466c96a3843ec78b326b52861),
+    (0x80,    0xfe5dee046a99a2a811c461f1969c3053),
+    (0x100,   0xfcbe86c7900a88aedcffc83b479aa3a4),
+    (0x200,   0xf987a7253ac413176f2b074cf7815e54),
+    (0x400,   0xf3392b0822b70005940c7a398e4b70f3),
+    (0x800,   0xe7159475a2c29b7443b29c7fa6e889d9),
+    (0x1000,  0xd097f3bdfd2022b8845ad8f792aa5825),
+    (0x2000,  0xa9f746462d870fdf8a65dc1f90e061e5),
+    (0x4000,  0x70d869a156d2a1b890bb3df62baf32f7),
+    (0x8000,  0x31be135f97d08fd981231505542fcfa6),
+    (0x10000, 0x9aa508b5b7a84e1c677de54f3e99bc9),
+    (0x20000, 0x5d6af8dedb81196699c329225ee604),
+    (0x40000, 0x2216e584f5fa1ea926041bedfe98),
+];
+const RATIO_FACTOR_0X80000: u128 = 0x48a170391f7dc42444e8fa2;
+
+/// Exact TickMath.getSqrtRatioAtTick (Q64.96).
+pub fn get_sqrt_ratio_at_tick(tick: i32) -> U256 {
+    assert!((MIN_TICK..=MAX_TICK).contains(&tick), "tick out of range");
+    let abs_tick = tick.unsigned_abs();
+
+    // ratio is Q128.128 and never exceeds 2^128, so each product fits in 256 bits
+    let mut ratio = if abs_tick & 0x1 != 0 {
+        U256::from(0xfffcb933bd6fad37aa2d162d1a594001_u128)
+    } else {
+        U256::one() << 128
+    };
+    for (bit, factor) in RATIO_FACTORS {
+        if abs_tick & bit != 0 {
+            ratio = (ratio * U256::from(factor)) >> 128;
+        }
+    }
+    if abs_tick & 0x80000 != 0 {
+        ratio = (ratio * U256::from(RATIO_FACTOR_0X80000)) >> 128;
+    }
+
+    if tick > 0 {
+        ratio = U256::MAX / ratio;
+    }
+    // round-up shift by 32 (Q128.128 -> Q64.96)
+    div_rounding_up(ratio, U256::one() << 32)
+}
+
+/// Binary search inverse of get_sqrt_ratio_at_tick (exact on-grid).
+pub fn get_tick_at_sqrt_ratio(sqrt_price_x96: U256) -> i32 {
+    let mut lo = MIN_TICK;
+    let mut hi = MAX_TICK;
+    while lo < hi {
+        let mid = lo + ((hi - lo + 1) / 2);
+        if get_sqrt_ratio_at_tick(mid) <= sqrt_price_x96 { lo = mid; } else { hi = mid - 1; }
+    }
+    lo
+}
+
+pub(crate) fn default_limit(zero_for_one: bool) -> U256 {
+    if zero_for_one { get_sqrt_ratio_at_tick(MIN_TICK + 1) } else { get_sqrt_ratio_at_tick(MAX_TICK - 1) }
+}
+
+// --------------------------- SqrtPriceMath deltas ---------------------------
+
+/// SqrtPriceMath.getAmount0Delta: (L << 96) * (sb - sa) / sb / sa, both divisions rounded the same way.
+pub fn amount0_delta(sqrt_ratio_a_x96: U256, sqrt_ratio_b_x96: U256, liquidity: u128, round_up: bool) -> Option<U256> {
+    if liquidity == 0 { return Some(U256::zero()); }
+    let (sa, sb) = (min(sqrt_ratio_a_x96, sqrt_ratio_b_x96), max(sqrt_ratio_a_x96, sqrt_ratio_b_x96));
+    if sa.is_zero() || sa == sb { return Some(U256::zero()); }
+
+    let numerator1 = U256::from(liquidity) << 96;
+    let numerator2 = sb - sa;
+    if round_up {
+        Some(div_rounding_up(mul_div_rounding_up(numerator1, numerator2, sb)?, sa))
+    } else {
+        Some(mul_div(numerator1, numerator2, sb)? / sa)
+    }
+}
+
+/// SqrtPriceMath.getAmount1Delta: L * (sb - sa) / Q96.
+pub fn amount1_delta(sqrt_ratio_a_x96: U256, sqrt_ratio_b_x96: U256, liquidity: u128, round_up: bool) -> Option<U256> {
+    if liquidity == 0 { return Some(U256::zero()); }
+    let (sa, sb) = (min(sqrt_ratio_a_x96, sqrt_ratio_b_x96), max(sqrt_ratio_a_x96, sqrt_ratio_b_x96));
+    if round_up {
+        mul_div_rounding_up(U256::from(liquidity), sb - sa, q96())
+    } else {
+        mul_div(U256::from(liquidity), sb - sa, q96())
+    }
+}
+
+// ----------------------------- Next price helpers -----------------------------
+
+/// getNextSqrtPriceFromAmount0RoundingUp, adding: ceil( (L<<96) * P / ( (L<<96) + amountIn * P ) ).
+pub fn next_sqrt_from_input_zero_for_one(liquidity: u128, sqrt_p_x96: U256, amount_in_net: U256) -> Option<U256> {
+    if amount_in_net.is_zero() || liquidity == 0 { return Some(sqrt_p_x96); }
+    let numerator1 = U256::from(liquidity) << 96;
+    let denominator = numerator1.checked_add(amount_in_net.checked_mul(sqrt_p_x96)?)?;
+    mul_div_rounding_up(numerator1, sqrt_p_x96, denominator)
+}
+
+/// getNextSqrtPriceFromAmount1RoundingDown, adding: P + floor( amountIn * Q96 / L ).
+pub fn next_sqrt_from_input_one_for_zero(liquidity: u128, sqrt_p_x96: U256, amount_in_net: U256) -> Option<U256> {
+    if amount_in_net.is_zero() || liquidity == 0 { return Some(sqrt_p_x96); }
+    sqrt_p_x96.checked_add(mul_div(amount_in_net, q96(), U256::from(liquidity))?)
+}
+
+/// getNextSqrtPriceFromOutput: token1 out rounds the price down, token0 out rounds it up.
+pub fn next_sqrt_from_output(liquidity: u128, sqrt_p_x96: U256, amount_out: U256, zero_for_one: bool) -> Option<U256> {
+    if amount_out.is_zero() || liquidity == 0 { return Some(sqrt_p_x96); }
+    if zero_for_one {
+        // P - ceil( amountOut * Q96 / L )
+        sqrt_p_x96.checked_sub(mul_div_rounding_up(amount_out, q96(), U256::from(liquidity))?)
+    } else {
+        // ceil( (L<<96) * P / ( (L<<96) - amountOut * P ) )
+        let numerator1 = U256::from(liquidity) << 96;
+        let denominator = numerator1.checked_sub(amount_out.checked_mul(sqrt_p_x96)?)?;
+        mul_div_rounding_up(numerator1, sqrt_p_x96, denominator)
+    }
+}
+
+// ------------------------------- Swap math step -------------------------------
+
+/// (sqrtQ, amountIn, amountOut, fee) for one step, as uniswap_v4::compute_swap_step.
+pub type Step = (U256, U256, U256, U256);
+
+/// SwapMath.computeSwapStep, exact-input branch. A step that stops short of the target takes all
+/// that's left: amountIn is the remainder net of fee and the fee is the rest, as in V4 (V3
+/// re-derives amountIn from the price moved, which splits the same total differently).
+pub fn compute_swap_step(
+    sqrt_price_x96: U256,
+    sqrt_price_target_x96: U256,
+    liquidity: u128,
+    amount_remaining: U256,
+    fee_ppm: u32,
+    zero_for_one: bool,
+) -> Option<Step> {
+    let fee_complement = U256::from(FEE_DENOMINATOR_PPM.checked_sub(fee_ppm)?);
+
+    // amount remaining net of fee (if we partially move)
+    let amount_remaining_less_fee = mul_div(amount_remaining, fee_complement, U256::from(FEE_DENOMINATOR_PPM))?;
+
+    let amount_in_to_target = if zero_for_one {
+        amount0_delta(sqrt_price_target_x96, sqrt_price_x96, liquidity, true)?
+    } else {
+        amount1_delta(sqrt_price_x96, sqrt_price_target_x96, liquidity, true)?
+    };
+
+    let (sqrt_q, amount_in, fee_amt) = if amount_remaining_less_fee >= amount_in_to_target {
+        // fee = ceil( amountIn * fee / (1e6 - fee) )
+        let fee_amt = mul_div_rounding_up(amount_in_to_target, U256::from(fee_ppm), fee_complement)?;
+        (sqrt_price_target_x96, amount_in_to_target, fee_amt)
+    } else {
+        let sqrt_q = if zero_for_one {
+            next_sqrt_from_input_zero_for_one(liquidity, sqrt_price_x96, amount_remaining_less_fee)?
+        } else {
+            next_sqrt_from_input_one_for_zero(liquidity, sqrt_price_x96, amount_remaining_less_fee)?
+        };
+        (sqrt_q, amount_remaining_less_fee, amount_remaining - amount_remaining_less_fee)
+    };
+    let amount_out = if zero_for_one {
+        amount1_delta(sqrt_q, sqrt_price_x96, liquidity, false)?
+    } else {
+        amount0_delta(sqrt_price_x96, sqrt_q, liquidity, false)?
+    };
+    Some((sqrt_q, amount_in, amount_out, fee_amt))
+}
+
+/// SwapMath.computeSwapStep, exact-output branch: `amount_remaining` is the output still owed.
+pub fn compute_swap_step_exact_out(
+    sqrt_price_x96: U256,
+    sqrt_price_target_x96: U256,
+    liquidity: u128,
+    amount_remaining: U256,
+    fee_ppm: u32,
+    zero_for_one: bool,
+) -> Option<Step> {
+    // output available before the target
+    let out_to_target = if zero_for_one {
+        amount1_delta(sqrt_price_target_x96, sqrt_price_x96, liquidity, false)?
+    } else {
+        amount0_delta(sqrt_price_x96, sqrt_price_target_x96, liquidity, false)?
+    };
+    let (sqrt_q, amount_out) = if amount_remaining >= out_to_target {
+        (sqrt_price_target_x96, out_to_target)
+    } else {
+        (next_sqrt_from_output(liquidity, sqrt_price_x96, amount_remaining, zero_for_one)?, amount_remaining)
+    };
+    let amount_in = if zero_for_one {
+        amount0_delta(sqrt_q, sqrt_price_x96, liquidity, true)?
+    } else {
+        amount1_delta(sqrt_price_x96, sqrt_q, liquidity, true)?
+    };
+    // fee = ceil( amountIn * fee / (1e6 - fee) )
+    let fee_amt = mul_div_rounding_up(
+        amount_in,
+        U256::from(fee_ppm),
+        U256::from(FEE_DENOMINATOR_PPM.checked_sub(fee_ppm)?),
+    )?;
+    Some((sqrt_q, amount_in, amount_out, fee_amt))
+}
+
+// -------------------------------- Simulator ---------------------------------
+
+/// uniswap_v4::simulate_swap_bigint on fixed widths. `params` must already have passed
+/// uniswap_v4's checks (non-zero amount, limit on the right side of the price); None if the
+/// pool, the amount or any intermediate doesn't fit.
+pub fn simulate_swap(pool: &PoolState, params: &SwapParams, hook: &dyn HookFee) -> Option<SwapResult> {
+    let zero_for_one = matches!(params.direction, SwapDirection::ZeroForOne);
+    let exact_in = params.amount_specified.is_positive();
+    let mut amount_remaining = to_u256(&params.amount_specified.abs())?;
+    let mut sqrt_price = to_u256(&pool.sqrt_price_x96)?;
+    let mut liquidity = pool.liquidity.to_u128()?;
+    let mut current_tick = pool.tick;
+    let limit = to_u256(&params.sqrt_price_limit_x96)?;
+
+    let mut amount0_total = I256::zero();
+    let mut amount1_total = I256::zero();
+    let mut amount_to_protocol = U256::zero();
+    let mut ticks_crossed = 0usize;
+
+    let lp_fee_ppm = hook.lp_fee_ppm(pool, params);
+    let protocol_fee_ppm = pool.protocol_fee_ppm(zero_for_one);
+    while !amount_remaining.is_zero() && liquidity > 0 {
+        let hook_adj = hook.adjust_fee_ppm(pool, params, &to_bigint(amount_remaining));
+        let max_fee = FEE_DENOMINATOR_PPM - 1;
+        let step_lp_fee = min(max_fee, lp_fee_ppm.saturating_add(hook_adj));
+        let eff_fee_ppm = min(max_fee, combined_swap_fee_ppm(protocol_fee_ppm, step_lp_fee));
+
+        let (next_tick, has_next) = next_initialized_tick(&pool.ticks, current_tick, params.direction);
+        let sqrt_next = if has_next { get_sqrt_ratio_at_tick(next_tick) } else { default_limit(zero_for_one) };
+        let sqrt_target_bound = if zero_for_one { max(limit, sqrt_next) } else { min(limit, sqrt_next) };
+
+        let (sqrt_q, used_in, got_out, fee_amt) = if exact_in {
+            compute_swap_step(sqrt_price, sqrt_target_bound, liquidity, amount_remaining, eff_fee_ppm, zero_for_one)?
+        } else {
+            compute_swap_step_exact_out(sqrt_price, sqrt_target_bound, liquidity, amount_remaining, eff_fee_ppm, zero_for_one)?
+        };
+
+        let gross_in = used_in.checked_add(fee_amt)?;
+        let spent = I256::checked_from_sign_and_abs(Sign::Positive, gross_in)?;
+        let received = I256::checked_from_sign_and_abs(Sign::Positive, got_out)?;
+        if zero_for_one {
+            amount0_total = amount0_total.checked_sub(spent)?;    // spent token0 (gross)
+            amount1_total = amount1_total.checked_add(received)?; // received token1
+        } else {
+            amount1_total = amount1_total.checked_sub(spent)?;    // spent token1 (gross)
+            amount0_total = amount0_total.checked_add(received)?; // received token0
+        }
+        if protocol_fee_ppm > 0 {
+            // Pool.swap: the protocol's cut is on the gross input, the LPs keep the rest of the fee
+            let cut = if eff_fee_ppm == protocol_fee_ppm {
+                fee_amt
+            } else {
+                mul_div(gross_in, U256::from(protocol_fee_ppm), U256::from(FEE_DENOMINATOR_PPM))?
+            };
+            amount_to_protocol = amount_to_protocol.checked_add(cut)?;
+        }
+        amount_remaining = amount_remaining.checked_sub(if exact_in { gross_in } else { got_out })?;
+        sqrt_price = sqrt_q;
+
+        if has_next && sqrt_price == sqrt_next {
+            ticks_crossed += 1;
+            if let Some(ti) = pool.ticks.get(&next_tick) {
+                let net = ti.liquidity_net.to_i128()?;
+                // moving left subtracts liquidityNet, moving right adds it
+                let net = if zero_for_one { net.checked_neg()? } else { net };
+                liquidity = liquidity.checked_add_signed(net)?;
+            }
+            current_tick = if zero_for_one { next_tick - 1 } else { next_tick };
+        } else {
+            current_tick = get_tick_at_sqrt_ratio(sqrt_price);
+            break;
+        }
+    }
+
+    let sqrt_price_x96 = to_bigint(sqrt_price);
+    Some(SwapResult {
+        amount0: i256_to_bigint(amount0_total),
+        amount1: i256_to_bigint(amount1_total),
+        beyond_loaded_ticks: pool.past_loaded_ticks(&sqrt_price_x96),
+        sqrt_price_x96,
+        tick: current_tick,
+        liquidity: BigInt::from(liquidity),
+        crossed_ticks: ticks_crossed,
+        amount_to_protocol: to_bigint(amount_to_protocol),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::uniswap_v4 as reference;
+    use crate::math::uniswap_v4::{create_pool_with_real_data, simulate_swap_bigint, NoHook};
+    use ethers::types::Address;
+
+    /// xorshift64*, so the property tests are reproducible without a proptest dependency
+    struct Rng(u64);
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 >> 12;
+            self.0 ^= self.0 << 25;
+            self.0 ^= self.0 >> 27;
+            self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+        }
+        fn range(&mut self, lo: i64, hi: i64) -> i64 {
+            lo + (self.next() % (hi - lo + 1) as u64) as i64
+        }
+        /// Uniform-ish in [0, 2^bits), with the width itself random so small values show up too
+        fn u256(&mut self, max_bits: usize) -> U256 {
+            let bits = self.range(1, max_bits as i64) as usize;
+            let v = U256([self.next(), self.next(), self.next(), self.next()]);
+            if bits >= 256 { v } else { v & ((U256::one() << bits) - 1) }
+        }
+        fn u128(&mut self, max_bits: usize) -> u128 {
+            self.u256(max_bits.min(128)).as_u128()
+        }
+        fn sqrt_price(&mut self) -> U256 {
+            let tick = self.range(MIN_TICK as i64, MAX_TICK as i64 - 1) as i32;
+            let lo = get_sqrt_ratio_at_tick(tick);
+            let span = get_sqrt_ratio_at_tick(tick + 1) - lo;
+            lo + U256::from(self.next()) % span
+        }
+    }
+
+    fn big(v: U256) -> BigInt { to_bigint(v) }
+
+    #[test]
+    fn tick_math_hits_the_contract_bounds() {
+        // TickMath.MIN_SQRT_PRICE / MAX_SQRT_PRICE
+        assert_eq!(get_sqrt_ratio_at_tick(MIN_TICK), U256::from(4_295_128_739u64));
+        assert_eq!(
+            get_sqrt_ratio_at_tick(MAX_TICK),
+            U256::from_dec_str("1461446703485210103287273052203988822378723970342").unwrap(),
+        );
+        assert_eq!(get_sqrt_ratio_at_tick(0), q96());
+    }
+
+    #[test]
+    fn tick_math_matches_bigint() {
+        let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+        let mut ticks = vec![MIN_TICK, MIN_TICK + 1, -1, 0, 1, MAX_TICK - 1, MAX_TICK];
+        ticks.extend((0..400).map(|_| rng.range(MIN_TICK as i64, MAX_TICK as i64) as i32));
+        for tick in ticks {
+            assert_eq!(big(get_sqrt_ratio_at_tick(tick)), reference::get_sqrt_ratio_at_tick(tick), "tick {}", tick);
+        }
+        for _ in 0..40 {
+            let p = rng.sqrt_price();
+            assert_eq!(get_tick_at_sqrt_ratio(p), reference::get_tick_at_sqrt_ratio(&big(p)), "sqrt {}", p);
+        }
+        assert_eq!(default_limit(true), to_u256(&reference::default_limit(SwapDirection::ZeroForOne)).unwrap());
+        assert_eq!(default_limit(false), to_u256(&reference::default_limit(SwapDirection::OneForZero)).unwrap());
+    }
+
+    #[test]
+    fn sqrt_price_math_matches_bigint() {
+        let mut rng = Rng(0xdead_beef_cafe_f00d);
+        for _ in 0..2_000 {
+            let (a, b) = (rng.sqrt_price(), rng.sqrt_price());
+            let l = rng.u128(128);
+            let lb = BigInt::from(l);
+            for round_up in [false, true] {
+                assert_eq!(amount0_delta(a, b, l, round_up).map(big), Some(reference::amount0_delta(&big(a), &big(b), &lb, round_up)));
+                assert_eq!(amount1_delta(a, b, l, round_up).map(big), Some(reference::amount1_delta(&big(a), &big(b), &lb, round_up)));
+            }
+
+            // Wherever the fixed-width version doesn't overflow it must agree to the unit
+            let amount = rng.u256(160);
+            if let Some(q) = next_sqrt_from_input_zero_for_one(l, a, amount) {
+                assert_eq!(big(q), reference::next_sqrt_from_input_zero_for_one(&lb, &big(a), &big(amount)));
+            }
+            if let Some(q) = next_sqrt_from_input_one_for_zero(l, a, amount) {
+                assert_eq!(big(q), reference::next_sqrt_from_input_one_for_zero(&lb, &big(a), &big(amount)));
+            }
+            for zero_for_one in [false, true] {
+                if let Some(q) = next_sqrt_from_output(l, a, amount, zero_for_one) {
+                    assert_eq!(big(q), reference::next_sqrt_from_output(&lb, &big(a), &big(amount), zero_for_one));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn swap_math_matches_bigint() {
+        let mut rng = Rng(0x0123_4567_89ab_cdef);
+        let mut checked = 0;
+        for _ in 0..2_000 {
+            let (p, target) = (rng.sqrt_price(), rng.sqrt_price());
+            let zero_for_one = target < p;
+            let l = rng.u128(128);
+            let remaining = rng.u256(200);
+            let fee = [0, 100, 500, 3_000, 10_000, 999_999][rng.range(0, 5) as usize];
+            let args = (&big(p), &big(target), &BigInt::from(l), &big(remaining));
+            let as_big = |s: Step| (big(s.0), big(s.1), big(s.2), big(s.3));
+            if let Some(step) = compute_swap_step(p, target, l, remaining, fee, zero_for_one) {
+                assert_eq!(as_big(step), reference::compute_swap_step(args.0, args.1, args.2, args.3, fee, zero_for_one));
+                checked += 1;
+            }
+            if let Some(step) = compute_swap_step_exact_out(p, target, l, remaining, fee, zero_for_one) {
+                assert_eq!(as_big(step), reference::compute_swap_step_exact_out(args.0, args.1, args.2, args.3, fee, zero_for_one));
+                checked += 1;
+            }
+        }
+        assert!(checked > 3_000, "most random steps fit fixed widths ({} did)", checked);
+    }
+
+    #[test]
+    fn partial_exact_in_steps_match_the_contract() {
+        // Cases from v4-core's SwapMath tests. From 1:1 with the target out of reach, 1e18 in at 600 pips
+        let q96 = U256::one() << 96;
+        let target = next_sqrt_from_input_one_for_zero(2_000_000_000_000_000_000, q96, U256::exp10(21)).unwrap();
+        let (sqrt_q, amount_in, amount_out, fee) =
+            compute_swap_step(q96, target, 2_000_000_000_000_000_000, U256::exp10(18), 600, false).unwrap();
+        assert_eq!(amount_in, U256::from(999_400_000_000_000_000u64));
+        assert_eq!(fee, U256::from(600_000_000_000_000u64));
+        assert_eq!(amount_out, U256::from(666_399_946_655_997_866u64));
+        assert!(sqrt_q < target);
+        // Too little to move the price a unit: the fee is what the swap took beyond its net input
+        let step = compute_swap_step(
+            U256::from(2413),
+            U256::from(79_887_613_182_836_312u64),
+            1_985_041_575_832_132_834_610_021_537_970,
+            U256::from(10),
+            1872,
+            false,
+        );
+        assert_eq!(step, Some((U256::from(2413), U256::from(9), U256::zero(), U256::one())));
+    }
+
+    fn random_pool(rng: &mut Rng) -> PoolState {
+        let spacing = [1, 10, 60, 200][rng.range(0, 3) as usize];
+        let tick = rng.range(-300_000, 300_000) as i32;
+        let mut ticks = Vec::new();
+        let mut liquidity = BigInt::from(0);
+        for _ in 0..rng.range(1, 6) {
+            // Positions of random width around the price, on the spacing grid
+            let l = BigInt::from(rng.u128(100) + 1);
+            let lower = (tick - rng.range(1, 5_000) as i32) / spacing * spacing;
+            let upper = (tick + rng.range(1, 5_000) as i32) / spacing * spacing + spacing;
+            ticks.push((lower, l.clone()));
+            ticks.push((upper, -l.clone()));
+            liquidity += l;
+        }
+        let sqrt = get_sqrt_ratio_at_tick(tick) + U256::from(rng.next());
+        let mut pool = create_pool_with_real_data(
+            Address::from([1; 20]), Address::from([2; 20]), 3_000, spacing, Address::zero(),
+            big(sqrt), tick, liquidity, ticks,
+        );
+        pool.protocol_fee = if rng.range(0, 1) == 1 { (rng.range(0, 1_000) as u32) << 12 | rng.range(0, 1_000) as u32 } else { 0 };
+        pool
+    }
+
+    #[test]
+    fn swaps_match_bigint() {
+        let mut rng = Rng(0x5151_2020_abcd_0001);
+        for i in 0..60 {
+            let pool = random_pool(&mut rng);
+            let direction = if i % 2 == 0 { SwapDirection::ZeroForOne } else { SwapDirection::OneForZero };
+            let magnitude = big(rng.u256(90) + 1);
+            let amount_specified = if i % 4 < 2 { magnitude } else { -magnitude };
+            let params = SwapParams {
+                direction,
+                amount_specified,
+                sqrt_price_limit_x96: reference::default_limit(direction),
+                fee_ppm: [100, 500, 3_000, 10_000][rng.range(0, 3) as usize],
+            };
+            let fixed = simulate_swap(&pool, &params, &NoHook).expect("random pools fit fixed widths");
+            let bigint = simulate_swap_bigint(&pool, &params, &NoHook).expect("valid swap");
+            assert_eq!(
+                (&fixed.amount0, &fixed.amount1, &fixed.sqrt_price_x96, fixed.tick, &fixed.liquidity, fixed.crossed_ticks, &fixed.amount_to_protocol),
+                (&bigint.amount0, &bigint.amount1, &bigint.sqrt_price_x96, bigint.tick, &bigint.liquidity, bigint.crossed_ticks, &bigint.amount_to_protocol),
+                "swap {} on {:?}", i, params,
+            );
+        }
+    }
+
+    #[test]
+    fn overflowing_state_is_left_to_bigint() {
+        let mut rng = Rng(7);
+        let mut pool = random_pool(&mut rng);
+        pool.liquidity = BigInt::from(u128::MAX) + 1; // wider than uint128
+        let params = SwapParams {
+            direction: SwapDirection::ZeroForOne,
+            amount_specified: BigInt::from(10u64.pow(18)),
+            sqrt_price_limit_x96: reference::default_limit(SwapDirection::ZeroForOne),
+            fee_ppm: 3_000,
+        };
+        assert!(simulate_swap(&pool, &params, &NoHook).is_none());
+        // ...and the public entry point still answers through the BigInt path
+        let res = reference::simulate_swap(&pool, &params, &NoHook).unwrap();
+        assert_eq!(res.amount0, simulate_swap_bigint(&pool, &params, &NoHook).unwrap().amount0);
+    }
+}
@@ -0,0 +1,286 @@
+// src/math/venue.rs
+//
+// A pool the optimizer can trade on, whatever its curve. Quotes are raw token units oriented
+// by token0/token1, so a pool state needs nothing beyond itself to implement this; mapping
+// base/quote and decimals onto it is the optimizer's job (engine::optimizer::PairVenue).
+// - Exact-in runs the pool's own swap math
+// - Exact-out too: one tick walk on concentrated-liquidity pools, getAmountIn on volatile pairs;
+//   stable pairs have no closed form, so it searches the exact-in quote for the smallest input
+//   that reaches the target
+// - Spot price is token1 per token0 in raw units, before fees
+
+use std::fmt::Debug;
+
+use ethers::types::U256;
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+
+use crate::math::amount::u256_to_f64;
+use crate::math::aerodrome_volatile::{map_direction, volatile_amount_in, SwapDirection as AeroDir, VolatilePairState};
+use crate::math::uniswap_v4::{default_limit, simulate_swap, PoolState, SwapDirection as UniDir, SwapParams, SwapResult};
+use crate::math::uniswap_v4_fixed::{to_bigint, to_u256};
+
+pub trait Venue: Debug + Send + Sync {
+    /// Output for exactly `amount_in` of token0 (`zero_for_one`) or token1.
+    fn quote_exact_in(&self, zero_for_one: bool, amount_in: U256) -> Option<U256>;
+    /// Input needed to receive at least `amount_out`; None if the pool can't deliver it.
+    fn quote_exact_out(&self, zero_for_one: bool, amount_out: U256) -> Option<U256>;
+    /// Marginal token1-per-token0 price, raw units, before fees.
+    fn spot_price(&self) -> f64;
+    /// Gas of one swap through this venue.
+    fn gas_units(&self) -> u64;
+    fn chain_id(&self) -> u64;
+}
+
+/// Lossy f64 -> U256 (saturating), for search seeds only.
+fn f64_to_u256(v: f64) -> U256 {
+    const TWO_128: f64 = 340_282_366_920_938_463_463_374_607_431_768_211_456.0;
+    if !v.is_finite() || v <= 0.0 {
+        U256::zero()
+    } else if v < TWO_128 {
+        U256::from(v as u128)
+    } else if v < TWO_128 * TWO_128 {
+        U256::from((v / TWO_128) as u128) << 128
+    } else {
+        U256::MAX
+    }
+}
+
+/// Smallest input whose exact-in `quote` reaches `amount_out`. `spot_out_per_in` seeds the
+/// bracket: at spot, before fees and impact, the input is a lower bound, so the bracket steps
+/// up from there (1/64 of the seed, doubling each time) until the quote gets there; None if it
+/// never does, or not by `max_in`.
+fn exact_out_by_search(
+    amount_out: U256,
+    spot_out_per_in: f64,
+    max_in: U256,
+    quote: impl Fn(U256) -> Option<U256>,
+) -> Option<U256> {
+    if amount_out.is_zero() {
+        return Some(U256::zero());
+    }
+    let reaches = |amount_in: U256| quote(amount_in).is_some_and(|out| out >= amount_out);
+
+    let seed = if spot_out_per_in.is_finite() && spot_out_per_in > 0.0 {
+        f64_to_u256(u256_to_f64(amount_out) / spot_out_per_in)
+    } else {
+        amount_out
+    };
+    let mut lo = U256::zero(); // largest input known to fall short
+    let mut hi = seed.max(U256::one());
+    let mut step = (hi >> 6).max(U256::one());
+    let mut steps = 0;
+    while !reaches(hi) {
+        if steps == 96 || hi >= max_in || hi > U256::MAX - step {
+            return None;
+        }
+        lo = hi;
+        hi += step;
+        step = step.saturating_mul(U256::from(2u8));
+        steps += 1;
+    }
+
+    loop {
+        let gap = hi - lo;
+        if gap <= U256::one() {
+            return Some(hi);
+        }
+        let mid = lo + (gap >> 1);
+        if reaches(mid) { hi = mid } else { lo = mid }
+    }
+}
+
+impl PoolState {
+    /// Swap with no price limit; `amount_specified` > 0 is exact input, < 0 exact output.
+    /// None past the loaded ticks, where the fill would be priced on unknown liquidity.
+    fn swap_unbounded(&self, zero_for_one: bool, amount_specified: BigInt) -> Option<SwapResult> {
+        let direction = if zero_for_one { UniDir::ZeroForOne } else { UniDir::OneForZero };
+        let params = SwapParams {
+            direction,
+            amount_specified,
+            sqrt_price_limit_x96: default_limit(direction),
+            fee_ppm: self.key.fee_ppm,
+        };
+        simulate_swap(self, &params, &*self.hook_fee()).ok().filter(|r| !r.beyond_loaded_ticks)
+    }
+}
+
+impl Venue for PoolState {
+    fn quote_exact_in(&self, zero_for_one: bool, amount_in: U256) -> Option<U256> {
+        let res = self.swap_unbounded(zero_for_one, to_bigint(amount_in))?;
+        to_u256(if zero_for_one { &res.amount1 } else { &res.amount0 })
+    }
+
+    fn quote_exact_out(&self, zero_for_one: bool, amount_out: U256) -> Option<U256> {
+        if amount_out.is_zero() {
+            return Some(U256::zero());
+        }
+        let wanted = to_bigint(amount_out);
+        let res = self.swap_unbounded(zero_for_one, -wanted.clone())?;
+        let (paid, received) = if zero_for_one { (-res.amount0, res.amount1) } else { (-res.amount1, res.amount0) };
+        if received < wanted {
+            return None; // ran out of liquidity
+        }
+        to_u256(&paid)
+    }
+
+    fn spot_price(&self) -> f64 {
+        let sqrt = self.sqrt_price_x96.to_f64().unwrap_or(0.0) / 2f64.powi(96);
+        sqrt * sqrt
+    }
+
+    fn gas_units(&self) -> u64 {
+        self.gas_units
+    }
+
+    fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+}
+
+impl Venue for VolatilePairState {
+    fn quote_exact_in(&self, zero_for_one: bool, amount_in: U256) -> Option<U256> {
+        let direction = if zero_for_one { AeroDir::ZeroForOne } else { AeroDir::OneForZero };
+        Some(self.amount_out(direction, amount_in))
+    }
+
+    fn quote_exact_out(&self, zero_for_one: bool, amount_out: U256) -> Option<U256> {
+        if !self.stable {
+            let direction = if zero_for_one { AeroDir::ZeroForOne } else { AeroDir::OneForZero };
+            let (reserve_in, reserve_out, _, _) = map_direction(self, direction);
+            return volatile_amount_in(amount_out, reserve_in, reserve_out, self.fee_bps);
+        }
+        // The pool can't pay out its whole reserve, and no trade it could fill needs more than
+        // 2^32 times the input reserve (the curve is that flat until the output side is drained)
+        let direction = if zero_for_one { AeroDir::ZeroForOne } else { AeroDir::OneForZero };
+        let (reserve_in, reserve_out, _, _) = map_direction(self, direction);
+        if amount_out >= reserve_out {
+            return None;
+        }
+        let max_in = reserve_in.saturating_mul(U256::one() << 32);
+        let spot = Venue::spot_price(self);
+        let out_per_in = if zero_for_one { spot } else { 1.0 / spot };
+        exact_out_by_search(amount_out, out_per_in, max_in, |a| self.quote_exact_in(zero_for_one, a))
+    }
+
+    fn spot_price(&self) -> f64 {
+        let human = VolatilePairState::spot_price(self, AeroDir::ZeroForOne);
+        human * 10f64.powi(self.decimals1 as i32 - self.decimals0 as i32)
+    }
+
+    fn gas_units(&self) -> u64 {
+        self.gas_units
+    }
+
+    fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::uniswap_v4::{create_pool_with_real_data, get_sqrt_ratio_at_tick, price_from_tick};
+    use ethers::types::Address;
+
+    fn pair(stable: bool) -> VolatilePairState {
+        VolatilePairState {
+            token0: Address::from([0x01; 20]),
+            token1: Address::from([0x02; 20]),
+            reserve0: U256::from(1_000u64) * U256::exp10(18), // 1000 WETH
+            reserve1: U256::from(3_400_000u64) * U256::exp10(6), // 3.4M USDC
+            decimals0: 18,
+            decimals1: 6,
+            fee_bps: 30,
+            block_number: 0,
+            block_timestamp: 0,
+            stable,
+            chain_id: 8453,
+            gas_units: 185_000,
+        }
+    }
+
+    fn cl_pool() -> PoolState {
+        let tick = -197_100; // ~2750 USDC per WETH (token0 = WETH, 18/6 decimals)
+        let liquidity = BigInt::from(10u128.pow(20));
+        let mut pool = create_pool_with_real_data(
+            Address::from([0x01; 20]), Address::from([0x02; 20]), 500, 10, Address::zero(),
+            get_sqrt_ratio_at_tick(tick), tick, liquidity.clone(),
+            vec![(tick - 20_000, liquidity.clone()), (tick + 20_000, -liquidity)],
+        );
+        pool.chain_id = 1;
+        pool.gas_units = 140_000;
+        pool
+    }
+
+    #[test]
+    fn test_volatile_exact_out_is_the_smallest_sufficient_input() {
+        let p = pair(false);
+        let want = U256::exp10(18); // 1 WETH out, paying USDC (token1)
+        let cost = p.quote_exact_out(false, want).expect("pool holds 1000 WETH");
+        assert!(p.quote_exact_in(false, cost).unwrap() >= want);
+        assert!(p.quote_exact_in(false, cost - 1).unwrap() < want);
+
+        // More than the reserve can never be bought
+        assert_eq!(p.quote_exact_out(false, p.reserve0 + 1), None);
+        assert_eq!(p.quote_exact_out(true, U256::zero()), Some(U256::zero()));
+
+        // Stable pairs still search their curve
+        let s = pair(true);
+        let want = U256::from(1_000u64) * U256::exp10(6); // 1000 USDC out, paying WETH (token0)
+        let cost = s.quote_exact_out(true, want).expect("pool holds 3.4M USDC");
+        assert!(s.quote_exact_in(true, cost).unwrap() >= want);
+        assert!(s.quote_exact_in(true, cost - 1).unwrap() < want);
+    }
+
+    #[test]
+    fn test_stable_exact_out_past_the_reserve() {
+        // Asking a stable pool for its whole reserve or more: refused, not an overflow panic
+        let s = pair(true);
+        assert_eq!(s.quote_exact_out(true, s.reserve1), None);
+        assert_eq!(s.quote_exact_out(true, s.reserve1 * 2), None);
+        assert_eq!(s.quote_exact_out(false, s.reserve0 + 1), None);
+
+        // Nearly all of it: the search may give up, but never panics
+        let _ = s.quote_exact_out(true, s.reserve1 - 1);
+
+        // Inputs big enough to overflow the invariant quote 0, as the contract reverts
+        assert_eq!(s.quote_exact_in(true, U256::one() << 200), Some(U256::zero()));
+        assert_eq!(s.quote_exact_in(false, U256::MAX), Some(U256::zero()));
+    }
+
+    #[test]
+    fn test_spot_prices_are_raw_token1_per_token0() {
+        // 3400 USDC per WETH -> 3400e6 / 1e18 raw
+        let spot = Venue::spot_price(&pair(false));
+        assert!((spot / 3.4e-9 - 1.0).abs() < 1e-9, "volatile spot {}", spot);
+
+        let pool = cl_pool();
+        let human = pool.spot_price() * 1e12;
+        assert!((human / price_from_tick(pool.tick, 18, 6) - 1.0).abs() < 1e-9);
+        assert_eq!((pool.chain_id(), pool.gas_units()), (1, 140_000));
+    }
+
+    #[test]
+    fn test_cl_exact_out_is_native() {
+        let pool = cl_pool();
+        let want = U256::from(5u64) * U256::exp10(18); // 5 WETH out for USDC
+        let cost = pool.quote_exact_out(false, want).expect("deep pool");
+        assert!(pool.quote_exact_in(false, cost).unwrap() >= want);
+        let tighter = cost - cost / U256::from(1_000_000u64); // 1 ppm less falls short
+        assert!(pool.quote_exact_in(false, tighter).unwrap() < want);
+
+        // Past the last position there is nothing left to buy
+        assert_eq!(pool.quote_exact_out(false, U256::MAX >> 128), None);
+        assert_eq!(pool.quote_exact_out(true, U256::zero()), Some(U256::zero()));
+    }
+
+    #[test]
+    fn test_seed_conversions() {
+        assert_eq!(f64_to_u256(u256_to_f64(U256::from(123_456u64))), U256::from(123_456u64));
+        let big = U256::one() << 200;
+        let back = f64_to_u256(u256_to_f64(big));
+        assert!(back > big - (big >> 40) && back < big + (big >> 40));
+        assert_eq!(f64_to_u256(-1.0), U256::zero());
+    }
+}
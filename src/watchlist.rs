@@ -0,0 +1,699 @@
+// src/watchlist.rs
+//
+// Pairs the service watches, loaded from a TOML file (WATCHLIST_PATH, default `watchlist.toml`).
+// Each pair names its Uniswap V4 PoolKey on Ethereum, its Aerodrome pool on Base (volatile,
+// stable or Slipstream) and the trade sizes the API accepts for it. Either leg can instead be priced on a
+// Uniswap V3 pool of that chain. The V4 pool can also live on Arbitrum, Optimism or Unichain, or on
+// Base, making the pair a same-chain arbitrage against Aerodrome. Without a file we fall back to the
+// single ETH/USDC pair described by the env config, so existing deployments keep working unchanged.
+
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::path::Path;
+
+use crate::chain::registry::{
+    BridgeAsset, CHAIN_ID_ARBITRUM, CHAIN_ID_BASE, CHAIN_ID_ETHEREUM, CHAIN_ID_OPTIMISM, CHAIN_ID_UNICHAIN,
+};
+use crate::config::Config;
+use crate::math::uniswap_v4::DYNAMIC_FEE_FLAG;
+
+/// `address(0)`: native ETH as a V4 currency, and "no hooks" as a V4 hooks address.
+pub const ZERO_ADDRESS: &str = "0x0000000000000000000000000000000000000000";
+
+fn zero_address() -> String {
+    ZERO_ADDRESS.to_string()
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Watchlist {
+    #[serde(rename = "pair")]
+    pub pairs: Vec<PairConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PairConfig {
+    /// Identifier used by the API `pair` parameter, e.g. "ETH-USDC".
+    pub name: String,
+    pub uniswap_v4: UniswapV4PairConfig,
+    pub aerodrome: AerodromePairConfig,
+    /// V3 pools priced in place of the V4 pool (Ethereum) or the Aerodrome pool (Base).
+    #[serde(default)]
+    pub uniswap_v3: Vec<UniswapV3PairConfig>,
+    /// Extra pools the optimizer may route either leg through.
+    #[serde(default)]
+    pub routing: Option<RoutingConfig>,
+    /// More pools of the same pair the split optimizer may spread either leg over.
+    #[serde(default)]
+    pub split: Option<SplitConfig>,
+    #[serde(default)]
+    pub cex: CexPricing,
+    /// Tokens Across may rebalance a cross-chain pair with; by default the ones that are WETH
+    /// (native ETH included) or USDC on both legs' chains.
+    #[serde(default)]
+    pub bridge: Option<Vec<PairToken>>,
+    #[serde(default)]
+    pub trade_size: TradeSizeBounds,
+}
+
+/// Coinbase currencies that value the pair in USD: `base` prices the rebalancing bridge amounts
+/// and the reference price, `quote` converts the legs' quote-token profit before gas and bridge
+/// fees (in USD) are netted off. "USD" counts as exactly $1.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct CexPricing {
+    pub base: String,
+    pub quote: String,
+}
+
+impl Default for CexPricing {
+    fn default() -> Self {
+        Self { base: "ETH".to_string(), quote: "USD".to_string() }
+    }
+}
+
+/// One side of a pair: the token traded (`base`) or the one it is priced in (`quote`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PairToken {
+    Base,
+    Quote,
+}
+
+/// Uniswap V4 PoolKey (currencies are sorted when the poolId is derived), on Ethereum unless
+/// `chain` says otherwise. Hooked pools set `hooks`; dynamic-fee pools set `fee = 0x800000` and
+/// are priced at slot0's lpFee.
+#[derive(Debug, Clone, Deserialize)]
+pub struct UniswapV4PairConfig {
+    #[serde(default)]
+    pub chain: Chain,
+    #[serde(default = "zero_address")]
+    pub base_token: String, // address(0) = native ETH
+    pub quote_token: String,
+    pub fee: u32,           // ppm, e.g. 3000 = 0.30%
+    pub tick_spacing: i32,
+    #[serde(default = "zero_address")]
+    pub hooks: String,
+    /// Fixed LP fee (ppm) the hook's beforeSwap overrides every swap with; dynamic-fee pools only.
+    #[serde(default)]
+    pub before_swap_fee: Option<u32>,
+}
+
+impl UniswapV4PairConfig {
+    fn validate(&self) -> Result<(), &'static str> {
+        if self.tick_spacing <= 0 {
+            return Err("tick_spacing must be positive");
+        }
+        let dynamic = self.fee == DYNAMIC_FEE_FLAG;
+        if !dynamic && self.fee >= 1_000_000 {
+            return Err("uniswap_v4.fee must be below 1000000 ppm, or 0x800000 for a dynamic-fee pool");
+        }
+        let hooked = self.hooks.parse::<ethers::types::Address>().is_ok_and(|a| !a.is_zero());
+        if dynamic && !hooked {
+            return Err("a dynamic-fee pool needs its hooks address");
+        }
+        match self.before_swap_fee {
+            Some(_) if !dynamic => Err("before_swap_fee only applies to dynamic-fee pools"),
+            Some(fee) if fee >= 1_000_000 => Err("before_swap_fee must be below 1000000 ppm"),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Aerodrome pool on Base: volatile (x*y=k) by default, stable (x³y+xy³), or a Slipstream
+/// concentrated-liquidity pool when `tick_spacing` is set.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AerodromePairConfig {
+    pub base_token: String,
+    pub quote_token: String,
+    /// Pool address; discovered through the factory when omitted.
+    #[serde(default)]
+    pub pool: Option<String>,
+    /// Stable pool, for stablecoin and LST pairs.
+    #[serde(default)]
+    pub stable: bool,
+    /// Slipstream pool with this tick spacing (the CL factory's pool key).
+    #[serde(default)]
+    pub tick_spacing: Option<i32>,
+}
+
+/// Chain a venue lives on. Aerodrome is Base-only; V4 pools can be on any of these.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Chain {
+    #[default]
+    Ethereum,
+    Base,
+    Arbitrum,
+    Optimism,
+    Unichain,
+}
+
+impl Chain {
+    pub fn chain_id(self) -> u64 {
+        match self {
+            Chain::Ethereum => CHAIN_ID_ETHEREUM,
+            Chain::Base => CHAIN_ID_BASE,
+            Chain::Arbitrum => CHAIN_ID_ARBITRUM,
+            Chain::Optimism => CHAIN_ID_OPTIMISM,
+            Chain::Unichain => CHAIN_ID_UNICHAIN,
+        }
+    }
+}
+
+/// Uniswap V3 pool standing in for the pair's venue on `chain`. V3 has no native ETH, so
+/// ETH pairs name WETH as the base token.
+#[derive(Debug, Clone, Deserialize)]
+pub struct UniswapV3PairConfig {
+    pub chain: Chain,
+    pub base_token: String,
+    pub quote_token: String,
+    pub fee: u32, // ppm, e.g. 500 = 0.05%
+    /// Pool address; discovered through the chain's V3 factory when omitted.
+    #[serde(default)]
+    pub pool: Option<String>,
+}
+
+fn default_max_hops() -> usize {
+    2
+}
+
+/// Pools a leg may route through on top of its own pool, e.g. ETH→USDT→USDC. Routes run
+/// between the leg's base and quote tokens, so intermediate pools must use the same token
+/// addresses (native ETH on V4, WETH elsewhere).
+#[derive(Debug, Clone, Deserialize)]
+pub struct RoutingConfig {
+    /// Longest route, in swaps.
+    #[serde(default = "default_max_hops")]
+    pub max_hops: usize,
+    /// V4 pools on Ethereum, keyed like `[pair.uniswap_v4]`.
+    #[serde(default)]
+    pub uniswap_v4: Vec<UniswapV4PairConfig>,
+    /// Volatile or stable Aerodrome pools on Base (Slipstream is not routed).
+    #[serde(default)]
+    pub aerodrome: Vec<AerodromePairConfig>,
+}
+
+/// Pools trading the pair itself, next to its two legs: split mode fills each leg across the
+/// legs' pools and these. Tokens must be the leg's own (WETH for V3).
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SplitConfig {
+    /// V4 pools on the pair's V4 chain (other fee tiers or hooks), keyed like `[pair.uniswap_v4]`.
+    #[serde(default)]
+    pub uniswap_v4: Vec<UniswapV4PairConfig>,
+    /// V3 pools on either leg's chain, keyed like `[[pair.uniswap_v3]]`.
+    #[serde(default)]
+    pub uniswap_v3: Vec<UniswapV3PairConfig>,
+    /// Aerodrome pools on Base: volatile, stable or Slipstream, keyed like `[pair.aerodrome]`.
+    #[serde(default)]
+    pub aerodrome: Vec<AerodromePairConfig>,
+}
+
+/// Trade-size bounds in base-token units.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct TradeSizeBounds {
+    pub min: f64,
+    pub max: f64,
+    /// Used by the fixed-size endpoint when the query omits `trade_size_eth`.
+    pub default: f64,
+}
+
+impl Default for TradeSizeBounds {
+    fn default() -> Self {
+        Self { min: 0.0, max: 1000.0, default: 10.0 }
+    }
+}
+
+impl TradeSizeBounds {
+    pub fn clamp(&self, size: f64) -> f64 {
+        if size.is_nan() { self.default } else { size.clamp(self.min, self.max) }
+    }
+}
+
+impl Watchlist {
+    /// Read the watchlist file if it exists, otherwise build the single env-configured pair.
+    pub fn load_or_default(config: &Config) -> Result<Self, Box<dyn std::error::Error>> {
+        let path = Path::new(&config.watchlist_path);
+        if path.exists() {
+            log::info!("Loading watchlist from {}", path.display());
+            Self::load(path)
+        } else {
+            log::info!("No watchlist at {}; using the env-configured pair", path.display());
+            Ok(Self::from_config(config))
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let raw = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read watchlist {}: {}", path.display(), e))?;
+        Self::parse(&raw)
+    }
+
+    pub fn parse(raw: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let watchlist: Watchlist = toml::from_str(raw)?;
+        watchlist.validate()?;
+        Ok(watchlist)
+    }
+
+    /// The pair the service priced before watchlists existed: native ETH/USDC at 0.30%.
+    pub fn from_config(config: &Config) -> Self {
+        let token = |chain_id: u64, asset: BridgeAsset| {
+            config.chain(chain_id).map_or_else(String::new, |c| format!("{:?}", c.bridge_token(asset)))
+        };
+        Watchlist {
+            pairs: vec![PairConfig {
+                name: "ETH-USDC".to_string(),
+                uniswap_v4: UniswapV4PairConfig {
+                    chain: Chain::Ethereum,
+                    base_token: zero_address(),
+                    quote_token: token(CHAIN_ID_ETHEREUM, BridgeAsset::Usdc),
+                    fee: 3000,
+                    tick_spacing: 60,
+                    hooks: zero_address(),
+                    before_swap_fee: None,
+                },
+                aerodrome: AerodromePairConfig {
+                    base_token: token(CHAIN_ID_BASE, BridgeAsset::Weth),
+                    quote_token: token(CHAIN_ID_BASE, BridgeAsset::Usdc),
+                    pool: config.aerodrome_weth_usdc_volatile_pool.clone(),
+                    stable: false,
+                    tick_spacing: None,
+                },
+                uniswap_v3: Vec::new(),
+                routing: None,
+                split: None,
+                cex: CexPricing::default(),
+                bridge: None,
+                trade_size: TradeSizeBounds::default(),
+            }],
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.pairs.is_empty() {
+            return Err("watchlist has no [[pair]] entries".into());
+        }
+        let mut seen = HashSet::new();
+        for pair in &self.pairs {
+            // Pairs are looked up case-insensitively, so "eth-usdc" would shadow "ETH-USDC"
+            if !seen.insert(pair.name.to_ascii_lowercase()) {
+                return Err(format!("duplicate pair name '{}'", pair.name));
+            }
+            let b = &pair.trade_size;
+            if !(b.min >= 0.0 && b.min <= b.default && b.default <= b.max) {
+                return Err(format!(
+                    "pair '{}': trade_size must satisfy 0 <= min <= default <= max",
+                    pair.name
+                ));
+            }
+            if pair.cex.base.trim().is_empty() || pair.cex.quote.trim().is_empty() {
+                return Err(format!("pair '{}': cex.base and cex.quote must name Coinbase currencies", pair.name));
+            }
+            if let Some(bridge) = &pair.bridge {
+                if bridge.is_empty() || bridge.iter().collect::<HashSet<_>>().len() != bridge.len() {
+                    return Err(format!("pair '{}': bridge lists \"base\" and/or \"quote\", each once", pair.name));
+                }
+            }
+            pair.uniswap_v4.validate().map_err(|e| format!("pair '{}': {}", pair.name, e))?;
+            match pair.aerodrome.tick_spacing {
+                Some(_) if pair.aerodrome.stable => {
+                    return Err(format!("pair '{}': an Aerodrome pool is either stable or Slipstream", pair.name));
+                }
+                Some(ts) if ts <= 0 => {
+                    return Err(format!("pair '{}': aerodrome.tick_spacing must be positive", pair.name));
+                }
+                _ => {}
+            }
+            // A V4 pool off Ethereum leaves no Ethereum leg for a V3 pool to replace or for
+            // Ethereum routing pools to extend (on Base it is a same-chain pair with Aerodrome)
+            if pair.uniswap_v4.chain != Chain::Ethereum {
+                if !pair.uniswap_v3.is_empty() {
+                    return Err(format!("pair '{}': a uniswap_v4 pool off Ethereum cannot be combined with uniswap_v3 pools", pair.name));
+                }
+                if pair.routing.as_ref().is_some_and(|r| !r.uniswap_v4.is_empty()) {
+                    return Err(format!("pair '{}': routing.uniswap_v4 pools extend the Ethereum leg, which a uniswap_v4 pool off Ethereum has none of", pair.name));
+                }
+            }
+            let mut chains = HashSet::new();
+            for v3 in &pair.uniswap_v3 {
+                if !matches!(v3.chain, Chain::Ethereum | Chain::Base) {
+                    return Err(format!("pair '{}': uniswap_v3 pools replace the Ethereum or Base leg", pair.name));
+                }
+                if !chains.insert(v3.chain) {
+                    return Err(format!("pair '{}': at most one uniswap_v3 pool per chain", pair.name));
+                }
+                if v3.base_token.parse::<ethers::types::Address>().is_ok_and(|a| a.is_zero()) {
+                    return Err(format!("pair '{}': uniswap_v3 has no native ETH; use WETH", pair.name));
+                }
+            }
+            if let Some(routing) = &pair.routing {
+                if !(1..=4).contains(&routing.max_hops) {
+                    return Err(format!("pair '{}': routing.max_hops must be between 1 and 4", pair.name));
+                }
+                for v4 in &routing.uniswap_v4 {
+                    v4.validate().map_err(|e| format!("pair '{}': routing: {}", pair.name, e))?;
+                    if v4.chain != Chain::Ethereum {
+                        return Err(format!("pair '{}': routing.uniswap_v4 pools are on Ethereum", pair.name));
+                    }
+                }
+                if routing.aerodrome.iter().any(|p| p.tick_spacing.is_some()) {
+                    return Err(format!("pair '{}': routing supports volatile and stable Aerodrome pools only", pair.name));
+                }
+            }
+            if let Some(split) = &pair.split {
+                for v4 in &split.uniswap_v4 {
+                    v4.validate().map_err(|e| format!("pair '{}': split: {}", pair.name, e))?;
+                    if v4.chain != pair.uniswap_v4.chain {
+                        return Err(format!("pair '{}': split.uniswap_v4 pools are on the pair's uniswap_v4 chain", pair.name));
+                    }
+                }
+                // Only the legs' two chains are bridged between
+                let leg_chains = [pair.uniswap_v4.chain, Chain::Base];
+                for v3 in &split.uniswap_v3 {
+                    if !leg_chains.contains(&v3.chain) || !matches!(v3.chain, Chain::Ethereum | Chain::Base) {
+                        return Err(format!("pair '{}': split.uniswap_v3 pools are on the chain of one of the pair's legs, Ethereum or Base", pair.name));
+                    }
+                    if v3.base_token.parse::<ethers::types::Address>().is_ok_and(|a| a.is_zero()) {
+                        return Err(format!("pair '{}': uniswap_v3 has no native ETH; use WETH", pair.name));
+                    }
+                }
+                for aero in &split.aerodrome {
+                    match aero.tick_spacing {
+                        Some(_) if aero.stable => {
+                            return Err(format!("pair '{}': an Aerodrome pool is either stable or Slipstream", pair.name));
+                        }
+                        Some(ts) if ts <= 0 => {
+                            return Err(format!("pair '{}': split.aerodrome tick_spacing must be positive", pair.name));
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"
+        [[pair]]
+        name = "ETH-USDC"
+        [pair.uniswap_v4]
+        quote_token = "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48"
+        fee = 500
+        tick_spacing = 10
+        [pair.aerodrome]
+        base_token = "0x4200000000000000000000000000000000000006"
+        quote_token = "0x833589fcd6edb6e08f4c7c32d4f71b54bda02913"
+        pool = "0xcDAC0d6c6C59727a65F871236188350531885C43"
+        [[pair.uniswap_v3]]
+        chain = "ethereum"
+        base_token = "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2"
+        quote_token = "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48"
+        fee = 500
+        pool = "0x88e6A0c2dDD26FEEb64F039a2c41296FcB3f5640"
+        [pair.routing]
+        [[pair.routing.uniswap_v4]]
+        quote_token = "0xdac17f958d2ee523a2206206994597c13d831ec7"
+        fee = 500
+        tick_spacing = 10
+        [[pair.routing.uniswap_v4]]
+        base_token = "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48"
+        quote_token = "0xdac17f958d2ee523a2206206994597c13d831ec7"
+        fee = 10
+        tick_spacing = 1
+        [[pair.routing.aerodrome]]
+        base_token = "0xc1cba3fcea344f92d9239c08c0568f6f2f0ee452"
+        quote_token = "0x833589fcd6edb6e08f4c7c32d4f71b54bda02913"
+        [pair.trade_size]
+        min = 0.5
+        max = 50.0
+        default = 5.0
+
+        [[pair]]
+        name = "CBBTC-USDC"
+        bridge = ["quote"]
+        [pair.uniswap_v4]
+        base_token = "0x2260fac5e5542a773aa44fbcfedf7c193bc2c599"
+        quote_token = "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48"
+        fee = 3000
+        tick_spacing = 60
+        hooks = "0x0000000000000000000000000000000000000000"
+        [pair.aerodrome]
+        base_token = "0xcbb7c0000ab88b473b1f5afd9ef808440eed33bf"
+        quote_token = "0x833589fcd6edb6e08f4c7c32d4f71b54bda02913"
+        [pair.cex]
+        base = "BTC"
+
+        [[pair]]
+        name = "USDT-USDC"
+        [pair.uniswap_v4]
+        base_token = "0xdac17f958d2ee523a2206206994597c13d831ec7"
+        quote_token = "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48"
+        fee = 10
+        tick_spacing = 1
+        [pair.aerodrome]
+        base_token = "0xfde4c96c8593536e31f229ea8f37b2ada2699bb2"
+        quote_token = "0x833589fcd6edb6e08f4c7c32d4f71b54bda02913"
+        stable = true
+
+        [[pair]]
+        name = "ETH-USDC-CL"
+        [pair.uniswap_v4]
+        quote_token = "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48"
+        fee = 500
+        tick_spacing = 10
+        [pair.aerodrome]
+        base_token = "0x4200000000000000000000000000000000000006"
+        quote_token = "0x833589fcd6edb6e08f4c7c32d4f71b54bda02913"
+        tick_spacing = 100
+        [pair.split]
+        [[pair.split.uniswap_v4]]
+        quote_token = "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48"
+        fee = 3000
+        tick_spacing = 60
+        [[pair.split.uniswap_v3]]
+        chain = "base"
+        base_token = "0x4200000000000000000000000000000000000006"
+        quote_token = "0x833589fcd6edb6e08f4c7c32d4f71b54bda02913"
+        fee = 500
+        [[pair.split.aerodrome]]
+        base_token = "0x4200000000000000000000000000000000000006"
+        quote_token = "0x833589fcd6edb6e08f4c7c32d4f71b54bda02913"
+    "#;
+
+    #[test]
+    fn test_parse_watchlist() {
+        let wl = Watchlist::parse(SAMPLE).expect("sample watchlist should parse");
+        assert_eq!(wl.pairs.len(), 4);
+
+        let eth = &wl.pairs[0];
+        assert_eq!(eth.name, "ETH-USDC");
+        assert_eq!(eth.uniswap_v4.base_token, ZERO_ADDRESS); // native ETH by default
+        assert_eq!(eth.uniswap_v4.hooks, ZERO_ADDRESS);
+        assert_eq!(eth.uniswap_v4.fee, 500);
+        assert_eq!(eth.uniswap_v4.tick_spacing, 10);
+        assert!(eth.aerodrome.pool.is_some());
+        assert!(!eth.aerodrome.stable); // volatile by default
+        assert_eq!(eth.aerodrome.tick_spacing, None);
+        assert_eq!(eth.trade_size, TradeSizeBounds { min: 0.5, max: 50.0, default: 5.0 });
+
+        let btc = &wl.pairs[1];
+        assert_eq!(eth.uniswap_v3.len(), 1);
+        assert_eq!(eth.uniswap_v3[0].chain, Chain::Ethereum);
+        assert_eq!(eth.uniswap_v3[0].chain.chain_id(), CHAIN_ID_ETHEREUM);
+        assert_eq!(eth.uniswap_v3[0].fee, 500);
+
+        assert!(btc.aerodrome.pool.is_none()); // factory discovery
+        assert!(btc.uniswap_v3.is_empty());
+        assert!(btc.routing.is_none());
+
+        let routing = eth.routing.as_ref().expect("ETH-USDC routes");
+        assert_eq!(routing.max_hops, 2);
+        assert_eq!(routing.uniswap_v4.len(), 2);
+        assert_eq!(routing.uniswap_v4[0].base_token, ZERO_ADDRESS);
+        assert!(!routing.aerodrome[0].stable);
+        assert_eq!(btc.trade_size, TradeSizeBounds::default());
+        assert_eq!(eth.cex, CexPricing::default());
+        assert_eq!((btc.cex.base.as_str(), btc.cex.quote.as_str()), ("BTC", "USD"));
+        assert_eq!(eth.bridge, None);
+        assert_eq!(btc.bridge, Some(vec![PairToken::Quote]));
+
+        assert!(wl.pairs[2].aerodrome.stable);
+        assert_eq!(wl.pairs[3].aerodrome.tick_spacing, Some(100));
+    }
+
+    #[test]
+    fn test_example_file_parses() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("watchlist.example.toml");
+        let wl = Watchlist::load(&path).expect("watchlist.example.toml should parse");
+        assert_eq!(wl.pairs[0].name, "ETH-USDC");
+        assert_eq!(wl.pairs[1].uniswap_v4.fee, 500);
+    }
+
+    #[test]
+    fn test_rejects_duplicate_names() {
+        let doubled = format!("{}\n{}", SAMPLE, SAMPLE);
+        assert!(Watchlist::parse(&doubled).is_err());
+
+        let recased = SAMPLE.replacen("name = \"CBBTC-USDC\"", "name = \"eth-usdc\"", 1);
+        let err = Watchlist::parse(&recased).unwrap_err().to_string();
+        assert!(err.contains("duplicate pair name 'eth-usdc'"), "{}", err);
+    }
+
+    #[test]
+    fn test_rejects_bad_bridge_tokens() {
+        for bridge in ["[]", "[\"quote\", \"quote\"]", "[\"usdc\"]"] {
+            let bad = SAMPLE.replace("bridge = [\"quote\"]", &format!("bridge = {}", bridge));
+            assert!(Watchlist::parse(&bad).is_err(), "{}", bridge);
+        }
+        let both = SAMPLE.replace("bridge = [\"quote\"]", "bridge = [\"base\", \"quote\"]");
+        assert_eq!(Watchlist::parse(&both).unwrap().pairs[1].bridge, Some(vec![PairToken::Base, PairToken::Quote]));
+    }
+
+    #[test]
+    fn test_rejects_empty_cex_currency() {
+        let bad = SAMPLE.replace("base = \"BTC\"", "base = \"\"");
+        assert!(Watchlist::parse(&bad).is_err());
+    }
+
+    #[test]
+    fn test_rejects_bad_bounds() {
+        let bad = SAMPLE.replace("default = 5.0", "default = 500.0");
+        assert!(Watchlist::parse(&bad).is_err());
+    }
+
+    #[test]
+    fn test_rejects_stable_slipstream() {
+        let bad = SAMPLE.replace("tick_spacing = 100", "tick_spacing = 100\n        stable = true");
+        assert!(Watchlist::parse(&bad).is_err());
+        let bad = SAMPLE.replace("tick_spacing = 100", "tick_spacing = 0");
+        assert!(Watchlist::parse(&bad).is_err());
+    }
+
+    #[test]
+    fn test_rejects_bad_uniswap_v3() {
+        let with_second_v3 = |chain: &str| {
+            let extra = format!(
+                "[[pair.uniswap_v3]]\nchain = \"{}\"\nbase_token = \"0x4200000000000000000000000000000000000006\"\n\
+                 quote_token = \"0x833589fcd6edb6e08f4c7c32d4f71b54bda02913\"\nfee = 500\n[pair.trade_size]",
+                chain
+            );
+            SAMPLE.replacen("[pair.trade_size]", &extra, 1)
+        };
+        let wl = Watchlist::parse(&with_second_v3("base")).expect("one V3 pool per chain is fine");
+        assert_eq!(wl.pairs[0].uniswap_v3[1].chain, Chain::Base);
+        assert!(Watchlist::parse(&with_second_v3("ethereum")).is_err());
+        assert!(Watchlist::parse(&with_second_v3("optimism")).is_err());
+        assert!(Watchlist::parse(&with_second_v3("arbitrum")).unwrap_err().to_string().contains("Ethereum or Base leg"));
+
+        let native = SAMPLE.replace("0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2", ZERO_ADDRESS);
+        assert!(Watchlist::parse(&native).is_err());
+    }
+
+    #[test]
+    fn test_rejects_bad_routing() {
+        let bad = SAMPLE.replace("[pair.routing]", "[pair.routing]\n        max_hops = 0");
+        assert!(Watchlist::parse(&bad).is_err());
+        let slipstream = SAMPLE.replace(
+            "base_token = \"0xc1cba3fcea344f92d9239c08c0568f6f2f0ee452\"",
+            "base_token = \"0xc1cba3fcea344f92d9239c08c0568f6f2f0ee452\"\n        tick_spacing = 1",
+        );
+        assert!(Watchlist::parse(&slipstream).is_err());
+    }
+
+    #[test]
+    fn test_split_pools() {
+        let wl = Watchlist::parse(SAMPLE).unwrap();
+        assert!(wl.pairs[0].split.is_none());
+        let split = wl.pairs[3].split.as_ref().expect("ETH-USDC-CL has split pools");
+        assert_eq!((split.uniswap_v4.len(), split.uniswap_v3.len(), split.aerodrome.len()), (1, 1, 1));
+        assert_eq!(split.uniswap_v4[0].fee, 3000);
+        assert_eq!(split.uniswap_v3[0].chain, Chain::Base);
+        assert!(!split.aerodrome[0].stable);
+
+        // Split pools stay on the legs' chains, and a V4 pool on the V4 leg's chain
+        let v3_on_arbitrum = SAMPLE.replacen("chain = \"base\"\n        base_token = \"0x42", "chain = \"arbitrum\"\n        base_token = \"0x42", 1);
+        assert!(Watchlist::parse(&v3_on_arbitrum).unwrap_err().to_string().contains("split.uniswap_v3"));
+        let v4_on_base = SAMPLE.replace("[[pair.split.uniswap_v4]]", "[[pair.split.uniswap_v4]]\n        chain = \"base\"");
+        assert!(Watchlist::parse(&v4_on_base).unwrap_err().to_string().contains("split.uniswap_v4"));
+        let stable_slipstream = format!("{}\n        stable = true\n        tick_spacing = 1\n", SAMPLE);
+        assert!(Watchlist::parse(&stable_slipstream).is_err());
+    }
+
+    #[test]
+    fn test_hooked_v4_pools() {
+        let hooked = SAMPLE.replacen(
+            "fee = 500\n        tick_spacing = 10\n        [pair.aerodrome]",
+            "fee = 0x800000\n        tick_spacing = 10\n        hooks = \"0x00000000000000000000000000000000000000c0\"\n        \
+             before_swap_fee = 2500\n        [pair.aerodrome]",
+            1,
+        );
+        let wl = Watchlist::parse(&hooked).expect("dynamic-fee pool with a hook");
+        assert_eq!(wl.pairs[0].uniswap_v4.fee, DYNAMIC_FEE_FLAG);
+        assert_eq!(wl.pairs[0].uniswap_v4.before_swap_fee, Some(2500));
+
+        // Dynamic fees need a hook; beforeSwap overrides need a dynamic-fee pool
+        assert!(Watchlist::parse(&hooked.replace("0x00000000000000000000000000000000000000c0", ZERO_ADDRESS)).is_err());
+        assert!(Watchlist::parse(&hooked.replace("fee = 0x800000", "fee = 500")).is_err());
+        assert!(Watchlist::parse(&SAMPLE.replacen("fee = 500", "fee = 1000000", 1)).is_err());
+    }
+
+    #[test]
+    fn test_uniswap_v4_on_base() {
+        let wl = Watchlist::parse(SAMPLE).unwrap();
+        assert_eq!(wl.pairs[1].uniswap_v4.chain, Chain::Ethereum); // by default
+
+        // CBBTC-USDC with both legs on Base
+        let base = SAMPLE.replacen(
+            "base_token = \"0x2260fac5e5542a773aa44fbcfedf7c193bc2c599\"",
+            "chain = \"base\"\n        base_token = \"0xcbb7c0000ab88b473b1f5afd9ef808440eed33bf\"",
+            1,
+        );
+        let wl = Watchlist::parse(&base).expect("a V4 pool on Base");
+        assert_eq!(wl.pairs[1].uniswap_v4.chain, Chain::Base);
+        assert_eq!(wl.pairs[1].uniswap_v4.chain.chain_id(), CHAIN_ID_BASE);
+
+        // ETH-USDC has a V3 pool on Ethereum and V4 routing pools: neither fits a Base pair
+        let with_v3 = SAMPLE.replacen("[pair.uniswap_v4]", "[pair.uniswap_v4]\n        chain = \"base\"", 1);
+        assert!(Watchlist::parse(&with_v3).is_err());
+        let v3 = with_v3.find("[[pair.uniswap_v3]]").unwrap();
+        let routing = with_v3.find("[pair.routing]").unwrap();
+        let without_v3 = format!("{}{}", &with_v3[..v3], &with_v3[routing..]);
+        assert!(Watchlist::parse(&without_v3).unwrap_err().to_string().contains("routing.uniswap_v4"));
+
+        // Any configured L2 works the same way, as a cross-chain pair against Aerodrome
+        let arbitrum = without_v3.replacen("chain = \"base\"", "chain = \"arbitrum\"", 1);
+        assert!(Watchlist::parse(&arbitrum).unwrap_err().to_string().contains("routing.uniswap_v4"));
+        let routing_end = arbitrum.find("[[pair.routing.aerodrome]]").unwrap();
+        let arbitrum = format!("{}{}", &arbitrum[..arbitrum.find("[pair.routing]").unwrap()], &arbitrum[routing_end..]);
+        let wl = Watchlist::parse(&arbitrum).expect("a V4 pool on Arbitrum");
+        assert_eq!(wl.pairs[0].uniswap_v4.chain.chain_id(), CHAIN_ID_ARBITRUM);
+        let unichain = arbitrum.replacen("chain = \"arbitrum\"", "chain = \"unichain\"", 1);
+        assert_eq!(Watchlist::parse(&unichain).unwrap().pairs[0].uniswap_v4.chain, Chain::Unichain);
+
+        // Routing pools stay on Ethereum
+        let routed_on_base = SAMPLE.replace(
+            "quote_token = \"0xdac17f958d2ee523a2206206994597c13d831ec7\"\n        fee = 500",
+            "quote_token = \"0xdac17f958d2ee523a2206206994597c13d831ec7\"\n        chain = \"base\"\n        fee = 500",
+        );
+        assert!(Watchlist::parse(&routed_on_base).is_err());
+    }
+
+    #[test]
+    fn test_rejects_empty_watchlist() {
+        assert!(Watchlist::parse("pair = []").is_err());
+    }
+
+    #[test]
+    fn test_trade_size_clamp() {
+        let b = TradeSizeBounds { min: 0.5, max: 50.0, default: 5.0 };
+        assert_eq!(b.clamp(0.1), 0.5);
+        assert_eq!(b.clamp(10.0), 10.0);
+        assert_eq!(b.clamp(1e9), 50.0);
+        assert_eq!(b.clamp(f64::NAN), 5.0);
+    }
+}
@@ -2,12 +2,15 @@ use rocket::serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, rocket::FromForm)]
 pub struct ArbitrageQuery {
+    pub pair: Option<String>, // watchlist name; defaults to the first pair
     pub trade_size_eth: Option<f64>,
 }
 
 #[derive(Deserialize, rocket::FromForm)]
 pub struct OptimalArbitrageQuery {
+    pub pair: Option<String>, // watchlist name; defaults to the first pair
     pub max_size_eth: Option<f64>,
+    pub split: Option<bool>, // spread each leg over the pair's split pools; defaults to false
 }
 
 #[derive(Serialize)]
@@ -38,18 +41,68 @@ pub struct ArbitrageSummary {
     pub recommended_action: String,
 }
 
+/// One venue's share of a split leg.
+#[derive(Serialize)]
+pub struct VenueFillDetails {
+    pub venue: String,
+    pub size_eth: f64,
+    pub usdc: f64, // received on a sell, paid on a buy
+}
+
+#[derive(Serialize)]
+pub struct VenueFills {
+    pub sells: Vec<VenueFillDetails>,
+    pub buys: Vec<VenueFillDetails>,
+}
+
+/// One swap of a routed leg, in raw token units.
+#[derive(Serialize)]
+pub struct RouteHopDetails {
+    pub pool: String,
+    pub token_in: String,
+    pub token_out: String,
+    pub amount_in: String,
+    pub amount_out: String,
+}
+
+#[derive(Serialize)]
+pub struct RouteDetails {
+    pub hops: Vec<RouteHopDetails>,
+    pub gas_units: u64, // summed over the hops; the leg's gas is charged for these
+}
+
+#[derive(Serialize)]
+pub struct SourceFreshness {
+    pub age_ms: u64,
+    pub block_number: Option<u64>,    // block the reads were pinned to; null for off-chain sources
+    pub block_timestamp: Option<u64>, // unix seconds of that block
+}
+
+/// Age of the cached market state the response was computed from.
+#[derive(Serialize)]
+pub struct SnapshotDetails {
+    pub cex_price: SourceFreshness,
+    pub gas: SourceFreshness,
+    pub uniswap_v4: SourceFreshness,
+    pub aerodrome: SourceFreshness,
+    pub bridge_fees: SourceFreshness,
+}
+
 #[derive(Serialize)]
 pub struct ArbitrageResponse {
+    pub pair: String,
     pub timestamp_utc: String,
     pub trade_size_eth: f64,
     pub reference_cex_price_usd: f64,
     pub uniswap_v4_details: UniswapDetails,
     pub aerodrome_details: AerodromeDetails,
     pub arbitrage_summary: ArbitrageSummary,
+    pub snapshot: Option<SnapshotDetails>, // null when no snapshot was available
 }
 
 #[derive(Serialize)]
 pub struct OptimalArbitrageResponse {
+    pub pair: String,
     pub timestamp_utc: String,
     pub reference_cex_price_usd: f64,
     pub optimal_trade_size_eth: f64,
@@ -62,4 +115,8 @@ pub struct OptimalArbitrageResponse {
     pub gas_cost_usd: f64,
     pub bridge_cost_usd: f64,
     pub recommended_action: String,
+    pub venue_fills: Option<VenueFills>, // split mode only; null for a single-venue pair of legs
+    pub sell_route: Option<RouteDetails>, // null unless the sell leg is routed
+    pub buy_route: Option<RouteDetails>,  // null unless the buy leg is routed
+    pub snapshot: Option<SnapshotDetails>, // null when no snapshot was available
 }
\ No newline at end of file
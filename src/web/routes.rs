@@ -1,32 +1,66 @@
 use rocket::{get, State};
 use std::sync::Arc;
-use crate::web::dto::{ArbitrageQuery, ArbitrageResponse, UniswapDetails, AerodromeDetails, ArbitrageSummary, OptimalArbitrageQuery, OptimalArbitrageResponse};
-use crate::engine::service::{analyze_arbitrage, find_optimal_arbitrage};
+use crate::web::dto::{ArbitrageQuery, ArbitrageResponse, UniswapDetails, AerodromeDetails, ArbitrageSummary, OptimalArbitrageQuery, OptimalArbitrageResponse, SnapshotDetails, SourceFreshness, VenueFillDetails, VenueFills, RouteDetails, RouteHopDetails};
+use crate::engine::service::{analyze_arbitrage, find_optimal_arbitrage, find_optimal_split, SplitFills};
+use crate::engine::optimizer::VenueFill;
+use crate::math::route::Route;
+use crate::engine::poller;
 use crate::bootstrap::AppState;
 
+fn snapshot_details(info: poller::SnapshotInfo) -> SnapshotDetails {
+    let f = |s: poller::SourceFreshness| SourceFreshness {
+        age_ms: s.age_ms,
+        block_number: s.block_number,
+        block_timestamp: s.block_timestamp,
+    };
+    SnapshotDetails {
+        cex_price: f(info.cex_price),
+        gas: f(info.gas),
+        uniswap_v4: f(info.uniswap_v4),
+        aerodrome: f(info.aerodrome),
+        bridge_fees: f(info.bridge_fees),
+    }
+}
+
+fn venue_fills(fills: SplitFills) -> VenueFills {
+    let f = |v: Vec<VenueFill>| v.into_iter()
+        .map(|f| VenueFillDetails { venue: f.venue, size_eth: f.size_eth, usdc: f.usdc })
+        .collect();
+    VenueFills { sells: f(fills.sells), buys: f(fills.buys) }
+}
+
+fn route_details(route: Route) -> RouteDetails {
+    let hops = route.hops.into_iter()
+        .map(|h| RouteHopDetails {
+            pool: h.pool,
+            token_in: format!("{:?}", h.token_in),
+            token_out: format!("{:?}", h.token_out),
+            amount_in: h.amount_in.to_string(),
+            amount_out: h.amount_out.to_string(),
+        })
+        .collect();
+    RouteDetails { hops, gas_units: route.gas_units }
+}
+
 #[get("/api/v1/arbitrage-opportunity?<query..>")]
 pub async fn arbitrage_opportunity(
     query: ArbitrageQuery,
     app_state: &State<Arc<AppState>>,
 ) -> rocket::serde::json::Json<ArbitrageResponse> {
-    let trade_size = query.trade_size_eth.unwrap_or(10.0).max(0.0).min(10_000.0);
+    // Pricing walks ticks and searches sizes: keep it off the async workers
+    let (app, name, size) = (app_state.inner().clone(), query.pair.clone(), query.trade_size_eth);
+    let result = tokio::task::spawn_blocking(move || {
+        let pair = app.pair(name.as_deref())?;
+        let trade_size = pair.trade_size.clamp(size.unwrap_or(pair.trade_size.default));
+        analyze_arbitrage(&app, pair, trade_size)
+    })
+    .await
+    .unwrap_or_else(|e| Err(e.into()));
 
-    match analyze_arbitrage(
-        app_state.eth_provider.clone(),
-        app_state.base_provider.clone(),
-        app_state.uniswap_state_view,
-        &app_state.cex_client,
-        trade_size,
-        app_state.eth_usdc_address,
-        app_state.base_weth_address,
-        app_state.base_usdc_address,
-        app_state.aerodrome_factory_address,
-        app_state.aerodrome_weth_usdc_volatile_pool,
-        app_state.gas_uniswap_v4_total,
-        app_state.gas_aerodrome_swap,
-    ).await {
+    match result {
         Ok(analysis) => {
             rocket::serde::json::Json(ArbitrageResponse {
+                pair: analysis.pair,
                 timestamp_utc: analysis.timestamp_utc,
                 trade_size_eth: analysis.trade_size_eth,
                 reference_cex_price_usd: analysis.reference_cex_price_usd,
@@ -52,13 +86,15 @@ pub async fn arbitrage_opportunity(
                     net_profit_best_usd: analysis.net_profit_best_usd,
                     recommended_action: analysis.recommended_action,
                 },
+                snapshot: Some(snapshot_details(analysis.snapshot)),
             })
         }
         Err(e) => {
             log::error!("Failed to calculate arbitrage: {}", e);
             rocket::serde::json::Json(ArbitrageResponse {
+                pair: query.pair.clone().unwrap_or_default(),
                 timestamp_utc: chrono::Utc::now().to_rfc3339(),
-                trade_size_eth: trade_size,
+                trade_size_eth: query.trade_size_eth.unwrap_or(0.0),
                 reference_cex_price_usd: 0.0,
                 uniswap_v4_details: UniswapDetails {
                     sell_price_usdc_per_eth: 0.0,
@@ -82,6 +118,7 @@ pub async fn arbitrage_opportunity(
                     net_profit_best_usd: 0.0,
                     recommended_action: format!("ERROR: {}", e),
                 },
+                snapshot: None,
             })
         }
     }
@@ -92,24 +129,24 @@ pub async fn optimal_arbitrage_opportunity(
     query: OptimalArbitrageQuery,
     app_state: &State<Arc<AppState>>,
 ) -> rocket::serde::json::Json<OptimalArbitrageResponse> {
-    let max_size = query.max_size_eth.unwrap_or(100.0).max(0.1).min(1000.0);
+    let (app, name, max_size, split) = (app_state.inner().clone(), query.pair.clone(), query.max_size_eth, query.split.unwrap_or(false));
+    let result = tokio::task::spawn_blocking(move || {
+        let pair = app.pair(name.as_deref())?;
+        // Search needs a positive upper bound even when the pair allows min = 0
+        let max_size = pair.trade_size.clamp(max_size.unwrap_or(pair.trade_size.max)).max(0.1);
+        if split {
+            find_optimal_split(&app, pair, max_size)
+        } else {
+            find_optimal_arbitrage(&app, pair, max_size)
+        }
+    })
+    .await
+    .unwrap_or_else(|e| Err(e.into()));
 
-    match find_optimal_arbitrage(
-        app_state.eth_provider.clone(),
-        app_state.base_provider.clone(),
-        app_state.uniswap_state_view,
-        &app_state.cex_client,
-        max_size,
-        app_state.eth_usdc_address,
-        app_state.base_weth_address,
-        app_state.base_usdc_address,
-        app_state.aerodrome_factory_address,
-        app_state.aerodrome_weth_usdc_volatile_pool,
-        app_state.gas_uniswap_v4_total,
-        app_state.gas_aerodrome_swap,
-    ).await {
+    match result {
         Ok(analysis) => {
             rocket::serde::json::Json(OptimalArbitrageResponse {
+                pair: analysis.pair,
                 timestamp_utc: analysis.timestamp_utc,
                 reference_cex_price_usd: analysis.reference_cex_price_usd,
                 optimal_trade_size_eth: analysis.optimal_trade_size_eth,
@@ -122,11 +159,16 @@ pub async fn optimal_arbitrage_opportunity(
                 gas_cost_usd: analysis.gas_cost_usd,
                 bridge_cost_usd: analysis.bridge_cost_usd,
                 recommended_action: analysis.recommended_action,
+                venue_fills: analysis.venue_fills.map(venue_fills),
+                sell_route: analysis.sell_route.map(route_details),
+                buy_route: analysis.buy_route.map(route_details),
+                snapshot: Some(snapshot_details(analysis.snapshot)),
             })
         }
         Err(e) => {
             log::error!("Failed to find optimal arbitrage: {}", e);
             rocket::serde::json::Json(OptimalArbitrageResponse {
+                pair: query.pair.clone().unwrap_or_default(),
                 timestamp_utc: chrono::Utc::now().to_rfc3339(),
                 reference_cex_price_usd: 0.0,
                 optimal_trade_size_eth: 0.0,
@@ -139,6 +181,10 @@ pub async fn optimal_arbitrage_opportunity(
                 gas_cost_usd: 0.0,
                 bridge_cost_usd: 0.0,
                 recommended_action: format!("ERROR: {}", e),
+                venue_fills: None,
+                sell_route: None,
+                buy_route: None,
+                snapshot: None,
             })
         }
     }
@@ -1,6 +1,7 @@
 // tests/aerodrome_base_integration.rs
 
 use arrakis_arbitrage::math::aerodrome_volatile::{VolatilePairState, SwapDirection, simulate_exact_in_volatile, from_raw};
+use arrakis_arbitrage::math::amount::TokenAmount;
 use ethers::prelude::*;
 use std::str::FromStr;
 use std::sync::Arc;
@@ -98,6 +99,7 @@ async fn read_fee_bps(
 }
 
 // Fetch real Aerodrome pool state from Base network
+#[allow(clippy::unnecessary_lazy_evaluations)]
 async fn fetch_aerodrome_pool_state(
     provider: Arc<Provider<Http>>,
     pool_address: Address,
@@ -157,6 +159,11 @@ async fn fetch_aerodrome_pool_state(
         decimals0,
         decimals1,
         fee_bps,
+        block_number: 0,
+        block_timestamp: 0,
+        stable: false,
+        chain_id: 0,
+        gas_units: 0,
     };
 
     Ok((pair_state, is_stable))
@@ -263,8 +270,8 @@ async fn test_aerodrome_router_vs_simulator() -> Result<(), Box<dyn std::error::
             SwapDirection::OneForZero => pool_state.decimals0,
         };
         
-        let amount_in_human = 1.0; // 1 WETH
         let amount_in_wei = U256::from_dec_str("1000000000000000000")?; // 1 WETH in wei
+        let amount_in = TokenAmount::new(amount_in_wei, 18);
         
         println!("  Direction: {:?} (WETH at position {})", direction, if is_token0_weth { "0" } else { "1" });
         
@@ -277,9 +284,9 @@ async fn test_aerodrome_router_vs_simulator() -> Result<(), Box<dyn std::error::
         
         // --- Our simulator
         let (_amount_in_raw, amount_out_raw, effective_price, spot_price, price_impact_pct) = 
-            simulate_exact_in_volatile(&pool_state, direction, amount_in_human);
+            simulate_exact_in_volatile(&pool_state, direction, amount_in);
         
-        let usdc_out_sim = from_raw(amount_out_raw, output_decimals);
+        let usdc_out_sim = amount_out_raw.to_f64();
         println!("  Simulator amountOut: {:.6} USDC", usdc_out_sim);
         println!("  Price impact: {:.3}%", price_impact_pct);
         println!("  Effective price: {:.2} USDC/WETH", effective_price);
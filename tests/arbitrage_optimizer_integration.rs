@@ -4,9 +4,10 @@
 // using bracket search and golden section optimization.
 
 use arrakis_arbitrage::engine::optimizer::{
-    optimize, OptimizerInputs, ArbDirection,
+    optimize, AerodromePoolState, OptimizerInputs, ArbDirection, PairVenue,
 };
-use arrakis_arbitrage::chain::gas::create_test_gas_estimate;
+use arrakis_arbitrage::chain::gas::{create_test_gas_estimate, GasEstimate};
+use arrakis_arbitrage::chain::tokens::{TokenInfo, TokenPair};
 use arrakis_arbitrage::math::aerodrome_volatile::{VolatilePairState, to_raw as aero_to_raw};
 use arrakis_arbitrage::math::uniswap_v4::{
     create_pool_with_real_data, PoolState as UniPoolState,
@@ -18,6 +19,11 @@ use std::str::FromStr;
 
 // ====== Test Helpers ======
 
+/// 18-decimal ETH base, 6-decimal USDC quote
+fn weth_usdc_tokens() -> TokenPair {
+    TokenPair::new(1, TokenInfo::native(), TokenInfo::new(Address::from([0x22; 20]), 6))
+}
+
 /// Create a mock Uniswap V4 pool with configurable price
 fn create_mock_uniswap_pool(
     weth_is_token0: bool,
@@ -97,9 +103,30 @@ fn create_mock_aerodrome_pool(
         decimals0: if weth_is_token0 { 18 } else { 6 },
         decimals1: if weth_is_token0 { 6 } else { 18 },
         fee_bps,
+        block_number: 0,
+        block_timestamp: 0,
+        stable: false,
+        chain_id: 8453,
+        gas_units: 0,
     }
 }
 
+/// Uniswap on Ethereum, gas priced from `gas`
+fn uniswap_venue(mut pool: UniPoolState, weth_is_token0: bool, gas: GasEstimate) -> PairVenue {
+    pool.chain_id = 1;
+    PairVenue::new("uniswap", pool, weth_usdc_tokens(), weth_is_token0, &gas)
+}
+
+/// Aerodrome on Base, gas priced from `gas`
+fn aerodrome_venue(pair: VolatilePairState, weth_is_token0: bool, gas: GasEstimate) -> PairVenue {
+    let tokens = TokenPair::new(8453, TokenInfo::new(Address::zero(), 18), TokenInfo::new(Address::zero(), 6));
+    PairVenue::new("aerodrome", AerodromePoolState::Pair(pair), tokens, weth_is_token0, &gas)
+}
+
+fn sell_uni_buy_aero() -> ArbDirection {
+    ArbDirection::new("uniswap", "aerodrome")
+}
+
 // ====== Integration Tests ======
 
 #[test]
@@ -111,16 +138,13 @@ fn test_optimizer_finds_profitable_arbitrage() {
     let aero_pair = create_mock_aerodrome_pool(true, 3400.0, 5_000_000.0, 30); // 0.3% fee
     
     let inputs = OptimizerInputs {
-        uni_pool,
-        uni_token0_is_weth: true,
-        uni_fee_ppm_override: Some(3000), // 0.3%
-        
-        aero_pair,
-        aero_token0_is_weth: true,
-        
-        gas_eth: create_test_gas_estimate(25_000_000_000, 200_000, 3500.0),
-        gas_base: create_test_gas_estimate(10_000_000_000, 200_000, 3500.0),
+        venues: vec![
+            uniswap_venue(uni_pool, true, create_test_gas_estimate(25_000_000_000, 200_000, 3500.0)),
+            aerodrome_venue(aero_pair, true, create_test_gas_estimate(10_000_000_000, 200_000, 3500.0)),
+        ],
         bridge_cost_usd: 50.0, // High bridge cost
+        bridge_usd_per_eth: 0.0,
+        quote_usd: 1.0,
         hint_size_eth: 1.0,
         max_size_eth: 100.0,
     };
@@ -150,14 +174,13 @@ fn test_optimizer_with_different_token_orders() {
     let aero_pair = create_mock_aerodrome_pool(false, 3400.0, 5_000_000.0, 30); // WETH is token1
     
     let inputs = OptimizerInputs {
-        uni_pool,
-        uni_token0_is_weth: false, // Important: WETH is token1
-        uni_fee_ppm_override: Some(3000),
-        aero_pair,
-        aero_token0_is_weth: false, // Important: WETH is token1
-        gas_eth: create_test_gas_estimate(25_000_000_000, 200_000, 3450.0),
-        gas_base: create_test_gas_estimate(100_000_000, 150_000, 3450.0),
+        venues: vec![
+            uniswap_venue(uni_pool, false, create_test_gas_estimate(25_000_000_000, 200_000, 3450.0)),
+            aerodrome_venue(aero_pair, false, create_test_gas_estimate(100_000_000, 150_000, 3450.0)),
+        ],
         bridge_cost_usd: 5.0,
+        bridge_usd_per_eth: 0.0,
+        quote_usd: 1.0,
         hint_size_eth: 1.0,
         max_size_eth: 100.0,
     };
@@ -172,7 +195,7 @@ fn test_optimizer_with_different_token_orders() {
     println!("  Optimal Size: {:.4} ETH", res.optimal_size_eth);
     
     assert!(res.net_profit_usd > 0.0, "Should still find profitable arbitrage");
-    assert_eq!(res.direction, ArbDirection::SellUniBuyAero, 
+    assert_eq!(res.direction, sell_uni_buy_aero(), 
               "Should identify correct direction with reversed tokens");
 }
 
@@ -183,14 +206,13 @@ fn test_optimizer_price_impact_consideration() {
     let aero_pair = create_mock_aerodrome_pool(true, 3400.0, 500_000.0, 30); // Small pool
     
     let inputs = OptimizerInputs {
-        uni_pool,
-        uni_token0_is_weth: true,
-        uni_fee_ppm_override: Some(3000),
-        aero_pair,
-        aero_token0_is_weth: true,
-        gas_eth: create_test_gas_estimate(25_000_000_000, 200_000, 3450.0),
-        gas_base: create_test_gas_estimate(100_000_000, 150_000, 3450.0),
+        venues: vec![
+            uniswap_venue(uni_pool, true, create_test_gas_estimate(25_000_000_000, 200_000, 3450.0)),
+            aerodrome_venue(aero_pair, true, create_test_gas_estimate(100_000_000, 150_000, 3450.0)),
+        ],
         bridge_cost_usd: 5.0,
+        bridge_usd_per_eth: 0.0,
+        quote_usd: 1.0,
         hint_size_eth: 1.0,
         max_size_eth: 100.0,
     };
@@ -217,14 +239,13 @@ fn test_optimizer_convergence_quality() {
     let aero_pair = create_mock_aerodrome_pool(true, 3420.0, 5_000_000.0, 30);
     
     let inputs = OptimizerInputs {
-        uni_pool,
-        uni_token0_is_weth: true,
-        uni_fee_ppm_override: Some(3000),
-        aero_pair,
-        aero_token0_is_weth: true,
-        gas_eth: create_test_gas_estimate(25, 200_000, 3450.0), // 25 gwei instead of 25 billion gwei
-        gas_base: create_test_gas_estimate(1, 150_000, 3450.0),  // 1 gwei instead of 100 million gwei
+        venues: vec![
+            uniswap_venue(uni_pool, true, create_test_gas_estimate(25, 200_000, 3450.0)), // 25 gwei instead of 25 billion gwei
+            aerodrome_venue(aero_pair, true, create_test_gas_estimate(1, 150_000, 3450.0)),  // 1 gwei instead of 100 million gwei
+        ],
         bridge_cost_usd: 5.0,
+        bridge_usd_per_eth: 0.0,
+        quote_usd: 1.0,
         hint_size_eth: 0.1, // Start with small hint
         max_size_eth: 100.0,
     };
@@ -250,20 +271,20 @@ fn test_optimizer_convergence_quality() {
 #[test]
 fn test_optimizer_fee_variations() {
     // Test with different fee tiers
-    let uni_pool = create_mock_uniswap_pool(true, 3500.0, 10_000_000.0);
+    let mut uni_pool = create_mock_uniswap_pool(true, 3500.0, 10_000_000.0);
+    uni_pool.key.fee_ppm = 500; // 0.05% Uniswap tier
     
     // High fee Aerodrome pool
     let aero_pair = create_mock_aerodrome_pool(true, 3400.0, 5_000_000.0, 100); // 1% fee
     
     let inputs = OptimizerInputs {
-        uni_pool,
-        uni_token0_is_weth: true,
-        uni_fee_ppm_override: Some(500), // 0.05% fee override for Uniswap
-        aero_pair,
-        aero_token0_is_weth: true,
-        gas_eth: create_test_gas_estimate(25_000_000_000, 200_000, 3450.0),
-        gas_base: create_test_gas_estimate(100_000_000, 150_000, 3450.0),
+        venues: vec![
+            uniswap_venue(uni_pool, true, create_test_gas_estimate(25_000_000_000, 200_000, 3450.0)),
+            aerodrome_venue(aero_pair, true, create_test_gas_estimate(100_000_000, 150_000, 3450.0)),
+        ],
         bridge_cost_usd: 5.0,
+        bridge_usd_per_eth: 0.0,
+        quote_usd: 1.0,
         hint_size_eth: 1.0,
         max_size_eth: 100.0,
     };
@@ -277,7 +298,7 @@ fn test_optimizer_fee_variations() {
         
         // Even with different fees, should find best direction
         // Lower Uniswap fee (0.05%) vs higher Aerodrome fee (1%) should affect profitability
-        assert!(res.direction == ArbDirection::SellUniBuyAero,
+        assert!(res.direction == sell_uni_buy_aero(),
                 "Should prefer selling on low-fee Uniswap");
     }
 }
\ No newline at end of file
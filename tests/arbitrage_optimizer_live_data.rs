@@ -4,9 +4,10 @@
 // and Aerodrome volatile pool (Base mainnet).
 
 use arrakis_arbitrage::engine::optimizer::{
-    optimize, OptimizerInputs,
+    optimize, AerodromePoolState, OptimizerInputs, PairVenue,
 };
 use arrakis_arbitrage::chain::gas::{GasEstimate, create_test_gas_estimate};
+use arrakis_arbitrage::chain::tokens::{TokenInfo, TokenPair};
 use arrakis_arbitrage::math::aerodrome_volatile::VolatilePairState;
 use arrakis_arbitrage::math::uniswap_v4::create_pool_with_real_data;
 use arrakis_arbitrage::math::uniswap_v4::PoolState as UniPoolState;
@@ -235,6 +236,11 @@ async fn fetch_aerodrome_pair(
         decimals0: if token0_is_weth { 18 } else { 6 },
         decimals1: if token0_is_weth { 6 } else { 18 },
         fee_bps,
+        block_number: 0,
+        block_timestamp: 0,
+        stable: false,
+        chain_id: 0,
+        gas_units: 0,
     };
     Ok((pair, token0_is_weth))
 }
@@ -310,21 +316,31 @@ async fn test_arbitrage_optimizer_live() -> Result<(), Box<dyn std::error::Error
 
     // Build optimizer inputs
     let inputs = OptimizerInputs {
-        // Uniswap v4 side
-        uni_pool,
-        // IMPORTANT: The simulator flag means “is token0 the 18-dec ETH side?”
-        // For v4 ETH/USDC, currency0=ETH(address(0)) → true if our pool currency0 is ETH.
-        uni_token0_is_weth: uni_token0_is_eth,
-        uni_fee_ppm_override: Some(3000), // set fee tier you queried
-
-        // Aerodrome side (Base)
-        aero_pair,
-        aero_token0_is_weth,
+        venues: vec![
+            // Uniswap v4 side
+            // IMPORTANT: The simulator flag means “is token0 the 18-dec ETH side?”
+            // For v4 ETH/USDC, currency0=ETH(address(0)) → true if our pool currency0 is ETH.
+            PairVenue::new(
+                "uniswap",
+                UniPoolState { chain_id: 1, ..uni_pool },
+                TokenPair::new(1, TokenInfo::native(), TokenInfo::new(usdc_eth, 6)),
+                uni_token0_is_eth,
+                &gas_eth,
+            ),
+            // Aerodrome side (Base)
+            PairVenue::new(
+                "aerodrome",
+                AerodromePoolState::Pair(VolatilePairState { chain_id: 8453, ..aero_pair }),
+                TokenPair::new(8453, TokenInfo::new(base_weth, 18), TokenInfo::new(base_usdc, 6)),
+                aero_token0_is_weth,
+                &gas_base,
+            ),
+        ],
 
         // Costs
-        gas_eth,
-        gas_base,
         bridge_cost_usd: 10.0,
+        bridge_usd_per_eth: 0.0,
+        quote_usd: 1.0,
 
         // Search
         hint_size_eth: 1.0,
@@ -55,6 +55,15 @@ fn test_pool_state_math() {
         tick: 200000,
         liquidity: BigInt::from(500000000000000000000000u128),
         ticks: BTreeMap::new(),
+        block_number: 0,
+        block_timestamp: 0,
+        chain_id: 0,
+        gas_units: 0,
+        lp_fee_ppm: 3000,
+        protocol_fee: 0,
+        before_swap_fee_ppm: None,
+        loaded_ticks: None,
+        synthetic_ticks: false,
     };
     
     assert!(!pool.sqrt_price_x96.is_zero());
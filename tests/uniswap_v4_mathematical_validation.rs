@@ -1,3 +1,4 @@
+use arrakis_arbitrage::math::amount::TokenAmount;
 use arrakis_arbitrage::math::uniswap_v4::*;
 use ethers::types::Address;
 use num_bigint::BigInt;
@@ -75,8 +76,7 @@ async fn test_real_pool_simulation_accuracy() {
                 pool,
                 SwapDirection::ZeroForOne,
                 None,
-                amount,
-                18,
+                TokenAmount::from_f64(amount, 18).unwrap(),
                 None,
             ).unwrap();
             
@@ -123,8 +123,7 @@ async fn test_arbitrage_opportunity_detection() {
         &pools_low[1], // 0.3% fee
         SwapDirection::ZeroForOne,
         None,
-        trade_size,
-        18,
+        TokenAmount::from_f64(trade_size, 18).unwrap(),
         None,
     ).unwrap();
     
@@ -141,8 +140,7 @@ async fn test_arbitrage_opportunity_detection() {
         &pools_high[1], // 0.3% fee  
         SwapDirection::OneForZero,
         None,
-        usdc_received / 1e6, // Convert back to token units for input
-        6,  // USDC decimals
+        TokenAmount::from_f64(usdc_received / 1e6, 6).unwrap(), // Convert back to token units for input
         None,
     ).unwrap();
     
@@ -191,8 +189,7 @@ async fn test_price_consistency_across_fee_tiers() {
             pool,
             SwapDirection::ZeroForOne,
             None,
-            small_trade,
-            18,
+            TokenAmount::from_f64(small_trade, 18).unwrap(),
             None,
         ).unwrap();
         
@@ -1,5 +1,6 @@
 // tests/uniswap_v4_quoter_simulator_accurate.rs
 
+use arrakis_arbitrage::math::amount::TokenAmount;
 use arrakis_arbitrage::math::uniswap_v4::{
   create_pool_with_real_data, simulate_exact_in_tokens, SwapDirection, PoolState,
 };
@@ -153,6 +154,7 @@ async fn fetch_real_ticks_around(
 // ======================= Main test =======================
 
 #[tokio::test]
+#[allow(clippy::useless_conversion)]
 async fn test_uniswap_v4_quoter_vs_simulator_eth_usdc_both_directions() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
   dotenv::dotenv().ok();
 
@@ -254,7 +256,7 @@ async fn test_uniswap_v4_quoter_vs_simulator_eth_usdc_both_directions() -> Resul
       };
 
       // Local simulator
-      let sim = simulate_exact_in_tokens(&pool, direction, Some(fee_ppm), 1.0, 18, None)
+      let sim = simulate_exact_in_tokens(&pool, direction, Some(fee_ppm), TokenAmount::from_f64(1.0, 18).unwrap(), None)
           .expect("simulate_exact_in_tokens failed");
       let out_sim = if zero_for_one {
           sim.amount1.to_f64().unwrap_or(0.0) / 1e6
@@ -297,7 +299,7 @@ async fn test_uniswap_v4_quoter_vs_simulator_eth_usdc_both_directions() -> Resul
       let out_quoter_eth = amount_out_quoter_u256.to_f64_lossy() / 1e18;
 
       // Simulator: input token has 6 decimals (USDC)
-      let sim = simulate_exact_in_tokens(&pool, direction, Some(fee_ppm), usdc_in, 6, None)
+      let sim = simulate_exact_in_tokens(&pool, direction, Some(fee_ppm), TokenAmount::from_f64(usdc_in, 6).unwrap(), None)
           .expect("simulate_exact_in_tokens failed");
       let out_sim_eth = if zero_for_one {
           // oneForZero -> amount0 (ETH) is positive out